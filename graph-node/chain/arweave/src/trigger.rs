@@ -10,6 +10,7 @@ use graph::runtime::AscHeap;
 use graph::runtime::AscPtr;
 use graph::runtime::HostExportError;
 use graph_runtime_wasm::module::ToAscPtr;
+use graph_runtime_wasm::rust_abi::ToRustTrigger;
 use std::{cmp::Ordering, sync::Arc};
 
 use crate::codec;
@@ -35,6 +36,8 @@ impl std::fmt::Debug for ArweaveTrigger {
     }
 }
 
+impl ToRustTrigger for ArweaveTrigger {}
+
 impl ToAscPtr for ArweaveTrigger {
     fn to_asc_ptr<H: AscHeap>(
         self,
@@ -183,6 +183,40 @@ impl EthGetLogsFilter {
             topic3: event.topic3,
         }
     }
+
+    /// An identifier for this filter under which the logs it matches are
+    /// cached. Two filters that differ only in the order of their
+    /// contracts, events or topics have the same id
+    pub(crate) fn cache_id(&self) -> [u8; 32] {
+        fn sorted<T: Ord + Clone>(items: &[T]) -> Vec<T> {
+            let mut items = items.to_vec();
+            items.sort();
+            items
+        }
+
+        let mut sponge = tiny_keccak::Keccak::new_keccak256();
+        for contract in sorted(&self.contracts) {
+            sponge.update(contract.as_bytes());
+        }
+        // Separate the parts so that different splits of the same bytes
+        // into contracts and topics do not collide
+        for (marker, topics) in [
+            (b"e", Some(&self.event_signatures)),
+            (b"1", self.topic1.as_ref()),
+            (b"2", self.topic2.as_ref()),
+            (b"3", self.topic3.as_ref()),
+        ] {
+            sponge.update(marker);
+            if let Some(topics) = topics {
+                for topic in sorted(topics) {
+                    sponge.update(topic.as_bytes());
+                }
+            }
+        }
+        let mut id = [0u8; 32];
+        sponge.finalize(&mut id);
+        id
+    }
 }
 
 impl fmt::Display for EthGetLogsFilter {
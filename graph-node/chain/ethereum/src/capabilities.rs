@@ -7,6 +7,9 @@ use crate::DataSource;
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct NodeCapabilities {
     pub archive: bool,
+    /// Whether the node can produce call triggers, either from
+    /// `trace_filter` or, with the `top_level_calls` provider feature, from
+    /// the transactions in a block
     pub traces: bool,
 }
 
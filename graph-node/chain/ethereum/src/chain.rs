@@ -26,6 +26,7 @@ use graph::{
             FirehoseMapper as FirehoseMapperTrait, TriggersAdapter as TriggersAdapterTrait,
         },
         firehose_block_stream::FirehoseBlockStream,
+        merged_blocks_block_stream::{MergedBlocksBlockStream, MergedBlocksSource},
         polling_block_stream::PollingBlockStream,
         Block, BlockPtr, Blockchain, ChainHeadUpdateListener, IngestorError,
         RuntimeAdapter as RuntimeAdapterTrait, TriggerFilter as _,
@@ -114,6 +115,42 @@ impl BlockStreamBuilder<Chain> for EthereumStreamBuilder {
         )))
     }
 
+    async fn build_merged_blocks(
+        &self,
+        chain: &Chain,
+        deployment: DeploymentLocator,
+        source: Arc<MergedBlocksSource>,
+        start_blocks: Vec<BlockNumber>,
+        subgraph_current_block: Option<BlockPtr>,
+        filter: Arc<<Chain as Blockchain>::TriggerFilter>,
+        unified_api_version: UnifiedMappingApiVersion,
+    ) -> Result<Box<dyn BlockStream<Chain>>> {
+        let requirements = filter.node_capabilities();
+        let adapter = chain
+            .triggers_adapter(&deployment, &requirements, unified_api_version)
+            .unwrap_or_else(|_| {
+                panic!(
+                    "no adapter for network {} with capabilities {}",
+                    chain.name, requirements
+                )
+            });
+
+        let logger = chain
+            .logger_factory
+            .subgraph_logger(&deployment)
+            .new(o!("component" => "MergedBlocksBlockStream"));
+
+        let firehose_mapper = Arc::new(FirehoseMapper { adapter, filter });
+
+        Ok(Box::new(MergedBlocksBlockStream::new(
+            source,
+            subgraph_current_block,
+            firehose_mapper,
+            start_blocks,
+            logger,
+        )))
+    }
+
     async fn build_substreams(
         &self,
         _chain: &Chain,
@@ -351,6 +388,7 @@ pub struct Chain {
     adapter_selector: Arc<dyn TriggersAdapterSelector<Self>>,
     runtime_adapter_builder: Arc<dyn RuntimeAdapterBuilder>,
     eth_adapters: Arc<EthereumNetworkAdapters>,
+    merged_blocks: Option<Arc<MergedBlocksSource>>,
 }
 
 impl std::fmt::Debug for Chain {
@@ -396,9 +434,18 @@ impl Chain {
             reorg_threshold,
             is_ingestible,
             polling_ingestor_interval,
+            merged_blocks: None,
         }
     }
 
+    /// Read the blocks for subgraph block streams from the Firehose
+    /// `merged-blocks` bundles in `source` instead of using Firehose or
+    /// RPC. The chain head is still tracked with the chain's client
+    pub fn with_merged_blocks(mut self, source: Arc<MergedBlocksSource>) -> Self {
+        self.merged_blocks = Some(source);
+        self
+    }
+
     /// Returns a handler to this chain's [`EthereumCallCache`].
     pub fn call_cache(&self) -> Arc<dyn EthereumCallCache> {
         self.call_cache.clone()
@@ -478,6 +525,21 @@ impl Blockchain for Chain {
                 .await;
         }
 
+        if let Some(source) = &self.merged_blocks {
+            return self
+                .block_stream_builder
+                .build_merged_blocks(
+                    self,
+                    deployment,
+                    source.cheap_clone(),
+                    start_blocks,
+                    current_ptr,
+                    filter.chain_filter.clone(),
+                    unified_api_version,
+                )
+                .await;
+        }
+
         match self.chain_client().as_ref() {
             ChainClient::Rpc(_) => {
                 self.block_stream_builder
@@ -543,6 +605,14 @@ impl Blockchain for Chain {
         self.block_refetcher.required(self)
     }
 
+    fn check_capabilities(&self, capabilities: &NodeCapabilities) -> Result<(), Error> {
+        match self.client.as_ref() {
+            // Firehose blocks contain all the data that triggers need
+            ChainClient::Firehose(_) => Ok(()),
+            ChainClient::Rpc(adapters) => adapters.check_capabilities(capabilities),
+        }
+    }
+
     async fn refetch_firehose_block(
         &self,
         logger: &Logger,
@@ -1273,6 +1343,8 @@ mod tests {
     use graph::{slog, tokio};
 
     use super::*;
+    use graph::firehose::BstreamBlock;
+    use graph::futures03::StreamExt;
     use std::sync::Arc;
 
     // Helper function to create test blocks
@@ -1399,4 +1471,138 @@ mod tests {
         assert!(missing.contains(&2));
         assert!(missing.contains(&3));
     }
+
+    /// A triggers adapter that finds no triggers in any block
+    struct NoTriggersAdapter;
+
+    #[async_trait]
+    impl TriggersAdapterTrait<Chain> for NoTriggersAdapter {
+        async fn ancestor_block(
+            &self,
+            _ptr: BlockPtr,
+            _offset: BlockNumber,
+            _root: Option<BlockHash>,
+        ) -> Result<Option<BlockFinality>, Error> {
+            unimplemented!()
+        }
+
+        async fn scan_triggers(
+            &self,
+            _from: BlockNumber,
+            _to: BlockNumber,
+            _filter: &TriggerFilter,
+        ) -> Result<(Vec<BlockWithTriggers<Chain>>, BlockNumber), Error> {
+            unimplemented!()
+        }
+
+        async fn triggers_in_block(
+            &self,
+            logger: &Logger,
+            block: BlockFinality,
+            _filter: &TriggerFilter,
+        ) -> Result<BlockWithTriggers<Chain>, Error> {
+            Ok(BlockWithTriggers::new(block, vec![], logger))
+        }
+
+        async fn is_on_main_chain(&self, _ptr: BlockPtr) -> Result<bool, Error> {
+            unimplemented!()
+        }
+
+        async fn parent_ptr(&self, _block: &BlockPtr) -> Result<Option<BlockPtr>, Error> {
+            unimplemented!()
+        }
+
+        async fn chain_head_ptr(&self) -> Result<Option<BlockPtr>, Error> {
+            unimplemented!()
+        }
+
+        async fn load_block_ptrs_by_numbers(
+            &self,
+            _logger: Logger,
+            _block_numbers: BTreeSet<BlockNumber>,
+        ) -> Result<Vec<BlockFinality>> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn merged_blocks_stream_reads_ethereum_blocks() {
+        let logger = Logger::root(slog::Discard, o!());
+
+        let block = codec::Block {
+            ver: 2,
+            hash: vec![0xaa; 32],
+            number: 100,
+            header: Some(codec::BlockHeader {
+                parent_hash: vec![0x99; 32],
+                uncle_hash: vec![0; 32],
+                coinbase: vec![0; 20],
+                state_root: vec![0; 32],
+                transactions_root: vec![0; 32],
+                receipt_root: vec![0; 32],
+                mix_hash: vec![0; 32],
+                hash: vec![0xaa; 32],
+                number: 100,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let message = BstreamBlock {
+            number: 100,
+            id: hex::encode(&block.hash),
+            parent_id: hex::encode(vec![0x99; 32]),
+            parent_num: 99,
+            payload: Some(prost_types::Any {
+                type_url: "type.googleapis.com/sf.ethereum.type.v2.Block".to_string(),
+                value: block.encode_to_vec(),
+            }),
+            ..Default::default()
+        }
+        .encode_to_vec();
+
+        // Write the bundle for blocks 100..200 the way Firehose does
+        let content_type = b"type.googleapis.com/sf.bstream.v1.Block";
+        let mut dbin = b"dbin\x01".to_vec();
+        dbin.extend_from_slice(&(content_type.len() as u16).to_be_bytes());
+        dbin.extend_from_slice(content_type);
+        dbin.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        dbin.extend_from_slice(&message);
+
+        let dir = std::env::temp_dir().join(format!("graph-merged-blocks-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("0000000100.dbin.zst"),
+            zstd::stream::encode_all(dbin.as_slice(), 0).unwrap(),
+        )
+        .unwrap();
+
+        let source =
+            MergedBlocksSource::new("bundles", dir.to_str().unwrap(), Duration::from_millis(10))
+                .unwrap();
+        let mapper = Arc::new(FirehoseMapper {
+            adapter: Arc::new(NoTriggersAdapter),
+            filter: Arc::new(TriggerFilter::default()),
+        });
+        let mut stream = MergedBlocksBlockStream::<Chain>::new(
+            Arc::new(source),
+            None,
+            mapper,
+            vec![100],
+            logger,
+        );
+        let event = stream.next().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        match event.unwrap() {
+            BlockStreamEvent::ProcessBlock(block, cursor) => {
+                assert_eq!(BlockPtr::new(vec![0xaa; 32].into(), 100), block.ptr());
+                assert_eq!(
+                    Some(BlockPtr::new(vec![0x99; 32].into(), 99)),
+                    block.parent_ptr()
+                );
+                assert!(cursor.is_none());
+            }
+            _ => panic!("expected a block to process"),
+        }
+    }
 }
@@ -6,7 +6,7 @@ use graph::components::store::{EthereumCallCache, StoredDynamicDataSource};
 use graph::components::subgraph::{HostMetrics, InstanceDSTemplateInfo, MappingError};
 use graph::components::trigger_processor::RunnableTriggers;
 use graph::data_source::common::{
-    CallDecls, DeclaredCall, FindMappingABI, MappingABI, UnresolvedMappingABI,
+    CallDecls, DeclaredCall, FindMappingABI, MappingABI, MappingLanguage, UnresolvedMappingABI,
 };
 use graph::data_source::{CausalityRegion, MappingTrigger as MappingTriggerType};
 use graph::env::ENV_VARS;
@@ -427,6 +427,10 @@ impl blockchain::DataSource<Chain> for DataSource {
     fn runtime(&self) -> Option<Arc<Vec<u8>>> {
         Some(self.mapping.runtime.cheap_clone())
     }
+
+    fn language(&self) -> MappingLanguage {
+        MappingLanguage::from(self.mapping.language.as_str())
+    }
 }
 
 impl DataSource {
@@ -883,30 +887,38 @@ impl DataSource {
                 // Take the output for the call, then call `function.decode_output` to
                 // get a vector of `Token`s. Match the `Token`s with the `Param`s in
                 // `function.outputs` to create a `Vec<LogParam>`.
-                let tokens = function_abi
-                    .decode_output(&call.output.0)
-                    .with_context(|| {
-                        format!(
-                            "Decoding function outputs for the call {:?} failed, raw output: {}",
-                            &function_abi,
-                            hex::encode(&call.output.0)
-                        )
-                    })?;
-
-                ensure!(
-                    tokens.len() == function_abi.outputs.len(),
-                    "Number of parameters in the call output does not match \
-                        number of outputs in the function signature."
-                );
+                //
+                // Calls that were built from transactions rather than traces
+                // (see the `top_level_calls` provider feature) have no output;
+                // they are passed to the handler without outputs.
+                let outputs = if call.from_transaction {
+                    vec![]
+                } else {
+                    let tokens = function_abi
+                        .decode_output(&call.output.0)
+                        .with_context(|| {
+                            format!(
+                                "Decoding function outputs for the call {:?} failed, raw output: {}",
+                                &function_abi,
+                                hex::encode(&call.output.0)
+                            )
+                        })?;
+
+                    ensure!(
+                        tokens.len() == function_abi.outputs.len(),
+                        "Number of parameters in the call output does not match \
+                            number of outputs in the function signature."
+                    );
 
-                let outputs = tokens
-                    .into_iter()
-                    .enumerate()
-                    .map(|(i, token)| LogParam {
-                        name: function_abi.outputs[i].name.clone(),
-                        value: token,
-                    })
-                    .collect::<Vec<_>>();
+                    tokens
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, token)| LogParam {
+                            name: function_abi.outputs[i].name.clone(),
+                            value: token,
+                        })
+                        .collect::<Vec<_>>()
+                };
 
                 let transaction = Arc::new(
                     block
@@ -95,6 +95,14 @@ pub struct EnvVars {
     /// When enabled, forces the use of RPC instead of Firehose for loading block pointers by numbers.
     /// This is used in composable subgraphs. Firehose can be slow for loading block pointers by numbers.
     pub force_rpc_for_block_ptrs: bool,
+    /// If set, idempotent JSON-RPC requests such as `eth_call` and
+    /// `eth_getBlockByNumber` that a provider has not answered within this
+    /// time are also sent to another provider for the same chain, and the
+    /// first response is used.
+    ///
+    /// Set by the environment variable `GRAPH_ETHEREUM_HEDGE_DELAY_MS`
+    /// (expressed in milliseconds). Hedging is off by default.
+    pub hedge_delay: Option<Duration>,
 }
 
 // This does not print any values avoid accidentally leaking any sensitive env vars
@@ -146,6 +154,10 @@ impl From<Inner> for EnvVars {
                 .map(str::to_string)
                 .collect(),
             force_rpc_for_block_ptrs: x.force_rpc_for_block_ptrs.0,
+            hedge_delay: x
+                .hedge_delay_in_ms
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis),
         }
     }
 }
@@ -199,4 +211,6 @@ struct Inner {
     eth_call_no_gas: String,
     #[envconfig(from = "GRAPH_ETHEREUM_FORCE_RPC_FOR_BLOCK_PTRS", default = "true")]
     force_rpc_for_block_ptrs: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_ETHEREUM_HEDGE_DELAY_MS")]
+    hedge_delay_in_ms: Option<u64>,
 }
@@ -81,6 +81,7 @@ pub struct EthereumAdapter {
     metrics: Arc<ProviderEthRpcMetrics>,
     supports_eip_1898: bool,
     call_only: bool,
+    top_level_calls: bool,
     supports_block_receipts: Arc<RwLock<Option<bool>>>,
 }
 
@@ -93,6 +94,7 @@ impl CheapClone for EthereumAdapter {
             metrics: self.metrics.cheap_clone(),
             supports_eip_1898: self.supports_eip_1898,
             call_only: self.call_only,
+            top_level_calls: self.top_level_calls,
             supports_block_receipts: self.supports_block_receipts.cheap_clone(),
         }
     }
@@ -110,6 +112,7 @@ impl EthereumAdapter {
         provider_metrics: Arc<ProviderEthRpcMetrics>,
         supports_eip_1898: bool,
         call_only: bool,
+        top_level_calls: bool,
     ) -> Self {
         let web3 = Arc::new(Web3::new(transport));
 
@@ -129,6 +132,7 @@ impl EthereumAdapter {
             metrics: provider_metrics,
             supports_eip_1898: supports_eip_1898 && !is_ganache,
             call_only,
+            top_level_calls,
             supports_block_receipts: Arc::new(RwLock::new(None)),
         }
     }
@@ -395,6 +399,109 @@ impl EthereumAdapter {
         .flatten()
     }
 
+    /// Build the calls in block `block_number` from the transactions in
+    /// that block, for chains like Filecoin's FEVM whose nodes do not
+    /// support `trace_filter`. This only finds the top-level call of each
+    /// transaction; calls that contracts make to each other can only be
+    /// seen with traces. If `block_hash` is given, the block must have
+    /// that hash.
+    async fn top_level_calls_in_block(
+        self,
+        logger: Logger,
+        block_number: BlockNumber,
+        block_hash: Option<H256>,
+    ) -> Result<Vec<EthereumCall>, Error> {
+        let web3 = self.web3.cheap_clone();
+        let retry_log_message = format!(
+            "eth_getBlockByNumber RPC call for block number {}",
+            block_number
+        );
+        let res = retry(retry_log_message, &logger)
+            .redact_log_urls(true)
+            .when(|res| !res.is_ok() && !detect_null_block(res))
+            .limit(ENV_VARS.request_retries)
+            .timeout_secs(ENV_VARS.json_rpc_timeout.as_secs())
+            .run(move || {
+                let web3 = web3.cheap_clone();
+                async move {
+                    web3.eth()
+                        .block_with_txs(BlockId::Number(block_number.into()))
+                        .await
+                        .map_err(Error::from)
+                }
+            })
+            .await
+            .map_err(move |e| {
+                e.into_inner().unwrap_or_else(move || {
+                    anyhow!(
+                        "Ethereum node took too long to return block {}",
+                        block_number
+                    )
+                })
+            });
+
+        // A null round has no transactions and therefore no calls
+        if detect_null_block(&res) {
+            return Ok(vec![]);
+        }
+        let block = match res? {
+            Some(block) => block,
+            None => bail!("Ethereum node could not find block {}", block_number),
+        };
+        if let Some(block_hash) = block_hash {
+            if block.hash != Some(block_hash) {
+                bail!(
+                    "Ethereum node returned an unexpected block: number = `{}`, hash = `{}`",
+                    block_number,
+                    block_hash,
+                );
+            }
+        }
+
+        let block = self.load_full_block(&logger, block).await?;
+        let receipts: HashMap<H256, &TransactionReceipt> = block
+            .transaction_receipts
+            .iter()
+            .map(|receipt| (receipt.transaction_hash, receipt.as_ref()))
+            .collect();
+
+        block
+            .block
+            .transactions
+            .iter()
+            .filter_map(|tx| match receipts.get(&tx.hash) {
+                Some(receipt) => {
+                    EthereumCall::try_from_transaction(&block.block, tx, receipt).map(Ok)
+                }
+                None => Some(Err(anyhow!(
+                    "missing receipt for transaction {} in block {}",
+                    tx.hash,
+                    block_number
+                ))),
+            })
+            .collect()
+    }
+
+    fn top_level_call_stream(
+        self,
+        logger: &Logger,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> impl Stream<Item = EthereumCall, Error = Error> + Send {
+        let eth = self;
+        let logger = logger.clone();
+        stream::iter_ok::<_, Error>(from..=to)
+            .map(move |number| {
+                eth.clone()
+                    .top_level_calls_in_block(logger.cheap_clone(), number, None)
+                    .boxed()
+                    .compat()
+            })
+            .buffered(ENV_VARS.block_batch_size)
+            .map(stream::iter_ok)
+            .flatten()
+    }
+
     fn log_stream(
         &self,
         logger: Logger,
@@ -920,6 +1027,9 @@ impl EthereumAdapter {
         Ok(canonical_block == block_ptr)
     }
 
+    /// Get the logs matching `log_filter` in the blocks `from..=to`. If
+    /// `log_cache` is given, all blocks in the range must be final; logs
+    /// are then looked up in and added to that cache
     pub(crate) fn logs_in_block_range(
         &self,
         logger: &Logger,
@@ -927,18 +1037,45 @@ impl EthereumAdapter {
         from: BlockNumber,
         to: BlockNumber,
         log_filter: EthereumLogFilter,
+        log_cache: Option<Arc<dyn ChainStore>>,
     ) -> DynTryFuture<'static, Vec<Log>, Error> {
         let eth: Self = self.cheap_clone();
         let logger = logger.clone();
 
         futures03::stream::iter(log_filter.eth_get_logs_filters().map(move |filter| {
-            eth.cheap_clone().log_stream(
-                logger.cheap_clone(),
-                subgraph_metrics.cheap_clone(),
-                from,
-                to,
-                filter,
-            )
+            let eth = eth.cheap_clone();
+            let logger = logger.cheap_clone();
+            let subgraph_metrics = subgraph_metrics.cheap_clone();
+            let log_cache = log_cache.cheap_clone();
+            async move {
+                let Some(log_cache) = log_cache else {
+                    return eth
+                        .log_stream(logger, subgraph_metrics, from, to, filter)
+                        .await;
+                };
+
+                let filter_id = filter.cache_id();
+                match log_cache.cached_logs(&filter_id, from, to).await {
+                    Ok(Some(logs)) => {
+                        trace!(logger, "Using cached logs";
+                               "filter" => %filter, "from" => from, "to" => to,
+                               "logs" => logs.len());
+                        return Ok(logs);
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        warn!(logger, "Failed to read log cache: {}", e);
+                    }
+                }
+
+                let logs = eth
+                    .log_stream(logger.cheap_clone(), subgraph_metrics, from, to, filter)
+                    .await?;
+                if let Err(e) = log_cache.set_cached_logs(&filter_id, from, to, &logs).await {
+                    warn!(logger, "Failed to update log cache: {}", e);
+                }
+                Ok(logs)
+            }
         }))
         // Real limits on the number of parallel requests are imposed within the adapter.
         .buffered(ENV_VARS.block_ingestor_max_concurrent_json_rpc_calls)
@@ -975,6 +1112,13 @@ impl EthereumAdapter {
             return Box::new(stream::empty());
         }
 
+        if self.top_level_calls {
+            return Box::new(
+                eth.top_level_call_stream(logger, from, to)
+                    .filter(move |call| call_filter.matches(call)),
+            );
+        }
+
         // if wildcard_signatures is on, we can't filter by topic so we need to get all the traces.
         if addresses.len() > 100 || !wildcard_signatures.is_empty() {
             // If the address list is large, request all traces, this avoids generating huge
@@ -1073,6 +1217,13 @@ impl EthereumAdapter {
         block_hash: H256,
     ) -> Result<Vec<EthereumCall>, Error> {
         let eth = self.clone();
+
+        if self.top_level_calls {
+            return eth
+                .top_level_calls_in_block(logger.clone(), block_number, Some(block_hash))
+                .await;
+        }
+
         let addresses = Vec::new();
         let traces = eth
             .trace_stream(
@@ -1847,6 +1998,7 @@ pub(crate) async fn blocks_with_triggers(
 
     // Scan for Logs
     if !filter.log.is_empty() {
+        let log_cache = final_log_cache(&logger, &chain_store, to).await;
         let logs_future = get_logs_and_transactions(
             &eth,
             &logger,
@@ -1855,6 +2007,7 @@ pub(crate) async fn blocks_with_triggers(
             to,
             filter.log.clone(),
             &unified_api_version,
+            log_cache,
         )
         .boxed();
         trigger_futs.push(logs_future)
@@ -2536,7 +2689,32 @@ fn resolve_transaction_receipt(
     }
 }
 
+/// Return the chain store as a cache for logs and receipts if the block
+/// `to` is far enough behind the chain head that it can not be reorged
+/// anymore. Logs of blocks that might still change must never be cached
+async fn final_log_cache(
+    logger: &Logger,
+    chain_store: &Arc<dyn ChainStore>,
+    to: BlockNumber,
+) -> Option<Arc<dyn ChainStore>> {
+    match chain_store.chain_head_ptr().await {
+        Ok(Some(head)) if to <= head.number - graph::env::ENV_VARS.reorg_threshold => {
+            Some(chain_store.cheap_clone())
+        }
+        Ok(_) => None,
+        Err(e) => {
+            warn!(
+                logger,
+                "Failed to get chain head, not using the log cache: {}", e
+            );
+            None
+        }
+    }
+}
+
 /// Retrieves logs and the associated transaction receipts, if required by the [`EthereumLogFilter`].
+/// If `log_cache` is given, all blocks in `from..=to` must be final, and
+/// logs and receipts are looked up in and added to the cache
 async fn get_logs_and_transactions(
     adapter: &Arc<EthereumAdapter>,
     logger: &Logger,
@@ -2545,6 +2723,7 @@ async fn get_logs_and_transactions(
     to: BlockNumber,
     log_filter: EthereumLogFilter,
     unified_api_version: &UnifiedMappingApiVersion,
+    log_cache: Option<Arc<dyn ChainStore>>,
 ) -> Result<Vec<EthereumTrigger>, anyhow::Error> {
     // Obtain logs externally
     let logs = adapter
@@ -2554,6 +2733,7 @@ async fn get_logs_and_transactions(
             from,
             to,
             log_filter.clone(),
+            log_cache.cheap_clone(),
         )
         .await?;
 
@@ -2592,6 +2772,7 @@ async fn get_logs_and_transactions(
         &transaction_hashes_by_block,
         subgraph_metrics,
         logger.cheap_clone(),
+        log_cache,
     )
     .await?;
 
@@ -2614,6 +2795,7 @@ async fn get_transaction_receipts_for_transaction_hashes(
     transaction_hashes_by_block: &HashMap<H256, HashSet<H256>>,
     subgraph_metrics: Arc<SubgraphEthRpcMetrics>,
     logger: Logger,
+    receipt_cache: Option<Arc<dyn ChainStore>>,
 ) -> Result<HashMap<H256, Arc<TransactionReceipt>>, anyhow::Error> {
     use std::collections::hash_map::Entry::Vacant;
 
@@ -2624,6 +2806,31 @@ async fn get_transaction_receipts_for_transaction_hashes(
         return Ok(receipts_by_hash);
     }
 
+    // Use the receipts we already have for transactions in the blocks we
+    // expect them in
+    if let Some(receipt_cache) = &receipt_cache {
+        let hashes: Vec<H256> = transaction_hashes_by_block
+            .values()
+            .flatten()
+            .cloned()
+            .collect();
+        match receipt_cache.cached_receipts(&hashes).await {
+            Ok(receipts) => {
+                for receipt in receipts {
+                    let expected = receipt.block_hash.map_or(false, |block_hash| {
+                        transaction_hashes_by_block
+                            .get(&block_hash)
+                            .map_or(false, |hashes| hashes.contains(&receipt.transaction_hash))
+                    });
+                    if expected {
+                        receipts_by_hash.insert(receipt.transaction_hash, Arc::new(receipt));
+                    }
+                }
+            }
+            Err(e) => warn!(logger, "Failed to read receipt cache: {}", e),
+        }
+    }
+
     // Keep a record of all unique transaction hashes for which we'll request receipts. We will
     // later use this to check if we have collected the receipts from all required transactions.
     let mut unique_transaction_hashes: HashSet<&H256> = HashSet::new();
@@ -2634,6 +2841,9 @@ async fn get_transaction_receipts_for_transaction_hashes(
     let web3 = Arc::clone(&adapter.web3);
     for (block_hash, transaction_hashes) in transaction_hashes_by_block {
         for transaction_hash in transaction_hashes {
+            if receipts_by_hash.contains_key(transaction_hash) {
+                continue;
+            }
             unique_transaction_hashes.insert(transaction_hash);
             let receipt_future = fetch_transaction_receipt_with_retry(
                 web3.cheap_clone(),
@@ -2645,6 +2855,11 @@ async fn get_transaction_receipts_for_transaction_hashes(
         }
     }
 
+    if unique_transaction_hashes.is_empty() {
+        // All receipts came from the cache
+        return Ok(receipts_by_hash);
+    }
+
     // Execute futures while monitoring elapsed time
     let start = Instant::now();
     let receipts: Vec<_> = match receipt_futures.try_collect().await {
@@ -2668,7 +2883,7 @@ async fn get_transaction_receipts_for_transaction_hashes(
     };
 
     // Build a map between transaction hashes and their receipts
-    for receipt in receipts.into_iter() {
+    for receipt in receipts.iter().cloned() {
         if !unique_transaction_hashes.remove(&receipt.transaction_hash) {
             bail!("Received a receipt for a different transaction hash")
         }
@@ -2685,6 +2900,12 @@ async fn get_transaction_receipts_for_transaction_hashes(
         "Didn't receive all necessary transaction receipts"
     );
 
+    if let Some(receipt_cache) = receipt_cache {
+        if let Err(e) = receipt_cache.set_cached_receipts(&receipts).await {
+            warn!(logger, "Failed to update receipt cache: {}", e);
+        }
+    }
+
     Ok(receipts_by_hash)
 }
 
@@ -2701,7 +2922,9 @@ mod tests {
     use graph::prelude::ethabi::ethereum_types::U64;
     use graph::prelude::tokio::{self};
     use graph::prelude::web3::transports::test::TestTransport;
-    use graph::prelude::web3::types::{Address, Block, Bytes, H256};
+    use graph::prelude::web3::types::{
+        Address, Block, Bytes, Transaction, TransactionReceipt, H256,
+    };
     use graph::prelude::web3::Web3;
     use graph::prelude::EthereumCall;
     use jsonrpc_core::serde_json::{self, Value};
@@ -2942,6 +3165,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn top_level_calls_from_transactions() {
+        let block = Block {
+            hash: Some(hash(2)),
+            number: Some(U64::from(2)),
+            ..Default::default()
+        };
+        let tx = Transaction {
+            hash: hash(3),
+            from: Some(address(1)),
+            to: Some(address(4)),
+            input: bytes(vec![1; 36]),
+            transaction_index: Some(U64::from(0)),
+            ..Default::default()
+        };
+        let receipt = TransactionReceipt {
+            transaction_hash: hash(3),
+            status: Some(U64::from(1)),
+            ..Default::default()
+        };
+
+        let call = EthereumCall::try_from_transaction(&block, &tx, &receipt)
+            .expect("successful transaction to a contract is a call");
+        assert_eq!(address(4), call.to);
+        assert_eq!(2, call.block_number);
+        assert_eq!(Some(hash(3)), call.transaction_hash);
+        assert!(call.output.0.is_empty());
+
+        let failed = TransactionReceipt {
+            status: Some(U64::from(0)),
+            ..receipt.clone()
+        };
+        assert_eq!(
+            None,
+            EthereumCall::try_from_transaction(&block, &tx, &failed),
+            "failed transactions are not calls"
+        );
+
+        let creation = Transaction {
+            to: None,
+            ..tx.clone()
+        };
+        assert_eq!(
+            None,
+            EthereumCall::try_from_transaction(&block, &creation, &receipt),
+            "contract creations are not calls"
+        );
+
+        let transfer = Transaction {
+            input: bytes(vec![]),
+            ..tx.clone()
+        };
+        assert_eq!(
+            None,
+            EthereumCall::try_from_transaction(&block, &transfer, &receipt),
+            "value transfers are not calls"
+        );
+    }
+
     fn address(id: u64) -> Address {
         Address::from_low_u64_be(id)
     }
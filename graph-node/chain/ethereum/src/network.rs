@@ -74,6 +74,21 @@ impl EthereumNetworkAdapter {
     pub fn current_error_count(&self) -> u64 {
         self.endpoint_metrics.get_count(&self.provider().into())
     }
+
+    /// Combined latency and error score of this adapter; lower is better
+    pub fn current_score(&self) -> f64 {
+        self.endpoint_metrics
+            .score(&self.provider().into())
+            .map(|score| score.score())
+            .unwrap_or(0.0)
+    }
+
+    /// Returns `false` while this adapter is taken out of rotation by the
+    /// circuit breaker
+    pub fn is_available(&self) -> bool {
+        self.endpoint_metrics.is_available(&self.provider().into())
+    }
+
     pub fn provider(&self) -> &str {
         self.adapter.provider()
     }
@@ -158,6 +173,35 @@ impl EthereumNetworkAdapters {
             .filter(|adapter| adapter.get_capacity() > AvailableCapacity::Unavailable)
     }
 
+    /// Check that some adapter for this network has the
+    /// `required_capabilities`, regardless of its current availability
+    pub fn check_capabilities(
+        &self,
+        required_capabilities: &NodeCapabilities,
+    ) -> Result<(), Error> {
+        if self
+            .manager
+            .providers_unchecked(&self.chain_id)
+            .any(|adapter| &adapter.capabilities >= required_capabilities)
+        {
+            return Ok(());
+        }
+
+        let hint = if required_capabilities.traces {
+            ". Call handlers and block handlers with a `call` filter need a provider \
+             with the `traces` feature, or with the `top_level_calls` feature if the \
+             chain does not support `trace_filter`"
+        } else {
+            ""
+        };
+        bail!(
+            "no provider for network {} has the capabilities `{}` that the subgraph requires{}",
+            self.chain_id,
+            required_capabilities,
+            hint
+        )
+    }
+
     /// returns all the available adapters that meet the required capabilities
     /// if no adapters are available at the time or none that meet the capabilities then
     /// an empty iterator is returned.
@@ -198,6 +242,18 @@ impl EthereumNetworkAdapters {
     ) -> Result<Arc<EthereumAdapter>, Error> {
         let retest_rng: f64 = (&mut rand::thread_rng()).gen();
 
+        // Skip adapters whose circuit breaker is open, unless that would
+        // leave us with nothing at all; a bad adapter is still better than
+        // no adapter
+        let (available, unavailable): (Vec<_>, Vec<_>) = input
+            .into_iter()
+            .partition(|adapter| adapter.is_available());
+        let input = if available.is_empty() {
+            unavailable
+        } else {
+            available
+        };
+
         let cheapest = input
             .into_iter()
             .choose_multiple(&mut rand::thread_rng(), 3);
@@ -214,8 +270,8 @@ impl EthereumNetworkAdapters {
             // So we essentially assume if it had available capacity when calling
             // `all_cheapest_with` then it prolly maintains that state and so we
             // just select whichever adapter is working better according to
-            // the number of errors.
-            cheapest.min_by_key(|adapter| adapter.current_error_count())
+            // its recent latency and error rate.
+            cheapest.min_by(|a, b| a.current_score().total_cmp(&b.current_score()))
         }
         .map(|adapter| adapter.adapter.clone())
         .ok_or(anyhow!(
@@ -385,6 +441,68 @@ mod tests {
         assert_eq!(true, &full_traces >= &full_traces);
     }
 
+    #[tokio::test]
+    async fn check_capabilities_rejects_missing_traces() {
+        let metrics = Arc::new(EndpointMetrics::mock());
+        let logger = graph::log::logger(true);
+        let mock_registry = Arc::new(MetricsRegistry::mock());
+        let transport = Transport::new_rpc(
+            Url::parse("http://127.0.0.1").unwrap(),
+            HeaderMap::new(),
+            metrics.clone(),
+            "",
+        );
+        let provider_metrics = Arc::new(ProviderEthRpcMetrics::new(mock_registry.clone()));
+
+        let eth_adapter = Arc::new(
+            EthereumAdapter::new(
+                logger.clone(),
+                String::new(),
+                transport.clone(),
+                provider_metrics.clone(),
+                true,
+                false,
+                false,
+            )
+            .await,
+        );
+
+        let adapters = EthereumNetworkAdapters::for_testing(
+            vec![EthereumNetworkAdapter::new(
+                metrics.cheap_clone(),
+                NodeCapabilities {
+                    archive: true,
+                    traces: false,
+                },
+                eth_adapter.clone(),
+                SubgraphLimit::Unlimited,
+            )],
+            vec![],
+        )
+        .await;
+
+        adapters
+            .check_capabilities(&NodeCapabilities {
+                archive: true,
+                traces: false,
+            })
+            .unwrap();
+
+        let err = adapters
+            .check_capabilities(&NodeCapabilities {
+                archive: false,
+                traces: true,
+            })
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("no provider for network testing has the capabilities"),
+            "{}",
+            err
+        );
+        assert!(err.contains("`top_level_calls` feature"), "{}", err);
+    }
+
     #[tokio::test]
     async fn adapter_selector_selects_eth_call() {
         let metrics = Arc::new(EndpointMetrics::mock());
@@ -406,6 +524,7 @@ mod tests {
                 provider_metrics.clone(),
                 true,
                 true,
+                false,
             )
             .await,
         );
@@ -418,6 +537,7 @@ mod tests {
                 provider_metrics.clone(),
                 true,
                 false,
+                false,
             )
             .await,
         );
@@ -512,6 +632,7 @@ mod tests {
                 provider_metrics.clone(),
                 true,
                 true,
+                false,
             )
             .await,
         );
@@ -524,6 +645,7 @@ mod tests {
                 provider_metrics.clone(),
                 true,
                 false,
+                false,
             )
             .await,
         );
@@ -583,6 +705,7 @@ mod tests {
                 provider_metrics.clone(),
                 true,
                 true,
+                false,
             )
             .await,
         );
@@ -595,6 +718,7 @@ mod tests {
                 provider_metrics.clone(),
                 true,
                 false,
+                false,
             )
             .await,
         );
@@ -650,6 +774,7 @@ mod tests {
                 provider_metrics.clone(),
                 true,
                 false,
+                false,
             )
             .await,
         );
@@ -921,6 +1046,84 @@ mod tests {
         assert!(res.is_err(), "{:?}", res);
     }
 
+    #[tokio::test]
+    async fn eth_adapter_selection_uses_scores_and_circuit_breaker() {
+        let logger = Logger::root(Discard, o!());
+        let broken_provider = "broken-provider";
+        let slow_provider = "slow-provider";
+        let fast_provider = "fast-provider";
+
+        let mock_registry = Arc::new(MetricsRegistry::mock());
+        let metrics = Arc::new(EndpointMetrics::new(
+            logger,
+            &[broken_provider, slow_provider, fast_provider],
+            mock_registry.clone(),
+        ));
+        let chain_id: Word = "chain_id".into();
+        let logger = graph::log::logger(true);
+        let provider_metrics = Arc::new(ProviderEthRpcMetrics::new(mock_registry.clone()));
+
+        // Fail the broken provider often enough to open its circuit breaker
+        for _ in 0..graph::env::ENV_VARS.provider_circuit_breaker_failures {
+            metrics.report_for_test(&ProviderName::from(broken_provider), false);
+        }
+        metrics.latency(
+            &ProviderName::from(slow_provider),
+            std::time::Duration::from_millis(500),
+        );
+        metrics.latency(
+            &ProviderName::from(fast_provider),
+            std::time::Duration::from_millis(50),
+        );
+
+        let mut adapters = vec![];
+        for provider in [broken_provider, slow_provider, fast_provider] {
+            adapters.push(EthereumNetworkAdapter {
+                endpoint_metrics: metrics.clone(),
+                capabilities: NodeCapabilities {
+                    archive: true,
+                    traces: false,
+                },
+                adapter: fake_adapter(&logger, provider, &provider_metrics, &metrics, false).await,
+                limit: SubgraphLimit::Unlimited,
+            });
+        }
+        let manager = ProviderManager::<EthereumNetworkAdapter>::new(
+            logger,
+            vec![(chain_id.clone(), adapters)].into_iter(),
+            ProviderCheckStrategy::MarkAsValid,
+        );
+
+        let capabilities = NodeCapabilities {
+            archive: true,
+            traces: false,
+        };
+
+        let no_retest_adapters =
+            EthereumNetworkAdapters::new(chain_id.clone(), manager.clone(), vec![], Some(0f64));
+        assert_eq!(
+            no_retest_adapters
+                .cheapest_with(&capabilities)
+                .await
+                .unwrap()
+                .provider(),
+            fast_provider
+        );
+
+        // Retests would pick the adapter with the most errors, but the
+        // circuit breaker keeps the broken provider out of rotation
+        let always_retest_adapters =
+            EthereumNetworkAdapters::new(chain_id, manager, vec![], Some(1f64));
+        assert_ne!(
+            always_retest_adapters
+                .cheapest_with(&capabilities)
+                .await
+                .unwrap()
+                .provider(),
+            broken_provider
+        );
+    }
+
     async fn fake_adapter(
         logger: &Logger,
         provider: &str,
@@ -943,6 +1146,7 @@ mod tests {
                 provider_metrics.clone(),
                 true,
                 call_only,
+                false,
             )
             .await,
         )
@@ -3,15 +3,16 @@ use std::sync::Arc;
 use graph::{
     blockchain::{block_stream::BlockWithTriggers, BlockPtr, Trigger},
     prelude::{
-        web3::types::{Address, Bytes, Log, H160, H256, U64},
-        EthereumCall, LightEthereumBlock,
+        web3::types::{Address, Bytes, Log, Transaction, H160, H256, U256, U64},
+        BigInt, EthereumCall, LightEthereumBlock,
     },
     slog::{self, o, Logger},
 };
+use graph_runtime_wasm::rust_abi::{Decoder, FromRustBytes, ToRustTrigger};
 
 use crate::{
     chain::BlockFinality,
-    trigger::{EthereumBlockTriggerType, EthereumTrigger, LogRef},
+    trigger::{EthereumBlockTriggerType, EthereumTrigger, LogRef, MappingTrigger},
 };
 
 #[test]
@@ -212,3 +213,109 @@ fn test_trigger_dedup() {
 
     assert_eq!(block_with_triggers.trigger_data, expected);
 }
+
+#[test]
+fn rust_log_trigger_encoding() {
+    let block = Arc::new(LightEthereumBlock {
+        hash: Some(H256::from_low_u64_be(1)),
+        parent_hash: H256::from_low_u64_be(2),
+        number: Some(U64::from(3)),
+        timestamp: U256::from(4),
+        author: H160::from_low_u64_be(5),
+        gas_used: U256::from(6),
+        gas_limit: U256::from(7),
+        base_fee_per_gas: Some(U256::from(8)),
+        ..Default::default()
+    });
+    let transaction = Arc::new(Transaction {
+        hash: H256::from_low_u64_be(9),
+        transaction_index: Some(U64::from(10)),
+        from: Some(H160::from_low_u64_be(11)),
+        to: None,
+        value: U256::from(12),
+        gas: U256::from(13),
+        gas_price: None,
+        nonce: U256::from(14),
+        input: Bytes(vec![0xaa]),
+        ..Default::default()
+    });
+    let log = Arc::new(Log {
+        address: H160::from_low_u64_be(15),
+        topics: vec![H256::from_low_u64_be(16), H256::from_low_u64_be(17)],
+        data: Bytes(vec![0xbb, 0xcc]),
+        block_hash: block.hash,
+        block_number: block.number,
+        transaction_hash: Some(transaction.hash),
+        transaction_index: transaction.transaction_index,
+        log_index: Some(U256::from(18)),
+        transaction_log_index: None,
+        log_type: None,
+        removed: Some(false),
+    });
+    let trigger = MappingTrigger::Log {
+        block,
+        transaction,
+        log,
+        params: vec![],
+        receipt: None,
+        calls: vec![],
+    };
+
+    let bytes = trigger.to_rust_trigger().unwrap();
+    let mut dec = Decoder::new(&bytes);
+    let big_int = |dec: &mut Decoder<'_>| BigInt::decode(dec).unwrap();
+
+    // The block
+    assert_eq!(H256::from_low_u64_be(1).as_bytes(), dec.bytes().unwrap());
+    assert_eq!(H256::from_low_u64_be(2).as_bytes(), dec.bytes().unwrap());
+    assert_eq!(3, dec.u64().unwrap());
+    assert_eq!(BigInt::from(4), big_int(&mut dec));
+    assert_eq!(H160::from_low_u64_be(5).as_bytes(), dec.bytes().unwrap());
+    assert_eq!(BigInt::from(6), big_int(&mut dec));
+    assert_eq!(BigInt::from(7), big_int(&mut dec));
+    assert_eq!(
+        Some(BigInt::from(8)),
+        Option::<BigInt>::decode(&mut dec).unwrap()
+    );
+
+    // The transaction
+    assert_eq!(H256::from_low_u64_be(9).as_bytes(), dec.bytes().unwrap());
+    assert_eq!(10, dec.u64().unwrap());
+    assert_eq!(H160::from_low_u64_be(11).as_bytes(), dec.bytes().unwrap());
+    assert_eq!(0, dec.u8().unwrap(), "`to` is missing");
+    assert_eq!(BigInt::from(12), big_int(&mut dec));
+    assert_eq!(BigInt::from(13), big_int(&mut dec));
+    assert_eq!(BigInt::from(0), big_int(&mut dec), "no gas price");
+    assert_eq!(BigInt::from(14), big_int(&mut dec));
+    assert_eq!(&[0xaa], dec.bytes().unwrap());
+
+    // The log
+    assert_eq!(H160::from_low_u64_be(15).as_bytes(), dec.bytes().unwrap());
+    assert_eq!(BigInt::from(18), big_int(&mut dec));
+    assert_eq!(2, dec.len().unwrap());
+    assert_eq!(H256::from_low_u64_be(16).as_bytes(), dec.bytes().unwrap());
+    assert_eq!(H256::from_low_u64_be(17).as_bytes(), dec.bytes().unwrap());
+    assert_eq!(&[0xbb, 0xcc], dec.bytes().unwrap());
+    dec.finish().unwrap();
+}
+
+#[test]
+fn rust_block_trigger_encoding() {
+    let block = Arc::new(LightEthereumBlock {
+        hash: Some(H256::from_low_u64_be(1)),
+        number: Some(U64::from(2)),
+        ..Default::default()
+    });
+    let bytes = MappingTrigger::Block { block }.to_rust_trigger().unwrap();
+
+    let mut dec = Decoder::new(&bytes);
+    assert_eq!(H256::from_low_u64_be(1).as_bytes(), dec.bytes().unwrap());
+    assert_eq!(H256::zero().as_bytes(), dec.bytes().unwrap());
+    assert_eq!(2, dec.u64().unwrap());
+    for _ in 0..4 {
+        // timestamp, author, gas used and gas limit
+        dec.bytes().unwrap();
+    }
+    assert_eq!(None, Option::<BigInt>::decode(&mut dec).unwrap());
+    dec.finish().unwrap();
+}
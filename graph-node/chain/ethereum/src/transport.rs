@@ -3,12 +3,159 @@ use graph::endpoint::{EndpointMetrics, RequestLabels};
 use jsonrpc_core::types::Call;
 use jsonrpc_core::Value;
 
+use web3::error::TransportError;
 use web3::transports::{http, ipc, ws};
 use web3::RequestId;
 
+use graph::futures03::future::{select, Either};
+use graph::prelude::tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use graph::prelude::*;
 use graph::url::Url;
+use std::collections::HashSet;
 use std::future::Future;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// JSON-RPC methods that do not change any state and can therefore safely
+/// be sent to more than one provider
+const HEDGED_METHODS: &[&str] = &[
+    "eth_getBlockByNumber",
+    "eth_getBlockByHash",
+    "eth_getBlockReceipts",
+    "eth_getTransactionReceipt",
+    "eth_call",
+    "eth_getBalance",
+    "eth_getCode",
+];
+
+/// A second provider that idempotent requests are also sent to if the
+/// primary provider has not responded within `delay`
+#[derive(Clone, Debug)]
+pub struct Hedge {
+    client: http::Http,
+    provider: ProviderName,
+    limiter: Option<Arc<RequestLimiter>>,
+    delay: Duration,
+}
+
+/// Limits on the requests that are sent to a provider
+#[derive(Clone, Debug, Default)]
+pub struct RequestLimits {
+    /// Space requests out so that at most this many are sent per second
+    pub max_requests_per_second: Option<u32>,
+    /// Never have more than this many requests in flight at once
+    pub max_concurrent: Option<usize>,
+    /// If not empty, only these JSON-RPC methods may be sent
+    pub allow_methods: Vec<String>,
+    /// These JSON-RPC methods are never sent
+    pub deny_methods: Vec<String>,
+}
+
+impl RequestLimits {
+    /// `true` if these limits do not restrict requests in any way
+    pub fn is_empty(&self) -> bool {
+        self.max_requests_per_second.is_none()
+            && self.max_concurrent.is_none()
+            && self.allow_methods.is_empty()
+            && self.deny_methods.is_empty()
+    }
+}
+
+/// Enforces the `RequestLimits` of a provider
+#[derive(Debug)]
+pub struct RequestLimiter {
+    /// The minimum time between two requests, and the earliest time at
+    /// which the next request may be sent
+    rate: Option<(Duration, Mutex<Instant>)>,
+    concurrency: Option<Arc<Semaphore>>,
+    allow_methods: HashSet<String>,
+    deny_methods: HashSet<String>,
+}
+
+impl RequestLimiter {
+    fn new(limits: RequestLimits) -> Self {
+        let rate = limits
+            .max_requests_per_second
+            .filter(|rps| *rps > 0)
+            .map(|rps| (Duration::from_secs(1) / rps, Mutex::new(Instant::now())));
+        let concurrency = limits
+            .max_concurrent
+            .filter(|n| *n > 0)
+            .map(|n| Arc::new(Semaphore::new(n)));
+
+        Self {
+            rate,
+            concurrency,
+            allow_methods: limits.allow_methods.into_iter().collect(),
+            deny_methods: limits.deny_methods.into_iter().collect(),
+        }
+    }
+
+    fn allows(&self, method: &str) -> bool {
+        (self.allow_methods.is_empty() || self.allow_methods.contains(method))
+            && !self.deny_methods.contains(method)
+    }
+
+    /// Reserve the next slots for sending `requests` requests and return
+    /// how long the caller needs to wait from `now` before it may send them
+    fn reserve(&self, requests: u32, now: Instant) -> Duration {
+        match &self.rate {
+            Some((interval, next)) => {
+                let mut next = next.lock().unwrap();
+                let slot = (*next).max(now);
+                *next = slot + *interval * requests;
+                slot - now
+            }
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Wait until `requests` requests may be sent to `provider`. The
+    /// returned permit, if any, needs to be held until the requests are done
+    async fn acquire(
+        &self,
+        requests: u32,
+        metrics: &EndpointMetrics,
+        provider: &ProviderName,
+    ) -> Option<OwnedSemaphorePermit> {
+        // Make sure the queue gauge is decremented even if the caller
+        // stops waiting for us
+        struct Queued<'a>(&'a EndpointMetrics, &'a ProviderName);
+
+        impl Drop for Queued<'_> {
+            fn drop(&mut self) {
+                self.0.request_dequeued(self.1);
+            }
+        }
+
+        metrics.request_queued(provider);
+        let _queued = Queued(metrics, provider);
+
+        let wait = self.reserve(requests, Instant::now());
+        if !wait.is_zero() {
+            metrics.throttled(provider, "rate");
+            tokio::time::sleep(wait).await;
+        }
+
+        match &self.concurrency {
+            Some(semaphore) => match semaphore.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    metrics.throttled(provider, "concurrency");
+                    semaphore.clone().acquire_owned().await.ok()
+                }
+            },
+            None => None,
+        }
+    }
+}
+
+fn method_denied(provider: &ProviderName, method: &str) -> web3::error::Error {
+    web3::error::Error::Transport(TransportError::Message(format!(
+        "method `{}` is not allowed for provider {}",
+        method, provider
+    )))
+}
 
 /// Abstraction over the different web3 transports.
 #[derive(Clone, Debug)]
@@ -17,6 +164,8 @@ pub enum Transport {
         client: http::Http,
         metrics: Arc<EndpointMetrics>,
         provider: ProviderName,
+        limiter: Option<Arc<RequestLimiter>>,
+        hedge: Option<Hedge>,
     },
     IPC(ipc::Ipc),
     WS(ws::WebSocket),
@@ -60,7 +209,168 @@ impl Transport {
             client: http::Http::with_client(client, rpc),
             metrics,
             provider: provider.as_ref().into(),
+            limiter: None,
+            hedge: None,
+        }
+    }
+
+    /// Enforce `limits` on the requests sent through this transport. Only
+    /// JSON-RPC over HTTP transports can be limited; any other transport
+    /// is returned unchanged
+    pub fn with_limits(self, limits: RequestLimits) -> Self {
+        match self {
+            Transport::RPC {
+                client,
+                metrics,
+                provider,
+                limiter: _,
+                hedge,
+            } => Transport::RPC {
+                client,
+                metrics,
+                provider,
+                limiter: (!limits.is_empty()).then(|| Arc::new(RequestLimiter::new(limits))),
+                hedge,
+            },
+            transport => transport,
+        }
+    }
+
+    /// Send idempotent requests that `self` has not answered within
+    /// `delay` also to `other` and use whichever response arrives first.
+    /// Only JSON-RPC over HTTP transports can be hedged; for any other
+    /// combination, `self` is returned unchanged
+    pub fn with_hedge(self, other: &Transport, delay: Duration) -> Self {
+        match (self, other) {
+            (
+                Transport::RPC {
+                    client,
+                    metrics,
+                    provider,
+                    limiter,
+                    hedge: _,
+                },
+                Transport::RPC {
+                    client: other_client,
+                    provider: other_provider,
+                    limiter: other_limiter,
+                    ..
+                },
+            ) => Transport::RPC {
+                client,
+                metrics,
+                provider,
+                limiter,
+                hedge: Some(Hedge {
+                    client: other_client.clone(),
+                    provider: other_provider.clone(),
+                    limiter: other_limiter.clone(),
+                    delay,
+                }),
+            },
+            (transport, _) => transport,
+        }
+    }
+}
+
+/// Send `request` through `client` once `limiter` allows it and record its
+/// outcome and latency
+async fn send_measured(
+    client: http::Http,
+    limiter: Option<Arc<RequestLimiter>>,
+    id: RequestId,
+    request: Call,
+    metrics: Arc<EndpointMetrics>,
+    labels: RequestLabels,
+) -> Result<Value, web3::error::Error> {
+    let _permit = match &limiter {
+        Some(limiter) => limiter.acquire(1, &metrics, &labels.provider).await,
+        None => None,
+    };
+
+    let start = Instant::now();
+    let out = client.send(id, request).await;
+    metrics.latency(&labels.provider, start.elapsed());
+    match &out {
+        Err(e) if is_provider_failure(e) => {
+            if let web3::error::Error::Transport(TransportError::Code(429)) = e {
+                metrics.throttled(&labels.provider, "429");
+            }
+            metrics.failure(&labels)
+        }
+        _ => metrics.success(&labels),
+    }
+
+    out
+}
+
+/// Whether `err` means that the provider did not answer properly. JSON-RPC
+/// errors like the ones for reverted `eth_call`s are answers and must not
+/// count against the provider
+fn is_provider_failure(err: &web3::error::Error) -> bool {
+    !matches!(err, web3::error::Error::Rpc(_))
+}
+
+/// Whether `out` is an answer from the provider, either a result or a
+/// JSON-RPC error
+fn is_answer(out: &Result<Value, web3::error::Error>) -> bool {
+    out.as_ref()
+        .map_or_else(|e| !is_provider_failure(e), |_| true)
+}
+
+/// Send `request` to the primary provider, and if it has not responded
+/// after `hedge.delay`, also to the hedge provider. The first answer wins;
+/// a provider failure is only returned if both requests fail
+async fn send_hedged(
+    client: http::Http,
+    limiter: Option<Arc<RequestLimiter>>,
+    hedge: Hedge,
+    id: RequestId,
+    request: Call,
+    metrics: Arc<EndpointMetrics>,
+    labels: RequestLabels,
+) -> Result<Value, web3::error::Error> {
+    let start = Instant::now();
+    let mut primary = Box::pin(send_measured(
+        client,
+        limiter,
+        id,
+        request.clone(),
+        metrics.cheap_clone(),
+        labels.clone(),
+    ));
+
+    if let Ok(out) = tokio::time::timeout(hedge.delay, primary.as_mut()).await {
+        return out;
+    }
+    // Don't make a struggling hedge provider even busier
+    if !metrics.is_available(&hedge.provider) {
+        return primary.await;
+    }
+
+    let hedge_labels = RequestLabels {
+        provider: hedge.provider,
+        ..labels.clone()
+    };
+    let secondary = Box::pin(send_measured(
+        hedge.client,
+        hedge.limiter,
+        id,
+        request,
+        metrics.cheap_clone(),
+        hedge_labels,
+    ));
+
+    match select(primary, secondary).await {
+        Either::Left((out, _)) if is_answer(&out) => out,
+        Either::Left((_, secondary)) => secondary.await,
+        Either::Right((out, _)) if is_answer(&out) => {
+            // The primary request gets dropped without recording anything;
+            // make sure its latency reflects that it lost the race
+            metrics.latency(&labels.provider, start.elapsed());
+            out
         }
+        Either::Right((_, primary)) => primary.await,
     }
 }
 
@@ -69,11 +379,7 @@ impl web3::Transport for Transport {
 
     fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
         match self {
-            Transport::RPC {
-                client,
-                metrics: _,
-                provider: _,
-            } => client.prepare(method, params),
+            Transport::RPC { client, .. } => client.prepare(method, params),
             Transport::IPC(ipc) => ipc.prepare(method, params),
             Transport::WS(ws) => ws.prepare(method, params),
         }
@@ -85,30 +391,42 @@ impl web3::Transport for Transport {
                 client,
                 metrics,
                 provider,
+                limiter,
+                hedge,
             } => {
                 let metrics = metrics.cheap_clone();
                 let client = client.clone();
+                let limiter = limiter.clone();
                 let method = match request {
                     Call::MethodCall(ref m) => m.method.as_str(),
                     _ => "unknown",
                 };
 
+                if !limiter.as_ref().map_or(true, |l| l.allows(method)) {
+                    metrics.throttled(provider, "method");
+                    let err = method_denied(provider, method);
+                    return Box::pin(async move { Err::<Value, _>(err) });
+                }
+
+                // Only hedge to a provider that accepts the method
+                let hedge = hedge
+                    .as_ref()
+                    .filter(|_| HEDGED_METHODS.contains(&method))
+                    .filter(|hedge| hedge.limiter.as_ref().map_or(true, |l| l.allows(method)))
+                    .cloned();
+
                 let labels = RequestLabels {
                     provider: provider.clone(),
                     req_type: method.into(),
                     conn_type: graph::endpoint::ConnectionType::Rpc,
                 };
-                let out = async move {
-                    let out = client.send(id, request).await;
-                    match out {
-                        Ok(_) => metrics.success(&labels),
-                        Err(_) => metrics.failure(&labels),
-                    }
-
-                    out
-                };
 
-                Box::pin(out)
+                match hedge {
+                    Some(hedge) => Box::pin(send_hedged(
+                        client, limiter, hedge, id, request, metrics, labels,
+                    )),
+                    None => Box::pin(send_measured(client, limiter, id, request, metrics, labels)),
+                }
             }
             Transport::IPC(ipc) => Box::pin(ipc.send(id, request)),
             Transport::WS(ws) => Box::pin(ws.send(id, request)),
@@ -130,11 +448,233 @@ impl web3::BatchTransport for Transport {
         match self {
             Transport::RPC {
                 client,
-                metrics: _,
-                provider: _,
-            } => Box::new(client.send_batch(requests)),
+                metrics,
+                provider,
+                limiter: Some(limiter),
+                ..
+            } => {
+                let requests: Vec<_> = requests.into_iter().collect();
+                let denied = requests.iter().find_map(|(_, call)| match call {
+                    Call::MethodCall(m) if !limiter.allows(&m.method) => Some(m.method.clone()),
+                    _ => None,
+                });
+                if let Some(method) = denied {
+                    metrics.throttled(provider, "method");
+                    let err = method_denied(provider, &method);
+                    return Box::new(Box::pin(async move { Err::<Vec<_>, _>(err) }));
+                }
+
+                let client = client.clone();
+                let metrics = metrics.cheap_clone();
+                let provider = provider.clone();
+                let limiter = limiter.cheap_clone();
+                Box::new(Box::pin(async move {
+                    let _permit = limiter
+                        .acquire(requests.len() as u32, &metrics, &provider)
+                        .await;
+                    client.send_batch(requests).await
+                }))
+            }
+            Transport::RPC { client, .. } => Box::new(client.send_batch(requests)),
             Transport::IPC(ipc) => Box::new(ipc.send_batch(requests)),
             Transport::WS(ws) => Box::new(ws.send_batch(requests)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use graph::endpoint::EndpointMetrics;
+    use graph::http::HeaderMap;
+    use graph::prelude::{serde_json, MetricsRegistry};
+    use graph::slog::{o, Discard, Logger};
+    use graph::tokio;
+    use graph::url::Url;
+    use web3::Transport as _;
+
+    use super::{RequestLimiter, RequestLimits, Transport};
+
+    /// Answer each JSON-RPC request to the returned URL after `delay` with
+    /// `reply`, the `result` or `error` member of the response, and count
+    /// the requests
+    fn serve(delay: Duration, reply: &'static str) -> (Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let counter = requests.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                // Read the headers and then as much of the body as the
+                // `content-length` says
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 1024];
+                let body_start = loop {
+                    let n = stream.read(&mut chunk).unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                };
+                let headers = String::from_utf8_lossy(&buf[..body_start]).to_lowercase();
+                let len: usize = headers
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map(|len| len.trim().parse().unwrap())
+                    .unwrap_or(0);
+                while buf.len() < body_start + len {
+                    let n = stream.read(&mut chunk).unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let request: serde_json::Value =
+                    serde_json::from_slice(&buf[body_start..body_start + len]).unwrap();
+
+                std::thread::sleep(delay);
+                let body = format!(r#"{{"jsonrpc":"2.0","id":{},{}}}"#, request["id"], reply);
+                // The client might have given up on the request already
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            }
+        });
+        (url, requests)
+    }
+
+    fn metrics() -> Arc<EndpointMetrics> {
+        Arc::new(EndpointMetrics::new(
+            Logger::root(Discard, o!()),
+            &["primary", "hedge"],
+            Arc::new(MetricsRegistry::mock()),
+        ))
+    }
+
+    fn hedged(primary: Url, hedge: Url, metrics: &Arc<EndpointMetrics>) -> Transport {
+        let hedge = Transport::new_rpc(hedge, HeaderMap::new(), metrics.clone(), "hedge");
+        Transport::new_rpc(primary, HeaderMap::new(), metrics.clone(), "primary")
+            .with_hedge(&hedge, Duration::from_millis(100))
+    }
+
+    #[tokio::test]
+    async fn hedge_fires_for_slow_provider() {
+        let metrics = metrics();
+        let (primary, _) = serve(Duration::from_secs(5), r#""result":"primary""#);
+        let (hedge, hedge_requests) = serve(Duration::ZERO, r#""result":"hedge""#);
+        let transport = hedged(primary, hedge, &metrics);
+
+        let res = transport.execute("eth_call", vec![]).await.unwrap();
+        assert_eq!("hedge", res);
+        assert_eq!(1, hedge_requests.load(Ordering::SeqCst));
+
+        // Methods that are not idempotent are never hedged
+        let res = tokio::time::timeout(
+            Duration::from_millis(500),
+            transport.execute("eth_sendRawTransaction", vec![]),
+        )
+        .await;
+        assert!(res.is_err());
+        assert_eq!(1, hedge_requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn first_response_wins() {
+        let metrics = metrics();
+
+        // The primary answers before the hedge fires
+        let (primary, _) = serve(Duration::ZERO, r#""result":"primary""#);
+        let (hedge, hedge_requests) = serve(Duration::ZERO, r#""result":"hedge""#);
+        let transport = hedged(primary, hedge, &metrics);
+        let res = transport.execute("eth_call", vec![]).await.unwrap();
+        assert_eq!("primary", res);
+        assert_eq!(0, hedge_requests.load(Ordering::SeqCst));
+
+        // The primary answers after the hedge fired, but before the hedge
+        // provider answers
+        let (primary, _) = serve(Duration::from_millis(300), r#""result":"primary""#);
+        let (hedge, hedge_requests) = serve(Duration::from_secs(5), r#""result":"hedge""#);
+        let transport = hedged(primary, hedge, &metrics);
+        let res = transport.execute("eth_call", vec![]).await.unwrap();
+        assert_eq!("primary", res);
+        assert_eq!(1, hedge_requests.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn only_transport_errors_are_failures() {
+        let metrics = metrics();
+        let primary = "primary".into();
+
+        // A reverted call is an answer and does not count against the
+        // provider, and it is not hedged
+        let (url, _) = serve(
+            Duration::ZERO,
+            r#""error":{"code":3,"message":"execution reverted"}"#,
+        );
+        let (hedge, hedge_requests) = serve(Duration::ZERO, r#""result":"hedge""#);
+        let transport = hedged(url, hedge, &metrics);
+        let err = transport.execute("eth_call", vec![]).await.unwrap_err();
+        assert!(matches!(err, web3::error::Error::Rpc(_)), "{:?}", err);
+        assert_eq!(0, hedge_requests.load(Ordering::SeqCst));
+        let score = metrics.score(&primary).unwrap();
+        assert_eq!(0, score.consecutive_errors);
+        assert_eq!(0.0, score.error_rate);
+
+        // A provider that can not be reached does count
+        let url = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap()
+        };
+        let transport = Transport::new_rpc(url, HeaderMap::new(), metrics.clone(), "primary");
+        transport.execute("eth_call", vec![]).await.unwrap_err();
+        assert_eq!(1, metrics.score(&primary).unwrap().consecutive_errors);
+    }
+
+    #[test]
+    fn request_limiter_filters_methods() {
+        let limiter = RequestLimiter::new(RequestLimits {
+            allow_methods: vec!["eth_call".to_string(), "eth_getLogs".to_string()],
+            deny_methods: vec!["eth_getLogs".to_string()],
+            ..Default::default()
+        });
+        assert!(limiter.allows("eth_call"));
+        assert!(!limiter.allows("eth_getLogs"));
+        assert!(!limiter.allows("eth_getBlockByNumber"));
+
+        let limiter = RequestLimiter::new(RequestLimits::default());
+        assert!(limiter.allows("eth_getLogs"));
+    }
+
+    #[test]
+    fn request_limiter_spaces_requests() {
+        let limiter = RequestLimiter::new(RequestLimits {
+            max_requests_per_second: Some(10),
+            ..Default::default()
+        });
+
+        let now = Instant::now();
+
+        assert_eq!(Duration::ZERO, limiter.reserve(1, now));
+        // A batch of three has to wait for the slot of the first request to
+        // pass, and the request after it for the three slots of the batch
+        assert_eq!(Duration::from_millis(100), limiter.reserve(3, now));
+        assert_eq!(Duration::from_millis(400), limiter.reserve(1, now));
+        // Later requests only wait for the slots that are still reserved
+        let later = now + Duration::from_millis(450);
+        assert_eq!(Duration::from_millis(50), limiter.reserve(1, later));
+        assert_eq!(
+            Duration::ZERO,
+            limiter.reserve(1, later + Duration::from_secs(1))
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use graph::blockchain::MappingTriggerTrait;
 use graph::blockchain::TriggerData;
+use graph::data::store::scalar::Bytes;
 use graph::data::subgraph::API_VERSION_0_0_2;
 use graph::data::subgraph::API_VERSION_0_0_6;
 use graph::data::subgraph::API_VERSION_0_0_7;
@@ -17,7 +18,7 @@ use graph::prelude::web3::types::Transaction;
 use graph::prelude::web3::types::TransactionReceipt;
 use graph::prelude::BlockNumber;
 use graph::prelude::BlockPtr;
-use graph::prelude::{CheapClone, EthereumCall};
+use graph::prelude::{BigInt, CheapClone, EthereumCall};
 use graph::runtime::asc_new;
 use graph::runtime::gas::GasCounter;
 use graph::runtime::AscHeap;
@@ -25,6 +26,7 @@ use graph::runtime::AscPtr;
 use graph::runtime::HostExportError;
 use graph::semver::Version;
 use graph_runtime_wasm::module::ToAscPtr;
+use graph_runtime_wasm::rust_abi::{Encoder, ToRustBytes, ToRustTrigger};
 use std::{cmp::Ordering, sync::Arc};
 
 use crate::runtime::abi::AscEthereumBlock;
@@ -222,6 +224,60 @@ impl ToAscPtr for MappingTrigger {
     }
 }
 
+/// Triggers for mappings that use the Rust ABI. Events and calls are passed
+/// with their raw data so that mappings can decode them with the ABI library
+/// of their choice:
+///
+/// - event handlers receive `(block, transaction, address, log_index,
+///   topics, data)`
+/// - call handlers receive `(block, transaction, from, to, input, output)`
+/// - block handlers receive `block`
+impl ToRustTrigger for MappingTrigger {
+    fn to_rust_trigger(&self) -> Result<Vec<u8>, HostExportError> {
+        let mut enc = Encoder::new();
+        match self {
+            MappingTrigger::Log {
+                block,
+                transaction,
+                log,
+                params,
+                receipt: _,
+                calls: _,
+            } => {
+                let event = EthereumEventData::new(block, transaction, log, params);
+                event.block.encode(&mut enc);
+                event.transaction.encode(&mut enc);
+                enc.bytes(event.address().as_bytes());
+                BigInt::from_unsigned_u256(event.log_index()).encode(&mut enc);
+                enc.len(log.topics.len());
+                for topic in &log.topics {
+                    enc.bytes(topic.as_bytes());
+                }
+                enc.bytes(&log.data.0);
+            }
+            MappingTrigger::Call {
+                block,
+                transaction,
+                call,
+                inputs,
+                outputs,
+            } => {
+                let call = EthereumCallData::new(block, transaction, call, inputs, outputs);
+                call.block.encode(&mut enc);
+                call.transaction.encode(&mut enc);
+                enc.bytes(call.from().as_bytes());
+                enc.bytes(call.to().as_bytes());
+                enc.bytes(&call.call.input.0);
+                enc.bytes(&call.call.output.0);
+            }
+            MappingTrigger::Block { block } => {
+                EthereumBlockData::from(block.as_ref()).encode(&mut enc);
+            }
+        }
+        Ok(enc.finish())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum LogRef {
     FullLog(Arc<Log>, Option<Arc<TransactionReceipt>>),
@@ -486,6 +542,24 @@ impl<'a> EthereumBlockData<'a> {
     }
 }
 
+/// Encoded as `(hash, parent_hash, number, timestamp, author, gas_used,
+/// gas_limit, base_fee_per_gas)`
+impl ToRustBytes for EthereumBlockData<'_> {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.bytes(self.hash().as_bytes());
+        enc.bytes(self.parent_hash().as_bytes());
+        enc.u64(self.number().as_u64());
+        BigInt::from_unsigned_u256(self.timestamp()).encode(enc);
+        enc.bytes(self.author().as_bytes());
+        BigInt::from_unsigned_u256(self.gas_used()).encode(enc);
+        BigInt::from_unsigned_u256(self.gas_limit()).encode(enc);
+        self.base_fee_per_gas()
+            .as_ref()
+            .map(BigInt::from_unsigned_u256)
+            .encode(enc);
+    }
+}
+
 /// Ethereum transaction data.
 #[derive(Clone, Debug)]
 pub struct EthereumTransactionData<'a> {
@@ -539,6 +613,25 @@ impl<'a> EthereumTransactionData<'a> {
     }
 }
 
+/// Encoded as `(hash, index, from, to, value, gas_limit, gas_price, nonce,
+/// input)`
+impl ToRustBytes for EthereumTransactionData<'_> {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.bytes(self.hash().as_bytes());
+        enc.u64(self.index().as_u64());
+        enc.bytes(self.from().as_bytes());
+        self.to()
+            .as_ref()
+            .map(|to| Bytes::from(to.as_bytes()))
+            .encode(enc);
+        BigInt::from_unsigned_u256(self.value()).encode(enc);
+        BigInt::from_unsigned_u256(self.gas_limit()).encode(enc);
+        BigInt::from_unsigned_u256(self.gas_price()).encode(enc);
+        BigInt::from_unsigned_u256(self.nonce()).encode(enc);
+        enc.bytes(self.input());
+    }
+}
+
 /// An Ethereum event logged from a specific contract address and block.
 #[derive(Debug, Clone)]
 pub struct EthereumEventData<'a> {
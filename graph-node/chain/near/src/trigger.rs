@@ -8,6 +8,7 @@ use graph::prelude::BlockNumber;
 use graph::runtime::HostExportError;
 use graph::runtime::{asc_new, gas::GasCounter, AscHeap, AscPtr};
 use graph_runtime_wasm::module::ToAscPtr;
+use graph_runtime_wasm::rust_abi::ToRustTrigger;
 use std::{cmp::Ordering, sync::Arc};
 
 use crate::codec;
@@ -38,6 +39,8 @@ impl std::fmt::Debug for NearTrigger {
     }
 }
 
+impl ToRustTrigger for NearTrigger {}
+
 impl ToAscPtr for NearTrigger {
     fn to_asc_ptr<H: AscHeap>(
         self,
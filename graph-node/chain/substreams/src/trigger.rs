@@ -15,6 +15,7 @@ use graph::{
     substreams::Modules,
 };
 use graph_runtime_wasm::module::ToAscPtr;
+use graph_runtime_wasm::rust_abi::ToRustTrigger;
 use std::{collections::BTreeSet, sync::Arc};
 
 use crate::{Block, Chain, NoopDataSourceTemplate, ParsedChanges};
@@ -39,6 +40,8 @@ impl blockchain::TriggerData for TriggerData {
     }
 }
 
+impl ToRustTrigger for TriggerData {}
+
 impl ToAscPtr for TriggerData {
     // substreams doesn't rely on wasm on the graph-node so this is not needed.
     fn to_asc_ptr<H: graph::runtime::AscHeap>(
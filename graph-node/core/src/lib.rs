@@ -3,6 +3,6 @@ pub mod polling_monitor;
 mod subgraph;
 
 pub use crate::subgraph::{
-    SubgraphAssignmentProvider, SubgraphInstanceManager, SubgraphRegistrar, SubgraphRunner,
-    SubgraphTriggerProcessor,
+    BlockReplay, SubgraphAssignmentProvider, SubgraphInstanceManager, SubgraphRegistrar,
+    SubgraphRunner, SubgraphTriggerProcessor,
 };
@@ -4,6 +4,7 @@ use anyhow::ensure;
 use graph::futures01::sync::mpsc::Sender;
 use graph::{
     blockchain::{Blockchain, TriggerData as _},
+    components::store::StoredDynamicDataSource,
     data_source::{
         causality_region::CausalityRegionSeq, offchain, CausalityRegion, DataSource,
         DataSourceTemplate, TriggerData,
@@ -48,12 +49,14 @@ where
     T: RuntimeHostBuilder<C>,
 {
     /// All onchain data sources that are part of this subgraph. This includes data sources
-    /// that are included in the subgraph manifest and dynamic data sources.
+    /// that are included in the subgraph manifest and dynamic data sources, but not dynamic
+    /// data sources that mappings have removed.
     pub fn onchain_data_sources(&self) -> impl Iterator<Item = &C::DataSource> + Clone {
         let host_data_sources = self
             .onchain_hosts
             .hosts()
             .iter()
+            .filter(|h| h.done_at().is_none())
             .map(|h| h.data_source().as_onchain().unwrap());
 
         // Datasources that are defined in the subgraph manifest but does not correspond to any host
@@ -110,6 +113,7 @@ where
             } else {
                 let sender = T::spawn_mapping(
                     module_bytes.as_ref(),
+                    data_source.language(),
                     logger,
                     self.subgraph_id.clone(),
                     self.host_metrics.cheap_clone(),
@@ -189,28 +193,68 @@ where
     /// This function also reverts the done_at status if it was 'done' on this block or later.
     /// It only returns the offchain::Source because we don't currently need to know which
     /// DataSources were removed, the source is used so that the offchain DDS can be found again.
+    /// The returned flag is `true` if onchain data sources that a mapping had removed became
+    /// active again.
     pub(super) fn revert_data_sources(
         &mut self,
         reverted_block: BlockNumber,
-    ) -> Vec<offchain::Source> {
+    ) -> (Vec<offchain::Source>, bool) {
         self.revert_onchain_hosts(reverted_block);
         self.offchain_hosts.remove_ge_block(reverted_block);
 
+        let mut reactivated = false;
+        for host in self.onchain_hosts.hosts() {
+            if matches!(host.done_at(), Some(done_at) if done_at >= reverted_block) {
+                host.set_done_at(None);
+                reactivated = true;
+            }
+        }
+
         // Any File DataSources (Dynamic Data Sources), will have their own causality region
         // which currently is the next number of the sequence but that should be an internal detail.
         // Regardless of the sequence logic, if the current causality region is ONCHAIN then there are
         // no others and therefore the remaining code is a noop and we can just stop here.
         if self.causality_region_seq.0 == CausalityRegion::ONCHAIN {
-            return vec![];
+            return (vec![], reactivated);
         }
 
-        self.offchain_hosts
+        let sources = self
+            .offchain_hosts
             .all()
             .filter(|host| matches!(host.done_at(), Some(done_at) if done_at >= reverted_block))
             .map(|host| {
                 host.set_done_at(None);
                 host.data_source().as_offchain().unwrap().source.clone()
             })
+            .collect();
+        (sources, reactivated)
+    }
+
+    /// Mark the dynamic onchain data sources with the given name and address as removed at
+    /// `block` and return them so that their removal can be persisted. Data sources that were
+    /// already removed are ignored.
+    pub(super) fn remove_data_sources(
+        &self,
+        name: &str,
+        address: &[u8],
+        block: BlockNumber,
+    ) -> Vec<StoredDynamicDataSource> {
+        self.onchain_hosts
+            .matches_by_address(Some(address))
+            .filter(|host| {
+                let ds = host.data_source();
+                host.creation_block_number().is_some()
+                    && host.done_at().is_none()
+                    && ds.name() == name
+                    && ds.address().as_deref() == Some(address)
+            })
+            .map(|host| {
+                host.set_done_at(Some(block));
+                StoredDynamicDataSource {
+                    done_at: Some(block),
+                    ..host.data_source().as_stored_dynamic_data_source()
+                }
+            })
             .collect()
     }
 
@@ -235,9 +279,12 @@ where
         trigger: &TriggerData<C>,
     ) -> Box<dyn Iterator<Item = &T::Host> + Send + '_> {
         match trigger {
-            TriggerData::Onchain(trigger) => self
-                .onchain_hosts
-                .matches_by_address(trigger.address_match()),
+            // Data sources that a mapping removed do not handle any more triggers
+            TriggerData::Onchain(trigger) => Box::new(
+                self.onchain_hosts
+                    .matches_by_address(trigger.address_match())
+                    .filter(|host| host.done_at().is_none()),
+            ),
             TriggerData::Offchain(trigger) => self
                 .offchain_hosts
                 .matches_by_address(trigger.source.address().as_ref().map(|a| a.as_slice())),
@@ -259,3 +306,256 @@ where
         self.onchain_hosts.hosts().first()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    use graph::blockchain::BlockTime;
+    use graph::components::metrics::gas::GasMetrics;
+    use graph::components::store::SubgraphFork;
+    use graph::components::subgraph::{MappingError, SharedProofOfIndexing};
+    use graph::data_source::{
+        common::{MappingABI, MappingLanguage},
+        MappingTrigger, TriggerWithHandler,
+    };
+    use graph::futures01::sync::mpsc;
+    use graph::prelude::ethabi::Contract;
+    use graph::prelude::web3::types::{Address, H256};
+    use graph::semver;
+    use graph_chain_ethereum::trigger::{EthereumBlockTriggerType, EthereumTrigger};
+    use graph_chain_ethereum::{Chain, DataSource as EthereumDataSource, Mapping};
+
+    use super::*;
+
+    const NOT_REMOVED: BlockNumber = -1;
+
+    /// A host that only tracks what `SubgraphInstance` needs to route
+    /// triggers; it never runs a mapping
+    struct MockHost {
+        data_source: DataSource<Chain>,
+        removed_at: AtomicI32,
+    }
+
+    impl PartialEq for MockHost {
+        fn eq(&self, other: &Self) -> bool {
+            self.data_source.is_duplicate_of(&other.data_source)
+        }
+    }
+
+    #[async_trait]
+    impl RuntimeHost<Chain> for MockHost {
+        fn data_source(&self) -> &DataSource<Chain> {
+            &self.data_source
+        }
+
+        fn match_and_decode(
+            &self,
+            _trigger: &TriggerData<Chain>,
+            _block: &Arc<<Chain as Blockchain>::Block>,
+            _logger: &Logger,
+        ) -> Result<Option<TriggerWithHandler<MappingTrigger<Chain>>>, Error> {
+            unimplemented!()
+        }
+
+        async fn process_block(
+            &self,
+            _logger: &Logger,
+            _block_ptr: BlockPtr,
+            _block_time: BlockTime,
+            _block_data: Box<[u8]>,
+            _handler: String,
+            _state: BlockState,
+            _proof_of_indexing: SharedProofOfIndexing,
+            _debug_fork: &Option<Arc<dyn SubgraphFork>>,
+            _instrument: bool,
+        ) -> Result<BlockState, MappingError> {
+            unimplemented!()
+        }
+
+        async fn process_mapping_trigger(
+            &self,
+            _logger: &Logger,
+            _trigger: TriggerWithHandler<MappingTrigger<Chain>>,
+            _state: BlockState,
+            _proof_of_indexing: SharedProofOfIndexing,
+            _debug_fork: &Option<Arc<dyn SubgraphFork>>,
+            _instrument: bool,
+        ) -> Result<BlockState, MappingError> {
+            unimplemented!()
+        }
+
+        fn creation_block_number(&self) -> Option<BlockNumber> {
+            self.data_source.creation_block()
+        }
+
+        fn done_at(&self) -> Option<BlockNumber> {
+            match self.removed_at.load(Ordering::SeqCst) {
+                NOT_REMOVED => None,
+                block => Some(block),
+            }
+        }
+
+        fn set_done_at(&self, block: Option<BlockNumber>) {
+            self.removed_at
+                .store(block.unwrap_or(NOT_REMOVED), Ordering::SeqCst)
+        }
+
+        fn host_metrics(&self) -> Arc<HostMetrics> {
+            unimplemented!()
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockHostBuilder;
+
+    impl RuntimeHostBuilder<Chain> for MockHostBuilder {
+        type Host = MockHost;
+        type Req = ();
+
+        fn build(
+            &self,
+            _network_name: String,
+            _subgraph_id: DeploymentHash,
+            data_source: DataSource<Chain>,
+            _top_level_templates: Arc<Vec<DataSourceTemplate<Chain>>>,
+            _mapping_request_sender: mpsc::Sender<()>,
+            _metrics: Arc<HostMetrics>,
+        ) -> Result<MockHost, Error> {
+            Ok(MockHost {
+                data_source,
+                removed_at: AtomicI32::new(NOT_REMOVED),
+            })
+        }
+
+        fn spawn_mapping(
+            _raw_module: &[u8],
+            _language: MappingLanguage,
+            _logger: Logger,
+            _subgraph_id: DeploymentHash,
+            _metrics: Arc<HostMetrics>,
+        ) -> Result<mpsc::Sender<()>, Error> {
+            Ok(mpsc::channel(1).0)
+        }
+    }
+
+    fn instance() -> SubgraphInstance<Chain, MockHostBuilder> {
+        let subgraph_id = DeploymentHash::new("removeDataSources").unwrap();
+        let registry = Arc::new(MetricsRegistry::mock());
+        let stopwatch = StopwatchMetrics::new(
+            Logger::root(slog::Discard, o!()),
+            subgraph_id.clone(),
+            "test",
+            registry.clone(),
+            "test_shard".to_string(),
+        );
+        let host_metrics = Arc::new(HostMetrics::new(
+            registry,
+            subgraph_id.as_str(),
+            stopwatch,
+            GasMetrics::mock(),
+        ));
+
+        SubgraphInstance {
+            subgraph_id,
+            network: "mainnet".to_string(),
+            host_builder: MockHostBuilder,
+            templates: Arc::new(vec![]),
+            static_data_sources: Arc::new(vec![]),
+            host_metrics,
+            onchain_hosts: OnchainHosts::new(),
+            subgraph_hosts: OnchainHosts::new(),
+            offchain_hosts: OffchainHosts::new(),
+            module_cache: HashMap::new(),
+            causality_region_seq: CausalityRegionSeq::from_current(None),
+        }
+    }
+
+    fn dynamic_data_source(
+        name: &str,
+        address: Address,
+        creation_block: BlockNumber,
+    ) -> DataSource<Chain> {
+        let contract_abi = Arc::new(MappingABI {
+            name: "Contract".to_string(),
+            contract: Contract::load(&b"[]"[..]).unwrap(),
+        });
+        DataSource::Onchain(EthereumDataSource {
+            kind: "ethereum/contract".to_string(),
+            network: Some("mainnet".to_string()),
+            name: name.to_string(),
+            manifest_idx: 0,
+            address: Some(address),
+            start_block: creation_block,
+            end_block: None,
+            mapping: Mapping {
+                kind: "ethereum/events".to_string(),
+                api_version: semver::Version::new(0, 0, 9),
+                language: "wasm/assemblyscript".to_string(),
+                entities: vec![],
+                abis: vec![contract_abi.cheap_clone()],
+                block_handlers: vec![],
+                call_handlers: vec![],
+                event_handlers: vec![],
+                runtime: Arc::new(vec![0]),
+                link: "mapping.wasm".into(),
+            },
+            context: Arc::new(None),
+            creation_block: Some(creation_block),
+            contract_abi,
+        })
+    }
+
+    fn trigger_for(address: Address) -> TriggerData<Chain> {
+        TriggerData::Onchain(EthereumTrigger::Block(
+            BlockPtr::new(H256::zero().into(), 10),
+            EthereumBlockTriggerType::WithCallTo(address),
+        ))
+    }
+
+    fn matching_hosts(
+        instance: &SubgraphInstance<Chain, MockHostBuilder>,
+        address: Address,
+    ) -> Vec<String> {
+        instance
+            .hosts_for_trigger(&trigger_for(address))
+            .map(|host| host.data_source().name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn removed_data_sources_stop_matching_triggers() {
+        let logger = Logger::root(slog::Discard, o!());
+        let mut instance = instance();
+        let token = Address::from_low_u64_be(1);
+        let other = Address::from_low_u64_be(2);
+
+        for (name, address, block) in [("Token", token, 1), ("Token", other, 2), ("Pair", token, 3)]
+        {
+            instance
+                .add_dynamic_data_source(&logger, dynamic_data_source(name, address, block))
+                .unwrap()
+                .unwrap();
+        }
+        assert_eq!(vec!["Token", "Pair"], matching_hosts(&instance, token));
+
+        // Only the data source with the given name and address is removed
+        let removed = instance.remove_data_sources("Token", token.as_bytes(), 5);
+        assert_eq!(1, removed.len());
+        assert_eq!(Some(5), removed[0].done_at);
+        assert_eq!(Some(1), removed[0].creation_block);
+        assert_eq!(vec!["Pair"], matching_hosts(&instance, token));
+        assert_eq!(vec!["Token"], matching_hosts(&instance, other));
+        assert_eq!(2, instance.onchain_data_sources().count());
+
+        // Removing it again is a noop
+        assert!(instance
+            .remove_data_sources("Token", token.as_bytes(), 6)
+            .is_empty());
+
+        // Reverting the block that removed it makes it match again
+        let (_, reactivated) = instance.revert_data_sources(5);
+        assert!(reactivated);
+        assert_eq!(vec!["Token", "Pair"], matching_hosts(&instance, token));
+    }
+}
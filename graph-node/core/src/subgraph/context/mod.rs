@@ -8,7 +8,7 @@ use bytes::Bytes;
 use graph::{
     blockchain::{BlockTime, Blockchain, TriggerFilterWrapper},
     components::{
-        store::{DeploymentId, SubgraphFork},
+        store::{DeploymentId, StoredDynamicDataSource, SubgraphFork},
         subgraph::{HostMetrics, MappingError, RuntimeHost as _, SharedProofOfIndexing},
     },
     data::subgraph::SubgraphManifest,
@@ -172,12 +172,26 @@ impl<C: Blockchain, T: RuntimeHostBuilder<C>> IndexingContext<C, T> {
     /// `process_trigger`.
     ///
     /// File data sources that have been marked not done during this process will get re-queued
-    pub fn revert_data_sources(&mut self, reverted_block: BlockNumber) -> Result<(), Error> {
-        let removed = self.instance.revert_data_sources(reverted_block);
+    /// Returns `true` if reverting made data sources that a mapping had removed active again,
+    /// in which case the trigger filters need to be rebuilt.
+    pub fn revert_data_sources(&mut self, reverted_block: BlockNumber) -> Result<bool, Error> {
+        let (removed, reactivated) = self.instance.revert_data_sources(reverted_block);
 
         removed
             .into_iter()
-            .try_for_each(|source| self.offchain_monitor.add_source(source))
+            .try_for_each(|source| self.offchain_monitor.add_source(source))?;
+        Ok(reactivated)
+    }
+
+    /// Mark the dynamic onchain data sources with the given name and address as removed at
+    /// `block`, and return them for persisting their removal.
+    pub fn remove_data_sources(
+        &self,
+        name: &str,
+        address: &[u8],
+        block: BlockNumber,
+    ) -> Vec<StoredDynamicDataSource> {
+        self.instance.remove_data_sources(name, address, block)
     }
 
     pub fn add_dynamic_data_source(
@@ -8,7 +8,7 @@ use crate::subgraph::loader::load_dynamic_data_sources;
 use crate::subgraph::Decoder;
 use std::collections::BTreeSet;
 
-use crate::subgraph::runner::SubgraphRunner;
+use crate::subgraph::runner::{BlockReplay, SubgraphRunner};
 use graph::blockchain::block_stream::{BlockStreamMetrics, TriggersAdapterWrapper};
 use graph::blockchain::{Blockchain, BlockchainKind, DataSource, NodeCapabilities};
 use graph::components::metrics::gas::GasMetrics;
@@ -22,6 +22,7 @@ use graph::env::EnvVars;
 use graph::prelude::{SubgraphInstanceManager as SubgraphInstanceManagerTrait, *};
 use graph::{blockchain::BlockchainMap, components::store::DeploymentLocator};
 use graph_runtime_wasm::module::ToAscPtr;
+use graph_runtime_wasm::rust_abi::ToRustTrigger;
 use graph_runtime_wasm::RuntimeHostBuilder;
 use tokio::task;
 
@@ -178,6 +179,7 @@ impl<S: SubgraphStore> SubgraphInstanceManagerTrait for SubgraphInstanceManager<
         }
 
         self.instances.remove(&loc.id);
+        self.metrics_registry.mapping_profiles().remove(&loc.hash);
 
         info!(logger, "Stopped subgraph");
     }
@@ -250,7 +252,7 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
     ) -> anyhow::Result<SubgraphRunner<C, RuntimeHostBuilder<C>>>
     where
         C: Blockchain,
-        <C as Blockchain>::MappingTrigger: ToAscPtr,
+        <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
     {
         self.build_subgraph_runner_inner(
             logger,
@@ -265,6 +267,87 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
         .await
     }
 
+    /// Replay `block` of the deployment `loc` and return the changes it
+    /// would make to the store. Nothing is written, and the deployment can
+    /// keep indexing while the block is being replayed
+    pub async fn replay_block(
+        &self,
+        logger: Logger,
+        loc: DeploymentLocator,
+        manifest: serde_yaml::Mapping,
+        block: BlockNumber,
+    ) -> anyhow::Result<BlockReplay> {
+        async fn replay<C, S>(
+            manager: &SubgraphInstanceManager<S>,
+            logger: Logger,
+            loc: DeploymentLocator,
+            manifest: serde_yaml::Mapping,
+            block: BlockNumber,
+            tp: Box<dyn TriggerProcessor<C, RuntimeHostBuilder<C>>>,
+        ) -> anyhow::Result<BlockReplay>
+        where
+            C: Blockchain,
+            <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
+            S: SubgraphStore,
+        {
+            let runner = manager
+                .build_runner::<C>(
+                    logger,
+                    manager.env_vars.cheap_clone(),
+                    loc.clone(),
+                    manifest,
+                    None,
+                    tp,
+                    manager.new_deployment_status_metric(&loc),
+                    false,
+                    Some(block),
+                )
+                .await?;
+            runner.replay_block(block).await
+        }
+
+        match BlockchainKind::from_manifest(&manifest)? {
+            BlockchainKind::Arweave => {
+                replay::<graph_chain_arweave::Chain, _>(
+                    self,
+                    logger,
+                    loc,
+                    manifest,
+                    block,
+                    Box::new(SubgraphTriggerProcessor {}),
+                )
+                .await
+            }
+            BlockchainKind::Ethereum => {
+                replay::<graph_chain_ethereum::Chain, _>(
+                    self,
+                    logger,
+                    loc,
+                    manifest,
+                    block,
+                    Box::new(SubgraphTriggerProcessor {}),
+                )
+                .await
+            }
+            BlockchainKind::Near => {
+                replay::<graph_chain_near::Chain, _>(
+                    self,
+                    logger,
+                    loc,
+                    manifest,
+                    block,
+                    Box::new(SubgraphTriggerProcessor {}),
+                )
+                .await
+            }
+            BlockchainKind::Substreams => {
+                let tp = Box::new(graph_chain_substreams::TriggerProcessor::new(loc.clone()));
+                replay::<graph_chain_substreams::Chain, _>(self, logger, loc, manifest, block, tp)
+                    .await
+            }
+        }
+    }
+
     pub async fn build_subgraph_runner_inner<C>(
         &self,
         logger: Logger,
@@ -278,7 +361,40 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
     ) -> anyhow::Result<SubgraphRunner<C, RuntimeHostBuilder<C>>>
     where
         C: Blockchain,
-        <C as Blockchain>::MappingTrigger: ToAscPtr,
+        <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
+    {
+        self.build_runner(
+            logger,
+            env_vars,
+            deployment,
+            manifest,
+            stop_block,
+            tp,
+            deployment_status_metric,
+            is_runner_test,
+            None,
+        )
+        .await
+    }
+
+    /// Build a runner for `deployment`. If `replay_block` is set, the
+    /// runner can only be used to replay that block, and building it does
+    /// not change anything in the store
+    async fn build_runner<C>(
+        &self,
+        logger: Logger,
+        env_vars: Arc<EnvVars>,
+        deployment: DeploymentLocator,
+        manifest: serde_yaml::Mapping,
+        stop_block: Option<BlockNumber>,
+        tp: Box<dyn TriggerProcessor<C, RuntimeHostBuilder<C>>>,
+        deployment_status_metric: DeploymentStatusMetric,
+        is_runner_test: bool,
+        replay_block: Option<BlockNumber>,
+    ) -> anyhow::Result<SubgraphRunner<C, RuntimeHostBuilder<C>>>
+    where
+        C: Blockchain,
+        <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
     {
         let subgraph_store = self.subgraph_store.cheap_clone();
         let registry = self.metrics_registry.cheap_clone();
@@ -290,10 +406,12 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
         let link_resolver = Arc::from(self.link_resolver.with_retries());
 
         // Make sure the `raw_yaml` is present on both this subgraph and the graft base.
-        self.subgraph_store
-            .set_manifest_raw_yaml(&deployment.hash, raw_yaml)
-            .await?;
-        if let Some(graft) = &manifest.graft {
+        if replay_block.is_none() {
+            self.subgraph_store
+                .set_manifest_raw_yaml(&deployment.hash, raw_yaml)
+                .await?;
+        }
+        if let Some(graft) = manifest.graft.as_ref().filter(|_| replay_block.is_none()) {
             if self.subgraph_store.is_deployed(&graft.base)? {
                 let file_bytes = self
                     .link_resolver
@@ -334,21 +452,29 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
             );
         }
 
-        let store = self
-            .subgraph_store
-            .cheap_clone()
-            .writable(
-                logger.clone(),
-                deployment.id,
-                Arc::new(manifest.template_idx_and_name().collect()),
-            )
-            .await?;
+        let manifest_idx_and_name = Arc::new(manifest.template_idx_and_name().collect());
+        let store = match replay_block {
+            Some(block) => {
+                self.subgraph_store
+                    .cheap_clone()
+                    .replay_writable(logger.clone(), deployment.id, block, manifest_idx_and_name)
+                    .await?
+            }
+            None => {
+                self.subgraph_store
+                    .cheap_clone()
+                    .writable(logger.clone(), deployment.id, manifest_idx_and_name)
+                    .await?
+            }
+        };
 
         // Create deployment features from the manifest
         // Write it to the database
-        let deployment_features = manifest.deployment_features();
-        self.subgraph_store
-            .create_subgraph_features(deployment_features)?;
+        if replay_block.is_none() {
+            let deployment_features = manifest.deployment_features();
+            self.subgraph_store
+                .create_subgraph_features(deployment_features)?;
+        }
 
         // Start the subgraph deployment before reading dynamic data
         // sources; if the subgraph is a graft or a copy, starting it will
@@ -361,9 +487,21 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
                 .await
                 .context("Failed to load dynamic data sources")?;
 
+        // Remember which dynamic data sources mappings have removed
+        let removed_at: Vec<Option<BlockNumber>> = manifest
+            .data_sources
+            .iter()
+            .map(|_| None)
+            .chain(
+                dynamic_data_sources
+                    .iter()
+                    .map(|(_, removed_at)| *removed_at),
+            )
+            .collect();
+
         // Combine the data sources from the manifest with the dynamic data sources
         let mut data_sources = manifest.data_sources.clone();
-        data_sources.extend(dynamic_data_sources);
+        data_sources.extend(dynamic_data_sources.into_iter().map(|(ds, _)| ds));
 
         info!(logger, "Data source count at start: {}", data_sources.len());
 
@@ -544,8 +682,11 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
                 tp,
                 decoder,
             );
-            for data_source in data_sources {
-                ctx.add_dynamic_data_source(&logger, data_source)?;
+            for (data_source, removed_at) in data_sources.into_iter().zip(removed_at) {
+                let host = ctx.add_dynamic_data_source(&logger, data_source)?;
+                if let (Some(host), Some(removed_at)) = (host, removed_at) {
+                    host.set_done_at(Some(removed_at));
+                }
             }
             ctx
         };
@@ -572,7 +713,7 @@ impl<S: SubgraphStore> SubgraphInstanceManager<S> {
         runner: SubgraphRunner<C, RuntimeHostBuilder<C>>,
     ) -> Result<(), Error>
     where
-        <C as Blockchain>::MappingTrigger: ToAscPtr,
+        <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
     {
         let registry = self.metrics_registry.cheap_clone();
         let subgraph_metrics = runner.metrics.subgraph.cheap_clone();
@@ -2,20 +2,23 @@ use std::time::Instant;
 
 use graph::blockchain::Blockchain;
 use graph::components::store::WritableStore;
-use graph::data_source::DataSource;
+use graph::data_source::{CausalityRegion, DataSource};
 use graph::prelude::*;
 
+/// Load the dynamic data sources of the subgraph, together with the block
+/// at which a mapping removed them for onchain data sources that have been
+/// removed
 pub async fn load_dynamic_data_sources<C: Blockchain>(
     store: Arc<dyn WritableStore>,
     logger: Logger,
     manifest: &SubgraphManifest<C>,
-) -> Result<Vec<DataSource<C>>, Error> {
+) -> Result<Vec<(DataSource<C>, Option<BlockNumber>)>, Error> {
     let manifest_idx_and_name = manifest.template_idx_and_name().collect();
     let start_time = Instant::now();
 
-    let mut data_sources: Vec<DataSource<C>> = vec![];
+    let mut data_sources: Vec<(DataSource<C>, Option<BlockNumber>)> = vec![];
 
-    for stored in store
+    for mut stored in store
         .load_dynamic_data_sources(manifest_idx_and_name)
         .await?
     {
@@ -25,16 +28,23 @@ pub async fn load_dynamic_data_sources<C: Blockchain>(
             .find(|template| template.manifest_idx() == stored.manifest_idx)
             .ok_or_else(|| anyhow!("no template with idx `{}` was found", stored.manifest_idx))?;
 
+        // For onchain data sources, `done_at` is the block at which a
+        // mapping removed them; the data source itself does not track that
+        let removed_at = match stored.causality_region {
+            CausalityRegion::ONCHAIN => stored.done_at.take(),
+            _ => None,
+        };
+
         let ds = DataSource::from_stored_dynamic_data_source(template, stored)?;
 
         // The data sources are ordered by the creation block.
         // See also 8f1bca33-d3b7-4035-affc-fd6161a12448.
         anyhow::ensure!(
-            data_sources.last().and_then(|d| d.creation_block()) <= ds.creation_block(),
+            data_sources.last().and_then(|(d, _)| d.creation_block()) <= ds.creation_block(),
             "Assertion failure: new data source has lower creation block than existing ones"
         );
 
-        data_sources.push(ds);
+        data_sources.push((ds, removed_at));
     }
 
     trace!(
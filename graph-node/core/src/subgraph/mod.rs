@@ -13,5 +13,5 @@ mod trigger_processor;
 pub use self::instance_manager::SubgraphInstanceManager;
 pub use self::provider::SubgraphAssignmentProvider;
 pub use self::registrar::SubgraphRegistrar;
-pub use self::runner::SubgraphRunner;
+pub use self::runner::{BlockReplay, SubgraphRunner};
 pub use self::trigger_processor::*;
@@ -5,6 +5,7 @@ use async_trait::async_trait;
 use graph::blockchain::Blockchain;
 use graph::blockchain::BlockchainKind;
 use graph::blockchain::BlockchainMap;
+use graph::blockchain::NodeCapabilities;
 use graph::components::store::{DeploymentId, DeploymentLocator, SubscriptionManager};
 use graph::components::subgraph::Settings;
 use graph::data::subgraph::schema::DeploymentCreate;
@@ -600,6 +601,18 @@ async fn create_subgraph_version<C: Blockchain, S: SubgraphStore>(
         .map_err(SubgraphRegistrarError::NetworkNotSupported)?
         .cheap_clone();
 
+    // Reject subgraphs that need capabilities, e.g., traces for call
+    // handlers, that no provider for the network has
+    let onchain_data_sources = manifest
+        .data_sources
+        .iter()
+        .filter_map(|ds| ds.as_onchain().cloned())
+        .collect::<Vec<_>>();
+    let required_capabilities = C::NodeCapabilities::from_data_sources(&onchain_data_sources);
+    chain
+        .check_capabilities(&required_capabilities)
+        .map_err(SubgraphRegistrarError::NetworkNotSupported)?;
+
     let logger = logger.clone();
     let store = store.clone();
     let deployment_store = store.clone();
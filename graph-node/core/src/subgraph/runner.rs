@@ -11,7 +11,7 @@ use graph::blockchain::{
     TriggerFilterWrapper,
 };
 use graph::components::store::{EmptyStore, GetScope, ReadStore, StoredDynamicDataSource};
-use graph::components::subgraph::InstanceDSTemplate;
+use graph::components::subgraph::{InstanceDSTemplate, RemovedDataSource};
 use graph::components::{
     store::ModificationsAndCache,
     subgraph::{MappingError, PoICausalityRegion, ProofOfIndexing, SharedProofOfIndexing},
@@ -55,6 +55,18 @@ where
     pub metrics: RunnerMetrics,
 }
 
+/// The result of `SubgraphRunner::replay_block`
+pub struct BlockReplay {
+    pub block_ptr: BlockPtr,
+    /// The changes to entities, including the PoI entities of the block
+    pub modifications: Vec<EntityModification>,
+    /// The new PoI digest for each causality region
+    pub poi_digests: Vec<(String, Bytes)>,
+    pub created_data_sources: Vec<StoredDynamicDataSource>,
+    pub removed_data_sources: Vec<StoredDynamicDataSource>,
+    pub deterministic_errors: Vec<SubgraphError>,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum SubgraphRunnerError {
     #[error("subgraph runner terminated because a newer one was active")]
@@ -104,14 +116,15 @@ where
     /// be removed. The same thing also applies to the block cache.
     /// This function must be called before continuing to process in order to avoid
     /// duplicated host insertion and POI issues with dirty entity changes.
-    fn revert_state_to(&mut self, block_number: BlockNumber) -> Result<(), Error> {
+    /// Returns `true` if the revert made data sources that a mapping had removed active again.
+    fn revert_state_to(&mut self, block_number: BlockNumber) -> Result<bool, Error> {
         self.state.entity_lfu_cache = LfuCache::new();
 
         // 1. Revert all hosts(created by DDS) at a block higher than `block_number`.
-        // 2. Unmark any offchain data sources that were marked done on the blocks being removed.
-        // When no offchain datasources are present, 2. should be a noop.
-        self.ctx.revert_data_sources(block_number + 1)?;
-        Ok(())
+        // 2. Unmark any data sources that were marked done or removed on the blocks being
+        //    reverted.
+        // When no offchain datasources are present, 2. should be a noop for them.
+        self.ctx.revert_data_sources(block_number + 1)
     }
 
     #[cfg(debug_assertions)]
@@ -124,6 +137,121 @@ where
         self.run_inner(break_on_restart).await.map_err(Into::into)
     }
 
+    /// Run the triggers of block `block` and return the changes that
+    /// indexing would make to the store without writing anything. The
+    /// runner must have been built with a store from
+    /// `SubgraphStore::replay_writable` so that it sees the state of the
+    /// deployment before `block`. File data sources are not processed.
+    pub async fn replay_block(mut self, block: BlockNumber) -> Result<BlockReplay, Error> {
+        let filter = Arc::new(self.build_filter());
+        let (blocks, _) = self
+            .inputs
+            .triggers_adapter
+            .scan_triggers(&self.logger, block, block, &filter)
+            .await?;
+        let block = match blocks.into_iter().find(|b| b.ptr().number == block) {
+            Some(block) => block,
+            None => return Err(anyhow!("block {} has no triggers for this subgraph", block)),
+        };
+
+        let triggers = block.trigger_data;
+        let block = Arc::new(block.block);
+        let block_ptr = block.ptr();
+
+        let logger = self.logger.new(o!(
+                "block_number" => format!("{:?}", block_ptr.number),
+                "block_hash" => format!("{}", block_ptr.hash)
+        ));
+
+        debug!(logger, "Start replaying block";
+               "triggers" => triggers.len());
+
+        let proof_of_indexing =
+            SharedProofOfIndexing::new(block_ptr.number, self.inputs.poi_version);
+        let causality_region = PoICausalityRegion::from_network(&self.inputs.network);
+        let block_state = BlockState::new(self.inputs.store.clone(), LfuCache::new());
+
+        let block_state = self
+            .process_triggers(
+                &logger,
+                &block,
+                triggers,
+                block_state,
+                &proof_of_indexing,
+                &causality_region,
+            )
+            .await
+            .map_err(|e| match e {
+                MappingError::PossibleReorg(e) | MappingError::Unknown(e) => e,
+            })?;
+
+        // Chains that refetch the block for new data sources do that with
+        // the Firehose cursor of the block, and we do not have that for
+        // blocks other than the subgraph head
+        if block_state.has_created_data_sources() && self.inputs.chain.is_refetch_block_required() {
+            return Err(anyhow!(
+                "block {} creates data sources, and replaying such blocks is not possible for chains that need to refetch the block",
+                block_ptr.number
+            ));
+        }
+
+        let mut block_state = self
+            .handle_created_data_sources(
+                &logger,
+                &block,
+                &FirehoseCursor::None,
+                block_state,
+                &proof_of_indexing,
+                &causality_region,
+            )
+            .await?;
+
+        let removed_data_sources = self.remove_dynamic_data_sources(
+            block_ptr.number,
+            block_state.drain_removed_data_sources(),
+        )?;
+
+        if let Some(proof_of_indexing) = proof_of_indexing.into_inner() {
+            update_proof_of_indexing(
+                proof_of_indexing,
+                block.timestamp(),
+                &self.metrics.host.stopwatch,
+                &mut block_state.entity_cache,
+            )
+            .await?;
+        }
+
+        let ModificationsAndCache { modifications, .. } =
+            block_state.entity_cache.as_modifications(block.number())?;
+
+        let digest = self.inputs.store.input_schema().poi_digest();
+        let poi_digests = modifications
+            .iter()
+            .filter(|m| m.key().entity_type.is_poi())
+            .filter_map(|m| match m {
+                EntityModification::Insert { key, data, .. }
+                | EntityModification::Overwrite { key, data, .. } => {
+                    match data.get(digest.as_str()) {
+                        Some(Value::Bytes(digest)) => {
+                            Some((key.entity_id.to_string(), digest.clone()))
+                        }
+                        _ => None,
+                    }
+                }
+                EntityModification::Remove { .. } => None,
+            })
+            .collect();
+
+        Ok(BlockReplay {
+            block_ptr,
+            modifications,
+            poi_digests,
+            created_data_sources: block_state.persisted_data_sources,
+            removed_data_sources,
+            deterministic_errors: block_state.deterministic_errors,
+        })
+    }
+
     fn is_static_filters_enabled(&self) -> bool {
         self.inputs.static_filters || self.ctx.hosts_len() > ENV_VARS.static_filters_threshold
     }
@@ -383,59 +511,17 @@ where
             .stopwatch
             .start_section(PROCESS_TRIGGERS_SECTION_NAME);
 
-        // Match and decode all triggers in the block
-        let hosts_filter = |trigger: &TriggerData<C>| self.ctx.instance.hosts_for_trigger(trigger);
-        let match_res = self
-            .ctx
-            .decoder
-            .match_and_decode_many(
+        let res = self
+            .process_triggers(
                 &logger,
                 &block,
-                triggers.into_iter().map(|t| match t {
-                    Trigger::Chain(t) => TriggerData::Onchain(t),
-                    Trigger::Subgraph(t) => TriggerData::Subgraph(t),
-                }),
-                hosts_filter,
-                &self.metrics.subgraph,
+                triggers,
+                block_state,
+                &proof_of_indexing,
+                &causality_region,
             )
             .await;
 
-        // Process events one after the other, passing in entity operations
-        // collected previously to every new event being processed
-        let mut res = Ok(block_state);
-        match match_res {
-            Ok(runnables) => {
-                for runnable in runnables {
-                    let process_res = self
-                        .ctx
-                        .trigger_processor
-                        .process_trigger(
-                            &self.logger,
-                            runnable.hosted_triggers,
-                            &block,
-                            res.unwrap(),
-                            &proof_of_indexing,
-                            &causality_region,
-                            &self.inputs.debug_fork,
-                            &self.metrics.subgraph,
-                            self.inputs.instrument,
-                        )
-                        .await
-                        .map_err(|e| e.add_trigger_context(&runnable.trigger));
-                    match process_res {
-                        Ok(state) => res = Ok(state),
-                        Err(e) => {
-                            res = Err(e);
-                            break;
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                res = Err(e);
-            }
-        };
-
         match res {
             // Triggers processed with no errors or with only deterministic errors.
             Ok(state) => block_state = state,
@@ -472,146 +558,25 @@ where
         // or data sources that have reached their end block.
         let needs_restart = created_data_sources_needs_restart || has_expired_data_sources;
 
-        {
-            let _section = self
-                .metrics
-                .stream
-                .stopwatch
-                .start_section(HANDLE_CREATED_DS_SECTION_NAME);
-
-            // This loop will:
-            // 1. Instantiate created data sources.
-            // 2. Process those data sources for the current block.
-            // Until no data sources are created or MAX_DATA_SOURCES is hit.
-
-            // Note that this algorithm processes data sources spawned on the same block _breadth
-            // first_ on the tree implied by the parent-child relationship between data sources. Only a
-            // very contrived subgraph would be able to observe this.
-            while block_state.has_created_data_sources() {
-                // Instantiate dynamic data sources, removing them from the block state.
-                let (data_sources, runtime_hosts) =
-                    self.create_dynamic_data_sources(block_state.drain_created_data_sources())?;
-
-                let filter = &Arc::new(TriggerFilterWrapper::new(
-                    C::TriggerFilter::from_data_sources(
-                        data_sources.iter().filter_map(DataSource::as_onchain),
-                    ),
-                    vec![],
-                ));
-
-                let block: Arc<C::Block> = if self.inputs.chain.is_refetch_block_required() {
-                    let cur = firehose_cursor.clone();
-                    let log = logger.cheap_clone();
-                    let chain = self.inputs.chain.cheap_clone();
-                    Arc::new(
-                        retry(
-                            "refetch firehose block after dynamic datasource was added",
-                            &logger,
-                        )
-                        .limit(5)
-                        .no_timeout()
-                        .run(move || {
-                            let cur = cur.clone();
-                            let log = log.cheap_clone();
-                            let chain = chain.cheap_clone();
-                            async move { chain.refetch_firehose_block(&log, cur).await }
-                        })
-                        .await?,
-                    )
-                } else {
-                    block.cheap_clone()
-                };
-
-                // Reprocess the triggers from this block that match the new data sources
-                let block_with_triggers = self
-                    .inputs
-                    .triggers_adapter
-                    .triggers_in_block(&logger, block.as_ref().clone(), filter)
-                    .await?;
-
-                let triggers = block_with_triggers.trigger_data;
-
-                if triggers.len() == 1 {
-                    info!(
-                        &logger,
-                        "1 trigger found in this block for the new data sources"
-                    );
-                } else if triggers.len() > 1 {
-                    info!(
-                        &logger,
-                        "{} triggers found in this block for the new data sources",
-                        triggers.len()
-                    );
-                }
-
-                // Add entity operations for the new data sources to the block state
-                // and add runtimes for the data sources to the subgraph instance.
-                self.persist_dynamic_data_sources(&mut block_state, data_sources);
-
-                // Process the triggers in each host in the same order the
-                // corresponding data sources have been created.
-                let match_res: Result<Vec<_>, _> = self
-                    .ctx
-                    .decoder
-                    .match_and_decode_many(
-                        &logger,
-                        &block,
-                        triggers.into_iter().map(|t| match t {
-                            Trigger::Chain(t) => TriggerData::Onchain(t),
-                            Trigger::Subgraph(_) => unreachable!(), // TODO(krishna): Re-evaulate this
-                        }),
-                        |_| Box::new(runtime_hosts.iter().map(Arc::as_ref)),
-                        &self.metrics.subgraph,
-                    )
-                    .await;
-
-                let mut res = Ok(block_state);
-                match match_res {
-                    Ok(runnables) => {
-                        for runnable in runnables {
-                            let process_res = self
-                                .ctx
-                                .trigger_processor
-                                .process_trigger(
-                                    &self.logger,
-                                    runnable.hosted_triggers,
-                                    &block,
-                                    res.unwrap(),
-                                    &proof_of_indexing,
-                                    &causality_region,
-                                    &self.inputs.debug_fork,
-                                    &self.metrics.subgraph,
-                                    self.inputs.instrument,
-                                )
-                                .await
-                                .map_err(|e| e.add_trigger_context(&runnable.trigger));
-                            match process_res {
-                                Ok(state) => res = Ok(state),
-                                Err(e) => {
-                                    res = Err(e);
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        res = Err(e);
-                    }
-                }
+        block_state = self
+            .handle_created_data_sources(
+                &logger,
+                &block,
+                &firehose_cursor,
+                block_state,
+                &proof_of_indexing,
+                &causality_region,
+            )
+            .await?;
 
-                block_state = res.map_err(|e| {
-                    // This treats a `PossibleReorg` as an ordinary error which will fail the subgraph.
-                    // This can cause an unnecessary subgraph failure, to fix it we need to figure out a
-                    // way to revert the effect of `create_dynamic_data_sources` so we may return a
-                    // clean context as in b21fa73b-6453-4340-99fb-1a78ec62efb1.
-                    match e {
-                        MappingError::PossibleReorg(e) | MappingError::Unknown(e) => {
-                            BlockProcessingError::Unknown(e)
-                        }
-                    }
-                })?;
-            }
-        }
+        // Data sources that mappings removed stop handling triggers after this block. Unless
+        // static filters are in use, the block stream needs to be restarted without them.
+        let mut removed_data_sources = self.remove_dynamic_data_sources(
+            block_ptr.number,
+            block_state.drain_removed_data_sources(),
+        )?;
+        let needs_restart = needs_restart
+            || (!self.is_static_filters_enabled() && !removed_data_sources.is_empty());
 
         let has_errors = block_state.has_errors();
         let is_non_fatal_errors_active = self
@@ -715,6 +680,7 @@ where
         let is_caught_up = self.is_caught_up(&block_ptr).await?;
 
         persisted_data_sources.extend(persisted_off_chain_data_sources);
+        removed_data_sources.extend(processed_offchain_data_sources);
         self.inputs
             .store
             .transact_block_operations(
@@ -725,7 +691,7 @@ where
                 &self.metrics.host.stopwatch,
                 persisted_data_sources,
                 deterministic_errors,
-                processed_offchain_data_sources,
+                removed_data_sources,
                 is_non_fatal_errors_active,
                 is_caught_up,
             )
@@ -775,6 +741,226 @@ where
         }
     }
 
+    /// Match the triggers of `block` to the hosts of the subgraph and run
+    /// them one after the other
+    async fn process_triggers(
+        &mut self,
+        logger: &Logger,
+        block: &Arc<C::Block>,
+        triggers: Vec<Trigger<C>>,
+        block_state: BlockState,
+        proof_of_indexing: &SharedProofOfIndexing,
+        causality_region: &str,
+    ) -> Result<BlockState, MappingError> {
+        // Match and decode all triggers in the block
+        let hosts_filter = |trigger: &TriggerData<C>| self.ctx.instance.hosts_for_trigger(trigger);
+        let match_res = self
+            .ctx
+            .decoder
+            .match_and_decode_many(
+                logger,
+                block,
+                triggers.into_iter().map(|t| match t {
+                    Trigger::Chain(t) => TriggerData::Onchain(t),
+                    Trigger::Subgraph(t) => TriggerData::Subgraph(t),
+                }),
+                hosts_filter,
+                &self.metrics.subgraph,
+            )
+            .await;
+
+        // Process events one after the other, passing in entity operations
+        // collected previously to every new event being processed
+        let mut res = Ok(block_state);
+        match match_res {
+            Ok(runnables) => {
+                for runnable in runnables {
+                    let process_res = self
+                        .ctx
+                        .trigger_processor
+                        .process_trigger(
+                            &self.logger,
+                            runnable.hosted_triggers,
+                            block,
+                            res.unwrap(),
+                            proof_of_indexing,
+                            causality_region,
+                            &self.inputs.debug_fork,
+                            &self.metrics.subgraph,
+                            self.inputs.instrument,
+                        )
+                        .await
+                        .map_err(|e| e.add_trigger_context(&runnable.trigger));
+                    match process_res {
+                        Ok(state) => res = Ok(state),
+                        Err(e) => {
+                            res = Err(e);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                res = Err(e);
+            }
+        };
+
+        res
+    }
+
+    /// Instantiate the data sources that the triggers of `block` created and
+    /// run the triggers of `block` that match them
+    async fn handle_created_data_sources(
+        &mut self,
+        logger: &Logger,
+        block: &Arc<C::Block>,
+        firehose_cursor: &FirehoseCursor,
+        mut block_state: BlockState,
+        proof_of_indexing: &SharedProofOfIndexing,
+        causality_region: &str,
+    ) -> Result<BlockState, BlockProcessingError> {
+        let _section = self
+            .metrics
+            .stream
+            .stopwatch
+            .start_section(HANDLE_CREATED_DS_SECTION_NAME);
+
+        // This loop will:
+        // 1. Instantiate created data sources.
+        // 2. Process those data sources for the current block.
+        // Until no data sources are created or MAX_DATA_SOURCES is hit.
+
+        // Note that this algorithm processes data sources spawned on the same block _breadth
+        // first_ on the tree implied by the parent-child relationship between data sources. Only a
+        // very contrived subgraph would be able to observe this.
+        while block_state.has_created_data_sources() {
+            // Instantiate dynamic data sources, removing them from the block state.
+            let (data_sources, runtime_hosts) =
+                self.create_dynamic_data_sources(block_state.drain_created_data_sources())?;
+
+            let filter = &Arc::new(TriggerFilterWrapper::new(
+                C::TriggerFilter::from_data_sources(
+                    data_sources.iter().filter_map(DataSource::as_onchain),
+                ),
+                vec![],
+            ));
+
+            let block: Arc<C::Block> = if self.inputs.chain.is_refetch_block_required() {
+                let cur = firehose_cursor.clone();
+                let log = logger.cheap_clone();
+                let chain = self.inputs.chain.cheap_clone();
+                Arc::new(
+                    retry(
+                        "refetch firehose block after dynamic datasource was added",
+                        logger,
+                    )
+                    .limit(5)
+                    .no_timeout()
+                    .run(move || {
+                        let cur = cur.clone();
+                        let log = log.cheap_clone();
+                        let chain = chain.cheap_clone();
+                        async move { chain.refetch_firehose_block(&log, cur).await }
+                    })
+                    .await?,
+                )
+            } else {
+                block.cheap_clone()
+            };
+
+            // Reprocess the triggers from this block that match the new data sources
+            let block_with_triggers = self
+                .inputs
+                .triggers_adapter
+                .triggers_in_block(logger, block.as_ref().clone(), filter)
+                .await?;
+
+            let triggers = block_with_triggers.trigger_data;
+
+            if triggers.len() == 1 {
+                info!(
+                    logger,
+                    "1 trigger found in this block for the new data sources"
+                );
+            } else if triggers.len() > 1 {
+                info!(
+                    logger,
+                    "{} triggers found in this block for the new data sources",
+                    triggers.len()
+                );
+            }
+
+            // Add entity operations for the new data sources to the block state
+            // and add runtimes for the data sources to the subgraph instance.
+            self.persist_dynamic_data_sources(&mut block_state, data_sources);
+
+            // Process the triggers in each host in the same order the
+            // corresponding data sources have been created.
+            let match_res: Result<Vec<_>, _> = self
+                .ctx
+                .decoder
+                .match_and_decode_many(
+                    logger,
+                    &block,
+                    triggers.into_iter().map(|t| match t {
+                        Trigger::Chain(t) => TriggerData::Onchain(t),
+                        Trigger::Subgraph(_) => unreachable!(), // TODO(krishna): Re-evaulate this
+                    }),
+                    |_| Box::new(runtime_hosts.iter().map(Arc::as_ref)),
+                    &self.metrics.subgraph,
+                )
+                .await;
+
+            let mut res = Ok(block_state);
+            match match_res {
+                Ok(runnables) => {
+                    for runnable in runnables {
+                        let process_res = self
+                            .ctx
+                            .trigger_processor
+                            .process_trigger(
+                                &self.logger,
+                                runnable.hosted_triggers,
+                                &block,
+                                res.unwrap(),
+                                proof_of_indexing,
+                                causality_region,
+                                &self.inputs.debug_fork,
+                                &self.metrics.subgraph,
+                                self.inputs.instrument,
+                            )
+                            .await
+                            .map_err(|e| e.add_trigger_context(&runnable.trigger));
+                        match process_res {
+                            Ok(state) => res = Ok(state),
+                            Err(e) => {
+                                res = Err(e);
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    res = Err(e);
+                }
+            }
+
+            block_state = res.map_err(|e| {
+                // This treats a `PossibleReorg` as an ordinary error which will fail the subgraph.
+                // This can cause an unnecessary subgraph failure, to fix it we need to figure out a
+                // way to revert the effect of `create_dynamic_data_sources` so we may return a
+                // clean context as in b21fa73b-6453-4340-99fb-1a78ec62efb1.
+                match e {
+                    MappingError::PossibleReorg(e) | MappingError::Unknown(e) => {
+                        BlockProcessingError::Unknown(e)
+                    }
+                }
+            })?;
+        }
+
+        Ok(block_state)
+    }
+
     async fn process_wasm_block(
         &mut self,
         proof_of_indexing: &SharedProofOfIndexing,
@@ -878,6 +1064,80 @@ where
         Ok((data_sources, runtime_hosts))
     }
 
+    /// Mark the data sources that mappings asked to remove as done at `block` and return
+    /// them so that their removal can be persisted
+    fn remove_dynamic_data_sources(
+        &mut self,
+        block: BlockNumber,
+        removed_data_sources: Vec<RemovedDataSource>,
+    ) -> Result<Vec<StoredDynamicDataSource>, Error> {
+        let mut data_sources = vec![];
+
+        for removed in removed_data_sources {
+            let (name, address) = match removed {
+                RemovedDataSource::Current { name, address } => (name, address),
+                RemovedDataSource::Template(info) => {
+                    let name = info.template.name().to_string();
+                    let manifest_idx = info
+                        .template
+                        .manifest_idx()
+                        .ok_or_else(|| anyhow!("Expected template to have an idx"))?;
+                    let template = self
+                        .inputs
+                        .templates
+                        .iter()
+                        .find(|t| t.manifest_idx() == manifest_idx)
+                        .ok_or_else(|| {
+                            anyhow!("Expected to find a template for this dynamic data source")
+                        })?;
+
+                    // Instantiate the data source to find out which address the
+                    // parameters refer to
+                    let res = C::DataSource::from_template_info(info, template)
+                        .map_err(DataSourceCreationError::from);
+                    match res {
+                        Ok(ds) => match ds.address() {
+                            Some(address) => (name, address.to_vec()),
+                            None => {
+                                warn!(
+                                    self.logger,
+                                    "ignoring removal of data source without an address";
+                                    "name" => &name,
+                                );
+                                continue;
+                            }
+                        },
+                        Err(e @ DataSourceCreationError::Ignore(..)) => {
+                            warn!(self.logger, "{}", e.to_string());
+                            continue;
+                        }
+                        Err(DataSourceCreationError::Unknown(e)) => return Err(e),
+                    }
+                }
+            };
+
+            let removed = self.ctx.remove_data_sources(&name, &address, block);
+            if removed.is_empty() {
+                warn!(
+                    self.logger,
+                    "no active data source to remove";
+                    "name" => &name,
+                    "address" => hex::encode(&address),
+                );
+            } else {
+                debug!(
+                    self.logger,
+                    "Removing {} dynamic data source(s)", removed.len();
+                    "name" => &name,
+                    "address" => hex::encode(&address),
+                );
+            }
+            data_sources.extend(removed);
+        }
+
+        Ok(data_sources)
+    }
+
     async fn handle_action(
         &mut self,
         start: Instant,
@@ -1576,9 +1836,11 @@ where
             .deployment_head
             .set(subgraph_ptr.number as f64);
 
-        self.revert_state_to(revert_to_ptr.number)?;
+        let reactivated = self.revert_state_to(revert_to_ptr.number)?;
 
-        let needs_restart: bool = self.needs_restart(revert_to_ptr, subgraph_ptr);
+        // Data sources that were removed in the reverted blocks need to be added back to the
+        // trigger filters
+        let needs_restart: bool = reactivated || self.needs_restart(revert_to_ptr, subgraph_ptr);
 
         let action = if needs_restart {
             Action::Restart
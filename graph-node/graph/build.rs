@@ -5,6 +5,7 @@ fn main() {
         .compile_protos(
             &[
                 "proto/firehose.proto",
+                "proto/bstream.proto",
                 "proto/ethereum/transforms.proto",
                 "proto/near/transforms.proto",
             ],
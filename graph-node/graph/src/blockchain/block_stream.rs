@@ -14,6 +14,7 @@ use std::time::Instant;
 use thiserror::Error;
 use tokio::sync::mpsc::{self, Receiver, Sender};
 
+use super::merged_blocks_block_stream::MergedBlocksSource;
 use super::substreams_block_stream::SubstreamsLogData;
 use super::{Block, BlockPtr, BlockTime, Blockchain, Trigger, TriggerFilterWrapper};
 use crate::anyhow::Result;
@@ -153,6 +154,25 @@ pub trait BlockStreamBuilder<C: Blockchain>: Send + Sync {
         unified_api_version: UnifiedMappingApiVersion,
     ) -> Result<Box<dyn BlockStream<C>>>;
 
+    /// Build a block stream that reads blocks from Firehose `merged-blocks`
+    /// bundles in `source`. Only chains whose blocks can be decoded from
+    /// Firehose responses support this
+    async fn build_merged_blocks(
+        &self,
+        _chain: &C,
+        _deployment: DeploymentLocator,
+        _source: Arc<MergedBlocksSource>,
+        _start_blocks: Vec<BlockNumber>,
+        _subgraph_current_block: Option<BlockPtr>,
+        _filter: Arc<C::TriggerFilter>,
+        _unified_api_version: UnifiedMappingApiVersion,
+    ) -> Result<Box<dyn BlockStream<C>>> {
+        Err(anyhow!(
+            "merged blocks are not supported for {} chains",
+            C::KIND
+        ))
+    }
+
     async fn build_subgraph_block_stream(
         &self,
         chain: &C,
@@ -0,0 +1,508 @@
+use super::block_stream::{
+    BlockStream, BlockStreamError, BlockStreamEvent, FirehoseMapper, FIREHOSE_BUFFER_STREAM_SIZE,
+};
+use super::Blockchain;
+use crate::components::network_provider::ProviderName;
+use crate::firehose::{self, BstreamBlock};
+use crate::prelude::*;
+use crate::util::backoff::ExponentialBackoff;
+use anyhow::bail;
+use async_stream::try_stream;
+use futures03::{Stream, StreamExt};
+use object_store::{local::LocalFileSystem, path::Path, ObjectStore};
+use prost::Message;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// The number of blocks in each `merged-blocks` bundle. Bundles are named
+/// after the number of their first block, which is always a multiple of
+/// this
+pub const MERGED_BLOCKS_BUNDLE_SIZE: BlockNumber = 100;
+
+const DBIN_MAGIC: &[u8] = b"dbin";
+
+/// A directory or object store path that holds Firehose `merged-blocks`
+/// bundles, i.e., files named like `0000012300.dbin.zst` that each contain
+/// `MERGED_BLOCKS_BUNDLE_SIZE` final blocks
+pub struct MergedBlocksSource {
+    pub provider: ProviderName,
+    url: String,
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    poll_interval: Duration,
+}
+
+impl std::fmt::Debug for MergedBlocksSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MergedBlocksSource")
+            .field("provider", &self.provider)
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+impl MergedBlocksSource {
+    /// Create a source for the bundles at `url`, which is either the path
+    /// of a local directory or an object store URL like
+    /// `gs://bucket/merged-blocks`. When the next bundle is not available
+    /// yet, check for it again every `poll_interval`
+    pub fn new(
+        provider: impl Into<ProviderName>,
+        url: &str,
+        poll_interval: Duration,
+    ) -> Result<Self, Error> {
+        // Single letter schemes are Windows drive letters, not URLs
+        let (store, prefix): (Arc<dyn ObjectStore>, Path) = match url::Url::parse(url) {
+            Ok(parsed) if parsed.scheme().len() > 1 => {
+                let (store, prefix) = object_store::parse_url(&parsed)
+                    .with_context(|| format!("invalid merged blocks url `{}`", url))?;
+                (Arc::from(store), prefix)
+            }
+            _ => {
+                let store = LocalFileSystem::new_with_prefix(url)
+                    .with_context(|| format!("invalid merged blocks directory `{}`", url))?;
+                (Arc::new(store), Path::default())
+            }
+        };
+
+        Ok(Self {
+            provider: provider.into(),
+            url: url.to_string(),
+            store,
+            prefix,
+            poll_interval,
+        })
+    }
+
+    /// Read the bundle whose first block is `base`. Returns `None` if that
+    /// bundle does not exist (yet)
+    pub async fn bundle(&self, base: BlockNumber) -> Result<Option<Vec<BstreamBlock>>, Error> {
+        for (extension, compressed) in [("dbin.zst", true), ("dbin", false)] {
+            let path = self.prefix.child(format!("{:010}.{}", base, extension));
+            let bytes = match self.store.get(&path).await {
+                Ok(result) => result.bytes().await?,
+                Err(object_store::Error::NotFound { .. }) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let blocks =
+                crate::spawn_blocking_allow_panic(move || decode_bundle(&bytes, compressed))
+                    .await??;
+            return Ok(Some(blocks));
+        }
+        Ok(None)
+    }
+}
+
+impl std::fmt::Display for MergedBlocksSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.url)
+    }
+}
+
+/// Decode a bundle, which is a `dbin` file, optionally compressed with
+/// zstd, whose messages are `sf.bstream.v1.Block`
+fn decode_bundle(bytes: &[u8], compressed: bool) -> Result<Vec<BstreamBlock>, Error> {
+    let decompressed;
+    let bytes = if compressed {
+        decompressed = zstd::stream::decode_all(bytes).context("invalid zstd data")?;
+        decompressed.as_slice()
+    } else {
+        bytes
+    };
+
+    read_dbin(bytes)?
+        .into_iter()
+        .map(|message| BstreamBlock::decode(message).map_err(Error::from))
+        .collect()
+}
+
+/// Split a `dbin` file into its messages. The file starts with the magic
+/// bytes `dbin` and a format version. Version 0 is followed by a 3 byte
+/// content type and a 2 byte content version, version 1 by the length of
+/// the content type as a big endian `u16` and the content type. After the
+/// header, each message is prefixed with its length as a big endian `u32`
+fn read_dbin(mut data: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+        if data.len() < len {
+            bail!("unexpected end of dbin file");
+        }
+        let (head, tail) = data.split_at(len);
+        *data = tail;
+        Ok(head)
+    }
+
+    if take(&mut data, DBIN_MAGIC.len())? != DBIN_MAGIC {
+        bail!("not a dbin file");
+    }
+    match take(&mut data, 1)?[0] {
+        0 => {
+            take(&mut data, 5)?;
+        }
+        1 => {
+            let len = u16::from_be_bytes(take(&mut data, 2)?.try_into()?) as usize;
+            take(&mut data, len)?;
+        }
+        version => bail!("unsupported dbin version {}", version),
+    }
+
+    let mut messages = Vec::new();
+    while !data.is_empty() {
+        let len = u32::from_be_bytes(take(&mut data, 4)?.try_into()?) as usize;
+        messages.push(take(&mut data, len)?);
+    }
+    Ok(messages)
+}
+
+/// Select the blocks from `bundle` that continue the chain that ends at
+/// `last`, ignoring blocks before `next`. Older bundles can contain blocks
+/// from forks; we only use blocks that the blocks with the highest number
+/// in the bundle build on. When several forks reach the end of the bundle,
+/// the blocks after their common ancestor are put into `held` and
+/// considered again together with the next bundle
+fn chain_blocks(
+    bundle: Vec<BstreamBlock>,
+    next: BlockNumber,
+    last: &mut Option<BlockPtr>,
+    held: &mut Vec<BstreamBlock>,
+) -> Result<Vec<BstreamBlock>, Error> {
+    let bundle: Vec<_> = held
+        .drain(..)
+        .chain(
+            bundle
+                .into_iter()
+                .filter(|block| block.number as BlockNumber >= next),
+        )
+        .collect();
+    let Some(tip) = bundle.iter().map(|block| block.number).max() else {
+        return Ok(vec![]);
+    };
+
+    let by_id: HashMap<_, _> = bundle
+        .iter()
+        .map(|block| (block.id.as_str(), block))
+        .collect();
+    // The blocks that all blocks with the highest number build on
+    let canonical = bundle
+        .iter()
+        .filter(|block| block.number == tip)
+        .map(|mut block| {
+            let mut ids = HashSet::new();
+            while ids.insert(block.id.as_str()) {
+                match by_id.get(block.parent_id.as_str()) {
+                    Some(&parent) => block = parent,
+                    None => break,
+                }
+            }
+            ids
+        })
+        .reduce(|common, ids| &common & &ids)
+        .unwrap_or_default();
+    let settled = bundle
+        .iter()
+        .filter(|block| canonical.contains(block.id.as_str()))
+        .map(|block| block.number)
+        .max();
+
+    let mut blocks = Vec::with_capacity(bundle.len());
+    for block in bundle.iter() {
+        if !canonical.contains(block.id.as_str()) {
+            if settled.map_or(true, |settled| block.number > settled) {
+                held.push(block.clone());
+            }
+            continue;
+        }
+        let number = block.number as BlockNumber;
+        let hash = BlockHash::try_from(block.id.as_str())
+            .with_context(|| format!("invalid id `{}` for block {}", block.id, number))?;
+
+        if let Some(last) = last.as_ref() {
+            if number <= last.number {
+                // A fork of a block we already passed on
+                continue;
+            }
+            let parent = BlockHash::try_from(block.parent_id.as_str()).with_context(|| {
+                format!(
+                    "invalid parent id `{}` for block {}",
+                    block.parent_id, number
+                )
+            })?;
+            if parent != last.hash {
+                bail!(
+                    "block #{} ({}) in merged blocks does not build on block #{} ({}); \
+                     rewind the subgraph to a final block to continue",
+                    number,
+                    hash,
+                    last.number,
+                    last.hash
+                );
+            }
+        }
+
+        *last = Some(BlockPtr::new(hash, number));
+        blocks.push(block.clone());
+    }
+    Ok(blocks)
+}
+
+/// Wrap a block from a bundle in the response a Firehose endpoint would
+/// send for it so that it can be passed to a `FirehoseMapper`. The cursor
+/// is empty since merged blocks have no Firehose cursor
+fn to_response(block: BstreamBlock) -> firehose::Response {
+    let payload = block.payload.unwrap_or_else(|| prost_types::Any {
+        type_url: String::new(),
+        value: block.payload_buffer,
+    });
+
+    firehose::Response {
+        block: Some(payload),
+        step: firehose::ForkStep::StepNew as i32,
+        cursor: String::new(),
+    }
+}
+
+/// A block stream that reads final blocks from Firehose `merged-blocks`
+/// bundles instead of a Firehose endpoint. When it reaches the end of the
+/// available bundles, it waits for the next bundle to appear
+pub struct MergedBlocksBlockStream<C: Blockchain> {
+    stream: Pin<Box<dyn Stream<Item = Result<BlockStreamEvent<C>, BlockStreamError>> + Send>>,
+}
+
+impl<C> MergedBlocksBlockStream<C>
+where
+    C: Blockchain,
+{
+    pub fn new<F>(
+        source: Arc<MergedBlocksSource>,
+        subgraph_current_block: Option<BlockPtr>,
+        mapper: Arc<F>,
+        start_blocks: Vec<BlockNumber>,
+        logger: Logger,
+    ) -> Self
+    where
+        F: FirehoseMapper<C> + 'static,
+    {
+        let start_block_num = subgraph_current_block
+            .as_ref()
+            .map(|ptr| ptr.number + 1)
+            .unwrap_or_else(|| start_blocks.into_iter().min().unwrap_or(0));
+
+        MergedBlocksBlockStream {
+            stream: Box::pin(stream_blocks(
+                source,
+                mapper,
+                start_block_num,
+                subgraph_current_block,
+                logger,
+            )),
+        }
+    }
+}
+
+fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
+    source: Arc<MergedBlocksSource>,
+    mapper: Arc<F>,
+    start_block_num: BlockNumber,
+    subgraph_current_block: Option<BlockPtr>,
+    logger: Logger,
+) -> impl Stream<Item = Result<BlockStreamEvent<C>, BlockStreamError>> {
+    let logger = logger.new(o!("provider" => source.provider.to_string()));
+    let mut next = start_block_num;
+    let mut last = subgraph_current_block;
+    let mut held = Vec::new();
+
+    // Back off exponentially when reading or decoding a bundle fails
+    let mut backoff = ExponentialBackoff::new(Duration::from_millis(500), Duration::from_secs(45));
+
+    info!(
+        &logger,
+        "Streaming blocks from merged blocks";
+        "url" => source.to_string(),
+        "start_block" => start_block_num,
+    );
+
+    try_stream! {
+        loop {
+            let base = next - next % MERGED_BLOCKS_BUNDLE_SIZE;
+            let bundle = match source.bundle(base).await {
+                Ok(Some(bundle)) => {
+                    backoff.reset();
+                    bundle
+                }
+                Ok(None) => {
+                    debug!(&logger, "Waiting for merged blocks bundle"; "bundle" => base);
+                    tokio::time::sleep(source.poll_interval).await;
+                    continue;
+                }
+                Err(e) => {
+                    error!(&logger, "Failed to read merged blocks bundle: {:#}", e; "bundle" => base);
+                    backoff.sleep_async().await;
+                    continue;
+                }
+            };
+
+            for block in chain_blocks(bundle, next, &mut last, &mut held)? {
+                let number = block.number as BlockNumber;
+                let event = mapper
+                    .to_block_stream_event(&logger, &to_response(block))
+                    .await
+                    .context("Mapping block to BlockStreamEvent failed")?;
+
+                yield event;
+
+                next = number + 1;
+            }
+
+            // Chains with null blocks can have bundles with fewer blocks
+            next = next.max(base + MERGED_BLOCKS_BUNDLE_SIZE);
+        }
+    }
+}
+
+impl<C: Blockchain> Stream for MergedBlocksBlockStream<C> {
+    type Item = Result<BlockStreamEvent<C>, BlockStreamError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.poll_next_unpin(cx)
+    }
+}
+
+impl<C: Blockchain> BlockStream<C> for MergedBlocksBlockStream<C> {
+    fn buffer_size_hint(&self) -> usize {
+        FIREHOSE_BUFFER_STREAM_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chain_blocks, read_dbin, BstreamBlock};
+    use crate::blockchain::BlockPtr;
+
+    fn dbin(version: u8, messages: &[&[u8]]) -> Vec<u8> {
+        let mut data = b"dbin".to_vec();
+        data.push(version);
+        match version {
+            0 => data.extend_from_slice(b"ETH00"),
+            _ => {
+                let content_type = b"type.googleapis.com/sf.bstream.v1.Block";
+                data.extend_from_slice(&(content_type.len() as u16).to_be_bytes());
+                data.extend_from_slice(content_type);
+            }
+        }
+        for message in messages {
+            data.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            data.extend_from_slice(message);
+        }
+        data
+    }
+
+    fn block(number: u64, id: &str, parent_id: &str) -> BstreamBlock {
+        BstreamBlock {
+            number,
+            id: id.to_string(),
+            parent_id: parent_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn numbers_and_ids(blocks: &[BstreamBlock]) -> Vec<(u64, &str)> {
+        blocks.iter().map(|b| (b.number, b.id.as_str())).collect()
+    }
+
+    #[test]
+    fn reads_dbin_files() {
+        for version in [0, 1] {
+            let data = dbin(version, &[b"one", b"", b"three"]);
+            let messages = read_dbin(&data).unwrap();
+            assert_eq!(vec![&b"one"[..], b"", b"three"], messages);
+        }
+
+        assert!(read_dbin(b"nope\x01").is_err());
+        assert!(read_dbin(b"dbin\x02").is_err());
+
+        let mut data = dbin(1, &[b"one"]);
+        data.truncate(data.len() - 1);
+        assert!(read_dbin(&data).is_err());
+    }
+
+    #[test]
+    fn chain_blocks_skips_forks() {
+        let bundle = vec![
+            block(100, "0a", "09"),
+            block(101, "1b", "0a"),
+            block(101, "1a", "0a"),
+            block(102, "2a", "1a"),
+            block(103, "3a", "2a"),
+        ];
+
+        // Of the two blocks #101, the one that #102 builds on is used
+        let mut last = None;
+        let mut held = Vec::new();
+        let blocks = chain_blocks(bundle.clone(), 100, &mut last, &mut held).unwrap();
+        assert_eq!(
+            vec![(100, "0a"), (101, "1a"), (102, "2a"), (103, "3a")],
+            numbers_and_ids(&blocks)
+        );
+
+        let mut last = Some(BlockPtr::new(vec![0x0a].into(), 100));
+        let blocks = chain_blocks(bundle.clone(), 101, &mut last, &mut held).unwrap();
+        assert_eq!(
+            vec![(101, "1a"), (102, "2a"), (103, "3a")],
+            numbers_and_ids(&blocks)
+        );
+        assert_eq!(Some(BlockPtr::new(vec![0x3a].into(), 103)), last);
+
+        // A block that does not build on the subgraph pointer is an error
+        let mut last = Some(BlockPtr::new(vec![0xff].into(), 101));
+        assert!(chain_blocks(bundle, 102, &mut last, &mut held).is_err());
+        assert!(held.is_empty());
+    }
+
+    #[test]
+    fn chain_blocks_holds_back_forks_at_the_end_of_a_bundle() {
+        // Neither block #199 is a parent in this bundle, and the first one
+        // is not the one the chain continues on
+        let bundle = vec![
+            block(198, "c6", "c5"),
+            block(199, "7b", "c6"),
+            block(199, "7a", "c6"),
+        ];
+        let mut last = Some(BlockPtr::new(vec![0xc5].into(), 197));
+        let mut held = Vec::new();
+        let blocks = chain_blocks(bundle, 198, &mut last, &mut held).unwrap();
+        assert_eq!(vec![(198, "c6")], numbers_and_ids(&blocks));
+        assert_eq!(vec![(199, "7b"), (199, "7a")], numbers_and_ids(&held));
+
+        // The next bundle decides which block #199 is used
+        let bundle = vec![block(200, "c8", "7a"), block(201, "c9", "c8")];
+        let blocks = chain_blocks(bundle, 200, &mut last, &mut held).unwrap();
+        assert_eq!(
+            vec![(199, "7a"), (200, "c8"), (201, "c9")],
+            numbers_and_ids(&blocks)
+        );
+        assert!(held.is_empty());
+        assert_eq!(Some(BlockPtr::new(vec![0xc9].into(), 201)), last);
+
+        // Forks that are longer than one block are held back from where
+        // they split off
+        let bundle = vec![
+            block(202, "ca", "c9"),
+            block(203, "3a", "ca"),
+            block(203, "3b", "ca"),
+            block(204, "4a", "3a"),
+            block(204, "4b", "3b"),
+        ];
+        let blocks = chain_blocks(bundle, 202, &mut last, &mut held).unwrap();
+        assert_eq!(vec![(202, "ca")], numbers_and_ids(&blocks));
+        assert_eq!(4, held.len());
+
+        let bundle = vec![block(205, "c5", "4b")];
+        let blocks = chain_blocks(bundle, 205, &mut last, &mut held).unwrap();
+        assert_eq!(
+            vec![(203, "3b"), (204, "4b"), (205, "c5")],
+            numbers_and_ids(&blocks)
+        );
+        assert!(held.is_empty());
+    }
+}
@@ -22,7 +22,7 @@ use std::{
     convert::TryFrom,
     sync::Arc,
 };
-use web3::types::H256;
+use web3::types::{Log, TransactionReceipt, H256};
 
 use super::{
     block_stream::{self, BlockStream, FirehoseCursor},
@@ -559,6 +559,35 @@ impl ChainStore for MockChainStore {
     async fn clear_call_cache(&self, _from: BlockNumber, _to: BlockNumber) -> Result<(), Error> {
         unimplemented!()
     }
+    async fn cached_logs(
+        &self,
+        _filter_id: &[u8],
+        _from: BlockNumber,
+        _to: BlockNumber,
+    ) -> Result<Option<Vec<Log>>, Error> {
+        unimplemented!()
+    }
+    async fn set_cached_logs(
+        &self,
+        _filter_id: &[u8],
+        _from: BlockNumber,
+        _to: BlockNumber,
+        _logs: &[Log],
+    ) -> Result<(), Error> {
+        unimplemented!()
+    }
+    async fn cached_receipts(&self, _hashes: &[H256]) -> Result<Vec<TransactionReceipt>, Error> {
+        unimplemented!()
+    }
+    async fn set_cached_receipts(
+        &self,
+        _receipts: &[Arc<TransactionReceipt>],
+    ) -> Result<(), Error> {
+        unimplemented!()
+    }
+    async fn clear_log_cache(&self, _from: BlockNumber, _to: BlockNumber) -> Result<(), Error> {
+        unimplemented!()
+    }
     fn chain_identifier(&self) -> Result<ChainIdentifier, Error> {
         unimplemented!()
     }
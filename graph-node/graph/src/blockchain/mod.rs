@@ -8,6 +8,7 @@ pub mod client;
 mod empty_node_capabilities;
 pub mod firehose_block_ingestor;
 pub mod firehose_block_stream;
+pub mod merged_blocks_block_stream;
 pub mod mock;
 mod noop_runtime_adapter;
 pub mod polling_block_stream;
@@ -26,7 +27,7 @@ use crate::{
         trigger_processor::RunnableTriggers,
     },
     data::subgraph::{UnifiedMappingApiVersion, MIN_SPEC_VERSION},
-    data_source::{self, subgraph, DataSourceTemplateInfo},
+    data_source::{self, common::MappingLanguage, subgraph, DataSourceTemplateInfo},
     prelude::{DataSourceContext, DeploymentHash},
     runtime::{gas::GasCounter, AscHeap, HostExportError},
 };
@@ -212,6 +213,13 @@ pub trait Blockchain: Debug + Sized + Send + Sync + Unpin + 'static {
 
     fn is_refetch_block_required(&self) -> bool;
 
+    /// Check that this chain has a provider that can serve subgraphs that
+    /// require `capabilities`. Used to reject deployments that this node
+    /// could never index.
+    fn check_capabilities(&self, _capabilities: &Self::NodeCapabilities) -> Result<(), Error> {
+        Ok(())
+    }
+
     fn runtime(&self) -> anyhow::Result<(Arc<dyn RuntimeAdapter<Self>>, Self::DecoderHook)>;
 
     fn chain_client(&self) -> Arc<ChainClient<Self>>;
@@ -332,6 +340,12 @@ pub trait DataSource<C: Blockchain>: 'static + Sized + Send + Sync + Clone {
 
     fn runtime(&self) -> Option<Arc<Vec<u8>>>;
 
+    /// The language of the mapping, which determines the ABI that is used
+    /// to run it
+    fn language(&self) -> MappingLanguage {
+        MappingLanguage::AssemblyScript
+    }
+
     fn handler_kinds(&self) -> HashSet<&str>;
 
     /// Checks if `trigger` matches this data source, and if so decodes it into a `MappingTrigger`.
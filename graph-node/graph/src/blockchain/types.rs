@@ -1,4 +1,5 @@
 use anyhow::anyhow;
+use chrono::{Datelike, TimeZone, Utc};
 use diesel::deserialize::FromSql;
 use diesel::pg::Pg;
 use diesel::serialize::{Output, ToSql};
@@ -611,14 +612,30 @@ impl BlockTime {
     }
 
     /// Return the number of the last bucket that starts before `self`
-    /// assuming buckets have the given `length`
-    pub(crate) fn bucket(&self, length: Duration) -> usize {
-        // Treat any time before the epoch as zero, i.e., the epoch; in
-        // practice, we will only deal with block times that are pretty far
-        // after the epoch
-        let ts = self.0.timestamp_millis().max(0);
-        let nr = ts as u128 / length.as_millis();
-        usize::try_from(nr).unwrap()
+    /// assuming buckets have the given `length` and the first bucket
+    /// starts `origin` after the epoch
+    pub(crate) fn bucket(&self, length: Duration, origin: Duration) -> i64 {
+        let ts = self.0.timestamp_millis() - origin.as_millis() as i64;
+        ts.div_euclid(length.as_millis() as i64)
+    }
+
+    /// Return the number of calendar months between the start of the
+    /// month of the epoch and the start of the month of `self`
+    pub(crate) fn months_since_epoch(&self) -> i64 {
+        let ts = self.0 .0;
+        (ts.year() as i64 - 1970) * 12 + ts.month0() as i64
+    }
+
+    /// Return the block time at the start of the month that is `months`
+    /// calendar months after the month of the epoch
+    pub(crate) fn start_of_month(months: i64) -> Self {
+        let year = 1970 + months.div_euclid(12);
+        let month = months.rem_euclid(12) as u32 + 1;
+        let start = Utc
+            .with_ymd_and_hms(year as i32, month, 1, 0, 0, 0)
+            .single()
+            .expect("the start of a month is a valid time");
+        Self(Timestamp(start))
     }
 }
 
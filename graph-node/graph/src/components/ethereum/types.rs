@@ -122,6 +122,9 @@ pub struct EthereumCall {
     pub block_hash: H256,
     pub transaction_hash: Option<H256>,
     pub transaction_index: u64,
+    /// Whether the call was built from a transaction instead of a trace;
+    /// such calls have no `output`
+    pub from_transaction: bool,
 }
 
 impl EthereumCall {
@@ -158,6 +161,42 @@ impl EthereumCall {
             block_hash: trace.block_hash,
             transaction_hash: trace.transaction_hash,
             transaction_index,
+            from_transaction: false,
+        })
+    }
+
+    /// Build the call that `transaction` in `block` makes to a contract
+    /// from the transaction itself, for chains whose nodes can not trace
+    /// transactions. Since the transaction was not traced, the `output` of
+    /// the call is always empty.
+    pub fn try_from_transaction(
+        block: &LightEthereumBlock,
+        transaction: &Transaction,
+        receipt: &TransactionReceipt,
+    ) -> Option<Self> {
+        // Call handlers should only run on successful calls
+        if !evaluate_transaction_status(receipt.status) {
+            return None;
+        }
+        // Contract creations have no `to`, and plain value transfers have
+        // no input; neither is an explicit method call
+        let to = transaction.to?;
+        if transaction.input.0.len() < 4 {
+            return None;
+        }
+
+        Some(EthereumCall {
+            from: transaction.from?,
+            to,
+            value: transaction.value,
+            gas_used: receipt.gas_used.unwrap_or_default(),
+            input: transaction.input.clone(),
+            output: Bytes::default(),
+            block_number: BlockNumber::try_from(block.number?.as_u64()).ok()?,
+            block_hash: block.hash?,
+            transaction_hash: Some(transaction.hash),
+            transaction_index: transaction.transaction_index?.as_u64(),
+            from_transaction: true,
         })
     }
 }
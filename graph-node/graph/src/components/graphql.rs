@@ -1,6 +1,7 @@
+use crate::components::store::DeploymentId;
 use crate::data::query::QueryResults;
 use crate::data::query::{Query, QueryTarget};
-use crate::prelude::DeploymentHash;
+use crate::prelude::{BlockNumber, DeploymentHash};
 
 use async_trait::async_trait;
 use std::sync::Arc;
@@ -27,6 +28,18 @@ pub trait GraphQlRunner: Send + Sync + 'static {
         max_skip: Option<u32>,
     ) -> QueryResults;
 
+    /// Returns the deployment that `target` currently resolves to and the
+    /// number of the latest block it has processed. Live queries use this
+    /// to decide whether they need to be rerun. Returns `None` if the head
+    /// can not be determined, in which case live queries are rerun
+    /// unconditionally.
+    async fn deployment_head(
+        self: Arc<Self>,
+        _target: QueryTarget,
+    ) -> Option<(DeploymentId, BlockNumber)> {
+        None
+    }
+
     fn metrics(&self) -> Arc<dyn GraphQLMetrics>;
 }
 
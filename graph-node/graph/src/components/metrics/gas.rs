@@ -1,12 +1,19 @@
 use super::MetricsRegistry;
+use crate::components::store::BlockNumber;
+use crate::env::ENV_VARS;
 use crate::{cheap_clone::CheapClone, prelude::DeploymentHash};
 use prometheus::CounterVec;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
 #[derive(Clone)]
 pub struct GasMetrics {
     pub gas_counter: CounterVec,
     pub op_counter: CounterVec,
+    /// The profile of this deployment's handlers if the mapping profiler
+    /// is turned on
+    pub profile: Option<Arc<MappingProfile>>,
 }
 
 impl CheapClone for GasMetrics {
@@ -14,6 +21,7 @@ impl CheapClone for GasMetrics {
         Self {
             gas_counter: self.gas_counter.clone(),
             op_counter: self.op_counter.clone(),
+            profile: self.profile.cheap_clone(),
         }
     }
 }
@@ -48,9 +56,15 @@ impl GasMetrics {
                 )
             });
 
+        let profile = ENV_VARS
+            .mappings
+            .profiler
+            .then(|| registry.mapping_profiles().deployment(&subgraph_id));
+
         GasMetrics {
             gas_counter,
             op_counter,
+            profile,
         }
     }
 
@@ -71,3 +85,186 @@ impl GasMetrics {
             .inc_by(op_count as f64);
     }
 }
+
+/// The calls a handler made to one host function
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HostFnProfile {
+    pub calls: u64,
+    pub gas: u64,
+}
+
+/// The cost of running a handler, summed over all its invocations
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HandlerProfile {
+    pub calls: u64,
+    /// The calls that failed, including ones that timed out
+    pub failures: u64,
+    pub wall_time: Duration,
+    pub gas: u64,
+    /// The calls the handler made to each host function
+    pub host_fns: HashMap<String, HostFnProfile>,
+}
+
+impl HandlerProfile {
+    fn add(&mut self, other: &HandlerProfile) {
+        self.calls += other.calls;
+        self.failures += other.failures;
+        self.wall_time += other.wall_time;
+        self.gas += other.gas;
+        for (name, host_fn) in &other.host_fns {
+            let entry = self.host_fns.entry(name.clone()).or_default();
+            entry.calls += host_fn.calls;
+            entry.gas += host_fn.gas;
+        }
+    }
+}
+
+/// The profiles of the handlers of one deployment for its most recent
+/// blocks. We only keep profiles for the last
+/// `GRAPH_MAPPING_PROFILER_BLOCKS` blocks that we processed
+#[derive(Debug, Default)]
+pub struct MappingProfile {
+    blocks: Mutex<BTreeMap<BlockNumber, HashMap<String, HandlerProfile>>>,
+}
+
+impl MappingProfile {
+    /// Add the cost of running `handler` once in `block`
+    pub fn record(&self, block: BlockNumber, handler: &str, profile: &HandlerProfile) {
+        let mut blocks = self.blocks.lock().unwrap();
+        blocks
+            .entry(block)
+            .or_default()
+            .entry(handler.to_string())
+            .or_default()
+            .add(profile);
+        while blocks.len() > ENV_VARS.mappings.profiler_blocks {
+            blocks.pop_first();
+        }
+    }
+
+    /// The profile of all handlers, summed over the blocks in `range`, and
+    /// the first and last block for which we actually have data in that
+    /// range. Returns `None` if we have no data for `range`
+    pub fn summarize(
+        &self,
+        range: std::ops::RangeInclusive<BlockNumber>,
+    ) -> Option<(BlockNumber, BlockNumber, HashMap<String, HandlerProfile>)> {
+        let blocks = self.blocks.lock().unwrap();
+        let mut blocks = blocks.range(range).peekable();
+        let first = *blocks.peek()?.0;
+
+        let mut last = first;
+        let mut handlers: HashMap<String, HandlerProfile> = HashMap::new();
+        for (block, profiles) in blocks {
+            last = *block;
+            for (handler, profile) in profiles {
+                handlers.entry(handler.clone()).or_default().add(profile);
+            }
+        }
+        Some((first, last, handlers))
+    }
+}
+
+/// The mapping profiles of all deployments in this process. The runtime
+/// records profiles here and the index node API reads them
+#[derive(Debug, Default)]
+pub struct MappingProfiles {
+    profiles: RwLock<HashMap<DeploymentHash, Arc<MappingProfile>>>,
+}
+
+impl MappingProfiles {
+    /// The profile for `deployment`, which is created if it does not exist
+    /// yet
+    pub fn deployment(&self, deployment: &DeploymentHash) -> Arc<MappingProfile> {
+        if let Some(profile) = self.get(deployment) {
+            return profile;
+        }
+        self.profiles
+            .write()
+            .unwrap()
+            .entry(deployment.clone())
+            .or_default()
+            .clone()
+    }
+
+    pub fn get(&self, deployment: &DeploymentHash) -> Option<Arc<MappingProfile>> {
+        self.profiles.read().unwrap().get(deployment).cloned()
+    }
+
+    /// Forget the profile of `deployment`; this is called when the
+    /// deployment stops so that we only keep profiles of running
+    /// deployments
+    pub fn remove(&self, deployment: &DeploymentHash) {
+        self.profiles.write().unwrap().remove(deployment);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(gas: u64, failed: bool) -> HandlerProfile {
+        HandlerProfile {
+            calls: 1,
+            failures: failed as u64,
+            wall_time: Duration::from_millis(gas),
+            gas,
+            host_fns: HashMap::from([(
+                "store.get".to_string(),
+                HostFnProfile { calls: 1, gas: 1 },
+            )]),
+        }
+    }
+
+    #[test]
+    fn records_and_summarizes_profiles() {
+        let mapping = MappingProfile::default();
+        assert!(mapping.summarize(0..=10).is_none());
+
+        mapping.record(1, "handleA", &profile(10, false));
+        mapping.record(2, "handleA", &profile(20, true));
+        mapping.record(2, "handleB", &profile(5, false));
+        mapping.record(5, "handleA", &profile(40, false));
+
+        let (first, last, handlers) = mapping.summarize(2..=4).unwrap();
+        assert_eq!((2, 2), (first, last));
+        assert_eq!(profile(20, true), handlers["handleA"]);
+        assert_eq!(profile(5, false), handlers["handleB"]);
+
+        let (first, last, handlers) = mapping.summarize(0..=10).unwrap();
+        assert_eq!((1, 5), (first, last));
+        let handle_a = &handlers["handleA"];
+        assert_eq!(3, handle_a.calls);
+        assert_eq!(1, handle_a.failures);
+        assert_eq!(70, handle_a.gas);
+        assert_eq!(Duration::from_millis(70), handle_a.wall_time);
+        assert_eq!(
+            HostFnProfile { calls: 3, gas: 3 },
+            handle_a.host_fns["store.get"]
+        );
+    }
+
+    #[test]
+    fn keeps_only_recent_blocks() {
+        let mapping = MappingProfile::default();
+        let blocks = ENV_VARS.mappings.profiler_blocks as BlockNumber;
+        for block in 0..blocks + 10 {
+            mapping.record(block, "handleA", &profile(1, false));
+        }
+        let (first, last, handlers) = mapping.summarize(0..=BlockNumber::MAX).unwrap();
+        assert_eq!((10, blocks + 9), (first, last));
+        assert_eq!(blocks as u64, handlers["handleA"].calls);
+    }
+
+    #[test]
+    fn removes_deployments() {
+        let profiles = MappingProfiles::default();
+        let id = DeploymentHash::new("QmProfile").unwrap();
+        profiles
+            .deployment(&id)
+            .record(1, "handleA", &profile(1, false));
+        assert!(profiles.get(&id).is_some());
+        profiles.remove(&id);
+        assert!(profiles.get(&id).is_none());
+    }
+}
@@ -5,6 +5,7 @@ use prometheus::IntGauge;
 use prometheus::{labels, Histogram, IntCounterVec};
 use slog::debug;
 
+use crate::components::metrics::gas::MappingProfiles;
 use crate::components::metrics::{counter_with_labels, gauge_with_labels};
 use crate::prelude::Collector;
 use crate::prometheus::{
@@ -27,6 +28,10 @@ pub struct MetricsRegistry {
     global_gauges: RwLock<HashMap<u64, Gauge>>,
     global_gauge_vecs: RwLock<HashMap<u64, GaugeVec>>,
     global_histogram_vecs: RwLock<HashMap<u64, HistogramVec>>,
+
+    /// Profiles of mapping handlers, only filled in when the mapping
+    /// profiler is turned on
+    mapping_profiles: Arc<MappingProfiles>,
 }
 
 impl MetricsRegistry {
@@ -47,9 +52,14 @@ impl MetricsRegistry {
             global_gauges: RwLock::new(HashMap::new()),
             global_gauge_vecs: RwLock::new(HashMap::new()),
             global_histogram_vecs: RwLock::new(HashMap::new()),
+            mapping_profiles: Arc::new(MappingProfiles::default()),
         }
     }
 
+    pub fn mapping_profiles(&self) -> Arc<MappingProfiles> {
+        self.mapping_profiles.clone()
+    }
+
     pub fn mock() -> Self {
         MetricsRegistry::new(Logger::root(slog::Discard, o!()), Arc::new(Registry::new()))
     }
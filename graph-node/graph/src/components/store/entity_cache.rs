@@ -1,19 +1,19 @@
 use anyhow::{anyhow, bail};
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::{self, Debug};
 use std::sync::Arc;
 
 use crate::cheap_clone::CheapClone;
 use crate::components::store::write::EntityModification;
 use crate::components::store::{self as s, Entity, EntityOperation};
-use crate::data::store::{EntityValidationError, Id, IdType, IntoEntityIterator};
+use crate::data::store::{EntityValidationError, Id, IdType, IntoEntityIterator, Value};
 use crate::prelude::{CacheWeight, ENV_VARS};
 use crate::schema::{EntityKey, InputSchema};
 use crate::util::intern::Error as InternError;
 use crate::util::lfu_cache::{EvictStats, LfuCache};
 
-use super::{BlockNumber, DerivedEntityQuery, LoadRelatedRequest, StoreError};
+use super::{BlockNumber, DerivedEntityQuery, FindByQuery, LoadRelatedRequest, StoreError};
 
 pub type EntityLfuCache = LfuCache<EntityKey, Option<Arc<Entity>>>;
 
@@ -233,6 +233,18 @@ impl EntityCache {
         Ok(entity)
     }
 
+    /// Get the entity for `key` as it was at the end of `block`. Since
+    /// `block` must lie before the block that is currently being
+    /// processed, this bypasses the cache and any pending changes and
+    /// reads straight from the store
+    pub fn get_at(
+        &self,
+        key: &EntityKey,
+        block: BlockNumber,
+    ) -> Result<Option<Entity>, StoreError> {
+        self.store.get_at(key, block)
+    }
+
     pub fn load_related(
         &mut self,
         eref: &LoadRelatedRequest,
@@ -355,6 +367,70 @@ impl EntityCache {
         Ok(entity_map.into_values().collect())
     }
 
+    /// Find the entities of `query.entity_type` whose attribute
+    /// `query.attribute` equals `query.value`, taking changes made in this
+    /// block into account. Returns the `query.limit` matching entities with
+    /// the smallest ids, ordered by id
+    pub fn find_by(&mut self, query: &FindByQuery) -> Result<Vec<Entity>, anyhow::Error> {
+        query.validate()?;
+
+        // Entities with pending changes might match in the store but not
+        // anymore, or the other way around. Ask the store for enough
+        // entities that we still have `query.limit` of them even if all
+        // pending changes turn out to be removals
+        let pending: BTreeSet<EntityKey> = self
+            .updates
+            .keys()
+            .chain(self.handler_updates.keys())
+            .filter(|key| {
+                key.entity_type == query.entity_type
+                    && key.causality_region == query.causality_region
+            })
+            .cloned()
+            .collect();
+        let store_query = FindByQuery {
+            limit: query.limit + pending.len(),
+            ..query.clone()
+        };
+        let mut entity_map = self.store.find_by(&store_query)?;
+
+        for (key, entity) in entity_map.iter() {
+            // Only insert to the cache if it's not already there
+            if !self.current.contains_key(key) {
+                self.current
+                    .insert(key.clone(), Some(Arc::new(entity.clone())));
+            }
+        }
+
+        // An entity that the store did not return can only match now if a
+        // pending change sets the attribute to the value we are looking for
+        let sets_value = |op: Option<&EntityOp>| match op {
+            Some(EntityOp::Update(entity)) | Some(EntityOp::Overwrite(entity)) => entity
+                .get(&query.attribute)
+                .map(|v| v == &query.value)
+                .unwrap_or(false),
+            Some(EntityOp::Remove) | None => false,
+        };
+        for key in pending {
+            if !entity_map.contains_key(&key)
+                && !sets_value(self.updates.get(&key))
+                && !sets_value(self.handler_updates.get(&key))
+            {
+                continue;
+            }
+            match self.get(&key, GetScope::Store)? {
+                Some(entity) if query.matches(&key, &entity) => {
+                    entity_map.insert(key, entity.as_ref().clone());
+                }
+                _ => {
+                    entity_map.remove(&key);
+                }
+            }
+        }
+
+        Ok(entity_map.into_values().take(query.limit).collect())
+    }
+
     pub fn remove(&mut self, key: EntityKey) {
         self.entity_op(key, EntityOp::Remove);
     }
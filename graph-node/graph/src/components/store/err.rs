@@ -76,6 +76,8 @@ pub enum StoreError {
     WriteFailure(String, BlockNumber, String, String),
     #[error("database query timed out")]
     StatementTimeout,
+    #[error("can not read entities at block {0} since history before block {1} has been pruned")]
+    HistoryPruned(BlockNumber, BlockNumber),
 }
 
 // Convenience to report a constraint violation
@@ -136,6 +138,7 @@ impl Clone for StoreError {
                 Self::WriteFailure(arg0.clone(), arg1.clone(), arg2.clone(), arg3.clone())
             }
             Self::StatementTimeout => Self::StatementTimeout,
+            Self::HistoryPruned(arg0, arg1) => Self::HistoryPruned(*arg0, *arg1),
         }
     }
 }
@@ -34,7 +34,7 @@ use crate::data_source::CausalityRegion;
 use crate::derive::CheapClone;
 use crate::env::ENV_VARS;
 use crate::prelude::{s, Attribute, DeploymentHash, ValueType};
-use crate::schema::{ast as sast, EntityKey, EntityType, InputSchema};
+use crate::schema::{ast as sast, AggregationCurrent, EntityKey, EntityType, InputSchema};
 use crate::util::stats::MovingStats;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -96,6 +96,71 @@ impl DerivedEntityQuery {
     }
 }
 
+/// The largest number of entities that a `FindByQuery` can return
+pub const FIND_BY_MAX_LIMIT: usize = 1000;
+
+/// Look up the entities of one type whose `attribute` equals `value`. The
+/// entities are ordered by their id, and at most `limit` of them are
+/// returned
+#[derive(Clone, Debug)]
+pub struct FindByQuery {
+    /// Name of the entity to search
+    pub entity_type: EntityType,
+    /// The attribute to check
+    pub attribute: Word,
+    /// The value to compare against
+    pub value: Value,
+    /// The causality region of the data source doing the lookup. Only
+    /// entities in that causality region are returned
+    pub causality_region: CausalityRegion,
+    /// The maximum number of entities to return
+    pub limit: usize,
+}
+
+impl FindByQuery {
+    /// Check that the query can be run: the attribute must be a scalar
+    /// attribute of the entity type, the value must have its type, and the
+    /// limit must not exceed `FIND_BY_MAX_LIMIT`. Errors only depend on
+    /// the query and the schema, and are therefore deterministic
+    pub fn validate(&self) -> Result<(), anyhow::Error> {
+        let query_err = |err: &str| {
+            anyhow::anyhow!("findBy for {}.{}: {err}", self.entity_type, self.attribute)
+        };
+
+        let field = self
+            .entity_type
+            .field(&self.attribute)
+            .ok_or_else(|| query_err("unknown attribute"))?;
+        if field.is_derived() || field.is_list() {
+            return Err(query_err("derived and list attributes can not be used"));
+        }
+        if matches!(self.value, Value::Null) || !self.value.is_assignable(&field.value_type, false)
+        {
+            return Err(query_err(&format!(
+                "the value `{}` has the wrong type",
+                self.value
+            )));
+        }
+        if self.limit > FIND_BY_MAX_LIMIT {
+            return Err(query_err(&format!(
+                "the limit can be at most {}",
+                FIND_BY_MAX_LIMIT
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks if a given key and entity match this query.
+    pub fn matches(&self, key: &EntityKey, entity: &Entity) -> bool {
+        key.entity_type == self.entity_type
+            && key.causality_region == self.causality_region
+            && entity
+                .get(&self.attribute)
+                .map(|v| &self.value == v)
+                .unwrap_or(false)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Child {
     pub attr: Attribute,
@@ -129,6 +194,13 @@ pub enum EntityFilter {
     EndsWithNoCase(Attribute, Value),
     NotEndsWith(Attribute, Value),
     NotEndsWithNoCase(Attribute, Value),
+    /// Match a string attribute against a POSIX regular expression
+    Matches(Attribute, Value),
+    MatchesNoCase(Attribute, Value),
+    /// The list attribute has at least one element in common with the value
+    Overlaps(Attribute, Value),
+    /// Every element of the list attribute is also in the value
+    ContainedBy(Attribute, Value),
     ChangeBlockGte(BlockNumber),
     Child(Child),
     Fulltext(Attribute, Value),
@@ -170,6 +242,10 @@ impl fmt::Display for EntityFilter {
             EndsWithNoCase(a, v) => write!(f, "{a} ~ *{v}$i"),
             NotEndsWith(a, v) => write!(f, "{a} !~ *{v}$"),
             NotEndsWithNoCase(a, v) => write!(f, "{a} !~ *{v}$i"),
+            Matches(a, v) => write!(f, "{a} ~ /{v}/"),
+            MatchesNoCase(a, v) => write!(f, "{a} ~ /{v}/i"),
+            Overlaps(a, v) => write!(f, "{a} && {v}"),
+            ContainedBy(a, v) => write!(f, "{a} <@ {v}"),
             ChangeBlockGte(b) => write!(f, "block >= {b}"),
             Child(child /* a, et, cf, _ */) => write!(
                 f,
@@ -251,6 +327,11 @@ pub enum EntityOrder {
     ChildAscending(EntityOrderByChild),
     /// Order descending by the given attribute of a child entity. Use `id` as a tie-breaker
     ChildDescending(EntityOrderByChild),
+    /// Order by several attributes, each in its own direction. The entries
+    /// can only be `Ascending`, `Descending`, `ChildAscending` or
+    /// `ChildDescending`. Use `id`, in the direction of the last entry, as
+    /// a tie-breaker
+    Multi(Vec<EntityOrder>),
     /// Order by the `id` of the entities
     Default,
     /// Do not order at all. This speeds up queries where we know that
@@ -266,6 +347,11 @@ pub struct EntityRange {
 
     /// How many entities to skip.
     pub skip: u32,
+
+    /// Only return entities that come after this position in the order
+    /// of the query. This makes it possible to page through large result
+    /// sets without the cost of skipping over a large number of entities
+    pub after: Option<EntityCursor>,
 }
 
 impl EntityRange {
@@ -278,6 +364,7 @@ impl EntityRange {
         Self {
             first: Some(n),
             skip: 0,
+            after: None,
         }
     }
 }
@@ -287,10 +374,34 @@ impl std::default::Default for EntityRange {
         Self {
             first: Some(Self::FIRST),
             skip: 0,
+            after: None,
         }
     }
 }
 
+/// A position in the result of a query that is used for keyset
+/// pagination. The position is determined by the value of the attribute
+/// the query is ordered by and the `id` of the entity at that position,
+/// since `id` is always used to break ties when ordering
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntityCursor {
+    /// The value of the attribute the query is ordered by, or `None` if
+    /// the query is ordered by `id`
+    pub value: Option<Value>,
+    pub id: Value,
+}
+
+/// An aggregate that is computed over all entities that match a query.
+/// Except for `Count`, aggregates are computed over the attribute with the
+/// given name, which must be numeric
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntityAggregate {
+    Count,
+    Sum(String),
+    Min(String),
+    Max(String),
+}
+
 /// The attribute we want to window by in an `EntityWindow`. We have to
 /// distinguish between scalar and list attributes since we need to use
 /// different queries for them, and the JSONB storage scheme can not
@@ -451,6 +562,10 @@ pub struct EntityQuery {
     /// A range to limit the size of the result.
     pub range: EntityRange,
 
+    /// Whether a query for an aggregation should include the current,
+    /// partially filled bucket. Ignored for anything but aggregations
+    pub current: AggregationCurrent,
+
     /// Optional logger for anything related to this query
     pub logger: Option<Logger>,
 
@@ -474,6 +589,7 @@ impl EntityQuery {
             filter: None,
             order: EntityOrder::Default,
             range: EntityRange::default(),
+            current: AggregationCurrent::default(),
             logger: None,
             query_id: None,
             trace: false,
@@ -496,6 +612,11 @@ impl EntityQuery {
         self
     }
 
+    pub fn current(mut self, current: AggregationCurrent) -> Self {
+        self.current = current;
+        self
+    }
+
     pub fn first(mut self, first: u32) -> Self {
         self.range.first = Some(first);
         self
@@ -570,6 +691,10 @@ impl AssignmentChange {
         Self::new(deployment, AssignmentOperation::Removed)
     }
 
+    pub fn deployment(&self) -> &DeploymentLocator {
+        &self.deployment
+    }
+
     pub fn into_parts(self) -> (DeploymentLocator, AssignmentOperation) {
         (self.deployment, self.operation)
     }
@@ -870,6 +995,10 @@ impl ReadStore for EmptyStore {
         Ok(None)
     }
 
+    fn get_at(&self, _key: &EntityKey, _block: BlockNumber) -> Result<Option<Entity>, StoreError> {
+        Ok(None)
+    }
+
     fn get_many(&self, _: BTreeSet<EntityKey>) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
         Ok(BTreeMap::new())
     }
@@ -881,6 +1010,10 @@ impl ReadStore for EmptyStore {
         Ok(BTreeMap::new())
     }
 
+    fn find_by(&self, _query: &FindByQuery) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        Ok(BTreeMap::new())
+    }
+
     fn input_schema(&self) -> InputSchema {
         self.schema.cheap_clone()
     }
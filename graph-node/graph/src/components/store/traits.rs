@@ -3,7 +3,7 @@ use std::ops::Range;
 
 use anyhow::Error;
 use async_trait::async_trait;
-use web3::types::{Address, H256};
+use web3::types::{Address, Log, TransactionReceipt, H256};
 
 use super::*;
 use crate::blockchain::block_stream::{EntitySourceOperation, FirehoseCursor};
@@ -44,6 +44,14 @@ pub trait EnsLookup: Send + Sync + 'static {
     fn is_table_empty(&self) -> Result<bool, StoreError>;
 }
 
+/// The allow-list of persisted queries that the operator registered
+#[async_trait]
+pub trait PersistedQueryStore: Send + Sync + 'static {
+    /// Return all registered queries as pairs of the hash of the query
+    /// (see `persisted_query_hash`) and its text
+    async fn persisted_queries(&self) -> Result<Vec<(String, String)>, StoreError>;
+}
+
 /// An entry point for all operations that require access to the node's storage
 /// layer. It provides access to a [`BlockStore`] and a [`SubgraphStore`].
 pub trait Store: Clone + StatusStore + Send + Sync + 'static {
@@ -63,6 +71,8 @@ pub trait Store: Clone + StatusStore + Send + Sync + 'static {
 pub trait SubgraphStore: Send + Sync + 'static {
     fn ens_lookup(&self) -> Arc<dyn EnsLookup>;
 
+    fn persisted_queries(&self) -> Arc<dyn PersistedQueryStore>;
+
     /// Check if the store is accepting queries for the specified subgraph.
     /// May return true even if the specified subgraph is not currently assigned to an indexing
     /// node, as the store will still accept queries.
@@ -183,6 +193,18 @@ pub trait SubgraphStore: Send + Sync + 'static {
         manifest_idx_and_name: Arc<Vec<(u32, String)>>,
     ) -> Result<Arc<dyn WritableStore>, StoreError>;
 
+    /// Return a `WritableStore` for replaying `block` for debugging. All
+    /// reads see the state of the deployment at `block - 1`, and all
+    /// attempts to change the deployment fail. It is safe to use while the
+    /// deployment is being indexed.
+    async fn replay_writable(
+        self: Arc<Self>,
+        logger: Logger,
+        deployment: DeploymentId,
+        block: BlockNumber,
+        manifest_idx_and_name: Arc<Vec<(u32, String)>>,
+    ) -> Result<Arc<dyn WritableStore>, StoreError>;
+
     async fn sourceable(
         self: Arc<Self>,
         deployment: DeploymentId,
@@ -224,6 +246,11 @@ pub trait ReadStore: Send + Sync + 'static {
     /// Looks up an entity using the given store key at the latest block.
     fn get(&self, key: &EntityKey) -> Result<Option<Entity>, StoreError>;
 
+    /// Looks up an entity as it was at the end of `block`. Fails with
+    /// `StoreError::HistoryPruned` if the history for `block` has been
+    /// pruned away
+    fn get_at(&self, key: &EntityKey, block: BlockNumber) -> Result<Option<Entity>, StoreError>;
+
     /// Look up multiple entities as of the latest block.
     fn get_many(
         &self,
@@ -236,6 +263,10 @@ pub trait ReadStore: Send + Sync + 'static {
         query_derived: &DerivedEntityQuery,
     ) -> Result<BTreeMap<EntityKey, Entity>, StoreError>;
 
+    /// Look up entities by the value of one of their attributes. Returns
+    /// at most `query.limit` entities, those with the smallest ids
+    fn find_by(&self, query: &FindByQuery) -> Result<BTreeMap<EntityKey, Entity>, StoreError>;
+
     fn input_schema(&self) -> InputSchema;
 }
 
@@ -245,6 +276,10 @@ impl<T: ?Sized + ReadStore> ReadStore for Arc<T> {
         (**self).get(key)
     }
 
+    fn get_at(&self, key: &EntityKey, block: BlockNumber) -> Result<Option<Entity>, StoreError> {
+        (**self).get_at(key, block)
+    }
+
     fn get_many(
         &self,
         keys: BTreeSet<EntityKey>,
@@ -259,6 +294,10 @@ impl<T: ?Sized + ReadStore> ReadStore for Arc<T> {
         (**self).get_derived(entity_derived)
     }
 
+    fn find_by(&self, query: &FindByQuery) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        (**self).find_by(query)
+    }
+
     fn input_schema(&self) -> InputSchema {
         (**self).input_schema()
     }
@@ -383,7 +422,7 @@ pub trait WritableStore: ReadStore + DeploymentCursorTracker {
         stopwatch: &StopwatchMetrics,
         data_sources: Vec<StoredDynamicDataSource>,
         deterministic_errors: Vec<SubgraphError>,
-        offchain_to_remove: Vec<StoredDynamicDataSource>,
+        data_sources_to_remove: Vec<StoredDynamicDataSource>,
         is_non_fatal_errors_active: bool,
         is_caught_up_with_chain_head: bool,
     ) -> Result<(), StoreError>;
@@ -582,6 +621,38 @@ pub trait ChainStore: Send + Sync + 'static {
     /// Clears call cache of the chain for the given `from` and `to` block number.
     async fn clear_call_cache(&self, from: BlockNumber, to: BlockNumber) -> Result<(), Error>;
 
+    /// Return the logs cached for the `eth_getLogs` filter with `filter_id`
+    /// in the blocks `from..=to`, or `None` if no single cached block range
+    /// covers all of `from..=to`
+    async fn cached_logs(
+        &self,
+        filter_id: &[u8],
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Option<Vec<Log>>, Error>;
+
+    /// Cache the `logs` that the `eth_getLogs` filter with `filter_id`
+    /// returned for the blocks `from..=to`. All of these blocks must be
+    /// final since the cache is never invalidated by reorgs
+    async fn set_cached_logs(
+        &self,
+        filter_id: &[u8],
+        from: BlockNumber,
+        to: BlockNumber,
+        logs: &[Log],
+    ) -> Result<(), Error>;
+
+    /// Return the cached receipts for those of the transactions in
+    /// `hashes` that have one
+    async fn cached_receipts(&self, hashes: &[H256]) -> Result<Vec<TransactionReceipt>, Error>;
+
+    /// Cache `receipts`. They must all be for transactions in final blocks
+    async fn set_cached_receipts(&self, receipts: &[Arc<TransactionReceipt>]) -> Result<(), Error>;
+
+    /// Clears the log and receipt cache of the chain for the given `from`
+    /// and `to` block number.
+    async fn clear_log_cache(&self, from: BlockNumber, to: BlockNumber) -> Result<(), Error>;
+
     /// Return the chain identifier for this store.
     fn chain_identifier(&self) -> Result<ChainIdentifier, Error>;
 
@@ -635,6 +706,17 @@ pub trait QueryStore: Send + Sync {
         query: EntityQuery,
     ) -> Result<(Vec<QueryObject>, Trace), QueryExecutionError>;
 
+    /// Compute `aggregates` over all entities that match `query`; the
+    /// range and order of `query` are ignored. The values are returned in
+    /// the same order as `aggregates`. The database query is cancelled if
+    /// it takes longer than `timeout`
+    fn aggregate_query_values(
+        &self,
+        query: EntityQuery,
+        aggregates: &[EntityAggregate],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Value>, QueryExecutionError>;
+
     async fn is_deployment_synced(&self) -> Result<bool, Error>;
 
     async fn block_ptr(&self) -> Result<Option<BlockPtr>, StoreError>;
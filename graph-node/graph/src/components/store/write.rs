@@ -647,7 +647,9 @@ pub struct Batch {
     /// New data sources
     pub data_sources: DataSources,
     pub deterministic_errors: Vec<SubgraphError>,
-    pub offchain_to_remove: DataSources,
+    /// Offchain data sources that were processed and onchain data sources
+    /// that mappings removed
+    pub data_sources_to_remove: DataSources,
     pub error: Option<StoreError>,
     pub is_non_fatal_errors_active: bool,
     /// Memoize the indirect weight of the batch. We need the `CacheWeight`
@@ -666,7 +668,7 @@ impl Batch {
         mut raw_mods: Vec<EntityModification>,
         data_sources: Vec<StoredDynamicDataSource>,
         deterministic_errors: Vec<SubgraphError>,
-        offchain_to_remove: Vec<StoredDynamicDataSource>,
+        data_sources_to_remove: Vec<StoredDynamicDataSource>,
         is_non_fatal_errors_active: bool,
     ) -> Result<Self, StoreError> {
         let block = block_ptr.number;
@@ -688,7 +690,8 @@ impl Batch {
         }
 
         let data_sources = DataSources::new(block_ptr.cheap_clone(), data_sources);
-        let offchain_to_remove = DataSources::new(block_ptr.cheap_clone(), offchain_to_remove);
+        let data_sources_to_remove =
+            DataSources::new(block_ptr.cheap_clone(), data_sources_to_remove);
         let first_block = block_ptr.number;
         let block_times = vec![(block, block_time)];
         let mut batch = Self {
@@ -699,7 +702,7 @@ impl Batch {
             mods,
             data_sources,
             deterministic_errors,
-            offchain_to_remove,
+            data_sources_to_remove,
             error: None,
             is_non_fatal_errors_active,
             indirect_weight: 0,
@@ -720,7 +723,8 @@ impl Batch {
         self.data_sources.append(batch.data_sources);
         self.deterministic_errors
             .append(&mut batch.deterministic_errors);
-        self.offchain_to_remove.append(batch.offchain_to_remove);
+        self.data_sources_to_remove
+            .append(batch.data_sources_to_remove);
         Ok(())
     }
 
@@ -776,13 +780,27 @@ impl Batch {
             .flatten()
             .filter(|ds| {
                 !self
-                    .offchain_to_remove
+                    .data_sources_to_remove
                     .entries
                     .iter()
                     .any(|(_, entries)| entries.contains(ds))
             })
     }
 
+    /// The onchain data sources that mappings removed at or before `at`
+    pub fn removed_data_sources(
+        &self,
+        at: BlockNumber,
+    ) -> impl Iterator<Item = &StoredDynamicDataSource> {
+        self.data_sources_to_remove
+            .entries
+            .iter()
+            .filter(move |(ptr, _)| ptr.number <= at)
+            .map(|(_, ds)| ds)
+            .flatten()
+            .filter(|ds| ds.causality_region == CausalityRegion::ONCHAIN)
+    }
+
     pub fn groups<'a>(&'a self) -> impl Iterator<Item = &'a RowGroup> {
         self.mods.groups.iter()
     }
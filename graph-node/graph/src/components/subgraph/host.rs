@@ -10,7 +10,8 @@ use crate::blockchain::BlockTime;
 use crate::components::metrics::gas::GasMetrics;
 use crate::components::store::SubgraphFork;
 use crate::data_source::{
-    DataSource, DataSourceTemplate, MappingTrigger, TriggerData, TriggerWithHandler,
+    common::MappingLanguage, DataSource, DataSourceTemplate, MappingTrigger, TriggerData,
+    TriggerWithHandler,
 };
 use crate::prelude::*;
 use crate::runtime::HostExportError;
@@ -99,11 +100,12 @@ pub trait RuntimeHost<C: Blockchain>: Send + Sync + 'static {
     fn creation_block_number(&self) -> Option<BlockNumber>;
 
     /// Offchain data sources track done_at which is set once the
-    /// trigger has been processed.
+    /// trigger has been processed. For onchain data sources, it is the
+    /// block at which a mapping removed the data source.
     fn done_at(&self) -> Option<BlockNumber>;
 
     /// Convenience function to avoid leaking internal representation of
-    /// mutable number. Calling this on OnChain Datasources is a noop.
+    /// mutable number. Calling this on subgraph data sources is a noop.
     fn set_done_at(&self, block: Option<BlockNumber>);
 
     /// Return a metrics object for this host.
@@ -231,6 +233,7 @@ pub trait RuntimeHostBuilder<C: Blockchain>: Clone + Send + Sync + 'static {
     /// cached and shared among mappings that use the same wasm file.
     fn spawn_mapping(
         raw_module: &[u8],
+        language: MappingLanguage,
         logger: Logger,
         subgraph_id: DeploymentHash,
         metrics: Arc<HostMetrics>,
@@ -63,6 +63,17 @@ pub struct InstanceDSTemplateInfo {
     pub creation_block: BlockNumber,
 }
 
+/// A dynamic data source that a mapping asked to stop indexing with
+/// `dataSource.remove` or `dataSource.removeTemplate`
+#[derive(Clone, Debug)]
+pub enum RemovedDataSource {
+    /// The data source with this name and address, i.e., the data source
+    /// whose handler made the call
+    Current { name: String, address: Vec<u8> },
+    /// The data sources created from a template with these parameters
+    Template(InstanceDSTemplateInfo),
+}
+
 #[derive(Debug)]
 pub struct BlockState {
     pub entity_cache: EntityCache,
@@ -78,6 +89,12 @@ pub struct BlockState {
     // data source that have been processed.
     pub processed_data_sources: Vec<StoredDynamicDataSource>,
 
+    // Data sources that mappings asked to remove.
+    removed_data_sources: Vec<RemovedDataSource>,
+
+    // Data sources removed in the current handler.
+    handler_removed_data_sources: Vec<RemovedDataSource>,
+
     // Marks whether a handler is currently executing.
     in_handler: bool,
 
@@ -95,6 +112,8 @@ impl BlockState {
             persisted_data_sources: Vec::new(),
             handler_created_data_sources: Vec::new(),
             processed_data_sources: Vec::new(),
+            removed_data_sources: Vec::new(),
+            handler_removed_data_sources: Vec::new(),
             in_handler: false,
             metrics: BlockStateMetrics::new(),
             write_capacity_remaining: ENV_VARS.block_write_capacity,
@@ -113,14 +132,22 @@ impl BlockState {
             persisted_data_sources,
             handler_created_data_sources,
             processed_data_sources,
+            removed_data_sources,
+            handler_removed_data_sources,
             in_handler,
             metrics,
             write_capacity_remaining,
         } = self;
 
         match in_handler {
-            true => handler_created_data_sources.extend(other.created_data_sources),
-            false => created_data_sources.extend(other.created_data_sources),
+            true => {
+                handler_created_data_sources.extend(other.created_data_sources);
+                handler_removed_data_sources.extend(other.removed_data_sources);
+            }
+            false => {
+                created_data_sources.extend(other.created_data_sources);
+                removed_data_sources.extend(other.removed_data_sources);
+            }
         }
         deterministic_errors.extend(other.deterministic_errors);
         entity_cache.extend(other.entity_cache);
@@ -155,6 +182,11 @@ impl BlockState {
         std::mem::take(&mut self.created_data_sources)
     }
 
+    pub fn drain_removed_data_sources(&mut self) -> Vec<RemovedDataSource> {
+        assert!(!self.in_handler);
+        std::mem::take(&mut self.removed_data_sources)
+    }
+
     pub fn enter_handler(&mut self) {
         assert!(!self.in_handler);
         self.in_handler = true;
@@ -166,6 +198,8 @@ impl BlockState {
         self.in_handler = false;
         self.created_data_sources
             .append(&mut self.handler_created_data_sources);
+        self.removed_data_sources
+            .append(&mut self.handler_removed_data_sources);
         self.entity_cache.exit_handler()
     }
 
@@ -173,6 +207,7 @@ impl BlockState {
         assert!(self.in_handler);
         self.in_handler = false;
         self.handler_created_data_sources.clear();
+        self.handler_removed_data_sources.clear();
         self.entity_cache.exit_handler_and_discard_changes();
         self.deterministic_errors.push(e);
     }
@@ -182,6 +217,11 @@ impl BlockState {
         self.handler_created_data_sources.push(ds);
     }
 
+    pub fn push_removed_data_source(&mut self, ds: RemovedDataSource) {
+        assert!(self.in_handler);
+        self.handler_removed_data_sources.push(ds);
+    }
+
     pub fn persist_data_source(&mut self, ds: StoredDynamicDataSource) {
         self.persisted_data_sources.push(ds)
     }
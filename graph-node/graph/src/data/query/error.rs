@@ -39,10 +39,14 @@ pub enum QueryExecutionError {
     OrderByNotSupportedError(String, String),
     OrderByNotSupportedForType(String),
     FilterNotSupportedError(String, String),
+    RegexNotSupported(String, String),
+    PersistedQueryNotFound,
+    PersistedQueryNotAllowed,
     UnknownField(Pos, String, String),
     EmptyQuery,
     SubgraphDeploymentIdError(String),
     RangeArgumentsError(&'static str, u32, i64),
+    InvalidCursor(String),
     InvalidFilterError,
     EntityFieldError(String, String),
     ListTypesError(String, Vec<String>),
@@ -94,10 +98,12 @@ impl QueryExecutionError {
             | OrderByNotSupportedError(_, _)
             | OrderByNotSupportedForType(_)
             | FilterNotSupportedError(_, _)
+            | RegexNotSupported(_, _)
             | ChildFilterNestingNotSupportedError(_, _)
             | UnknownField(_, _, _)
             | EmptyQuery
             | SubgraphDeploymentIdError(_)
+            | InvalidCursor(_)
             | InvalidFilterError
             | EntityFieldError(_, _)
             | ListTypesError(_, _)
@@ -124,6 +130,8 @@ impl QueryExecutionError {
             | Panic(_)
             | TooExpensive
             | Throttled
+            | PersistedQueryNotFound
+            | PersistedQueryNotAllowed
             | DeploymentReverted
             | SubgraphManifestResolveError(_)
             | InvalidSubgraphManifest
@@ -196,6 +204,15 @@ impl fmt::Display for QueryExecutionError {
             FilterNotSupportedError(value, filter) => {
                 write!(f, "Filter not supported by value `{}`: `{}`", value, filter)
             }
+            RegexNotSupported(pattern, reason) => {
+                write!(f, "Regular expression `{}` is not supported: {}", pattern, reason)
+            }
+            // Clients that use automatic persisted queries look for this
+            // exact message to decide whether to resend the query text
+            PersistedQueryNotFound => write!(f, "PersistedQueryNotFound"),
+            PersistedQueryNotAllowed => {
+                write!(f, "Only persisted queries can be run on this endpoint")
+            }
             ChildFilterNestingNotSupportedError(value, filter) => {
                 write!(f, "Child filter nesting not supported by value `{}`: `{}`", value, filter)
             }
@@ -209,6 +226,7 @@ impl fmt::Display for QueryExecutionError {
             RangeArgumentsError(arg, max, actual) => {
                 write!(f, "The `{}` argument must be between 0 and {}, but is {}", arg, max, actual)
             }
+            InvalidCursor(s) => write!(f, "Invalid cursor: {}", s),
             InvalidFilterError => write!(f, "Filter must by an object"),
             EntityFieldError(e, a) => {
                 write!(f, "Entity `{}` has no attribute `{}`", e, a)
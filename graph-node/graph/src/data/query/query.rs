@@ -1,5 +1,6 @@
 use serde::de::Deserializer;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::ops::{Deref, DerefMut};
@@ -7,7 +8,7 @@ use std::sync::Arc;
 
 use crate::{
     data::graphql::shape_hash::shape_hash,
-    prelude::{q, r, ApiVersion, DeploymentHash, SubgraphName, ENV_VARS},
+    prelude::{hex, q, r, ApiVersion, DeploymentHash, SubgraphName, ENV_VARS},
 };
 
 fn deserialize_number<'de, D>(deserializer: D) -> Result<q::Number, D::Error>
@@ -124,6 +125,13 @@ impl QueryTarget {
     }
 }
 
+/// The hash under which the query with text `query` is persisted. It is
+/// the hex-encoded SHA-256 hash of the text, which is also what clients
+/// send for automatic persisted queries
+pub fn persisted_query_hash(query: &str) -> String {
+    hex::encode(Sha256::digest(query.as_bytes()))
+}
+
 /// A GraphQL query as submitted by a client, either directly or through a subscription.
 #[derive(Clone, Debug)]
 pub struct Query {
@@ -1,11 +1,13 @@
 use diesel::deserialize::FromSqlRow;
 use diesel::expression::AsExpression;
 use num_bigint::{self, ToBigInt};
-use num_traits::FromPrimitive;
+use num_integer::{Integer, Roots};
+use num_traits::{FromPrimitive, Pow, Signed, Zero};
 use serde::{self, Deserialize, Serialize};
 use stable_hash::{FieldAddress, StableHash};
 use stable_hash_legacy::SequenceNumber;
 
+use std::convert::TryFrom;
 use std::fmt::{self, Display, Formatter};
 use std::ops::{Add, Div, Mul, Sub};
 use std::str::FromStr;
@@ -51,8 +53,6 @@ impl BigDecimal {
     }
 
     pub fn zero() -> BigDecimal {
-        use old_bigdecimal::Zero;
-
         BigDecimal(OldBigDecimal::zero())
     }
 
@@ -106,6 +106,160 @@ impl BigDecimal {
     }
 }
 
+impl BigDecimal {
+    /// Raise `self` to the power `exp`. Intermediate results are rounded to
+    /// `MAX_SIGNFICANT_DIGITS` like all other operations, and the result
+    /// must have an exponent between `MIN_EXP` and `MAX_EXP`
+    pub fn pow(&self, exp: i32) -> Result<BigDecimal, anyhow::Error> {
+        if exp < 0 && self.is_zero() {
+            return Err(anyhow!("Cannot raise zero to the negative power {}", exp));
+        }
+
+        fn check(x: BigDecimal) -> Result<BigDecimal, anyhow::Error> {
+            let (_, scale) = x.as_bigint_and_exponent();
+            if -scale < BigDecimal::MIN_EXP as i64 || -scale > BigDecimal::MAX_EXP as i64 {
+                return Err(anyhow!(
+                    "The exponent of the result {} is outside the range from {} to {}",
+                    -scale,
+                    BigDecimal::MIN_EXP,
+                    BigDecimal::MAX_EXP
+                ));
+            }
+            Ok(x)
+        }
+
+        // Exponentiation by squaring
+        let mut result = BigDecimal::from(1);
+        let mut base = self.clone();
+        let mut n = exp.unsigned_abs();
+        while n > 0 {
+            if n & 1 == 1 {
+                result = check(result * base.clone())?;
+            }
+            n >>= 1;
+            if n > 0 {
+                base = check(base.clone() * base)?;
+            }
+        }
+
+        if exp < 0 {
+            result = check(BigDecimal::from(1) / result)?;
+        }
+        Ok(result)
+    }
+
+    /// The square root of `self`, rounded to `MAX_SIGNFICANT_DIGITS`
+    pub fn sqrt(&self) -> Result<BigDecimal, anyhow::Error> {
+        if self < &BigDecimal::zero() {
+            return Err(anyhow!("Cannot take the square root of {}", self));
+        }
+        if self.is_zero() {
+            return Ok(BigDecimal::zero());
+        }
+
+        // Scale the digits up so that their integer square root has one
+        // more digit than we keep, and so that the scale of the root is
+        // an integer
+        let (int, scale) = self.as_bigint_and_exponent();
+        let precision = 2 * (Self::MAX_SIGNFICANT_DIGITS as i64 + 1);
+        let mut shift = (precision - self.digits() as i64).max(0);
+        if (scale + shift).is_odd() {
+            shift += 1;
+        }
+        let int = int * Pow::pow(num_bigint::BigInt::from(10), shift as u32);
+        let root = Roots::sqrt(&int);
+
+        Ok(BigDecimal::from(OldBigDecimal::new(
+            root,
+            (scale + shift) / 2,
+        )))
+    }
+
+    /// Round `self` to `scale` digits after the decimal point, i.e., to a
+    /// multiple of `10^-scale`, using `mode` to decide in which direction
+    /// to round
+    pub fn round(&self, scale: i64, mode: RoundingMode) -> BigDecimal {
+        let (int, cur_scale) = self.as_bigint_and_exponent();
+        if cur_scale <= scale {
+            return self.clone();
+        }
+
+        // If we drop more digits than `self` has, the quotient is zero and
+        // the remainder is `int`, and it is enough to drop one more digit
+        // than `self` has to get that
+        let drop = (cur_scale - scale).min(self.digits() as i64 + 1);
+        let divisor = Pow::pow(num_bigint::BigInt::from(10), drop as u32);
+        let (quot, rem) = int.div_rem(&divisor);
+
+        let away = if rem.is_zero() {
+            false
+        } else {
+            let half = (rem.abs() * 2).cmp(&divisor);
+            match mode {
+                RoundingMode::Up => true,
+                RoundingMode::Down => false,
+                RoundingMode::Ceiling => rem.is_positive(),
+                RoundingMode::Floor => rem.is_negative(),
+                RoundingMode::HalfUp => half.is_ge(),
+                RoundingMode::HalfDown => half.is_gt(),
+                RoundingMode::HalfEven => half.is_gt() || (half.is_eq() && quot.is_odd()),
+            }
+        };
+        let quot = if away { quot + rem.signum() } else { quot };
+
+        BigDecimal::from(OldBigDecimal::new(quot, scale))
+    }
+
+    /// Drop all digits after `scale` digits after the decimal point
+    pub fn truncate(&self, scale: i64) -> BigDecimal {
+        self.round(scale, RoundingMode::Down)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+}
+
+/// How `BigDecimal::round` rounds values that lie between two multiples
+/// of the rounding unit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Away from zero
+    Up,
+    /// Towards zero
+    Down,
+    /// Towards positive infinity
+    Ceiling,
+    /// Towards negative infinity
+    Floor,
+    /// Towards the nearest neighbor, away from zero if both neighbors are
+    /// equally close
+    HalfUp,
+    /// Towards the nearest neighbor, towards zero if both neighbors are
+    /// equally close
+    HalfDown,
+    /// Towards the nearest neighbor, towards the even neighbor if both
+    /// neighbors are equally close
+    HalfEven,
+}
+
+impl TryFrom<i32> for RoundingMode {
+    type Error = anyhow::Error;
+
+    fn try_from(mode: i32) -> Result<Self, Self::Error> {
+        match mode {
+            0 => Ok(RoundingMode::Up),
+            1 => Ok(RoundingMode::Down),
+            2 => Ok(RoundingMode::Ceiling),
+            3 => Ok(RoundingMode::Floor),
+            4 => Ok(RoundingMode::HalfUp),
+            5 => Ok(RoundingMode::HalfDown),
+            6 => Ok(RoundingMode::HalfEven),
+            _ => Err(anyhow!("invalid rounding mode {}", mode)),
+        }
+    }
+}
+
 impl Display for BigDecimal {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
         self.0.fmt(f)
@@ -594,7 +748,7 @@ mod test {
     use super::{
         super::test::{crypto_stable_hash, same_stable_hash},
         super::Bytes,
-        BigDecimal, BigInt, OldBigDecimal,
+        BigDecimal, BigInt, OldBigDecimal, RoundingMode,
     };
     use std::str::FromStr;
 
@@ -685,4 +839,75 @@ mod test {
         assert_eq!("BigDecimal(-0.17)", format!("{:?}", bd));
         assert_eq!("Bytes(0xdeadbeef)", format!("{:?}", bytes));
     }
+
+    #[test]
+    fn pow() {
+        let bd = |s: &str| BigDecimal::from_str(s).unwrap();
+
+        assert_eq!("1024", bd("2").pow(10).unwrap().to_string());
+        assert_eq!("2.25", bd("1.5").pow(2).unwrap().to_string());
+        assert_eq!("0.25", bd("2").pow(-2).unwrap().to_string());
+        assert_eq!("1", bd("17.3").pow(0).unwrap().to_string());
+        assert_eq!("-8", bd("-2").pow(3).unwrap().to_string());
+        assert!(bd("0").pow(-1).is_err());
+        assert!(bd("10").pow(7000).is_err());
+        assert!(bd("0.1").pow(7000).is_err());
+    }
+
+    #[test]
+    fn sqrt() {
+        let bd = |s: &str| BigDecimal::from_str(s).unwrap();
+
+        assert_eq!("0", bd("0").sqrt().unwrap().to_string());
+        assert_eq!("2", bd("4").sqrt().unwrap().to_string());
+        assert_eq!("1.5", bd("2.25").sqrt().unwrap().to_string());
+        assert_eq!("300", bd("90000").sqrt().unwrap().to_string());
+        assert_eq!(
+            "1.414213562373095048801688724209698",
+            bd("2").sqrt().unwrap().to_string()
+        );
+        assert!(bd("-1").sqrt().is_err());
+    }
+
+    #[test]
+    fn round() {
+        use RoundingMode::*;
+
+        let bd = |s: &str| BigDecimal::from_str(s).unwrap();
+        let round = |s: &str, scale: i64, mode: RoundingMode| bd(s).round(scale, mode).to_string();
+
+        let cases = [
+            ("2.5", ["3", "2", "3", "2", "3", "2", "2"]),
+            ("3.5", ["4", "3", "4", "3", "4", "3", "4"]),
+            ("-2.5", ["-3", "-2", "-2", "-3", "-3", "-2", "-2"]),
+            ("2.6", ["3", "2", "3", "2", "3", "3", "3"]),
+            ("-2.4", ["-3", "-2", "-2", "-3", "-2", "-2", "-2"]),
+            ("7", ["7", "7", "7", "7", "7", "7", "7"]),
+        ];
+        let modes = [Up, Down, Ceiling, Floor, HalfUp, HalfDown, HalfEven];
+        for (value, expected) in cases {
+            for (mode, expected) in modes.iter().zip(expected) {
+                assert_eq!(expected, round(value, 0, *mode), "{} {:?}", value, mode);
+            }
+        }
+
+        assert_eq!("1.23", round("1.2345", 2, HalfUp));
+        assert_eq!("1.235", round("1.2345", 3, HalfUp));
+        assert_eq!("1.234", round("1.2345", 3, HalfEven));
+        assert_eq!("120", round("123.45", -1, HalfUp));
+        assert_eq!("0", round("0.0004", 2, HalfUp));
+        assert_eq!("0.01", round("0.0004", 2, Up));
+        assert_eq!("-0.01", round("-0.0004", 2, Floor));
+        assert_eq!("0.0004", round("0.0004", 2000, HalfUp));
+    }
+
+    #[test]
+    fn truncate() {
+        let bd = |s: &str| BigDecimal::from_str(s).unwrap();
+
+        assert_eq!("-1.9", bd("-1.999").truncate(1).to_string());
+        assert_eq!("1", bd("1.999").truncate(0).to_string());
+        assert_eq!("100", bd("199").truncate(-2).to_string());
+        assert_eq!("1.25", bd("1.25").truncate(5).to_string());
+    }
 }
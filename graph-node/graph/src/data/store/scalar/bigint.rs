@@ -222,6 +222,37 @@ impl BigInt {
 
         BigInt::new(self.inner().pow(&exponent))
     }
+
+    /// The integer square root of `self`, i.e., the square root rounded
+    /// down
+    pub fn sqrt(self) -> Result<BigInt, anyhow::Error> {
+        if self.sign() == BigIntSign::Minus {
+            anyhow::bail!("Cannot take the square root of {}", self);
+        }
+        Ok(BigInt::unchecked_new(num_integer::Roots::sqrt(
+            &self.inner(),
+        )))
+    }
+
+    /// Calculate `self^exponent mod modulus`. The result has the same sign
+    /// as `modulus`
+    pub fn mod_pow(self, exponent: BigInt, modulus: BigInt) -> Result<BigInt, anyhow::Error> {
+        if exponent.sign() == BigIntSign::Minus {
+            anyhow::bail!("Cannot use the negative exponent {} in modPow", exponent);
+        }
+        if modulus == BigInt::from(0) {
+            anyhow::bail!("Cannot use a modulus of zero in modPow");
+        }
+        Ok(BigInt::unchecked_new(
+            self.inner().modpow(&exponent.inner(), &modulus.inner()),
+        ))
+    }
+
+    /// The greatest common divisor of `self` and `other`, which is always
+    /// positive unless both are zero
+    pub fn gcd(self, other: BigInt) -> BigInt {
+        BigInt::unchecked_new(num_integer::Integer::gcd(&self.inner(), &other.inner()))
+    }
 }
 
 impl From<i32> for BigInt {
@@ -388,4 +419,38 @@ mod test {
             BigInt::from_signed_bytes_le(&(-1i32).to_le_bytes()).unwrap(),
         );
     }
+
+    #[test]
+    fn sqrt() {
+        assert_eq!(BigInt::from(0), BigInt::from(0).sqrt().unwrap());
+        assert_eq!(BigInt::from(3), BigInt::from(15).sqrt().unwrap());
+        assert_eq!(BigInt::from(4), BigInt::from(16).sqrt().unwrap());
+        assert_eq!(
+            BigInt::from(1u64 << 32),
+            BigInt::from(u64::MAX).sqrt().unwrap() + BigInt::from(1)
+        );
+        assert!(BigInt::from(-1).sqrt().is_err());
+    }
+
+    #[test]
+    fn mod_pow() {
+        let mod_pow =
+            |x: i32, exp: i32, m: i32| BigInt::from(x).mod_pow(BigInt::from(exp), BigInt::from(m));
+
+        assert_eq!(BigInt::from(445), mod_pow(4, 13, 497).unwrap());
+        assert_eq!(BigInt::from(1), mod_pow(7, 0, 13).unwrap());
+        assert_eq!(BigInt::from(2), mod_pow(-3, 3, 29).unwrap());
+        assert!(mod_pow(2, -1, 7).is_err());
+        assert!(mod_pow(2, 3, 0).is_err());
+    }
+
+    #[test]
+    fn gcd() {
+        let gcd = |x: i32, y: i32| BigInt::from(x).gcd(BigInt::from(y));
+
+        assert_eq!(BigInt::from(6), gcd(54, 24));
+        assert_eq!(BigInt::from(6), gcd(-54, 24));
+        assert_eq!(BigInt::from(7), gcd(0, 7));
+        assert_eq!(BigInt::from(0), gcd(0, 0));
+    }
 }
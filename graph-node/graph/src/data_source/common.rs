@@ -13,6 +13,48 @@ use slog::Logger;
 use std::{str::FromStr, sync::Arc};
 use web3::types::{Log, H160};
 
+/// The language a mapping is written in, taken from the `language` field
+/// of the mapping in the manifest. It determines the ABI the runtime uses to
+/// talk to the mapping
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MappingLanguage {
+    /// `wasm/assemblyscript`, using the AssemblyScript memory layout
+    AssemblyScript,
+    /// `wasm/rust`, using the length-prefixed binary encoding of the native
+    /// Rust ABI
+    Rust,
+}
+
+impl MappingLanguage {
+    pub const ASSEMBLYSCRIPT: &'static str = "wasm/assemblyscript";
+    pub const RUST: &'static str = "wasm/rust";
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MappingLanguage::AssemblyScript => Self::ASSEMBLYSCRIPT,
+            MappingLanguage::Rust => Self::RUST,
+        }
+    }
+}
+
+impl From<&str> for MappingLanguage {
+    /// We never validated the `language` of mappings, and there are
+    /// deployed subgraphs with all kinds of values in it. Anything that is
+    /// not explicitly `wasm/rust` is therefore treated as AssemblyScript
+    fn from(language: &str) -> Self {
+        match language {
+            Self::RUST => MappingLanguage::Rust,
+            _ => MappingLanguage::AssemblyScript,
+        }
+    }
+}
+
+impl std::fmt::Display for MappingLanguage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct MappingABI {
     pub name: String,
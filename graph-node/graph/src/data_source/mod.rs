@@ -19,7 +19,7 @@ use crate::{
         link_resolver::LinkResolver,
         store::{BlockNumber, StoredDynamicDataSource},
     },
-    data_source::{offchain::OFFCHAIN_KINDS, subgraph::SUBGRAPH_DS_KIND},
+    data_source::{common::MappingLanguage, offchain::OFFCHAIN_KINDS, subgraph::SUBGRAPH_DS_KIND},
     prelude::{CheapClone as _, DataSourceContext},
     schema::{EntityType, InputSchema},
 };
@@ -217,6 +217,14 @@ impl<C: Blockchain> DataSource<C> {
         }
     }
 
+    pub fn language(&self) -> MappingLanguage {
+        match self {
+            Self::Onchain(ds) => ds.language(),
+            Self::Offchain(ds) => MappingLanguage::from(ds.mapping.language.as_str()),
+            Self::Subgraph(ds) => MappingLanguage::from(ds.mapping.language.as_str()),
+        }
+    }
+
     pub fn entities(&self) -> EntityTypeAccess {
         match self {
             // Note: Onchain data sources have an `entities` field in the manifest, but it has never
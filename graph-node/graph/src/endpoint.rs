@@ -2,20 +2,75 @@ use std::{
     collections::HashMap,
     sync::{
         atomic::{AtomicU64, Ordering},
-        Arc,
+        Arc, Mutex,
     },
+    time::{Duration, Instant},
 };
 
-use prometheus::IntCounterVec;
-use slog::{warn, Logger};
+use prometheus::{GaugeVec, IntCounterVec};
+use slog::{info, warn, Logger};
 
 use crate::components::network_provider::ProviderName;
+use crate::env::ENV_VARS;
 use crate::{components::metrics::MetricsRegistry, data::value::Word};
 
+/// The weight of the most recent request when updating the rolling latency
+/// and error rate of a provider
+const SCORE_DECAY: f64 = 0.1;
+
+/// How many milliseconds of latency a provider that fails every request is
+/// worth when comparing scores. Makes a fast but failing provider look worse
+/// than a slow but reliable one
+const ERROR_PENALTY_MS: f64 = 10_000.0;
+
 /// ProviderCount is the underlying structure to keep the count,
 /// we require that all the hosts are known ahead of time, this way we can
 /// avoid locking since we don't need to modify the entire struture.
-type ProviderCount = Arc<HashMap<ProviderName, AtomicU64>>;
+type ProviderCount = Arc<HashMap<ProviderName, ProviderState>>;
+
+#[derive(Debug, Default)]
+struct ProviderState {
+    /// The number of consecutive failed requests
+    errors: AtomicU64,
+    stats: Mutex<ProviderStats>,
+}
+
+#[derive(Debug, Default)]
+struct ProviderStats {
+    /// Rolling average of the request latency in milliseconds; `None` if
+    /// we have not seen a request yet
+    latency_ms: Option<f64>,
+    /// Rolling fraction of failed requests
+    error_rate: f64,
+    /// Set while the circuit breaker for the provider is open
+    open_until: Option<Instant>,
+}
+
+impl ProviderStats {
+    fn is_open(&self, now: Instant) -> bool {
+        self.open_until.map_or(false, |until| until > now)
+    }
+}
+
+/// A snapshot of how well a provider has been doing recently
+#[derive(Clone, Debug)]
+pub struct ProviderScore {
+    pub provider: ProviderName,
+    /// Rolling average of the request latency in milliseconds
+    pub latency_ms: f64,
+    /// Rolling fraction of failed requests, between 0 and 1
+    pub error_rate: f64,
+    pub consecutive_errors: u64,
+    /// `true` while the provider is taken out of rotation
+    pub circuit_open: bool,
+}
+
+impl ProviderScore {
+    /// Combine latency and error rate into one number; lower is better
+    pub fn score(&self) -> f64 {
+        self.latency_ms + self.error_rate * ERROR_PENALTY_MS
+    }
+}
 
 /// This struct represents all the current labels except for the result
 /// which is added separately. If any new labels are necessary they should
@@ -61,10 +116,23 @@ impl RequestLabels {
 
 /// EndpointMetrics keeps track of calls success rate for specific calls,
 /// a success call to a host will clear the error count.
+///
+/// It also keeps a rolling latency and error rate per provider which are
+/// used to rank providers, and a circuit breaker that takes a provider out
+/// of rotation for a while after too many consecutive failures.
 pub struct EndpointMetrics {
     logger: Logger,
     providers: ProviderCount,
     counter: Box<IntCounterVec>,
+    latency_gauge: Box<GaugeVec>,
+    error_rate_gauge: Box<GaugeVec>,
+    circuit_open_gauge: Box<GaugeVec>,
+    queued_gauge: Box<GaugeVec>,
+    throttled: Box<IntCounterVec>,
+    /// Trip the circuit breaker after this many consecutive failures; 0
+    /// disables the circuit breaker
+    breaker_failures: u64,
+    breaker_cooldown: Duration,
 }
 
 impl std::fmt::Debug for EndpointMetrics {
@@ -79,11 +147,10 @@ impl EndpointMetrics {
         providers: &[impl AsRef<str>],
         registry: Arc<MetricsRegistry>,
     ) -> Self {
-        let providers = Arc::new(HashMap::from_iter(
-            providers
-                .iter()
-                .map(|h| (ProviderName::from(h.as_ref()), AtomicU64::new(0))),
-        ));
+        let providers =
+            Arc::new(HashMap::from_iter(providers.iter().map(|h| {
+                (ProviderName::from(h.as_ref()), ProviderState::default())
+            })));
 
         let counter = registry
             .new_int_counter_vec(
@@ -93,10 +160,47 @@ impl EndpointMetrics {
             )
             .expect("unable to create endpoint_request counter_vec");
 
+        let provider_gauge = |name: &str, help: &str| {
+            registry
+                .new_gauge_vec(name, help, vec!["provider".to_string()])
+                .unwrap_or_else(|_| panic!("unable to create {} gauge_vec", name))
+        };
+        let latency_gauge = provider_gauge(
+            "endpoint_latency_ms",
+            "rolling average of the request latency of a provider",
+        );
+        let error_rate_gauge = provider_gauge(
+            "endpoint_error_rate",
+            "rolling fraction of failed requests to a provider",
+        );
+        let circuit_open_gauge = provider_gauge(
+            "endpoint_circuit_open",
+            "1 if the provider is taken out of rotation because of errors",
+        );
+
+        let queued_gauge = provider_gauge(
+            "endpoint_queued_requests",
+            "number of requests waiting for the rate or concurrency limit of a provider",
+        );
+        let throttled = registry
+            .new_int_counter_vec(
+                "endpoint_throttled_requests",
+                "requests that were delayed or rejected because of provider limits",
+                &["provider", "reason"],
+            )
+            .expect("unable to create endpoint_throttled_requests counter_vec");
+
         Self {
             logger,
             providers,
             counter,
+            latency_gauge,
+            error_rate_gauge,
+            circuit_open_gauge,
+            queued_gauge,
+            throttled,
+            breaker_failures: ENV_VARS.provider_circuit_breaker_failures,
+            breaker_cooldown: ENV_VARS.provider_circuit_breaker_cooldown,
         }
     }
 
@@ -129,8 +233,15 @@ impl EndpointMetrics {
 
     pub fn success(&self, labels: &RequestLabels) {
         match self.providers.get(&labels.provider) {
-            Some(count) => {
-                count.store(0, Ordering::Relaxed);
+            Some(state) => {
+                state.errors.store(0, Ordering::Relaxed);
+                let mut stats = state.stats.lock().unwrap();
+                stats.error_rate *= 1.0 - SCORE_DECAY;
+                if stats.open_until.take().is_some() {
+                    info!(&self.logger, "Closing circuit breaker for provider";
+                          "provider" => labels.provider.as_str());
+                }
+                self.update_gauges(&labels.provider, &stats);
             }
             None => warn!(
                 &self.logger,
@@ -143,8 +254,25 @@ impl EndpointMetrics {
 
     pub fn failure(&self, labels: &RequestLabels) {
         match self.providers.get(&labels.provider) {
-            Some(count) => {
-                count.fetch_add(1, Ordering::Relaxed);
+            Some(state) => {
+                let errors = state.errors.fetch_add(1, Ordering::Relaxed) + 1;
+                let mut stats = state.stats.lock().unwrap();
+                stats.error_rate = stats.error_rate * (1.0 - SCORE_DECAY) + SCORE_DECAY;
+
+                // Once the cooldown has passed, the provider is tried again
+                // and one more failure is enough to take it out of rotation
+                let now = Instant::now();
+                if self.breaker_failures > 0
+                    && errors >= self.breaker_failures
+                    && !stats.is_open(now)
+                {
+                    warn!(&self.logger, "Opening circuit breaker for provider";
+                          "provider" => labels.provider.as_str(),
+                          "consecutive_errors" => errors,
+                          "cooldown_secs" => self.breaker_cooldown.as_secs());
+                    stats.open_until = Some(now + self.breaker_cooldown);
+                }
+                self.update_gauges(&labels.provider, &stats);
             }
             None => warn!(
                 &self.logger,
@@ -157,19 +285,109 @@ impl EndpointMetrics {
             .inc();
     }
 
+    /// Record how long a request to `provider` took, regardless of whether
+    /// it succeeded or not
+    pub fn latency(&self, provider: &ProviderName, elapsed: Duration) {
+        if let Some(state) = self.providers.get(provider) {
+            let mut stats = state.stats.lock().unwrap();
+            let elapsed = elapsed.as_secs_f64() * 1000.0;
+            stats.latency_ms = Some(match stats.latency_ms {
+                Some(latency) => latency * (1.0 - SCORE_DECAY) + elapsed * SCORE_DECAY,
+                None => elapsed,
+            });
+            self.update_gauges(provider, &stats);
+        }
+    }
+
+    /// A request to `provider` has to wait because of the provider's rate
+    /// or concurrency limit
+    pub fn request_queued(&self, provider: &ProviderName) {
+        self.queued_gauge
+            .with_label_values(&[provider.as_str()])
+            .inc();
+    }
+
+    /// A request that was waiting for `provider` is either sent or has been
+    /// abandoned
+    pub fn request_dequeued(&self, provider: &ProviderName) {
+        self.queued_gauge
+            .with_label_values(&[provider.as_str()])
+            .dec();
+    }
+
+    /// A request to `provider` was delayed or rejected for `reason`
+    pub fn throttled(&self, provider: &ProviderName, reason: &str) {
+        self.throttled
+            .with_label_values(&[provider.as_str(), reason])
+            .inc();
+    }
+
+    fn update_gauges(&self, provider: &ProviderName, stats: &ProviderStats) {
+        let labels = [provider.as_str()];
+        self.latency_gauge
+            .with_label_values(&labels)
+            .set(stats.latency_ms.unwrap_or(0.0));
+        self.error_rate_gauge
+            .with_label_values(&labels)
+            .set(stats.error_rate);
+        self.circuit_open_gauge
+            .with_label_values(&labels)
+            .set(if stats.is_open(Instant::now()) {
+                1.0
+            } else {
+                0.0
+            });
+    }
+
     /// Returns the current error count of a host or 0 if the host
     /// doesn't have a value on the map.
     pub fn get_count(&self, provider: &ProviderName) -> u64 {
         self.providers
             .get(provider)
-            .map(|c| c.load(Ordering::Relaxed))
+            .map(|state| state.errors.load(Ordering::Relaxed))
             .unwrap_or(0)
     }
+
+    /// Returns `false` while the circuit breaker for `provider` is open.
+    /// Unknown providers are always available
+    pub fn is_available(&self, provider: &ProviderName) -> bool {
+        self.providers
+            .get(provider)
+            .map(|state| !state.stats.lock().unwrap().is_open(Instant::now()))
+            .unwrap_or(true)
+    }
+
+    /// The current score of `provider`, or `None` if the provider is not
+    /// known
+    pub fn score(&self, provider: &ProviderName) -> Option<ProviderScore> {
+        self.providers.get(provider).map(|state| {
+            let stats = state.stats.lock().unwrap();
+            ProviderScore {
+                provider: provider.clone(),
+                latency_ms: stats.latency_ms.unwrap_or(0.0),
+                error_rate: stats.error_rate,
+                consecutive_errors: state.errors.load(Ordering::Relaxed),
+                circuit_open: stats.is_open(Instant::now()),
+            }
+        })
+    }
+
+    /// The scores of all providers, ordered by provider name
+    pub fn scores(&self) -> Vec<ProviderScore> {
+        let mut scores: Vec<_> = self
+            .providers
+            .keys()
+            .filter_map(|provider| self.score(provider))
+            .collect();
+        scores.sort_by(|a, b| a.provider.cmp(&b.provider));
+        scores
+    }
 }
 
 #[cfg(test)]
 mod test {
     use std::sync::Arc;
+    use std::time::Duration;
 
     use slog::{o, Discard, Logger};
 
@@ -197,4 +415,56 @@ mod test {
         assert_eq!(metrics.get_count(&b), 2);
         assert_eq!(metrics.get_count(&c), 0);
     }
+
+    #[tokio::test]
+    async fn circuit_breaker_opens_and_closes() {
+        let a: ProviderName = "a".into();
+        let hosts: &[&str] = &[&a];
+        let logger = Logger::root(Discard, o!());
+
+        let mut metrics = EndpointMetrics::new(logger, hosts, Arc::new(MetricsRegistry::mock()));
+        metrics.breaker_failures = 3;
+        metrics.breaker_cooldown = Duration::from_secs(3600);
+
+        metrics.report_for_test(&a, false);
+        metrics.report_for_test(&a, false);
+        assert!(metrics.is_available(&a));
+
+        metrics.report_for_test(&a, false);
+        assert!(!metrics.is_available(&a));
+        assert!(metrics.score(&a).unwrap().circuit_open);
+
+        metrics.report_for_test(&a, true);
+        assert!(metrics.is_available(&a));
+        assert_eq!(metrics.get_count(&a), 0);
+    }
+
+    #[tokio::test]
+    async fn score_prefers_fast_and_reliable_providers() {
+        let (a, b, c): (ProviderName, ProviderName, ProviderName) =
+            ("a".into(), "b".into(), "c".into());
+        let hosts: &[&str] = &[&a, &b, &c];
+        let logger = Logger::root(Discard, o!());
+
+        let metrics = EndpointMetrics::new(logger, hosts, Arc::new(MetricsRegistry::mock()));
+
+        metrics.latency(&a, Duration::from_millis(50));
+        metrics.report_for_test(&a, true);
+        metrics.latency(&b, Duration::from_millis(500));
+        metrics.report_for_test(&b, true);
+        metrics.latency(&c, Duration::from_millis(10));
+        metrics.report_for_test(&c, false);
+
+        let score = |p: &ProviderName| metrics.score(p).unwrap().score();
+        assert!(score(&a) < score(&b));
+        assert!(score(&b) < score(&c));
+        assert_eq!(
+            vec![a.clone(), b.clone(), c.clone()],
+            metrics
+                .scores()
+                .into_iter()
+                .map(|score| score.provider)
+                .collect::<Vec<_>>()
+        );
+    }
 }
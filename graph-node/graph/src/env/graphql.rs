@@ -100,6 +100,44 @@ pub struct EnvVarsGraphQl {
     /// Set by the env var `GRAPH_PARALLEL_BLOCK_CONSTRAINTS`
     /// Whether to run top-level queries with different block constraints in parallel
     pub parallel_block_constraints: bool,
+    /// How often live queries served over Server-Sent Events check whether
+    /// the head of the deployment they query has moved.
+    ///
+    /// Set by the environment variable
+    /// `GRAPH_GRAPHQL_LIVE_QUERY_POLL_INTERVAL` (expressed in milliseconds).
+    /// The default value is 1000.
+    pub live_query_poll_interval: Duration,
+    /// The maximum number of live queries that can be active at the same
+    /// time. Set to 0 to disable live queries.
+    ///
+    /// Set by the environment variable `GRAPH_GRAPHQL_MAX_LIVE_QUERIES`. The
+    /// default value is 1000.
+    pub max_live_queries: usize,
+    /// The maximum length in bytes of a regular expression passed to a
+    /// `_matches` or `_matches_nocase` filter.
+    ///
+    /// Set by the environment variable `GRAPH_GRAPHQL_MAX_REGEX_LENGTH`. The
+    /// default value is 256.
+    pub max_regex_length: usize,
+    /// Only run queries that the operator registered in the allow-list of
+    /// persisted queries with `graphman persisted-query add`
+    ///
+    /// Set by the flag `GRAPH_GRAPHQL_PERSISTED_QUERIES_ONLY`. Off by
+    /// default.
+    pub persisted_queries_only: bool,
+    /// How often the allow-list of persisted queries is reloaded from the
+    /// database.
+    ///
+    /// Set by the environment variable
+    /// `GRAPH_GRAPHQL_PERSISTED_QUERIES_REFRESH_INTERVAL` (expressed in
+    /// seconds). The default value is 60.
+    pub persisted_queries_refresh_interval: Duration,
+    /// The maximum amount of memory, in bytes, used to remember queries
+    /// that clients registered as automatic persisted queries.
+    ///
+    /// Set by the environment variable `GRAPH_GRAPHQL_APQ_CACHE_MAX_MEM`
+    /// (expressed in MB). The default value is 50MB.
+    pub apq_cache_max_mem: usize,
 }
 
 // This does not print any values avoid accidentally leaking any sensitive env vars
@@ -145,6 +183,14 @@ impl From<InnerGraphQl> for EnvVarsGraphQl {
             disable_child_sorting: x.disable_child_sorting.0,
             query_trace_token: x.query_trace_token,
             parallel_block_constraints: x.parallel_block_constraints.0,
+            live_query_poll_interval: Duration::from_millis(x.live_query_poll_interval_in_ms),
+            max_live_queries: x.max_live_queries,
+            max_regex_length: x.max_regex_length,
+            persisted_queries_only: x.persisted_queries_only.0,
+            persisted_queries_refresh_interval: Duration::from_secs(
+                x.persisted_queries_refresh_interval_in_secs,
+            ),
+            apq_cache_max_mem: x.apq_cache_max_mem_in_mb.0 * 1000 * 1000,
         }
     }
 }
@@ -196,4 +242,19 @@ pub struct InnerGraphQl {
     query_trace_token: String,
     #[envconfig(from = "GRAPH_PARALLEL_BLOCK_CONSTRAINTS", default = "false")]
     pub parallel_block_constraints: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_GRAPHQL_LIVE_QUERY_POLL_INTERVAL", default = "1000")]
+    live_query_poll_interval_in_ms: u64,
+    #[envconfig(from = "GRAPH_GRAPHQL_MAX_LIVE_QUERIES", default = "1000")]
+    max_live_queries: usize,
+    #[envconfig(from = "GRAPH_GRAPHQL_MAX_REGEX_LENGTH", default = "256")]
+    max_regex_length: usize,
+    #[envconfig(from = "GRAPH_GRAPHQL_PERSISTED_QUERIES_ONLY", default = "false")]
+    persisted_queries_only: EnvVarBoolean,
+    #[envconfig(
+        from = "GRAPH_GRAPHQL_PERSISTED_QUERIES_REFRESH_INTERVAL",
+        default = "60"
+    )]
+    persisted_queries_refresh_interval_in_secs: u64,
+    #[envconfig(from = "GRAPH_GRAPHQL_APQ_CACHE_MAX_MEM", default = "50")]
+    apq_cache_max_mem_in_mb: NoUnderscores<usize>,
 }
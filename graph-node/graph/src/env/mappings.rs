@@ -1,4 +1,5 @@
 use std::fmt;
+use std::path::PathBuf;
 
 use super::*;
 
@@ -26,6 +27,19 @@ pub struct EnvVarsMapping {
     /// Set by the environment variable `GRAPH_RUNTIME_MAX_STACK_SIZE`
     /// (expressed in bytes). The default value is 512KiB.
     pub max_stack_size: usize,
+    /// Directory in which compiled WASM modules are cached so that they
+    /// don't need to be compiled again when the node or subgraph restarts.
+    ///
+    /// Set by the environment variable `GRAPH_WASM_MODULE_CACHE_DIR`. No
+    /// cache is used if it is not set.
+    pub wasm_module_cache_dir: Option<PathBuf>,
+
+    /// Set by the environment variable `GRAPH_MAPPING_PROFILER`. Off by
+    /// default.
+    pub profiler: bool,
+    /// Set by the environment variable `GRAPH_MAPPING_PROFILER_BLOCKS`. The
+    /// default value is 1000.
+    pub profiler_blocks: usize,
 
     /// Set by the environment variable `GRAPH_MAX_IPFS_CACHE_FILE_SIZE`
     /// (expressed in bytes). The default value is 1MiB.
@@ -80,6 +94,9 @@ impl From<InnerMappingHandlers> for EnvVarsMapping {
             max_api_version: x.max_api_version,
             timeout: x.mapping_handler_timeout_in_secs.map(Duration::from_secs),
             max_stack_size: x.runtime_max_stack_size.0 .0,
+            wasm_module_cache_dir: x.wasm_module_cache_dir,
+            profiler: x.profiler.0,
+            profiler_blocks: x.profiler_blocks,
 
             max_ipfs_cache_file_size: x.max_ipfs_cache_file_size.0,
             max_ipfs_cache_size: x.max_ipfs_cache_size,
@@ -105,6 +122,12 @@ pub struct InnerMappingHandlers {
     mapping_handler_timeout_in_secs: Option<u64>,
     #[envconfig(from = "GRAPH_RUNTIME_MAX_STACK_SIZE", default = "")]
     runtime_max_stack_size: WithDefaultUsize<NoUnderscores<usize>, { 512 * 1024 }>,
+    #[envconfig(from = "GRAPH_WASM_MODULE_CACHE_DIR")]
+    wasm_module_cache_dir: Option<PathBuf>,
+    #[envconfig(from = "GRAPH_MAPPING_PROFILER", default = "false")]
+    profiler: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_MAPPING_PROFILER_BLOCKS", default = "1000")]
+    profiler_blocks: usize,
 
     // IPFS.
     #[envconfig(from = "GRAPH_MAX_IPFS_CACHE_FILE_SIZE", default = "")]
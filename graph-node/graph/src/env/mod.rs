@@ -250,6 +250,16 @@ pub struct EnvVars {
     /// Set by the environment variable `GRAPH_FIREHOSE_BLOCK_BATCH_SIZE`.
     /// The default value is 10.
     pub firehose_block_batch_size: usize,
+    /// Number of consecutive failed requests after which a provider is
+    /// taken out of rotation. Set by the environment variable
+    /// `GRAPH_PROVIDER_CIRCUIT_BREAKER_FAILURES`. The default value is 10;
+    /// setting it to 0 disables the circuit breaker.
+    pub provider_circuit_breaker_failures: u64,
+    /// How long a provider stays out of rotation once its circuit breaker
+    /// has tripped. Set by the environment variable
+    /// `GRAPH_PROVIDER_CIRCUIT_BREAKER_COOLDOWN_SECS`. The default value is
+    /// 60 seconds.
+    pub provider_circuit_breaker_cooldown: Duration,
 }
 
 impl EnvVars {
@@ -343,6 +353,10 @@ impl EnvVars {
             firehose_block_fetch_retry_limit: inner.firehose_block_fetch_retry_limit,
             firehose_block_fetch_timeout: inner.firehose_block_fetch_timeout,
             firehose_block_batch_size: inner.firehose_block_fetch_batch_size,
+            provider_circuit_breaker_failures: inner.provider_circuit_breaker_failures,
+            provider_circuit_breaker_cooldown: Duration::from_secs(
+                inner.provider_circuit_breaker_cooldown_in_secs,
+            ),
         })
     }
 
@@ -512,6 +526,10 @@ struct Inner {
     firehose_block_fetch_timeout: u64,
     #[envconfig(from = "GRAPH_FIREHOSE_FETCH_BLOCK_BATCH_SIZE", default = "10")]
     firehose_block_fetch_batch_size: usize,
+    #[envconfig(from = "GRAPH_PROVIDER_CIRCUIT_BREAKER_FAILURES", default = "10")]
+    provider_circuit_breaker_failures: u64,
+    #[envconfig(from = "GRAPH_PROVIDER_CIRCUIT_BREAKER_COOLDOWN_SECS", default = "60")]
+    provider_circuit_breaker_cooldown_in_secs: u64,
 }
 
 #[derive(Clone, Debug)]
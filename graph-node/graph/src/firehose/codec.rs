@@ -2,6 +2,10 @@
 #[path = "sf.firehose.v2.rs"]
 mod pbfirehose;
 
+#[rustfmt::skip]
+#[path = "sf.bstream.v1.rs"]
+mod pbbstream;
+
 #[rustfmt::skip]
 #[path = "sf.ethereum.transform.v1.rs"]
 mod pbethereum;
@@ -10,6 +14,7 @@ mod pbethereum;
 #[path = "sf.near.transform.v1.rs"]
 mod pbnear;
 
+pub use pbbstream::Block as BstreamBlock;
 pub use pbethereum::*;
 pub use pbfirehose::*;
 pub use pbnear::*;
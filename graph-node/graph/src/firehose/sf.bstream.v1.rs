@@ -0,0 +1,26 @@
+// This file is @generated by prost-build.
+/// Block is the envelope Firehose uses to store blocks, for example in
+/// `merged-blocks` bundles. Deprecated fields that graph-node does not need
+/// are not declared here.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Block {
+    #[prost(uint64, tag = "1")]
+    pub number: u64,
+    #[prost(string, tag = "2")]
+    pub id: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub parent_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "4")]
+    pub timestamp: ::core::option::Option<::prost_types::Timestamp>,
+    #[prost(uint64, tag = "5")]
+    pub lib_num: u64,
+    /// The chain specific block in bundles written before `payload` was
+    /// introduced
+    #[prost(bytes = "vec", tag = "8")]
+    pub payload_buffer: ::prost::alloc::vec::Vec<u8>,
+    #[prost(uint64, tag = "10")]
+    pub parent_num: u64,
+    /// The chain specific block, ex: sf.ethereum.type.v2.Block
+    #[prost(message, optional, tag = "11")]
+    pub payload: ::core::option::Option<::prost_types::Any>,
+}
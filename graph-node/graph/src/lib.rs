@@ -127,10 +127,10 @@ pub mod prelude {
     };
     pub use crate::components::store::{
         write::EntityModification, AssignmentChange, AssignmentOperation, AttributeNames,
-        BlockNumber, CachedEthereumCall, ChainStore, Child, ChildMultiplicity, EntityCache,
-        EntityCollection, EntityFilter, EntityLink, EntityOperation, EntityOrder,
-        EntityOrderByChild, EntityOrderByChildInfo, EntityQuery, EntityRange, EntityWindow,
-        EthereumCallCache, ParentLink, PartialBlockPtr, PoolWaitStats, QueryStore,
+        BlockNumber, CachedEthereumCall, ChainStore, Child, ChildMultiplicity, EntityAggregate,
+        EntityCache, EntityCollection, EntityCursor, EntityFilter, EntityLink, EntityOperation,
+        EntityOrder, EntityOrderByChild, EntityOrderByChildInfo, EntityQuery, EntityRange,
+        EntityWindow, EthereumCallCache, ParentLink, PartialBlockPtr, PoolWaitStats, QueryStore,
         QueryStoreManager, StoreError, StoreEvent, StoreEventStream, StoreEventStreamBox,
         SubgraphStore, UnfailOutcome, WindowAttribute, BLOCK_NUMBER_MAX,
     };
@@ -90,3 +90,35 @@ pub const YAML_FROM_BYTES: GasOp = GasOp {
     base_cost: DEFAULT_BASE_COST,
     size_mult: DEFAULT_GAS_PER_BYTE * 100,
 };
+
+// Hash functions are much faster than the default bandwidth assumption. Assume 100 MB/s for
+// SHA-256 and a bit more for BLAKE2b, which is faster on 64 bit platforms.
+pub const SHA256: GasOp = GasOp {
+    base_cost: DEFAULT_BASE_COST,
+    size_mult: GAS_PER_SECOND / 100_000_000,
+};
+
+pub const BLAKE2B256: GasOp = GasOp {
+    base_cost: DEFAULT_BASE_COST,
+    size_mult: GAS_PER_SECOND / 200_000_000,
+};
+
+// Recovering a secp256k1 public key takes about 50µs; the input has a fixed size.
+pub const SECP256K1_RECOVER: Gas = Gas(GAS_PER_SECOND / 20_000);
+
+// Verifying an ed25519 signature takes about 50µs plus hashing the message with SHA-512.
+pub const ED25519_VERIFY: GasOp = GasOp {
+    base_cost: GAS_PER_SECOND / 20_000,
+    size_mult: GAS_PER_SECOND / 100_000_000,
+};
+
+// Verifying a BLS signature requires two pairings and hashing the message to the curve, which
+// takes about 2ms.
+pub const BLS12381_VERIFY: GasOp = GasOp {
+    base_cost: GAS_PER_SECOND / 500,
+    size_mult: GAS_PER_SECOND / 100_000_000,
+};
+
+// Computing an EIP-712 digest hashes every field of the typed data separately. Charge for each
+// hashed byte what `crypto.keccak256` charges.
+pub const TYPED_DATA_HASH_BYTE: Gas = Gas(DEFAULT_GAS_PER_BYTE);
@@ -3,7 +3,8 @@ mod costs;
 mod ops;
 mod saturating;
 mod size_of;
-use crate::components::metrics::gas::GasMetrics;
+use crate::components::metrics::gas::{GasMetrics, HandlerProfile, HostFnProfile};
+use crate::components::store::BlockNumber;
 use crate::derive::CheapClone;
 use crate::prelude::ENV_VARS;
 use crate::runtime::DeterministicHostError;
@@ -12,8 +13,10 @@ pub use costs::DEFAULT_BASE_COST;
 pub use costs::*;
 pub use saturating::*;
 
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{fmt, fmt::Display};
 
 pub struct GasOp {
@@ -81,13 +84,18 @@ impl Display for Gas {
 pub struct GasCounter {
     counter: Arc<AtomicU64>,
     metrics: GasMetrics,
+    /// The calls to each host function, only tracked when the mapping
+    /// profiler is turned on
+    host_fns: Option<Arc<Mutex<HashMap<String, HostFnProfile>>>>,
 }
 
 impl GasCounter {
     pub fn new(metrics: GasMetrics) -> Self {
+        let host_fns = metrics.profile.as_ref().map(|_| Arc::default());
         Self {
             counter: Arc::new(AtomicU64::new(0)),
             metrics,
+            host_fns,
         }
     }
 
@@ -107,6 +115,17 @@ impl GasCounter {
             }
         }
 
+        if let (Some(host_fns), Some(method)) = (&self.host_fns, method) {
+            // The `gas` function is called by the instrumented code and not
+            // by the mapping
+            if method != "gas" {
+                let mut host_fns = host_fns.lock().unwrap();
+                let host_fn = host_fns.entry(method.to_string()).or_default();
+                host_fn.calls += 1;
+                host_fn.gas = host_fn.gas.saturating_add(amount.0);
+            }
+        }
+
         let old = self
             .counter
             .fetch_update(SeqCst, SeqCst, |v| Some(v.saturating_add(amount.0)))
@@ -137,4 +156,66 @@ impl GasCounter {
     pub fn get(&self) -> Gas {
         Gas(self.counter.load(SeqCst))
     }
+
+    /// Add the gas used and the host functions called so far to the
+    /// mapping profile of the deployment as one invocation of `handler`,
+    /// which `failed` if it trapped or timed out. Does nothing unless the
+    /// mapping profiler is turned on
+    pub fn record_profile(
+        &self,
+        block: BlockNumber,
+        handler: &str,
+        wall_time: Duration,
+        failed: bool,
+    ) {
+        let (Some(profile), Some(host_fns)) = (&self.metrics.profile, &self.host_fns) else {
+            return;
+        };
+        let handler_profile = HandlerProfile {
+            calls: 1,
+            failures: failed as u64,
+            wall_time,
+            gas: self.get().0,
+            host_fns: host_fns.lock().unwrap().clone(),
+        };
+        profile.record(block, handler, &handler_profile);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::components::metrics::gas::MappingProfile;
+
+    use super::*;
+
+    #[test]
+    fn records_profiles_when_profiling() {
+        let gas = GasCounter::new(GasMetrics::mock());
+        // Without a profile, there is nothing to record into
+        gas.record_profile(1, "handleA", Duration::from_millis(1), false);
+
+        let profile = Arc::new(MappingProfile::default());
+        let mut metrics = GasMetrics::mock();
+        metrics.profile = Some(profile.clone());
+        let gas = GasCounter::new(metrics);
+        gas.consume_host_fn_with_metrics(Gas(10), "store.set")
+            .unwrap();
+        gas.consume_host_fn_with_metrics(Gas(20), "store.set")
+            .unwrap();
+        gas.consume_host_fn_with_metrics(Gas(5), "gas").unwrap();
+        gas.record_profile(7, "handleA", Duration::from_millis(3), true);
+
+        let (first, last, handlers) = profile.summarize(0..=10).unwrap();
+        assert_eq!((7, 7), (first, last));
+        let handler = &handlers["handleA"];
+        assert_eq!(1, handler.calls);
+        assert_eq!(1, handler.failures);
+        assert_eq!(gas.get().0, handler.gas);
+        assert_eq!(Duration::from_millis(3), handler.wall_time);
+        // Gas used by the instrumented code itself is not a host function
+        assert_eq!(1, handler.host_fns.len());
+        let store_set = &handler.host_fns["store.set"];
+        assert_eq!(2, store_set.calls);
+        assert_eq!(30 + 2 * costs::HOST_EXPORT_GAS.0, store_set.gas);
+    }
 }
@@ -1,7 +1,7 @@
 //! Various implementations of GasSizeOf;
 
 use crate::{
-    components::store::LoadRelatedRequest,
+    components::store::{FindByQuery, LoadRelatedRequest},
     data::store::{scalar::Bytes, Value},
     schema::{EntityKey, EntityType},
 };
@@ -162,6 +162,12 @@ impl GasSizeOf for LoadRelatedRequest {
     }
 }
 
+impl GasSizeOf for FindByQuery {
+    fn gas_size_of(&self) -> Gas {
+        self.entity_type.gas_size_of() + self.attribute.gas_size_of() + self.value.gas_size_of()
+    }
+}
+
 impl GasSizeOf for EntityType {
     fn gas_size_of(&self) -> Gas {
         self.as_str().gas_size_of()
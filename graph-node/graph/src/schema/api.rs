@@ -9,9 +9,12 @@ use thiserror::Error;
 
 use crate::cheap_clone::CheapClone;
 use crate::data::graphql::{ObjectOrInterface, ObjectTypeExt, TypeExt};
-use crate::data::store::IdType;
+use crate::data::store::{IdType, ValueType, ID};
 use crate::env::ENV_VARS;
-use crate::schema::{ast, META_FIELD_NAME, META_FIELD_TYPE, SCHEMA_TYPE_NAME};
+use crate::schema::{
+    ast, kw, AggregationCurrent, AggregationInterval, AGGREGATE_SUFFIX, CURSOR_FIELD_NAME,
+    META_FIELD_NAME, META_FIELD_TYPE, SCHEMA_TYPE_NAME,
+};
 
 use crate::data::graphql::ext::{
     camel_cased_names, DefinitionExt, DirectiveExt, DocumentExt, ValueExt,
@@ -377,6 +380,28 @@ fn init_api_schema(input_schema: &InputSchema) -> Result<Schema, APISchemaError>
         }
     }
 
+    /// Add the `_cursor` field that exposes the position of an entity in
+    /// a collection for keyset pagination, unless the type already has a
+    /// field with that name
+    fn add_cursor_field(fields: &mut Vec<s::Field>) {
+        if fields.iter().any(|field| field.name == CURSOR_FIELD_NAME) {
+            return;
+        }
+        fields.push(s::Field {
+            position: Pos::default(),
+            description: Some(
+                "Opaque cursor for this entity that can be passed as `after` or `before` \
+                 to a collection query with the same `orderBy` to fetch the next or \
+                 previous page"
+                    .to_string(),
+            ),
+            name: CURSOR_FIELD_NAME.to_string(),
+            arguments: vec![],
+            field_type: s::Type::NamedType("String".to_string()),
+            directives: vec![],
+        });
+    }
+
     fn add_type_def(
         api: &mut s::Document,
         type_def: &s::TypeDefinition,
@@ -387,6 +412,7 @@ fn init_api_schema(input_schema: &InputSchema) -> Result<Schema, APISchemaError>
                 if ot.name != SCHEMA_TYPE_NAME {
                     let mut ot = ot.clone();
                     add_collection_arguments(&mut ot.fields, input_schema);
+                    add_cursor_field(&mut ot.fields);
                     let typedef = s::TypeDefinition::Object(ot);
                     let def = s::Definition::TypeDefinition(typedef);
                     api.definitions.push(def);
@@ -395,6 +421,7 @@ fn init_api_schema(input_schema: &InputSchema) -> Result<Schema, APISchemaError>
             s::TypeDefinition::Interface(it) => {
                 let mut it = it.clone();
                 add_collection_arguments(&mut it.fields, input_schema);
+                add_cursor_field(&mut it.fields);
                 let typedef = s::TypeDefinition::Interface(it);
                 let def = s::Definition::TypeDefinition(typedef);
                 api.definitions.push(def);
@@ -464,10 +491,91 @@ fn add_types_for_object_types(
     for (name, object_type) in schema.object_types() {
         add_order_by_type(&mut api.document, name, &object_type.fields)?;
         add_filter_type(api, name, &object_type.fields)?;
+        add_aggregate_types(&mut api.document, name, &object_type.fields)?;
     }
     Ok(())
 }
 
+/// Adds the `<type_name>_aggregate` type that the `<type>_aggregate` query
+/// field returns. Besides `count`, it has `sum`, `min`, and `max` fields
+/// whose types have one field for each numeric field of the object type
+fn add_aggregate_types(
+    api: &mut s::Document,
+    type_name: &str,
+    fields: &[Field],
+) -> Result<(), APISchemaError> {
+    fn add_object_type(
+        api: &mut s::Document,
+        name: String,
+        fields: Vec<s::Field>,
+    ) -> Result<(), APISchemaError> {
+        if api.get_named_type(&name).is_some() {
+            return Err(APISchemaError::TypeExists(name));
+        }
+        let typedef = s::TypeDefinition::Object(s::ObjectType {
+            position: Pos::default(),
+            description: None,
+            name,
+            implements_interfaces: vec![],
+            directives: vec![],
+            fields,
+        });
+        api.definitions.push(s::Definition::TypeDefinition(typedef));
+        Ok(())
+    }
+
+    fn field(name: &str, field_type: s::Type) -> s::Field {
+        s::Field {
+            position: Pos::default(),
+            description: None,
+            name: name.to_string(),
+            arguments: vec![],
+            field_type,
+            directives: vec![],
+        }
+    }
+
+    let numeric_fields: Vec<_> = fields
+        .iter()
+        .filter(|field| {
+            field.value_type.is_numeric()
+                && !field.is_list()
+                && !field.is_derived()
+                && field.name.as_str() != ID.as_str()
+        })
+        .collect();
+
+    let type_name = format!("{}{}", type_name, AGGREGATE_SUFFIX);
+    let mut aggregate_fields = vec![field(
+        "count",
+        s::Type::NonNullType(Box::new(s::Type::NamedType("Int".to_string()))),
+    )];
+    if !numeric_fields.is_empty() {
+        for (aggregate, sum) in [("sum", true), ("min", false), ("max", false)] {
+            let name = format!("{}_{}", type_name, aggregate);
+            let fields = numeric_fields
+                .iter()
+                .map(|numeric_field| {
+                    // Summing `Int` can overflow, and we use the next
+                    // larger type for it
+                    let value_type = match numeric_field.value_type {
+                        ValueType::Int if sum => ValueType::Int8,
+                        ValueType::Int8 if sum => ValueType::BigInt,
+                        value_type => value_type,
+                    };
+                    field(
+                        &numeric_field.name,
+                        s::Type::NamedType(value_type.to_str().to_string()),
+                    )
+                })
+                .collect();
+            add_object_type(api, name.clone(), fields)?;
+            aggregate_fields.push(field(aggregate, s::Type::NamedType(name)));
+        }
+    }
+    add_object_type(api, type_name, aggregate_fields)
+}
+
 /// Adds `*_orderBy` and `*_filter` enum types for the given interfaces to the schema.
 fn add_types_for_interface_types(
     api: &mut Schema,
@@ -493,30 +601,97 @@ fn add_types_for_aggregation_types(
         add_order_by_type(&mut api.document, name, &all_fields)?;
         add_aggregation_filter_type(api, name, agg_type)?;
     }
+    add_custom_intervals(&mut api.document, input_schema);
     Ok(())
 }
 
-/// Adds a `<type_name>_orderBy` enum type for the given fields to the schema.
+/// Add the custom intervals, e.g. `h4`, that aggregations in the input
+/// schema use to the `Aggregation_interval` enum from `meta.graphql`,
+/// which only contains the named intervals like `hour`
+fn add_custom_intervals(api: &mut s::Document, input_schema: &InputSchema) {
+    let mut custom: Vec<_> = input_schema
+        .aggregation_types()
+        .flat_map(|(_, agg_type)| agg_type.intervals.iter())
+        .filter(|interval| matches!(interval, AggregationInterval::Custom(_)))
+        .collect();
+    if custom.is_empty() {
+        return;
+    }
+    custom.sort();
+    custom.dedup();
+
+    let intervals = api.definitions.iter_mut().find_map(|defn| match defn {
+        s::Definition::TypeDefinition(s::TypeDefinition::Enum(et))
+            if et.name == "Aggregation_interval" =>
+        {
+            Some(et)
+        }
+        _ => None,
+    });
+    if let Some(intervals) = intervals {
+        intervals
+            .values
+            .extend(custom.into_iter().map(|interval| s::EnumValue {
+                position: Pos::default(),
+                description: None,
+                name: interval.to_string(),
+                directives: vec![],
+            }));
+    }
+}
+
+/// Adds a `<type_name>_orderBy` enum type for the given fields to the
+/// schema, and a `<type_name>_order` input type that combines one of these
+/// fields with a direction. The `orderBy` argument of collections takes a
+/// list of the latter so that they can be sorted by several fields
 fn add_order_by_type(
     api: &mut s::Document,
     type_name: &str,
     fields: &[Field],
 ) -> Result<(), APISchemaError> {
-    let type_name = format!("{}_orderBy", type_name);
+    let order_by_name = format!("{}_orderBy", type_name);
 
-    match api.get_named_type(&type_name) {
+    match api.get_named_type(&order_by_name) {
         None => {
             let typedef = s::TypeDefinition::Enum(s::EnumType {
                 position: Pos::default(),
                 description: None,
-                name: type_name,
+                name: order_by_name.clone(),
                 directives: vec![],
                 values: field_enum_values(api, fields)?,
             });
             let def = s::Definition::TypeDefinition(typedef);
             api.definitions.push(def);
         }
-        Some(_) => return Err(APISchemaError::TypeExists(type_name)),
+        Some(_) => return Err(APISchemaError::TypeExists(order_by_name)),
+    }
+
+    let order_name = format!("{}_order", type_name);
+    match api.get_named_type(&order_name) {
+        None => {
+            let field = input_value(
+                "field",
+                "",
+                s::Type::NonNullType(Box::new(s::Type::NamedType(order_by_name))),
+            );
+            // Without a direction, the `orderDirection` argument is used
+            let direction = input_value(
+                "direction",
+                "",
+                s::Type::NamedType("OrderDirection".to_string()),
+            );
+
+            let typedef = s::TypeDefinition::InputObject(s::InputObjectType {
+                position: Pos::default(),
+                description: None,
+                name: order_name,
+                directives: vec![],
+                fields: vec![field, direction],
+            });
+            let def = s::Definition::TypeDefinition(typedef);
+            api.definitions.push(def);
+        }
+        Some(_) => return Err(APISchemaError::TypeExists(order_name)),
     }
     Ok(())
 }
@@ -671,13 +846,28 @@ impl FilterOps {
         let filter_type = s::Type::NamedType(format!("{}_filter", type_name));
         let filter = input_value("where", "", filter_type);
 
+        // Opaque cursors as produced by the `_cursor` field for keyset
+        // pagination
+        let after = input_value("after", "", s::Type::NamedType("String".to_string()));
+        let before = input_value("before", "", s::Type::NamedType("String".to_string()));
+
+        // Sort by several fields, each with its own direction. A single
+        // value of the `<type_name>_orderBy` enum is also accepted for
+        // `orderBy` and is turned into a list with one entry during
+        // argument coercion
+        let order_by_arg = || {
+            input_value(
+                "orderBy",
+                "",
+                s::Type::ListType(Box::new(s::Type::NonNullType(Box::new(
+                    s::Type::NamedType(format!("{}_order", type_name)),
+                )))),
+            )
+        };
+
         let order_by = match self {
             FilterOps::Object => vec![
-                input_value(
-                    "orderBy",
-                    "",
-                    s::Type::NamedType(format!("{}_orderBy", type_name)),
-                ),
+                order_by_arg(),
                 input_value(
                     "orderDirection",
                     "",
@@ -692,11 +882,17 @@ impl FilterOps {
                         "Aggregation_interval".to_string(),
                     ))),
                 ),
-                input_value(
-                    "orderBy",
-                    "",
-                    s::Type::NamedType(format!("{}_orderBy", type_name)),
-                ),
+                {
+                    let mut current = input_value(
+                        kw::CURRENT,
+                        "",
+                        s::Type::NamedType("Aggregation_current".to_string()),
+                    );
+                    current.default_value =
+                        Some(s::Value::Enum(AggregationCurrent::Ignore.to_string()));
+                    current
+                },
+                order_by_arg(),
                 input_value(
                     "orderDirection",
                     "",
@@ -708,6 +904,8 @@ impl FilterOps {
         let mut args = vec![skip, first];
         args.extend(order_by);
         args.push(filter);
+        args.push(after);
+        args.push(before);
 
         args
     }
@@ -898,6 +1096,8 @@ fn field_filter_ops(set: FilterOpsSet<'_>) -> &'static [&'static str] {
             "ends_with_nocase",
             "not_ends_with",
             "not_ends_with_nocase",
+            "matches",
+            "matches_nocase",
         ],
         Aggregation("BigInt")
         | Aggregation("BigDecimal")
@@ -1006,6 +1206,8 @@ fn field_list_filter_input_values(
             "contains_nocase",
             "not_contains",
             "not_contains_nocase",
+            "overlaps",
+            "contained_by",
         ]
         .into_iter()
         .map(|filter_type| {
@@ -1054,8 +1256,17 @@ fn add_query_type(api: &mut s::Document, input_schema: &InputSchema) -> Result<(
     let mut fields = input_schema
         .object_types()
         .map(|(name, _)| name)
-        .chain(input_schema.interface_types().map(|(name, _)| name))
-        .flat_map(|name| query_fields_for_type(name, FilterOps::Object))
+        .flat_map(|name| {
+            let mut fields = query_fields_for_type(name, FilterOps::Object);
+            fields.push(query_field_for_aggregate(name));
+            fields
+        })
+        .chain(
+            input_schema
+                .interface_types()
+                .map(|(name, _)| name)
+                .flat_map(|name| query_fields_for_type(name, FilterOps::Object)),
+        )
         .collect::<Vec<s::Field>>();
     let mut agg_fields = input_schema
         .aggregation_types()
@@ -1234,6 +1445,36 @@ fn query_fields_for_type(type_name: &str, ops: FilterOps) -> Vec<s::Field> {
     ]
 }
 
+/// Generates the `Query` field for aggregates over a collection of
+/// entities of the given type (e.g. `user_aggregate`)
+fn query_field_for_aggregate(type_name: &str) -> s::Field {
+    let arguments = vec![
+        input_value(
+            "where",
+            "",
+            s::Type::NamedType(format!("{}_filter", type_name)),
+        ),
+        block_argument(),
+        subgraph_error_argument(),
+    ];
+
+    let (singular, _) = camel_cased_names(type_name);
+    s::Field {
+        position: Pos::default(),
+        description: Some(format!(
+            "Aggregates over the collection of `{}` entities",
+            type_name
+        )),
+        name: format!("{}{}", singular, AGGREGATE_SUFFIX),
+        arguments,
+        field_type: s::Type::NonNullType(Box::new(s::Type::NamedType(format!(
+            "{}{}",
+            type_name, AGGREGATE_SUFFIX
+        )))),
+        directives: vec![],
+    }
+}
+
 fn query_fields_for_agg_type(type_name: &str) -> Vec<s::Field> {
     let mut collection_arguments = FilterOps::Aggregation.collection_arguments(type_name);
     collection_arguments.push(block_argument());
@@ -1645,18 +1886,24 @@ mod tests {
                 "name_ends_with_nocase",
                 "name_not_ends_with",
                 "name_not_ends_with_nocase",
+                "name_matches",
+                "name_matches_nocase",
                 "favoritePetNames",
                 "favoritePetNames_not",
                 "favoritePetNames_contains",
                 "favoritePetNames_contains_nocase",
                 "favoritePetNames_not_contains",
                 "favoritePetNames_not_contains_nocase",
+                "favoritePetNames_overlaps",
+                "favoritePetNames_contained_by",
                 "pets",
                 "pets_not",
                 "pets_contains",
                 "pets_contains_nocase",
                 "pets_not_contains",
                 "pets_not_contains_nocase",
+                "pets_overlaps",
+                "pets_contained_by",
                 "pets_",
                 "favoriteFurType",
                 "favoriteFurType_not",
@@ -1682,6 +1929,8 @@ mod tests {
                 "favoritePet_ends_with_nocase",
                 "favoritePet_not_ends_with",
                 "favoritePet_not_ends_with_nocase",
+                "favoritePet_matches",
+                "favoritePet_matches_nocase",
                 "favoritePet_",
                 "leastFavoritePet_",
                 "mostFavoritePets_",
@@ -1750,12 +1999,16 @@ mod tests {
                 "name_ends_with_nocase",
                 "name_not_ends_with",
                 "name_not_ends_with_nocase",
+                "name_matches",
+                "name_matches_nocase",
                 "mostHatedBy",
                 "mostHatedBy_not",
                 "mostHatedBy_contains",
                 "mostHatedBy_contains_nocase",
                 "mostHatedBy_not_contains",
                 "mostHatedBy_not_contains_nocase",
+                "mostHatedBy_overlaps",
+                "mostHatedBy_contained_by",
                 "mostHatedBy_",
                 "mostLovedBy",
                 "mostLovedBy_not",
@@ -1763,6 +2016,8 @@ mod tests {
                 "mostLovedBy_contains_nocase",
                 "mostLovedBy_not_contains",
                 "mostLovedBy_not_contains_nocase",
+                "mostLovedBy_overlaps",
+                "mostLovedBy_contained_by",
                 "mostLovedBy_",
                 "_change_block",
                 "and",
@@ -1868,6 +2123,8 @@ mod tests {
                 "name_ends_with_nocase",
                 "name_not_ends_with",
                 "name_not_ends_with_nocase",
+                "name_matches",
+                "name_matches_nocase",
                 "pets_",
                 "favoritePet",
                 "favoritePet_not",
@@ -1889,6 +2146,8 @@ mod tests {
                 "favoritePet_ends_with_nocase",
                 "favoritePet_not_ends_with",
                 "favoritePet_not_ends_with_nocase",
+                "favoritePet_matches",
+                "favoritePet_matches_nocase",
                 "favoritePet_",
                 "_change_block",
                 "and",
@@ -1977,6 +2236,8 @@ mod tests {
                 "orderBy",
                 "orderDirection",
                 "where",
+                "after",
+                "before",
                 "block",
                 "subgraphError",
             ]
@@ -2010,6 +2271,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn api_schema_contains_aggregate_fields_on_query_type() {
+        let schema = parse(
+            "type User @entity { id: ID!, name: String!, age: Int!, balance: BigInt, tags: [Int!]! }",
+        );
+
+        let field = query_field(&schema, "user_aggregate");
+        assert_eq!(
+            field.field_type,
+            Type::NonNullType(Box::new(Type::NamedType("User_aggregate".to_string())))
+        );
+        assert_eq!(
+            field
+                .arguments
+                .iter()
+                .map(|input_value| input_value.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["where", "block", "subgraphError"]
+        );
+
+        let fields = |name: &str| match schema.get_named_type(name) {
+            Some(TypeDefinition::Object(t)) => t
+                .fields
+                .iter()
+                .map(|field| (field.name.clone(), field.field_type.to_string()))
+                .collect::<Vec<_>>(),
+            _ => panic!("{} is not an object type", name),
+        };
+        let fields_of = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(name, typ)| (name.to_string(), typ.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            fields("User_aggregate"),
+            fields_of(&[
+                ("count", "Int!"),
+                ("sum", "User_aggregate_sum"),
+                ("min", "User_aggregate_min"),
+                ("max", "User_aggregate_max")
+            ])
+        );
+        assert_eq!(
+            fields("User_aggregate_sum"),
+            fields_of(&[("age", "Int8"), ("balance", "BigInt")])
+        );
+        assert_eq!(
+            fields("User_aggregate_min"),
+            fields_of(&[("age", "Int"), ("balance", "BigInt")])
+        );
+
+        // Types without numeric fields only support `count`
+        let schema = parse("type User @entity { id: ID!, name: String! }");
+        match schema.get_named_type("User_aggregate") {
+            Some(TypeDefinition::Object(t)) => {
+                assert_eq!(
+                    t.fields
+                        .iter()
+                        .map(|field| field.name.as_str())
+                        .collect::<Vec<_>>(),
+                    vec!["count"]
+                );
+            }
+            _ => panic!("User_aggregate is not an object type"),
+        }
+        assert!(schema.get_named_type("User_aggregate_sum").is_none());
+    }
+
     #[test]
     fn api_schema_contains_interface_fields_on_query_type() {
         let schema = parse(
@@ -2072,6 +2403,8 @@ mod tests {
                 "orderBy",
                 "orderDirection",
                 "where",
+                "after",
+                "before",
                 "block",
                 "subgraphError"
             ]
@@ -2214,6 +2547,8 @@ type Gravatar @entity {
             let filter_type = format!("{typename}_filter");
             let interval = field.argument("interval").unwrap();
             assert_eq!("Aggregation_interval", interval.value_type.get_base_type());
+            let current = field.argument("current").unwrap();
+            assert_eq!("Aggregation_current", current.value_type.get_base_type());
             let filter = field.argument("where").unwrap();
             assert_eq!(&filter_type, filter.value_type.get_base_type());
 
@@ -2277,6 +2612,46 @@ type Gravatar @entity {
         assert_aggregation_field(&schema, stats, "Stats");
     }
 
+    #[test]
+    fn custom_aggregation_intervals() {
+        const SCHEMA: &str = r#"
+        type Data @entity(timeseries: true) {
+            id: Int8!
+            timestamp: Timestamp!
+            value: BigDecimal!
+        }
+
+        type Stats @aggregation(source: "Data", intervals: ["h4", "minute", "m15"]) {
+            id: Int8!
+            timestamp: Timestamp!
+            sum: BigDecimal! @aggregate(fn: "sum", arg: "value")
+        }
+
+        type Totals @aggregation(source: "Data", intervals: ["h4", "week"]) {
+            id: Int8!
+            timestamp: Timestamp!
+            sum: BigDecimal! @aggregate(fn: "sum", arg: "value")
+        }
+        "#;
+
+        let schema = parse(SCHEMA);
+        let s::TypeDefinition::Enum(intervals) = schema
+            .get_type_definition_from_type(&s::Type::NamedType("Aggregation_interval".to_string()))
+            .unwrap()
+        else {
+            panic!("Aggregation_interval is not an enum")
+        };
+        let names: Vec<_> = intervals
+            .values
+            .iter()
+            .map(|value| value.name.as_str())
+            .collect();
+        assert_eq!(
+            vec!["minute", "hour", "day", "week", "month", "m15", "h4"],
+            names
+        );
+    }
+
     #[test]
     fn no_extra_filters_for_interface_children() {
         #[track_caller]
@@ -2351,11 +2726,13 @@ type Gravatar @entity {
                 [
                     "pools",
                     "pools_",
+                    "pools_contained_by",
                     "pools_contains",
                     "pools_contains_nocase",
                     "pools_not",
                     "pools_not_contains",
                     "pools_not_contains_nocase",
+                    "pools_overlaps",
                 ],
                 pools_fields.as_slice(),
                 "Field {protos} has the wrong pools filters"
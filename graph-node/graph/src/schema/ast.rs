@@ -32,6 +32,10 @@ pub enum FilterOp {
     EndsWithNoCase,
     NotEndsWith,
     NotEndsWithNoCase,
+    Matches,
+    MatchesNoCase,
+    Overlaps,
+    ContainedBy,
     Equal,
     Child,
     And,
@@ -68,6 +72,10 @@ pub fn parse_field_as_filter(key: &str) -> (String, FilterOp) {
         }
         k if k.ends_with("_ends_with") => ("_ends_with", FilterOp::EndsWith),
         k if k.ends_with("_ends_with_nocase") => ("_ends_with_nocase", FilterOp::EndsWithNoCase),
+        k if k.ends_with("_matches") => ("_matches", FilterOp::Matches),
+        k if k.ends_with("_matches_nocase") => ("_matches_nocase", FilterOp::MatchesNoCase),
+        k if k.ends_with("_overlaps") => ("_overlaps", FilterOp::Overlaps),
+        k if k.ends_with("_contained_by") => ("_contained_by", FilterOp::ContainedBy),
         k if k.ends_with('_') => ("_", FilterOp::Child),
         k if k.eq("and") => ("and", FilterOp::And),
         k if k.eq("or") => ("or", FilterOp::Or),
@@ -51,6 +51,7 @@ pub mod kw {
     pub const INTERVALS: &str = "intervals";
     pub const INTERVAL: &str = "interval";
     pub const CUMULATIVE: &str = "cumulative";
+    pub const CURRENT: &str = "current";
 }
 
 /// The internal representation of a subgraph schema, i.e., the
@@ -612,6 +613,13 @@ pub enum AggregateFn {
     Count,
     First,
     Last,
+    Avg,
+    /// The population variance
+    Variance,
+    /// The population standard deviation
+    Stddev,
+    /// An approximate percentile between 1 and 99
+    Percentile(u8),
 }
 
 impl FromStr for AggregateFn {
@@ -625,7 +633,16 @@ impl FromStr for AggregateFn {
             "count" => Ok(AggregateFn::Count),
             "first" => Ok(AggregateFn::First),
             "last" => Ok(AggregateFn::Last),
-            _ => Err(anyhow!("invalid aggregate function `{}`", s)),
+            "avg" => Ok(AggregateFn::Avg),
+            "variance" => Ok(AggregateFn::Variance),
+            "stddev" => Ok(AggregateFn::Stddev),
+            _ => s
+                .strip_prefix('p')
+                .and_then(|p| p.parse::<u8>().ok())
+                .filter(|p| (1..=99).contains(p))
+                .filter(|p| s == format!("p{p}"))
+                .map(AggregateFn::Percentile)
+                .ok_or_else(|| anyhow!("invalid aggregate function `{}`", s)),
         }
     }
 }
@@ -634,46 +651,110 @@ impl AggregateFn {
     pub fn has_arg(&self) -> bool {
         use AggregateFn::*;
         match self {
-            Sum | Max | Min | First | Last => true,
+            Sum | Max | Min | First | Last | Avg | Variance | Stddev | Percentile(_) => true,
             Count => false,
         }
     }
 
-    fn as_str(&self) -> &'static str {
+    /// Whether the aggregate can only be combined with other aggregates
+    /// of the same kind through some additional state that needs to be
+    /// stored alongside the aggregate, like the count and sum for `avg`
+    pub fn has_state(&self) -> bool {
         use AggregateFn::*;
         match self {
-            Sum => "sum",
-            Max => "max",
-            Min => "min",
-            Count => "count",
-            First => "first",
-            Last => "last",
+            Sum | Max | Min | Count | First | Last => false,
+            Avg | Variance | Stddev | Percentile(_) => true,
         }
     }
 }
 
-/// The supported intervals for timeseries in order of decreasing
-/// granularity. The intervals must all be divisible by the smallest
-/// interval
-#[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
+impl std::fmt::Display for AggregateFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use AggregateFn::*;
+        match self {
+            Sum => f.write_str("sum"),
+            Max => f.write_str("max"),
+            Min => f.write_str("min"),
+            Count => f.write_str("count"),
+            First => f.write_str("first"),
+            Last => f.write_str("last"),
+            Avg => f.write_str("avg"),
+            Variance => f.write_str("variance"),
+            Stddev => f.write_str("stddev"),
+            Percentile(p) => write!(f, "p{p}"),
+        }
+    }
+}
+
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(3600);
+const DAY: Duration = Duration::from_secs(3600 * 24);
+const WEEK: Duration = Duration::from_secs(3600 * 24 * 7);
+/// Weekly buckets start on Mondays; the first Monday after the epoch was
+/// 1970-01-05
+const WEEK_ORIGIN: Duration = Duration::from_secs(3600 * 24 * 4);
+
+/// The supported intervals for timeseries. Intervals are ordered by their
+/// length, where a month counts as 31 days.
+///
+/// All intervals other than `Month` have a fixed length. Buckets for them
+/// start at the epoch, except for weekly buckets which start on Mondays.
+/// Monthly buckets follow the calendar and start at midnight UTC on the
+/// first day of each month. Custom intervals are written as a number of
+/// minutes, hours, or days, e.g., `m15`, `h4`, or `d2`, and always use the
+/// largest unit that divides their length evenly
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub enum AggregationInterval {
+    Minute,
     Hour,
     Day,
+    Week,
+    Month,
+    /// An interval whose length is a whole number of minutes
+    Custom(Duration),
 }
 
 impl AggregationInterval {
-    pub fn as_str(&self) -> &'static str {
+    /// The length of the interval; for `Month`, this is the length of the
+    /// longest month, 31 days
+    pub fn as_duration(&self) -> Duration {
+        use AggregationInterval::*;
         match self {
-            AggregationInterval::Hour => "hour",
-            AggregationInterval::Day => "day",
+            Minute => MINUTE,
+            Hour => HOUR,
+            Day => DAY,
+            Week => WEEK,
+            Month => DAY * 31,
+            Custom(length) => *length,
         }
     }
 
-    pub fn as_duration(&self) -> Duration {
+    /// Return the fixed length of buckets for this interval and when the
+    /// first bucket starts relative to the epoch, or `None` for intervals
+    /// that follow the calendar
+    pub fn fixed_length(&self) -> Option<(Duration, Duration)> {
         use AggregationInterval::*;
         match self {
-            Hour => Duration::from_secs(3600),
-            Day => Duration::from_secs(3600 * 24),
+            Minute | Hour | Day | Custom(_) => Some((self.as_duration(), Duration::ZERO)),
+            Week => Some((WEEK, WEEK_ORIGIN)),
+            Month => None,
+        }
+    }
+
+    fn bucket_nr(&self, time: BlockTime) -> i64 {
+        match self.fixed_length() {
+            Some((length, origin)) => time.bucket(length, origin),
+            None => time.months_since_epoch(),
+        }
+    }
+
+    fn bucket_start(&self, nr: i64) -> BlockTime {
+        match self.fixed_length() {
+            Some((length, origin)) => {
+                let secs = nr * length.as_secs() as i64 + origin.as_secs() as i64;
+                BlockTime::since_epoch(secs, 0)
+            }
+            None => BlockTime::start_of_month(nr),
         }
     }
 
@@ -682,22 +763,49 @@ impl AggregationInterval {
     /// that overlap `from..to` and end before `to`. The ranges are in
     /// increasing order of the start time
     pub fn buckets(&self, from: BlockTime, to: BlockTime) -> Vec<Range<BlockTime>> {
-        let first = from.bucket(self.as_duration());
-        let last = to.bucket(self.as_duration());
+        let first = self.bucket_nr(from);
+        let last = self.bucket_nr(to);
         (first..last)
-            .map(|nr| self.as_duration() * nr as u32)
-            .map(|start| {
-                let lower = BlockTime::from(start);
-                let upper = BlockTime::from(start + self.as_duration());
-                lower..upper
-            })
+            .map(|nr| self.bucket_start(nr)..self.bucket_start(nr + 1))
             .collect()
     }
 }
 
+impl Ord for AggregationInterval {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // Custom intervals sort before `Week` and `Month` when they have
+        // the same length
+        let key = |intv: &Self| (intv.as_duration(), !matches!(intv, Self::Custom(_)));
+        key(self).cmp(&key(other))
+    }
+}
+
+impl PartialOrd for AggregationInterval {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl std::fmt::Display for AggregationInterval {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.as_str())
+        use AggregationInterval::*;
+        match self {
+            Minute => f.write_str("minute"),
+            Hour => f.write_str("hour"),
+            Day => f.write_str("day"),
+            Week => f.write_str("week"),
+            Month => f.write_str("month"),
+            Custom(length) => {
+                let secs = length.as_secs();
+                if secs % DAY.as_secs() == 0 {
+                    write!(f, "d{}", secs / DAY.as_secs())
+                } else if secs % HOUR.as_secs() == 0 {
+                    write!(f, "h{}", secs / HOUR.as_secs())
+                } else {
+                    write!(f, "m{}", secs / MINUTE.as_secs())
+                }
+            }
+        }
     }
 }
 
@@ -726,16 +834,122 @@ fn buckets() {
     );
     assert_eq!(vec![eight_am..nine_am], Hour.buckets(one_hour, two_hour));
     assert_eq!(Vec::<Range<BlockTime>>::new(), Day.buckets(start, two_hour));
+
+    // Two-hour buckets start at even hours
+    let six_am = BlockTime::since_epoch(EIGHT_AM - 2 * 3600, 0);
+    let h2 = Custom(Duration::from_secs(2 * 3600));
+    assert_eq!(vec![six_am..eight_am], h2.buckets(start, two_hour));
+
+    // 2006-07-16 is a Sunday; the week started on Monday 2006-07-10 and
+    // the month on 2006-07-01
+    let monday = BlockTime::since_epoch(1152489600, 0);
+    let next_monday = BlockTime::since_epoch(1152489600 + 7 * 24 * 3600, 0);
+    let july = BlockTime::since_epoch(1151712000, 0);
+    let august = BlockTime::since_epoch(1154390400, 0);
+    let september = BlockTime::since_epoch(1157068800, 0);
+    let later = BlockTime::since_epoch(START + 24 * 3600, 0);
+    assert_eq!(vec![monday..next_monday], Week.buckets(start, later));
+    assert_eq!(Vec::<Range<BlockTime>>::new(), Month.buckets(start, later));
+    assert_eq!(
+        vec![july..august, august..september],
+        Month.buckets(start, BlockTime::since_epoch(1157068800 + 1, 0))
+    );
 }
 
 impl FromStr for AggregationInterval {
     type Err = Error;
 
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let interval = match s {
+            "minute" => AggregationInterval::Minute,
+            "hour" => AggregationInterval::Hour,
+            "day" => AggregationInterval::Day,
+            "week" => AggregationInterval::Week,
+            "month" => AggregationInterval::Month,
+            _ => {
+                let (unit, count) = [('m', MINUTE), ('h', HOUR), ('d', DAY)]
+                    .into_iter()
+                    .find_map(|(prefix, unit)| s.strip_prefix(prefix).map(|count| (unit, count)))
+                    .ok_or_else(|| anyhow!("invalid aggregation interval `{}`", s))?;
+                let count = count
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|count| *count > 0)
+                    .ok_or_else(|| anyhow!("invalid aggregation interval `{}`", s))?;
+                match unit * count {
+                    MINUTE => AggregationInterval::Minute,
+                    HOUR => AggregationInterval::Hour,
+                    DAY => AggregationInterval::Day,
+                    length => AggregationInterval::Custom(length),
+                }
+            }
+        };
+        // Only accept the canonical spelling of an interval so that every
+        // interval has exactly one name in the API and in the database
+        if interval.to_string() != s {
+            return Err(anyhow!(
+                "invalid aggregation interval `{}`, use `{}` instead",
+                s,
+                interval
+            ));
+        }
+        Ok(interval)
+    }
+}
+
+#[test]
+fn interval_names() {
+    use AggregationInterval::*;
+
+    for name in ["minute", "hour", "day", "week", "month", "m15", "h4", "d2"] {
+        let interval = name.parse::<AggregationInterval>().unwrap();
+        assert_eq!(name, interval.to_string());
+    }
+    assert_eq!(Custom(Duration::from_secs(900)), "m15".parse().unwrap());
+    for name in ["m60", "h24", "m120", "m0", "m015", "x5", "m", "fortnight"] {
+        assert!(name.parse::<AggregationInterval>().is_err(), "{name}");
+    }
+    let mut intervals = vec![Month, Custom(DAY * 31), Week, Custom(WEEK), Hour, Minute];
+    intervals.sort();
+    assert_eq!(
+        vec![Minute, Hour, Custom(WEEK), Week, Custom(DAY * 31), Month],
+        intervals
+    );
+}
+
+/// Whether a query for an aggregation should include the current bucket,
+/// i.e., the bucket that has not been rolled up yet because its interval
+/// has not ended
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AggregationCurrent {
+    #[default]
+    Ignore,
+    Include,
+}
+
+impl AggregationCurrent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AggregationCurrent::Ignore => "ignore",
+            AggregationCurrent::Include => "include",
+        }
+    }
+}
+
+impl std::fmt::Display for AggregationCurrent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AggregationCurrent {
+    type Err = Error;
+
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "hour" => Ok(AggregationInterval::Hour),
-            "day" => Ok(AggregationInterval::Day),
-            _ => Err(anyhow!("invalid aggregation interval `{}`", s)),
+            "ignore" => Ok(AggregationCurrent::Ignore),
+            "include" => Ok(AggregationCurrent::Include),
+            _ => Err(anyhow!("invalid value for current `{}`", s)),
         }
     }
 }
@@ -877,7 +1091,7 @@ impl Aggregation {
         let obj_types = intervals
             .iter()
             .map(|interval| {
-                let name = format!("{}_{}", &agg_type.name, interval.as_str());
+                let name = format!("{}_{}", &agg_type.name, interval);
                 let name = pool.lookup(&name).unwrap();
                 ObjectType {
                     name,
@@ -2492,7 +2706,7 @@ mod validations {
                                         errors.push(Err::AggregationMissingArg(
                                             agg_type.name.to_owned(),
                                             field.name.to_owned(),
-                                            func.as_str().to_owned(),
+                                            func.to_string(),
                                         ));
                                         continue;
                                     } else {
@@ -2522,6 +2736,16 @@ mod validations {
                                     continue;
                                 }
                             };
+                            // Statistical aggregates are generally not
+                            // whole numbers, even for integer arguments
+                            if func.has_state() && field_type != ValueType::BigDecimal {
+                                errors.push(Err::AggregationNonDecimalResult(
+                                    agg_type.name.to_owned(),
+                                    field.name.to_owned(),
+                                    func.to_string(),
+                                ));
+                                continue;
+                            }
                             // It would be nicer to use a proper struct here
                             // and have that implement
                             // `sqlexpr::ExprVisitor` but we need access to
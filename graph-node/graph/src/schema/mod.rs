@@ -32,8 +32,8 @@ pub use fulltext::{FulltextAlgorithm, FulltextConfig, FulltextDefinition, Fullte
 pub use input::sqlexpr::{ExprVisitor, VisitExpr};
 pub(crate) use input::POI_OBJECT;
 pub use input::{
-    kw, Aggregate, AggregateFn, Aggregation, AggregationInterval, AggregationMapping, Field,
-    InputSchema, InterfaceType, ObjectOrInterface, ObjectType, TypeKind,
+    kw, Aggregate, AggregateFn, Aggregation, AggregationCurrent, AggregationInterval,
+    AggregationMapping, Field, InputSchema, InterfaceType, ObjectOrInterface, ObjectType, TypeKind,
 };
 
 pub const SCHEMA_TYPE_NAME: &str = "_Schema_";
@@ -42,6 +42,16 @@ pub const INTROSPECTION_SCHEMA_FIELD_NAME: &str = "__schema";
 pub const META_FIELD_TYPE: &str = "_Meta_";
 pub const META_FIELD_NAME: &str = "_meta";
 
+/// The field through which the cursor for an entity in a collection is
+/// exposed; its value can be passed as `after` or `before` to page
+/// through the collection
+pub const CURSOR_FIELD_NAME: &str = "_cursor";
+
+/// Suffix of the query fields and types that expose aggregates like
+/// `count` over a collection of entities, e.g., the field
+/// `user_aggregate` of type `User_aggregate`
+pub const AGGREGATE_SUFFIX: &str = "_aggregate";
+
 pub const INTROSPECTION_TYPE_FIELD_NAME: &str = "__type";
 
 pub const BLOCK_FIELD_TYPE: &str = "_Block_";
@@ -177,6 +187,8 @@ pub enum SchemaValidationError {
     AggregationNonNumericArg(String, String, String, String),
     #[error("Field {1} in aggregation {0} has an invalid value for `cumulative`. It needs to be a boolean")]
     AggregationInvalidCumulative(String, String),
+    #[error("Field {1} in aggregation {0} uses the function {2} and must therefore have type BigDecimal")]
+    AggregationNonDecimalResult(String, String, String),
     #[error("Aggregations are not supported with spec version {0}; please migrate the subgraph to the latest version")]
     AggregationsNotSupported(Version),
     #[error("Using Int8 as the type for the `id` field is not supported with spec version {0}; please migrate the subgraph to the latest version")]
@@ -5,7 +5,10 @@ use graph::{
     data::{graphql::ObjectOrInterface, store::ID},
     env::ENV_VARS,
     prelude::{anyhow, q, r, s, QueryExecutionError, ValueMap},
-    schema::{ast::ObjectType, kw, AggregationInterval, ApiSchema, EntityType},
+    schema::{
+        ast::ObjectType, kw, AggregationCurrent, AggregationInterval, ApiSchema, EntityType,
+        CURSOR_FIELD_NAME,
+    },
 };
 
 /// A selection set is a table that maps object types to the fields that
@@ -326,23 +329,39 @@ impl Field {
             })
             .collect();
 
-        // We need to also select the `orderBy` field if there is one
-        use EntityOrder::*;
-        let order_field = match order {
-            Ascending(name, _) | Descending(name, _) => Some(name.as_str()),
-            Default => Some(ID.as_str()),
-            ChildAscending(_) | ChildDescending(_) | Unordered => {
-                // No need to select anything for these
-                None
+        // We need to also select the `orderBy` fields if there are any
+        fn order_fields<'o>(order: &'o EntityOrder, fields: &mut Vec<&'o str>) {
+            use EntityOrder::*;
+            match order {
+                Ascending(name, _) | Descending(name, _) => fields.push(name.as_str()),
+                Default => fields.push(ID.as_str()),
+                Multi(orders) => orders.iter().for_each(|order| order_fields(order, fields)),
+                ChildAscending(_) | ChildDescending(_) | Unordered => {
+                    // No need to select anything for these
+                }
             }
-        };
-        if let Some(order_field) = order_field {
+        }
+        let mut fields = Vec::new();
+        order_fields(order, &mut fields);
+        for order_field in fields {
             // We assume that `order` only contains valid field names
             column_names.insert(order_field.to_string());
         }
+        // The cursor is built from the `orderBy` field and the `id`
+        if self.selects_cursor() {
+            column_names.insert(ID.to_string());
+        }
         Ok(AttributeNames::Select(column_names))
     }
 
+    /// Return `true` if the `_cursor` field is selected for any of the
+    /// types in the selection set of this field
+    pub fn selects_cursor(&self) -> bool {
+        self.selection_set
+            .fields()
+            .any(|(_, mut fields)| fields.any(|field| field.name == CURSOR_FIELD_NAME))
+    }
+
     /// Return the value of the `interval` argument if there is one. Return
     /// `None` if the argument is not present, and an error if the argument
     /// is present but can not be parsed as an `AggregationInterval`
@@ -364,6 +383,28 @@ impl Field {
             })
             .transpose()
     }
+
+    /// Return the value of the `current` argument if there is one. Return
+    /// `None` if the argument is not present, and an error if the argument
+    /// is present but can not be parsed as an `AggregationCurrent`
+    pub fn aggregation_current(&self) -> Result<Option<AggregationCurrent>, QueryExecutionError> {
+        self.argument_value(kw::CURRENT)
+            .map(|value| match value {
+                r::Value::Enum(current) => current.parse::<AggregationCurrent>().map_err(|_| {
+                    QueryExecutionError::InvalidArgumentError(
+                        self.position.clone(),
+                        kw::CURRENT.to_string(),
+                        q::Value::from(value.clone()),
+                    )
+                }),
+                _ => Err(QueryExecutionError::InvalidArgumentError(
+                    self.position.clone(),
+                    kw::CURRENT.to_string(),
+                    q::Value::from(value.clone()),
+                )),
+            })
+            .transpose()
+    }
 }
 
 impl ValueMap for Field {
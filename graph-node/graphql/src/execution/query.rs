@@ -137,7 +137,15 @@ fn validate_query(
     metrics: &Arc<dyn GraphQLMetrics>,
     id: &DeploymentHash,
 ) -> Result<(), Vec<QueryExecutionError>> {
-    let validation_errors = validate(document, &query.document, &GRAPHQL_VALIDATION_PLAN);
+    if !ENV_VARS.graphql.enable_validations {
+        return Ok(());
+    }
+
+    let validation_errors = validate(
+        document,
+        &with_order_by_lists(&query.document),
+        &GRAPHQL_VALIDATION_PLAN,
+    );
 
     if !validation_errors.is_empty() {
         if !ENV_VARS.graphql.silent_graphql_validations {
@@ -171,6 +179,108 @@ fn validate_query(
     Ok(())
 }
 
+/// `orderBy` used to take a single value of the `<type>_orderBy` enum, and
+/// argument coercion still accepts that as a list with one entry. Since the
+/// validation rules only know the schema, where `orderBy` is a list of
+/// `<type>_order` objects, return a copy of `document` for validation in
+/// which such literals are objects and variables of type `<type>_orderBy`
+/// that are passed to `orderBy` are lists
+fn with_order_by_lists(document: &q::Document) -> q::Document {
+    fn single_order(name: &str) -> q::Value {
+        q::Value::Object(BTreeMap::from_iter([(
+            "field".to_string(),
+            q::Value::Enum(name.to_string()),
+        )]))
+    }
+
+    fn rewrite_fields(set: &mut q::SelectionSet, variables: &mut HashSet<String>) {
+        for selection in &mut set.items {
+            match selection {
+                q::Selection::Field(field) => {
+                    for (name, value) in &mut field.arguments {
+                        match value {
+                            q::Value::Enum(order) if *name == "orderBy" => {
+                                *value = single_order(order)
+                            }
+                            q::Value::Variable(var) if *name == "orderBy" => {
+                                variables.insert(var.clone());
+                            }
+                            _ => {}
+                        }
+                    }
+                    rewrite_fields(&mut field.selection_set, variables);
+                }
+                q::Selection::InlineFragment(fragment) => {
+                    rewrite_fields(&mut fragment.selection_set, variables)
+                }
+                q::Selection::FragmentSpread(_) => {}
+            }
+        }
+    }
+
+    fn rewrite_variable(var: &mut q::VariableDefinition) {
+        let (type_name, non_null) = match &var.var_type {
+            q::Type::NamedType(name) => (name, false),
+            q::Type::NonNullType(ty) => match ty.as_ref() {
+                q::Type::NamedType(name) => (name, true),
+                _ => return,
+            },
+            q::Type::ListType(_) => return,
+        };
+        let Some(type_name) = type_name.strip_suffix("_orderBy") else {
+            return;
+        };
+
+        let order = q::Type::NamedType(format!("{}_order", type_name));
+        let list = q::Type::ListType(Box::new(q::Type::NonNullType(Box::new(order))));
+        var.var_type = if non_null {
+            q::Type::NonNullType(Box::new(list))
+        } else {
+            list
+        };
+        if let Some(q::Value::Enum(order)) = &var.default_value {
+            var.default_value = Some(single_order(order));
+        }
+    }
+
+    let mut document = document.clone();
+    let mut variables = HashSet::new();
+    for defn in &mut document.definitions {
+        let set = match defn {
+            q::Definition::Operation(q::OperationDefinition::SelectionSet(set)) => set,
+            q::Definition::Operation(q::OperationDefinition::Query(query)) => {
+                &mut query.selection_set
+            }
+            q::Definition::Operation(q::OperationDefinition::Mutation(mutation)) => {
+                &mut mutation.selection_set
+            }
+            q::Definition::Operation(q::OperationDefinition::Subscription(subscription)) => {
+                &mut subscription.selection_set
+            }
+            q::Definition::Fragment(fragment) => &mut fragment.selection_set,
+        };
+        rewrite_fields(set, &mut variables);
+    }
+    for defn in &mut document.definitions {
+        let vars = match defn {
+            q::Definition::Operation(q::OperationDefinition::Query(query)) => {
+                &mut query.variable_definitions
+            }
+            q::Definition::Operation(q::OperationDefinition::Mutation(mutation)) => {
+                &mut mutation.variable_definitions
+            }
+            q::Definition::Operation(q::OperationDefinition::Subscription(subscription)) => {
+                &mut subscription.variable_definitions
+            }
+            _ => continue,
+        };
+        vars.iter_mut()
+            .filter(|var| variables.contains(&var.name))
+            .for_each(rewrite_variable);
+    }
+    document
+}
+
 impl Query {
     /// Process the raw GraphQL query `query` and prepare for executing it.
     /// The returned `Query` has already been validated and, if `max_complexity`
@@ -823,11 +933,21 @@ impl Transform {
             if arg_value.is_some() {
                 defined_args += 1;
             }
-            match coercion::coerce_input_value(
-                arg_value.as_deref().cloned(),
-                argument_def,
-                &resolver,
-            ) {
+            let arg_value_for_coercion = match arg_value.as_deref() {
+                // `orderBy` used to take a single value of the
+                // `<type>_orderBy` enum; accept that as a list with one
+                // entry
+                Some(r::Value::Enum(name) | r::Value::String(name))
+                    if argument_def.name == *"orderBy" =>
+                {
+                    Some(r::Value::Object(Object::from_iter(vec![(
+                        Word::from("field"),
+                        r::Value::Enum(name.clone()),
+                    )])))
+                }
+                value => value.cloned(),
+            };
+            match coercion::coerce_input_value(arg_value_for_coercion, argument_def, &resolver) {
                 Ok(Some(value)) => {
                     let value = if argument_def.name == *"text" {
                         r::Value::Object(Object::from_iter(vec![(Word::from(field_name), value)]))
@@ -1039,3 +1159,58 @@ impl Transform {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use graph::prelude::{q, DeploymentHash};
+    use graph::schema::InputSchema;
+    use graphql_tools::validation::rules::*;
+    use graphql_tools::validation::validate::{validate, ValidationPlan};
+
+    use super::with_order_by_lists;
+
+    #[test]
+    fn single_order_by_values_pass_validation() {
+        let schema = InputSchema::parse_latest(
+            "type User @entity { id: ID!, name: String! }",
+            DeploymentHash::new("orderBy").unwrap(),
+        )
+        .unwrap()
+        .api_schema()
+        .unwrap();
+        let plan = ValidationPlan::from(vec![
+            Box::new(VariablesInAllowedPosition::new()),
+            Box::new(ValuesOfCorrectType::new()),
+        ]);
+        let errors = |query: &str, rewrite: bool| {
+            let query = q::parse_query::<String>(query).unwrap().into_static();
+            let query = if rewrite {
+                with_order_by_lists(&query)
+            } else {
+                query
+            };
+            validate(schema.document(), &query, &plan)
+                .into_iter()
+                .map(|e| e.message)
+                .collect::<Vec<_>>()
+        };
+
+        const VARIABLE: &str = "query($o: User_orderBy) { users(orderBy: $o) { id } }";
+        assert!(!errors(VARIABLE, false).is_empty());
+
+        for query in [
+            VARIABLE,
+            "query($o: User_orderBy! = name) { users(orderBy: $o) { id } }",
+            "query { users(orderBy: name) { id } }",
+            "query { ...f } fragment f on Query { users(orderBy: name) { id } }",
+            "query($f: User_orderBy!) { users(orderBy: [{ field: $f }]) { id } }",
+            "query($o: [User_order!]) { users(orderBy: $o) { id } }",
+        ] {
+            assert_eq!(Vec::<String>::new(), errors(query, true), "{query}");
+        }
+
+        // Variables of other types are still rejected
+        let query = "query($o: String) { users(orderBy: $o) { id } }";
+        assert!(!errors(query, true).is_empty());
+    }
+}
@@ -4,11 +4,13 @@ use std::time::Instant;
 use crate::metrics::GraphQLMetrics;
 use crate::prelude::{QueryExecutionOptions, StoreResolver};
 use crate::query::execute_query;
+use graph::components::store::DeploymentId;
 use graph::futures03::future;
 use graph::prelude::MetricsRegistry;
 use graph::prelude::{
-    async_trait, o, CheapClone, DeploymentState, GraphQLMetrics as GraphQLMetricsTrait,
-    GraphQlRunner as GraphQlRunnerTrait, Logger, Query, QueryExecutionError, ENV_VARS,
+    async_trait, o, BlockNumber, CheapClone, DeploymentState,
+    GraphQLMetrics as GraphQLMetricsTrait, GraphQlRunner as GraphQlRunnerTrait, Logger, Query,
+    QueryExecutionError, ENV_VARS,
 };
 use graph::{data::graphql::load_manager::LoadManager, prelude::QueryStoreManager};
 use graph::{
@@ -248,6 +250,15 @@ where
         .unwrap_or_else(|e| e)
     }
 
+    async fn deployment_head(
+        self: Arc<Self>,
+        target: QueryTarget,
+    ) -> Option<(DeploymentId, BlockNumber)> {
+        let store = self.store.query_store(target).await.ok()?;
+        let head = store.block_ptr().await.ok().flatten()?;
+        Some((store.deployment_id(), head.number))
+    }
+
     fn metrics(&self) -> Arc<dyn GraphQLMetricsTrait> {
         self.graphql_metrics.clone()
     }
@@ -9,7 +9,6 @@ use graph::data::store::IdType;
 use graph::data::store::QueryObject;
 use graph::data::value::{Object, Word};
 use graph::prelude::{r, CacheWeight, CheapClone};
-use graph::schema::kw;
 use graph::schema::AggregationInterval;
 use graph::schema::Field;
 use graph::slog::warn;
@@ -20,14 +19,17 @@ use std::time::Instant;
 
 use graph::data::graphql::TypeExt;
 use graph::prelude::{
-    AttributeNames, ChildMultiplicity, EntityCollection, EntityFilter, EntityLink, EntityOrder,
-    EntityWindow, ParentLink, QueryExecutionError, Value as StoreValue, WindowAttribute, ENV_VARS,
+    AttributeNames, ChildMultiplicity, EntityAggregate, EntityCollection, EntityFilter, EntityLink,
+    EntityOrder, EntityWindow, ParentLink, QueryExecutionError, Value as StoreValue,
+    WindowAttribute, ENV_VARS,
+};
+use graph::schema::{
+    EntityType, InputSchema, ObjectOrInterface, AGGREGATE_SUFFIX, CURSOR_FIELD_NAME,
 };
-use graph::schema::{EntityType, InputSchema, ObjectOrInterface};
 
 use crate::execution::ast as a;
 use crate::metrics::GraphQLMetrics;
-use crate::store::query::build_query;
+use crate::store::query::{build_aggregate_query, build_query, encode_cursor, is_backwards};
 use crate::store::StoreResolver;
 
 pub const ARG_ID: &str = "id";
@@ -622,6 +624,32 @@ impl<'a> Loader<'a> {
                 let field_type = object_type
                     .field(&field.name)
                     .expect("field names are valid");
+
+                // Aggregates like `user_aggregate` are computed directly
+                // in the database and do not have any children that we
+                // need to fetch
+                if at_root {
+                    let aggregated = field_type
+                        .field_type
+                        .get_base_type()
+                        .strip_suffix(AGGREGATE_SUFFIX)
+                        .and_then(|name| input_schema.object_or_interface(name, None));
+                    if let Some(aggregated) = aggregated {
+                        match self.aggregate(&aggregated, field) {
+                            Ok(node) => {
+                                add_children(
+                                    &input_schema,
+                                    &mut parents,
+                                    vec![node],
+                                    field.response_key(),
+                                )?;
+                            }
+                            Err(e) => errors.push(e),
+                        }
+                        continue;
+                    }
+                }
+
                 let child_type = input_schema
                     .object_or_interface(field_type.field_type.get_base_type(), child_interval)
                     .expect("we only collect fields that are objects or interfaces");
@@ -636,8 +664,8 @@ impl<'a> Loader<'a> {
                                 "the type `{}`(interval {}) is not an object type",
                                 object_type.name,
                                 parent_interval
-                                    .map(|intv| intv.as_str())
-                                    .unwrap_or("<none>")
+                                    .map(|intv| intv.to_string())
+                                    .unwrap_or_else(|| "<none>".to_string())
                             ))]
                         })?;
                     let field_type = object_type
@@ -686,6 +714,101 @@ impl<'a> Loader<'a> {
         }
     }
 
+    /// Compute the aggregates that `field` asks for over all entities of
+    /// type `aggregated` and return a node that has the result of `count`
+    /// as an attribute and the results of `sum`, `min`, and `max` as
+    /// children. The query gets whatever time is left until the deadline
+    /// for the overall GraphQL query
+    fn aggregate(
+        &self,
+        aggregated: &ObjectOrInterface<'_>,
+        field: &a::Field,
+    ) -> Result<Node, QueryExecutionError> {
+        fn node(entity: Vec<(Word, r::Value)>) -> Node {
+            let entity = Object::from_iter(entity);
+            Node {
+                children_weight: entity.weight(),
+                parent: None,
+                entity,
+                children: BTreeMap::default(),
+            }
+        }
+
+        let input_schema = self.resolver.store.input_schema()?;
+        let (query, aggregates) = build_aggregate_query(
+            aggregated,
+            self.resolver.block_number(),
+            field,
+            &input_schema,
+        )?;
+        let timeout = match self.ctx.deadline {
+            Some(deadline) => Some(
+                deadline
+                    .checked_duration_since(Instant::now())
+                    .ok_or(QueryExecutionError::Timeout)?,
+            ),
+            None => None,
+        };
+        let values = self
+            .resolver
+            .store
+            .aggregate_query_values(query, &aggregates, timeout)?;
+        let value_of = |aggregate: &EntityAggregate| {
+            let value = aggregates
+                .iter()
+                .zip(values.iter())
+                .find(|(agg, _)| *agg == aggregate)
+                .map(|(_, value)| value.clone());
+            match value {
+                // `count` is an `Int` in the API schema, but the store
+                // counts with an `Int8`
+                Some(StoreValue::Int8(count)) if aggregate == &EntityAggregate::Count => {
+                    r::Value::Int(count)
+                }
+                Some(value) => r::Value::from(value),
+                None => r::Value::Null,
+            }
+        };
+
+        let typename = format!("{}{}", aggregated.name(), AGGREGATE_SUFFIX);
+        let mut entity = vec![(Word::from("__typename"), r::Value::String(typename.clone()))];
+        let mut children = Vec::new();
+        for (_, fields) in field.selection_set.fields() {
+            for field in fields {
+                let aggregate: fn(String) -> EntityAggregate = match field.name.as_str() {
+                    "count" => {
+                        entity.push((Word::from("count"), value_of(&EntityAggregate::Count)));
+                        continue;
+                    }
+                    "sum" => EntityAggregate::Sum,
+                    "min" => EntityAggregate::Min,
+                    "max" => EntityAggregate::Max,
+                    _ => continue,
+                };
+                let mut child = vec![(
+                    Word::from("__typename"),
+                    r::Value::String(format!("{}_{}", typename, field.name)),
+                )];
+                for (_, attrs) in field.selection_set.fields() {
+                    for attr in attrs {
+                        if attr.name.starts_with("__") {
+                            continue;
+                        }
+                        let value = value_of(&aggregate(attr.name.clone()));
+                        child.push((Word::from(attr.name.as_str()), value));
+                    }
+                }
+                children.push((field.response_key().to_string(), node(child)));
+            }
+        }
+
+        let mut root = node(entity);
+        for (response_key, child) in children {
+            root.set_children(response_key, vec![Rc::new(child)]);
+        }
+        Ok(root)
+    }
+
     /// Query child entities for `parents` from the store. The `join` indicates
     /// in which child field to look for the parent's id/join field. When
     /// `is_single` is `true`, there is at most one child per parent.
@@ -713,11 +836,12 @@ impl<'a> Loader<'a> {
             // that causes unnecessary work in the database
             query.order = EntityOrder::Unordered;
         }
-        // Apply default timestamp ordering for aggregations if no custom order is specified
-        if child_type.is_aggregation() && matches!(query.order, EntityOrder::Default) {
-            let ts = child_type.field(kw::TIMESTAMP).unwrap();
-            query.order = EntityOrder::Descending(ts.name.to_string(), ts.value_type);
-        }
+        // Only the attribute we order by goes into the cursor, not the
+        // direction, and that is not affected by `before` reversing the
+        // order of the query
+        let cursor_order = (field.multiplicity == ChildMultiplicity::Many
+            && field.selects_cursor())
+        .then(|| query.order.clone());
         query.logger = Some(self.ctx.logger.cheap_clone());
         if let Some(r::Value::String(id)) = field.argument_value(ARG_ID) {
             query.filter = Some(
@@ -740,10 +864,27 @@ impl<'a> Loader<'a> {
             }
             query.collection = EntityCollection::Window(windows);
         }
-        self.resolver
-            .store
-            .find_query_values(query)
-            .map(|(values, trace)| (values.into_iter().map(Node::from).collect(), trace))
+        let (values, trace) = self.resolver.store.find_query_values(query)?;
+        let mut nodes: Vec<Node> = values.into_iter().map(Node::from).collect();
+
+        if is_backwards(field) {
+            // The store returned the entities for each parent in reverse
+            // order; results are grouped by parent
+            for nodes in nodes.chunk_by_mut(|a, b| a.parent == b.parent) {
+                nodes.reverse();
+            }
+        }
+        if let Some(order) = cursor_order {
+            for node in nodes.iter_mut() {
+                if let Some(cursor) = encode_cursor(&order, &node.entity) {
+                    node.entity.extend(Some((
+                        Word::from(CURSOR_FIELD_NAME),
+                        r::Value::String(cursor),
+                    )));
+                }
+            }
+        }
+        Ok((nodes, trace))
     }
 
     fn check_result_size(&self, parents: &[&mut Node]) -> Result<(), QueryExecutionError> {
@@ -2,21 +2,22 @@ use std::mem::discriminant;
 
 use graph::cheap_clone::CheapClone;
 use graph::components::store::{
-    BlockNumber, Child, EntityCollection, EntityFilter, EntityOrder, EntityOrderByChild,
-    EntityOrderByChildInfo, EntityQuery, EntityRange,
+    AttributeNames, BlockNumber, Child, ChildMultiplicity, EntityAggregate, EntityCollection,
+    EntityCursor, EntityFilter, EntityOrder, EntityOrderByChild, EntityOrderByChildInfo,
+    EntityQuery, EntityRange,
 };
 use graph::data::graphql::TypeExt as _;
 use graph::data::query::QueryExecutionError;
-use graph::data::store::{Attribute, Value, ValueType};
+use graph::data::store::{Attribute, Value, ValueType, ID};
 use graph::data::value::Object;
 use graph::data::value::Value as DataValue;
-use graph::prelude::{r, TryFromValue, ENV_VARS};
+use graph::prelude::{hex, q, r, serde_json, TryFromValue, ENV_VARS};
 use graph::schema::ast::{self as sast, FilterOp};
-use graph::schema::{EntityType, InputSchema, ObjectOrInterface};
+use graph::schema::{kw, EntityType, InputSchema, ObjectOrInterface};
 
 use crate::execution::ast as a;
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 enum OrderDirection {
     Ascending,
     Descending,
@@ -33,7 +34,14 @@ pub(crate) fn build_query<'a>(
     max_skip: u32,
     schema: &InputSchema,
 ) -> Result<EntityQuery, QueryExecutionError> {
-    let order = build_order(entity, field, schema)?;
+    let mut order = build_order(entity, field, schema)?;
+    // Aggregations are ordered by timestamp, most recent first, unless
+    // the query asks for a different order
+    if entity.is_aggregation() && matches!(order, EntityOrder::Default) {
+        let ts = entity.field(kw::TIMESTAMP).unwrap();
+        order = EntityOrder::Descending(ts.name.to_string(), ts.value_type);
+    }
+    let (order, after) = build_cursor(entity, field, order)?;
     let object_types = entity
         .object_types()
         .into_iter()
@@ -43,15 +51,78 @@ pub(crate) fn build_query<'a>(
         })
         .collect::<Result<_, _>>()?;
     let entity_types = EntityCollection::All(object_types);
-    let mut query = EntityQuery::new(schema.id().cheap_clone(), block, entity_types)
-        .range(build_range(field, max_first, max_skip)?);
+    let mut range = build_range(field, max_first, max_skip)?;
+    range.after = after;
+    let mut query = EntityQuery::new(schema.id().cheap_clone(), block, entity_types).range(range);
     if let Some(filter) = build_filter(entity, field, schema)? {
         query = query.filter(filter);
     }
     query = query.order(order);
+    if entity.is_aggregation() {
+        query = query.current(field.aggregation_current()?.unwrap_or_default());
+    }
     Ok(query)
 }
 
+/// Builds an `EntityQuery` and the list of aggregates to compute for a
+/// `<type>_aggregate` field. The aggregates are taken from the selection
+/// set of the field: `count` turns into a count of all matching entities,
+/// and every attribute selected underneath `sum`, `min`, and `max` into
+/// the corresponding aggregate of that attribute
+pub(crate) fn build_aggregate_query<'a>(
+    entity: &ObjectOrInterface<'a>,
+    block: BlockNumber,
+    field: &a::Field,
+    schema: &InputSchema,
+) -> Result<(EntityQuery, Vec<EntityAggregate>), QueryExecutionError> {
+    let entity_types = EntityCollection::All(
+        entity
+            .object_types()
+            .into_iter()
+            .map(|entity_type| (entity_type, AttributeNames::All))
+            .collect(),
+    );
+    let range = EntityRange {
+        first: None,
+        skip: 0,
+        after: None,
+    };
+    let mut query = EntityQuery::new(schema.id().cheap_clone(), block, entity_types).range(range);
+    if let Some(filter) = build_filter(entity, field, schema)? {
+        query = query.filter(filter);
+    }
+
+    let mut aggregates = Vec::new();
+    for (_, fields) in field.selection_set.fields() {
+        for field in fields {
+            let aggregate: fn(String) -> EntityAggregate = match field.name.as_str() {
+                "count" => {
+                    if !aggregates.contains(&EntityAggregate::Count) {
+                        aggregates.push(EntityAggregate::Count);
+                    }
+                    continue;
+                }
+                "sum" => EntityAggregate::Sum,
+                "min" => EntityAggregate::Min,
+                "max" => EntityAggregate::Max,
+                _ => continue,
+            };
+            for (_, attrs) in field.selection_set.fields() {
+                for attr in attrs {
+                    if attr.name.starts_with("__") {
+                        continue;
+                    }
+                    let aggregate = aggregate(attr.name.clone());
+                    if !aggregates.contains(&aggregate) {
+                        aggregates.push(aggregate);
+                    }
+                }
+            }
+        }
+    }
+    Ok((query, aggregates))
+}
+
 /// Parses GraphQL arguments into a EntityRange, if present.
 fn build_range(
     field: &a::Field,
@@ -91,9 +162,140 @@ fn build_range(
     Ok(EntityRange {
         first: Some(first),
         skip,
+        after: None,
     })
 }
 
+/// Parses the `after` and `before` arguments of a collection field into a
+/// cursor. The store only knows how to return entities that come after a
+/// cursor; we implement `before` by reversing `order`, and it is up to the
+/// caller to reverse the entities it gets back from the store (see
+/// `is_backwards`)
+fn build_cursor(
+    entity: &ObjectOrInterface,
+    field: &a::Field,
+    order: EntityOrder,
+) -> Result<(EntityOrder, Option<EntityCursor>), QueryExecutionError> {
+    if field.multiplicity != ChildMultiplicity::Many {
+        return Ok((order, None));
+    }
+
+    let cursor = |name: &str| match field.argument_value(name) {
+        Some(r::Value::String(cursor)) => Some(cursor.as_str()),
+        _ => None,
+    };
+
+    match (cursor("after"), cursor("before")) {
+        (None, None) => Ok((order, None)),
+        (Some(_), Some(_)) => Err(QueryExecutionError::InvalidCursor(
+            "only one of `after` and `before` can be used".to_string(),
+        )),
+        (Some(after), None) => {
+            let after = decode_cursor(entity, &order, after)?;
+            Ok((order, Some(after)))
+        }
+        (None, Some(before)) => {
+            let before = decode_cursor(entity, &order, before)?;
+            let order = match order {
+                EntityOrder::Ascending(attr, value_type) => {
+                    EntityOrder::Descending(attr, value_type)
+                }
+                EntityOrder::Descending(attr, value_type) => {
+                    EntityOrder::Ascending(attr, value_type)
+                }
+                EntityOrder::Default => EntityOrder::Descending(
+                    ID.to_string(),
+                    entity.field(ID.as_str()).unwrap().value_type,
+                ),
+                EntityOrder::ChildAscending(_)
+                | EntityOrder::ChildDescending(_)
+                | EntityOrder::Multi(_)
+                | EntityOrder::Unordered => unreachable!("decode_cursor rejects these orders"),
+            };
+            Ok((order, Some(before)))
+        }
+    }
+}
+
+/// Return `true` if `field` pages backwards through a collection with
+/// `before`, in which case the store returned its entities in reverse
+/// order
+pub(crate) fn is_backwards(field: &a::Field) -> bool {
+    field.multiplicity == ChildMultiplicity::Many
+        && matches!(field.argument_value("before"), Some(r::Value::String(_)))
+}
+
+/// The attribute that determines the position of an entity in a
+/// collection ordered by `order`, or `None` if the position is determined
+/// by the `id` alone. Returns an error for orders that we can not produce
+/// cursors for
+fn cursor_attribute(order: &EntityOrder) -> Result<Option<&str>, QueryExecutionError> {
+    match order {
+        EntityOrder::Ascending(attr, _) | EntityOrder::Descending(attr, _) => {
+            if attr == ID.as_str() {
+                Ok(None)
+            } else {
+                Ok(Some(attr.as_str()))
+            }
+        }
+        EntityOrder::Default => Ok(None),
+        EntityOrder::ChildAscending(_)
+        | EntityOrder::ChildDescending(_)
+        | EntityOrder::Multi(_)
+        | EntityOrder::Unordered => Err(QueryExecutionError::NotSupported(
+            "Cursors for collections that are ordered by child attributes or by several \
+             attributes"
+                .to_string(),
+        )),
+    }
+}
+
+/// Produce the opaque cursor for `entity` in a collection that is ordered
+/// by `order`. The cursor is the hex encoding of the JSON array
+/// `[attr, value, id]` where `attr` is the attribute we order by and
+/// `value` the entity's value for it; both are `null` when ordering by
+/// `id`. Returns `None` if the entity lacks the values for the cursor
+pub(crate) fn encode_cursor(order: &EntityOrder, entity: &Object) -> Option<String> {
+    let attr = cursor_attribute(order).ok()?;
+    let value = match attr {
+        Some(attr) => entity.get(attr)?,
+        None => &r::Value::Null,
+    };
+    let id = entity.get(ID.as_str())?;
+    let json = serde_json::to_vec(&(attr, value, id)).ok()?;
+    Some(hex::encode(json))
+}
+
+fn decode_cursor(
+    entity: &ObjectOrInterface,
+    order: &EntityOrder,
+    cursor: &str,
+) -> Result<EntityCursor, QueryExecutionError> {
+    let invalid = || QueryExecutionError::InvalidCursor(cursor.to_string());
+
+    let attr = cursor_attribute(order)?;
+    let json = hex::decode(cursor).map_err(|_| invalid())?;
+    let (cursor_attr, value, id): (Option<String>, serde_json::Value, serde_json::Value) =
+        serde_json::from_slice(&json).map_err(|_| invalid())?;
+    if attr != cursor_attr.as_deref() {
+        return Err(QueryExecutionError::InvalidCursor(format!(
+            "the cursor was produced by a query with a different `orderBy` than `{}`",
+            attr.unwrap_or(ID.as_str())
+        )));
+    }
+
+    let from_json = |name: &str, value: serde_json::Value| {
+        let field = entity.field(name).ok_or_else(|| {
+            QueryExecutionError::EntityFieldError(entity.typename().to_owned(), name.to_owned())
+        })?;
+        Value::from_query_value(&r::Value::from(value), &field.field_type).map_err(|_| invalid())
+    };
+
+    let value = attr.map(|attr| from_json(attr, value)).transpose()?;
+    let id = from_json(ID.as_str(), id)?;
+    Ok(EntityCursor { value, id })
+}
+
 /// Parses GraphQL arguments into an EntityFilter, if present.
 fn build_filter(
     entity: &ObjectOrInterface,
@@ -186,11 +388,129 @@ fn build_entity_filter(
         FilterOp::EndsWithNoCase => Ok(EntityFilter::EndsWithNoCase(field_name, store_value)),
         FilterOp::NotEndsWith => Ok(EntityFilter::NotEndsWith(field_name, store_value)),
         FilterOp::NotEndsWithNoCase => Ok(EntityFilter::NotEndsWithNoCase(field_name, store_value)),
+        FilterOp::Matches => {
+            check_regex(&store_value)?;
+            Ok(EntityFilter::Matches(field_name, store_value))
+        }
+        FilterOp::MatchesNoCase => {
+            check_regex(&store_value)?;
+            Ok(EntityFilter::MatchesNoCase(field_name, store_value))
+        }
+        FilterOp::Overlaps => Ok(EntityFilter::Overlaps(field_name, store_value)),
+        FilterOp::ContainedBy => Ok(EntityFilter::ContainedBy(field_name, store_value)),
         FilterOp::Equal => Ok(EntityFilter::Equal(field_name, store_value)),
         _ => unreachable!(),
     }
 }
 
+/// Check that the pattern for a `_matches` filter is a regular expression
+/// that we are willing to hand to the database. Matching some patterns can
+/// take time that is exponential in the length of the input; we reject the
+/// usual culprits, backreferences and groups that contain a quantifier and
+/// are repeated without bound, like `(a+)*`. The check is conservative and
+/// does not verify that the pattern is syntactically valid
+fn check_regex(value: &Value) -> Result<(), QueryExecutionError> {
+    let pattern = match value {
+        Value::String(s) => s,
+        _ => return Ok(()),
+    };
+    let err = |reason: &str| {
+        Err(QueryExecutionError::RegexNotSupported(
+            pattern.to_string(),
+            reason.to_string(),
+        ))
+    };
+
+    let max_len = ENV_VARS.graphql.max_regex_length;
+    if pattern.len() > max_len {
+        return err(&format!("it is longer than {} bytes", max_len));
+    }
+
+    // For each open group, whether it contains a quantifier
+    let mut groups: Vec<bool> = Vec::new();
+    // Whether the atom we just finished is a group containing a quantifier
+    let mut quantified_group = false;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if matches!(chars.next(), Some('1'..='9')) {
+                    return err("backreferences are not allowed");
+                }
+                quantified_group = false;
+            }
+            '[' => {
+                // Skip over the bracket expression; a `]` right at the
+                // start is a literal, and character classes like
+                // `[:alpha:]` contain their own brackets
+                chars.next_if_eq(&'^');
+                chars.next_if_eq(&']');
+                while let Some(c) = chars.next() {
+                    match c {
+                        ']' => break,
+                        '[' => {
+                            let class = chars.next_if(|c| matches!(*c, ':' | '.' | '='));
+                            if let Some(delim) = class {
+                                while let Some(c) = chars.next() {
+                                    if c == delim && chars.next_if_eq(&']').is_some() {
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                        _ => { /* part of the bracket expression */ }
+                    }
+                }
+                quantified_group = false;
+            }
+            '(' => {
+                // Skip the `?` in `(?:`, `(?=` and `(?!`
+                if chars.next_if_eq(&'?').is_some() {
+                    chars.next();
+                }
+                groups.push(false);
+                quantified_group = false;
+            }
+            ')' => {
+                quantified_group = groups.pop().unwrap_or(false);
+                if quantified_group {
+                    if let Some(outer) = groups.last_mut() {
+                        *outer = true;
+                    }
+                }
+            }
+            '*' | '+' | '?' | '{' => {
+                let unbounded = match c {
+                    '*' | '+' => true,
+                    '?' => false,
+                    _ => {
+                        // A bound `{m}`, `{m,}` or `{m,n}`
+                        let mut bound = String::new();
+                        for c in chars.by_ref() {
+                            if c == '}' {
+                                break;
+                            }
+                            bound.push(c);
+                        }
+                        bound.ends_with(',')
+                    }
+                };
+                if quantified_group && unbounded {
+                    return err("nested quantifiers are not allowed");
+                }
+                // Non-greedy quantifiers like `*?`
+                chars.next_if_eq(&'?');
+                if let Some(group) = groups.last_mut() {
+                    *group = true;
+                }
+                quantified_group = false;
+            }
+            _ => quantified_group = false,
+        }
+    }
+    Ok(())
+}
+
 /// Iterate over the list and generate an EntityFilter from it
 fn build_list_filter_from_value(
     entity: &ObjectOrInterface,
@@ -418,7 +738,7 @@ enum OrderByValue {
     Child(String, String),
 }
 
-fn parse_order_by(enum_value: &String) -> Result<OrderByValue, QueryExecutionError> {
+fn parse_order_by(enum_value: &str) -> Result<OrderByValue, QueryExecutionError> {
     let mut parts = enum_value.split("__");
     let first = parts.next().ok_or_else(|| {
         QueryExecutionError::ValueParseError(
@@ -459,17 +779,78 @@ fn build_order(
     field: &a::Field,
     schema: &InputSchema,
 ) -> Result<EntityOrder, QueryExecutionError> {
-    let order = match (
-        build_order_by(entity, field, schema)?,
-        build_order_direction(field)?,
-    ) {
-        (Some((attr, value_type, None)), OrderDirection::Ascending) => {
-            EntityOrder::Ascending(attr, value_type)
-        }
-        (Some((attr, value_type, None)), OrderDirection::Descending) => {
-            EntityOrder::Descending(attr, value_type)
-        }
-        (Some((attr, _, Some(child))), OrderDirection::Ascending) => {
+    let direction = build_order_direction(field)?;
+    if let Some(order) = build_order_list(entity, field, direction, schema)? {
+        return Ok(order);
+    }
+    match build_text_order_by(field)? {
+        Some((attr, value_type, child)) => entity_order(attr, value_type, child, direction),
+        None => Ok(EntityOrder::Default),
+    }
+}
+
+/// Parses the `orderBy` argument, a list of `{ field, direction }`
+/// objects, into an `EntityOrder`, if present. Entries without a direction
+/// use `direction`, the value of the `orderDirection` argument
+fn build_order_list(
+    entity: &ObjectOrInterface<'_>,
+    field: &a::Field,
+    direction: OrderDirection,
+    schema: &InputSchema,
+) -> Result<Option<EntityOrder>, QueryExecutionError> {
+    let items = match field.argument_value("orderBy") {
+        Some(r::Value::List(items)) if !items.is_empty() => items,
+        _ => return Ok(None),
+    };
+
+    let mut orders = items
+        .iter()
+        .map(|item| {
+            let invalid = || {
+                QueryExecutionError::InvalidArgumentError(
+                    field.position,
+                    "orderBy".to_string(),
+                    q::Value::from(item.clone()),
+                )
+            };
+            let r::Value::Object(item) = item else {
+                return Err(invalid());
+            };
+            let name = match item.get("field") {
+                Some(r::Value::Enum(name)) => name,
+                _ => return Err(invalid()),
+            };
+            let direction = match item.get("direction") {
+                Some(r::Value::Enum(direction)) if direction == "asc" => OrderDirection::Ascending,
+                Some(r::Value::Enum(direction)) if direction == "desc" => {
+                    OrderDirection::Descending
+                }
+                _ => direction,
+            };
+            let (attr, value_type, child) = order_by_field(entity, name, schema)?;
+            entity_order(attr, value_type, child, direction)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if orders.len() == 1 {
+        Ok(orders.pop())
+    } else {
+        Ok(Some(EntityOrder::Multi(orders)))
+    }
+}
+
+/// Turn the attribute to order by into an `EntityOrder` for a single
+/// attribute
+fn entity_order(
+    attr: String,
+    value_type: ValueType,
+    child: Option<OrderByChild>,
+    direction: OrderDirection,
+) -> Result<EntityOrder, QueryExecutionError> {
+    let order = match (child, direction) {
+        (None, OrderDirection::Ascending) => EntityOrder::Ascending(attr, value_type),
+        (None, OrderDirection::Descending) => EntityOrder::Descending(attr, value_type),
+        (Some(child), OrderDirection::Ascending) => {
             if ENV_VARS.graphql.disable_child_sorting {
                 return Err(QueryExecutionError::NotSupported(
                     "Sorting by child attributes is not supported".to_string(),
@@ -498,7 +879,7 @@ fn build_order(
                 }
             }
         }
-        (Some((attr, _, Some(child))), OrderDirection::Descending) => {
+        (Some(child), OrderDirection::Descending) => {
             if ENV_VARS.graphql.disable_child_sorting {
                 return Err(QueryExecutionError::NotSupported(
                     "Sorting by child attributes is not supported".to_string(),
@@ -527,100 +908,103 @@ fn build_order(
                 }
             }
         }
-        (None, _) => EntityOrder::Default,
     };
     Ok(order)
 }
 
-/// Parses GraphQL arguments into an field name to order by, if present.
-fn build_order_by(
-    entity: &ObjectOrInterface,
+/// Parses the `text` argument of fulltext queries into the field name to
+/// order by, if present. Any `orderBy` argument has already been handled by
+/// `build_order_list` since coercion always turns it into a list
+fn build_text_order_by(
     field: &a::Field,
-    schema: &InputSchema,
 ) -> Result<Option<(String, ValueType, Option<OrderByChild>)>, QueryExecutionError> {
-    match field.argument_value("orderBy") {
-        Some(r::Value::Enum(name)) => match parse_order_by(name)? {
-            OrderByValue::Direct(name) => {
-                let field = entity.field(&name).ok_or_else(|| {
-                    QueryExecutionError::EntityFieldError(
+    match field.argument_value("text") {
+        Some(r::Value::Object(filter)) => build_fulltext_order_by_from_object(filter)
+            .map(|order_by| order_by.map(|(attr, value)| (attr, value, None))),
+        None => Ok(None),
+        _ => Err(QueryExecutionError::InvalidFilterError),
+    }
+}
+
+/// Resolve a value of the `<type>_orderBy` enum into the attribute to
+/// order by, its type, and how to get to the child entity if the value
+/// refers to an attribute of a child entity
+fn order_by_field(
+    entity: &ObjectOrInterface,
+    name: &str,
+    schema: &InputSchema,
+) -> Result<(String, ValueType, Option<OrderByChild>), QueryExecutionError> {
+    match parse_order_by(name)? {
+        OrderByValue::Direct(name) => {
+            let field = entity.field(&name).ok_or_else(|| {
+                QueryExecutionError::EntityFieldError(entity.typename().to_owned(), name.clone())
+            })?;
+            sast::get_field_value_type(&field.field_type)
+                .map(|value_type| (name.clone(), value_type, None))
+                .map_err(|_| {
+                    QueryExecutionError::OrderByNotSupportedError(
                         entity.typename().to_owned(),
                         name.clone(),
                     )
+                })
+        }
+        OrderByValue::Child(parent_field_name, child_field_name) => {
+            // Finds the field that connects the parent entity with the
+            // child entity. Note that `@derivedFrom` is only allowed on
+            // object types.
+            let field = entity
+                .implemented_field(&parent_field_name)
+                .ok_or_else(|| {
+                    QueryExecutionError::EntityFieldError(
+                        entity.typename().to_owned(),
+                        parent_field_name.clone(),
+                    )
+                })?;
+            let derived_from = field.derived_from(schema);
+            let base_type = field.field_type.get_base_type();
+
+            let child_entity = schema
+                .object_or_interface(base_type, None)
+                .ok_or_else(|| QueryExecutionError::NamedTypeError(base_type.into()))?;
+            let child_field = child_entity
+                .field(child_field_name.as_str())
+                .ok_or_else(|| {
+                    QueryExecutionError::EntityFieldError(
+                        child_entity.typename().to_owned(),
+                        child_field_name.clone(),
+                    )
                 })?;
-                sast::get_field_value_type(&field.field_type)
-                    .map(|value_type| Some((name.clone(), value_type, None)))
-                    .map_err(|_| {
-                        QueryExecutionError::OrderByNotSupportedError(
-                            entity.typename().to_owned(),
-                            name.clone(),
-                        )
-                    })
-            }
-            OrderByValue::Child(parent_field_name, child_field_name) => {
-                // Finds the field that connects the parent entity with the
-                // child entity. Note that `@derivedFrom` is only allowed on
-                // object types.
-                let field = entity
-                    .implemented_field(&parent_field_name)
-                    .ok_or_else(|| {
-                        QueryExecutionError::EntityFieldError(
-                            entity.typename().to_owned(),
-                            parent_field_name.clone(),
-                        )
-                    })?;
-                let derived_from = field.derived_from(schema);
-                let base_type = field.field_type.get_base_type();
-
-                let child_entity = schema
-                    .object_or_interface(base_type, None)
-                    .ok_or_else(|| QueryExecutionError::NamedTypeError(base_type.into()))?;
-                let child_field =
-                    child_entity
-                        .field(child_field_name.as_str())
-                        .ok_or_else(|| {
-                            QueryExecutionError::EntityFieldError(
-                                child_entity.typename().to_owned(),
-                                child_field_name.clone(),
-                            )
-                        })?;
-
-                let (join_attribute, derived) = match derived_from {
-                    Some(child_field) => (child_field.name.to_string(), true),
-                    None => (parent_field_name, false),
-                };
 
-                let child = match child_entity {
-                    ObjectOrInterface::Object(_, _) => OrderByChild::Object(ObjectOrderDetails {
-                        entity_type: schema.entity_type(base_type)?,
+            let (join_attribute, derived) = match derived_from {
+                Some(child_field) => (child_field.name.to_string(), true),
+                None => (parent_field_name, false),
+            };
+
+            let child = match child_entity {
+                ObjectOrInterface::Object(_, _) => OrderByChild::Object(ObjectOrderDetails {
+                    entity_type: schema.entity_type(base_type)?,
+                    join_attribute,
+                    derived,
+                }),
+                ObjectOrInterface::Interface(_, _) => {
+                    let entity_types = child_entity.object_types();
+                    OrderByChild::Interface(InterfaceOrderDetails {
+                        entity_types,
                         join_attribute,
                         derived,
-                    }),
-                    ObjectOrInterface::Interface(_, _) => {
-                        let entity_types = child_entity.object_types();
-                        OrderByChild::Interface(InterfaceOrderDetails {
-                            entity_types,
-                            join_attribute,
-                            derived,
-                        })
-                    }
-                };
-
-                sast::get_field_value_type(&child_field.field_type)
-                    .map(|value_type| Some((child_field_name.clone(), value_type, Some(child))))
-                    .map_err(|_| {
-                        QueryExecutionError::OrderByNotSupportedError(
-                            child_entity.typename().to_owned(),
-                            child_field_name.clone(),
-                        )
                     })
-            }
-        },
-        _ => match field.argument_value("text") {
-            Some(r::Value::Object(filter)) => build_fulltext_order_by_from_object(filter)
-                .map(|order_by| order_by.map(|(attr, value)| (attr, value, None))),
-            None => Ok(None),
-            _ => Err(QueryExecutionError::InvalidFilterError),
-        },
+                }
+            };
+
+            sast::get_field_value_type(&child_field.field_type)
+                .map(|value_type| (child_field_name.clone(), value_type, Some(child)))
+                .map_err(|_| {
+                    QueryExecutionError::OrderByNotSupportedError(
+                        child_entity.typename().to_owned(),
+                        child_field_name.clone(),
+                    )
+                })
+        }
     }
 }
 
@@ -663,15 +1047,15 @@ mod tests {
         prelude::{
             r,
             s::{self, Directive, Field, InputValue, ObjectType, Type, Value as SchemaValue},
-            AttributeNames, DeploymentHash, EntityCollection, EntityFilter, EntityOrder,
-            EntityRange, Value, ValueType, BLOCK_NUMBER_MAX,
+            AttributeNames, DeploymentHash, EntityCollection, EntityCursor, EntityFilter,
+            EntityOrder, EntityRange, Value, ValueType, BLOCK_NUMBER_MAX,
         },
         schema::{EntityType, InputSchema},
     };
     use std::collections::BTreeSet;
     use std::{iter::FromIterator, sync::Arc};
 
-    use super::{a, build_query};
+    use super::{a, build_query, encode_cursor};
 
     const DEFAULT_OBJECT: &str = "DefaultObject";
     const ENTITY1: &str = "Entity1";
@@ -820,6 +1204,15 @@ mod tests {
         field_with_vec(DEFAULT_OBJECT, args)
     }
 
+    /// The value of `orderBy` for sorting by `name` after argument
+    /// coercion, which always turns it into a list
+    fn order_by(name: &str) -> r::Value {
+        r::Value::List(vec![r::Value::Object(Object::from_iter(vec![(
+            "field".into(),
+            r::Value::Enum(name.to_string()),
+        )]))])
+    }
+
     #[test]
     fn build_query_uses_the_entity_name() {
         let attrs = if ENV_VARS.enable_select_by_specific_attributes {
@@ -847,13 +1240,13 @@ mod tests {
 
     #[test]
     fn build_query_parses_order_by_from_enum_values_correctly() {
-        let field = default_field_with("orderBy", r::Value::Enum("name".to_string()));
+        let field = default_field_with("orderBy", order_by("name"));
         assert_eq!(
             query(&field).order,
             EntityOrder::Ascending("name".to_string(), ValueType::String)
         );
 
-        let field = default_field_with("orderBy", r::Value::Enum("email".to_string()));
+        let field = default_field_with("orderBy", order_by("email"));
         assert_eq!(
             query(&field).order,
             EntityOrder::Ascending("email".to_string(), ValueType::String)
@@ -872,7 +1265,7 @@ mod tests {
     #[test]
     fn build_query_parses_order_direction_from_enum_values_correctly() {
         let field = default_field_with_vec(vec![
-            ("orderBy", r::Value::Enum("name".to_string())),
+            ("orderBy", order_by("name")),
             ("orderDirection", r::Value::Enum("asc".to_string())),
         ]);
         assert_eq!(
@@ -881,7 +1274,7 @@ mod tests {
         );
 
         let field = default_field_with_vec(vec![
-            ("orderBy", r::Value::Enum("name".to_string())),
+            ("orderBy", order_by("name")),
             ("orderDirection", r::Value::Enum("desc".to_string())),
         ]);
         assert_eq!(
@@ -890,7 +1283,7 @@ mod tests {
         );
 
         let field = default_field_with_vec(vec![
-            ("orderBy", r::Value::Enum("name".to_string())),
+            ("orderBy", order_by("name")),
             (
                 "orderDirection",
                 r::Value::Enum("descending...".to_string()),
@@ -909,6 +1302,55 @@ mod tests {
         assert_eq!(query(&field).order, EntityOrder::Default);
     }
 
+    #[test]
+    fn build_query_parses_order_lists() {
+        let order = |name: &str, direction: Option<&str>| {
+            let mut items = vec![("field".into(), r::Value::Enum(name.to_string()))];
+            if let Some(direction) = direction {
+                items.push(("direction".into(), r::Value::Enum(direction.to_string())));
+            }
+            r::Value::Object(Object::from_iter(items))
+        };
+
+        let field = default_field_with(
+            "orderBy",
+            r::Value::List(vec![order("name", Some("desc")), order("email", None)]),
+        );
+        assert_eq!(
+            query(&field).order,
+            EntityOrder::Multi(vec![
+                EntityOrder::Descending("name".to_string(), ValueType::String),
+                EntityOrder::Ascending("email".to_string(), ValueType::String)
+            ])
+        );
+
+        // A list with one entry is the same as a single value
+        let field = default_field_with(
+            "orderBy",
+            r::Value::List(vec![order("email", Some("desc"))]),
+        );
+        assert_eq!(
+            query(&field).order,
+            EntityOrder::Descending("email".to_string(), ValueType::String)
+        );
+
+        // Entries without a direction use `orderDirection`
+        let field = default_field_with_vec(vec![
+            (
+                "orderBy",
+                r::Value::List(vec![order("name", None), order("email", Some("asc"))]),
+            ),
+            ("orderDirection", r::Value::Enum("desc".to_string())),
+        ]);
+        assert_eq!(
+            query(&field).order,
+            EntityOrder::Multi(vec![
+                EntityOrder::Descending("name".to_string(), ValueType::String),
+                EntityOrder::Ascending("email".to_string(), ValueType::String)
+            ])
+        );
+    }
+
     #[test]
     fn build_query_yields_default_range_if_none_is_present() {
         assert_eq!(query(&default_field()).range, EntityRange::first(100));
@@ -924,6 +1366,7 @@ mod tests {
             EntityRange {
                 first: Some(100),
                 skip: 50,
+                after: None,
             },
         );
     }
@@ -946,6 +1389,55 @@ mod tests {
         )
     }
 
+    #[test]
+    fn build_query_yields_matches_filters() {
+        let query_field = default_field_with(
+            "where",
+            r::Value::Object(Object::from_iter(vec![(
+                "name_matches_nocase".into(),
+                r::Value::String("^h[a-z]+o$".to_string()),
+            )])),
+        );
+        assert_eq!(
+            query(&query_field).filter,
+            Some(EntityFilter::And(vec![EntityFilter::MatchesNoCase(
+                "name".to_string(),
+                Value::String("^h[a-z]+o$".to_string()),
+            )]))
+        )
+    }
+
+    #[test]
+    fn regex_check() {
+        fn check(pattern: &str) -> bool {
+            super::check_regex(&Value::String(pattern.to_string())).is_ok()
+        }
+
+        for ok in [
+            "^hello$",
+            "a+b*c?",
+            "(ab)+",
+            "(a+b)?",
+            "(a+){2}",
+            "(\\d+\\.){3}\\d+",
+            "[(a+)]*",
+            "[]a+]*",
+            "[[:alpha:]+]*",
+            "(?:ab)*",
+            "a+?b*?",
+            "\\(a+\\)*",
+        ] {
+            assert!(check(ok), "pattern `{ok}` should be allowed");
+        }
+
+        for bad in [
+            "(a+)+", "(a*)*", "(a+){2,}", "((a+)b)*", "(?:a+)+", "(a)\\1",
+        ] {
+            assert!(!check(bad), "pattern `{bad}` should be rejected");
+        }
+        assert!(!check(&"a".repeat(ENV_VARS.graphql.max_regex_length + 1)));
+    }
+
     #[test]
     fn build_query_yields_block_change_gte_filter() {
         let query_field = default_field_with(
@@ -963,4 +1455,96 @@ mod tests {
             Some(EntityFilter::And(vec![EntityFilter::ChangeBlockGte(10)]))
         )
     }
+
+    #[test]
+    fn build_query_decodes_cursors() {
+        let entity = Object::from_iter(vec![
+            ("id".into(), r::Value::String("u7".to_string())),
+            ("name".into(), r::Value::String("Anna".to_string())),
+        ]);
+
+        let order = EntityOrder::Ascending("name".to_string(), ValueType::String);
+        let cursor = encode_cursor(&order, &entity).unwrap();
+        let mut field = default_field_with_vec(vec![
+            ("orderBy", order_by("name")),
+            ("after", r::Value::String(cursor.clone())),
+        ]);
+        field.multiplicity = ChildMultiplicity::Many;
+        let entity_query = query(&field);
+        assert_eq!(entity_query.order, order);
+        assert_eq!(
+            entity_query.range.after,
+            Some(EntityCursor {
+                value: Some(Value::String("Anna".to_string())),
+                id: Value::String("u7".to_string()),
+            })
+        );
+
+        // `before` reverses the order
+        let mut field = default_field_with_vec(vec![
+            ("orderBy", order_by("name")),
+            ("before", r::Value::String(cursor)),
+        ]);
+        field.multiplicity = ChildMultiplicity::Many;
+        assert_eq!(
+            query(&field).order,
+            EntityOrder::Descending("name".to_string(), ValueType::String)
+        );
+
+        // Ordering by `id` only puts the `id` into the cursor
+        let cursor = encode_cursor(&EntityOrder::Default, &entity).unwrap();
+        let mut field = default_field_with("before", r::Value::String(cursor));
+        field.multiplicity = ChildMultiplicity::Many;
+        let entity_query = query(&field);
+        assert_eq!(
+            entity_query.order,
+            EntityOrder::Descending("id".to_string(), ValueType::String)
+        );
+        assert_eq!(
+            entity_query.range.after,
+            Some(EntityCursor {
+                value: None,
+                id: Value::String("u7".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn build_query_rejects_mismatched_cursors() {
+        let entity = Object::from_iter(vec![
+            ("id".into(), r::Value::String("u7".to_string())),
+            ("name".into(), r::Value::String("Anna".to_string())),
+        ]);
+        let cursor = encode_cursor(&EntityOrder::Default, &entity).unwrap();
+
+        let mut field = default_field_with_vec(vec![
+            ("orderBy", order_by("name")),
+            ("after", r::Value::String(cursor)),
+        ]);
+        field.multiplicity = ChildMultiplicity::Many;
+        let object = INPUT_SCHEMA
+            .object_or_interface(DEFAULT_OBJECT, None)
+            .unwrap();
+        let res = build_query(
+            &object,
+            BLOCK_NUMBER_MAX,
+            &field,
+            std::u32::MAX,
+            std::u32::MAX,
+            &*&INPUT_SCHEMA,
+        );
+        assert!(res.is_err());
+
+        let mut field = default_field_with("after", r::Value::String("not a cursor".to_string()));
+        field.multiplicity = ChildMultiplicity::Many;
+        let res = build_query(
+            &object,
+            BLOCK_NUMBER_MAX,
+            &field,
+            std::u32::MAX,
+            std::u32::MAX,
+            &*&INPUT_SCHEMA,
+        );
+        assert!(res.is_err());
+    }
 }
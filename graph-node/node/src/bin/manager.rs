@@ -8,7 +8,7 @@ use graph::components::network_provider::ChainName;
 use graph::endpoint::EndpointMetrics;
 use graph::env::ENV_VARS;
 use graph::log::logger_with_levels;
-use graph::prelude::{MetricsRegistry, BLOCK_NUMBER_MAX};
+use graph::prelude::{BlockNumber, MetricsRegistry, BLOCK_NUMBER_MAX};
 use graph::{data::graphql::load_manager::LoadManager, prelude::chrono, prometheus::Registry};
 use graph::{
     prelude::{
@@ -35,6 +35,7 @@ use graph_store_postgres::{
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use std::env;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::{collections::HashMap, num::ParseIntError, sync::Arc, time::Duration};
 const VERSION_LABEL_KEY: &str = "version";
@@ -258,6 +259,19 @@ pub enum Command {
         /// Prometheus push gateway endpoint.
         prometheus_host: Option<String>,
     },
+    /// Replay the triggers of a single block of a deployment
+    ///
+    /// Runs the mappings for all triggers in the block against the state
+    /// of the deployment at the previous block and prints the resulting
+    /// entity changes and PoI digests. Nothing is written to the store and
+    /// the deployment does not need to be paused. Logs from processing
+    /// the block are printed at `trace` level.
+    Replay {
+        /// The deployment (see `help info`)
+        deployment: DeploymentSearch,
+        /// The number of the block to replay
+        block: BlockNumber,
+    },
     /// Check and interrogate the configuration
     ///
     /// Print information about a configuration file without
@@ -333,6 +347,22 @@ pub enum Command {
     #[clap(subcommand)]
     Database(DatabaseCommand),
 
+    /// Manage the allow-list of persisted queries
+    ///
+    /// Queries in this list can be run by sending just their sha256 hash.
+    /// When `GRAPH_GRAPHQL_PERSISTED_QUERIES_ONLY` is set, they are the
+    /// only queries that can be run
+    #[clap(subcommand)]
+    PersistedQuery(PersistedQueryCommand),
+
+    /// Manage the cache of compiled WASM modules
+    ///
+    /// The cache is only used when `GRAPH_WASM_MODULE_CACHE_DIR` is set.
+    /// Clearing it is safe at any time; modules that are missing from the
+    /// cache are simply compiled again
+    #[clap(subcommand)]
+    WasmCache(WasmCacheCommand),
+
     /// Delete a deployment and all it's indexed data
     ///
     /// The deployment can be specified as either a subgraph name, an IPFS
@@ -470,6 +500,42 @@ pub enum ListenCommand {
     Assignments,
 }
 
+#[derive(Clone, Debug, Subcommand)]
+pub enum PersistedQueryCommand {
+    /// List all persisted queries
+    List {
+        /// Also print the text of each query
+        #[clap(long, short)]
+        query: bool,
+    },
+    /// Add a query to the allow-list
+    Add {
+        /// The file containing the query, or `-` to read it from stdin
+        file: String,
+    },
+    /// Remove a query from the allow-list
+    Remove {
+        /// The sha256 hash of the query
+        hash: String,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum WasmCacheCommand {
+    /// Show how many modules are cached and how much space they use
+    Info {
+        /// The cache directory. Defaults to `GRAPH_WASM_MODULE_CACHE_DIR`
+        #[clap(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Remove all modules from the cache
+    Clear {
+        /// The cache directory. Defaults to `GRAPH_WASM_MODULE_CACHE_DIR`
+        #[clap(long)]
+        dir: Option<PathBuf>,
+    },
+}
+
 #[derive(Clone, Debug, Subcommand)]
 pub enum CopyCommand {
     /// Create a copy of an existing subgraph
@@ -587,6 +653,49 @@ pub enum ChainCommand {
         #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new())]
         chain_name: String,
     },
+
+    /// Execute operations on the cache of logs and transaction receipts.
+    LogCache {
+        #[clap(subcommand)]
+        method: LogCacheCommand,
+        /// Chain name (must be an existing chain, see 'chain list')
+        #[clap(value_parser = clap::builder::NonEmptyStringValueParser::new())]
+        chain_name: String,
+    },
+
+    /// Run all available provider checks against all providers and show
+    /// their scores.
+    ///
+    /// Prints the latency and error score each provider earned while the
+    /// checks ran, and whether its circuit breaker is open
+    CheckProviders {
+        /// Maximum duration of all provider checks for a provider.
+        ///
+        /// Defaults to 60 seconds.
+        timeout_seconds: Option<u64>,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum LogCacheCommand {
+    /// Show how many log ranges and receipts are cached for the chain
+    Info,
+    /// Remove the log and receipt cache of the specified chain.
+    ///
+    /// Either remove entries in the range `--from` and `--to`, or remove
+    /// the entire cache with `--remove-entire-cache`. Cached logs are
+    /// removed if their block range overlaps the given range.
+    Remove {
+        /// Remove the entire cache
+        #[clap(long, conflicts_with_all = &["from", "to"])]
+        remove_entire_cache: bool,
+        /// Starting block number
+        #[clap(long, short, conflicts_with = "remove-entire-cache", requires = "to")]
+        from: Option<i32>,
+        /// Ending block number
+        #[clap(long, short, conflicts_with = "remove-entire-cache", requires = "from")]
+        to: Option<i32>,
+    },
 }
 
 #[derive(Clone, Debug, Subcommand)]
@@ -996,9 +1105,16 @@ impl Context {
     }
 
     async fn networks(&self) -> anyhow::Result<Networks> {
+        self.networks_with_metrics(Arc::new(EndpointMetrics::mock()))
+            .await
+    }
+
+    async fn networks_with_metrics(
+        &self,
+        metrics: Arc<EndpointMetrics>,
+    ) -> anyhow::Result<Networks> {
         let logger = self.logger.clone();
         let registry = self.metrics_registry();
-        let metrics = Arc::new(EndpointMetrics::mock());
 
         Networks::from_config(logger, &self.config, registry, metrics, &[]).await
     }
@@ -1169,7 +1285,8 @@ async fn main() -> anyhow::Result<()> {
                     let store = ctx.store().block_store();
                     let timeout = Duration::from_secs(timeout_seconds.unwrap_or(60));
 
-                    commands::provider_checks::execute(&logger, &networks, store, timeout).await;
+                    commands::provider_checks::execute(&logger, &networks, store, timeout, None)
+                        .await;
 
                     Ok(())
                 }
@@ -1297,6 +1414,28 @@ async fn main() -> anyhow::Result<()> {
             )
             .await
         }
+        Replay { deployment, block } => {
+            let logger = logger_with_levels(false, Some("trace"));
+            let config = ctx.config();
+            let registry = ctx.metrics_registry();
+            let node_id = ctx.node_id();
+            let store_builder = ctx.store_builder().await;
+            let ipfs_url = ctx.ipfs_url.clone();
+            let arweave_url = ctx.arweave_url.clone();
+
+            commands::replay::run(
+                logger,
+                store_builder,
+                ipfs_url,
+                arweave_url,
+                config,
+                registry,
+                node_id,
+                deployment,
+                block,
+            )
+            .await
+        }
         Listen(cmd) => {
             use ListenCommand::*;
             match cmd {
@@ -1452,6 +1591,50 @@ async fn main() -> anyhow::Result<()> {
                         }
                     }
                 }
+                LogCache { method, chain_name } => {
+                    let chain_store = ctx.chain_store(&chain_name)?;
+                    match method {
+                        LogCacheCommand::Info => commands::chain::log_cache_info(chain_store),
+                        LogCacheCommand::Remove {
+                            from,
+                            to,
+                            remove_entire_cache,
+                        } => {
+                            if !remove_entire_cache && from.is_none() && to.is_none() {
+                                bail!("you must specify either --from and --to or --remove-entire-cache");
+                            }
+                            let (from, to) = if remove_entire_cache {
+                                (0, BLOCK_NUMBER_MAX)
+                            } else {
+                                // Clap makes sure that this does not panic
+                                (from.unwrap(), to.unwrap())
+                            };
+                            commands::chain::clear_log_cache(chain_store, from, to).await
+                        }
+                    }
+                }
+                CheckProviders { timeout_seconds } => {
+                    let logger = ctx.logger.clone();
+                    let metrics = Arc::new(EndpointMetrics::new(
+                        logger.clone(),
+                        &ctx.config.chains.providers(),
+                        ctx.metrics_registry(),
+                    ));
+                    let networks = ctx.networks_with_metrics(metrics.clone()).await?;
+                    let store = ctx.store().block_store();
+                    let timeout = Duration::from_secs(timeout_seconds.unwrap_or(60));
+
+                    commands::provider_checks::execute(
+                        &logger,
+                        &networks,
+                        store,
+                        timeout,
+                        Some(&metrics),
+                    )
+                    .await;
+
+                    Ok(())
+                }
             }
         }
         Stats(cmd) => {
@@ -1584,6 +1767,22 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
+        PersistedQuery(cmd) => {
+            use PersistedQueryCommand::*;
+            let primary_pool = ctx.primary_pool();
+            match cmd {
+                List { query } => commands::persisted_query::list(primary_pool, query),
+                Add { file } => commands::persisted_query::add(primary_pool, file),
+                Remove { hash } => commands::persisted_query::remove(primary_pool, hash),
+            }
+        }
+        WasmCache(cmd) => {
+            use WasmCacheCommand::*;
+            match cmd {
+                Info { dir } => commands::wasm_cache::info(dir),
+                Clear { dir } => commands::wasm_cache::clear(dir),
+            }
+        }
         Prune {
             deployment,
             history,
@@ -1,6 +1,6 @@
 use crate::config::{Config, ProviderDetails};
 use crate::network_setup::{
-    AdapterConfiguration, EthAdapterConfig, FirehoseAdapterConfig, Networks,
+    AdapterConfiguration, EthAdapterConfig, FirehoseAdapterConfig, MergedBlocksConfig, Networks,
 };
 use ethereum::chain::{
     EthereumAdapterSelector, EthereumBlockRefetcher, EthereumRuntimeAdapterBuilder,
@@ -10,6 +10,7 @@ use ethereum::network::EthereumNetworkAdapter;
 use ethereum::ProviderEthRpcMetrics;
 use graph::anyhow::bail;
 use graph::blockchain::client::ChainClient;
+use graph::blockchain::merged_blocks_block_stream::MergedBlocksSource;
 use graph::blockchain::{
     BasicBlockchainBuilder, Blockchain, BlockchainBuilder as _, BlockchainKind, BlockchainMap,
     ChainIdentifier,
@@ -177,6 +178,43 @@ pub fn create_firehose_networks(
         .collect()
 }
 
+/// Creates a source for each merged blocks provider. Since they are only
+/// used for block streams, the chain's polling interval determines how
+/// often we look for new bundles
+pub fn create_merged_blocks_sources(
+    logger: Logger,
+    config: &Config,
+) -> anyhow::Result<Vec<AdapterConfiguration>> {
+    let mut sources = vec![];
+
+    for (name, chain) in &config.chains.chains {
+        let chain_id: ChainName = name.as_str().into();
+        for provider in &chain.providers {
+            if let ProviderDetails::MergedBlocks(ref merged_blocks) = provider.details {
+                info!(
+                    logger,
+                    "Configuring merged blocks source";
+                    "provider" => &provider.label,
+                    "network" => &chain_id.to_string(),
+                    "url" => &merged_blocks.url,
+                );
+
+                let source = MergedBlocksSource::new(
+                    provider.label.as_str(),
+                    &merged_blocks.url,
+                    chain.polling_interval,
+                )?;
+                sources.push(AdapterConfiguration::MergedBlocks(MergedBlocksConfig {
+                    chain_id: chain_id.clone(),
+                    source: Arc::new(source),
+                }));
+            }
+        }
+    }
+
+    Ok(sources)
+}
+
 /// Parses all Ethereum connection strings and returns their network names and
 /// `EthereumAdapter`.
 pub async fn create_all_ethereum_networks(
@@ -217,8 +255,7 @@ pub async fn create_ethereum_networks_for_chain(
         .chains
         .get(network_name)
         .ok_or_else(|| anyhow!("unknown network {}", network_name))?;
-    let mut adapters = vec![];
-    let mut call_only_adapters = vec![];
+    let mut transports = vec![];
 
     for provider in &chain.providers {
         let (web3, call_only) = match &provider.details {
@@ -250,12 +287,60 @@ pub async fn create_ethereum_networks_for_chain(
                 web3.headers.clone(),
                 endpoint_metrics.cheap_clone(),
                 &provider.label,
-            ),
+            )
+            .with_limits(web3.request_limits()),
             Ipc => Transport::new_ipc(&web3.url).await,
             Ws => Transport::new_ws(&web3.url).await,
         };
 
+        transports.push((provider, web3, call_only, capabilities, logger, transport));
+    }
+
+    // Hedge each provider with the next one that can serve the same
+    // requests, so that slow providers get backed up by a different one
+    let transports = match graph_chain_ethereum::ENV_VARS.hedge_delay {
+        Some(delay) => {
+            let n = transports.len();
+            (0..n)
+                .map(|i| {
+                    let (provider, web3, call_only, capabilities, logger, transport) =
+                        &transports[i];
+                    let partner = (1..n).map(|k| &transports[(i + k) % n]).find(
+                        |(_, _, other_call_only, other_capabilities, _, other_transport)| {
+                            !*call_only
+                                && !*other_call_only
+                                && other_capabilities >= capabilities
+                                && matches!(transport, Transport::RPC { .. })
+                                && matches!(other_transport, Transport::RPC { .. })
+                        },
+                    );
+                    let transport = match partner {
+                        Some((other, _, _, _, _, other_transport)) => {
+                            info!(logger, "Hedging requests"; "hedge" => &other.label);
+                            transport.clone().with_hedge(other_transport, delay)
+                        }
+                        None => transport.clone(),
+                    };
+                    (
+                        *provider,
+                        *web3,
+                        *call_only,
+                        *capabilities,
+                        logger.clone(),
+                        transport,
+                    )
+                })
+                .collect()
+        }
+        None => transports,
+    };
+
+    let mut adapters = vec![];
+    let mut call_only_adapters = vec![];
+
+    for (provider, web3, call_only, capabilities, logger, transport) in transports {
         let supports_eip_1898 = !web3.features.contains("no_eip1898");
+        let top_level_calls = web3.features.contains("top_level_calls");
         let adapter = EthereumNetworkAdapter::new(
             endpoint_metrics.cheap_clone(),
             capabilities,
@@ -267,6 +352,7 @@ pub async fn create_ethereum_networks_for_chain(
                     eth_rpc_metrics.clone(),
                     supports_eip_1898,
                     call_only,
+                    top_level_calls,
                 )
                 .await,
             ),
@@ -464,6 +550,10 @@ pub async fn networks_as_chains(
                     polling_interval,
                     true,
                 );
+                let chain = match networks.merged_blocks_source(chain_id) {
+                    Some(source) => chain.with_merged_blocks(source),
+                    None => chain,
+                };
 
                 blockchain_map
                     .insert::<graph_chain_ethereum::Chain>(chain_id.clone(), Arc::new(chain));
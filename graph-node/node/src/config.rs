@@ -17,7 +17,8 @@ use graph::{
     },
 };
 use graph_chain_ethereum as ethereum;
-use graph_chain_ethereum::NodeCapabilities;
+use graph_chain_ethereum::{NodeCapabilities, RequestLimits};
+use graph_server_http::ApiKey;
 use graph_store_postgres::{DeploymentPlacer, Shard as ShardName, PRIMARY_SHARD};
 
 use graph::http::{HeaderMap, Uri};
@@ -77,6 +78,8 @@ pub struct Config {
     pub stores: BTreeMap<String, Shard>,
     pub chains: ChainSection,
     pub deployment: Deployment,
+    #[serde(default)]
+    pub api_keys: ApiKeySection,
 }
 
 fn validate_name(s: &str) -> Result<()> {
@@ -156,6 +159,7 @@ impl Config {
         }
 
         self.chains.validate()?;
+        self.api_keys.validate()?;
 
         Ok(())
     }
@@ -199,6 +203,7 @@ impl Config {
             stores,
             chains,
             deployment,
+            api_keys: ApiKeySection::default(),
         })
     }
 
@@ -235,6 +240,78 @@ pub struct GeneralSection {
     query: Regex,
 }
 
+/// The API keys that the query server accepts
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ApiKeySection {
+    /// Reject queries that do not use one of the configured keys
+    #[serde(default)]
+    pub required: bool,
+    #[serde(flatten)]
+    pub keys: BTreeMap<String, ApiKeyConfig>,
+}
+
+impl ApiKeySection {
+    fn validate(&mut self) -> Result<()> {
+        if self.required && self.keys.is_empty() {
+            bail!("api_keys.required is set but no API keys are configured");
+        }
+        for (name, key) in self.keys.iter_mut() {
+            key.validate(name)?;
+        }
+        let mut secrets = self.keys.values().map(|key| &key.key).collect_vec();
+        secrets.sort();
+        secrets.dedup();
+        if secrets.len() != self.keys.len() {
+            bail!("API keys must be unique");
+        }
+        Ok(())
+    }
+
+    pub fn to_api_keys(&self) -> Vec<ApiKey> {
+        self.keys
+            .iter()
+            .map(|(name, key)| ApiKey {
+                name: name.clone(),
+                key: key.key.clone(),
+                rate_limit: key.rate_limit,
+                burst: key.burst,
+                monthly_quota: key.monthly_quota,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApiKeyConfig {
+    /// The secret clients send as a bearer token. Environment variables
+    /// like `${FRANKY_API_KEY}` are expanded
+    #[serde(skip_serializing)]
+    pub key: String,
+    /// Requests per second
+    pub rate_limit: Option<f64>,
+    pub burst: Option<u32>,
+    pub monthly_quota: Option<u64>,
+}
+
+impl ApiKeyConfig {
+    fn validate(&mut self, name: &str) -> Result<()> {
+        self.key = shellexpand::env(&self.key)?.into_owned();
+        if self.key.is_empty() {
+            bail!("API key `{}` must not be empty", name);
+        }
+        if let Some(rate_limit) = self.rate_limit {
+            if !rate_limit.is_finite() || rate_limit <= 0.0 {
+                bail!("the rate_limit for API key `{}` must be positive", name);
+            }
+        }
+        match (self.rate_limit, self.burst) {
+            (None, Some(_)) => bail!("API key `{}` sets a burst without a rate_limit", name),
+            (_, Some(0)) => bail!("the burst for API key `{}` must be positive", name),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Shard {
     pub connection: String,
@@ -502,6 +579,10 @@ impl ChainSection {
                         features,
                         headers: Default::default(),
                         rules: vec![],
+                        max_requests_per_second: None,
+                        max_concurrent: None,
+                        allow_methods: vec![],
+                        deny_methods: vec![],
                     }),
                 };
                 let entry = chains.entry(name.to_string()).or_insert_with(|| Chain {
@@ -562,6 +643,63 @@ impl Chain {
             }
         }
 
+        // Providers with traces and providers that only see top-level calls
+        // produce different call triggers for the same block. Mixing them
+        // would make indexing depend on which provider gets picked
+        let call_sources = self
+            .providers
+            .iter()
+            .filter_map(|provider| match &provider.details {
+                ProviderDetails::Web3(web3) => Some((
+                    web3.features.contains("traces"),
+                    web3.features.contains("top_level_calls"),
+                )),
+                _ => None,
+            })
+            .collect_vec();
+        if call_sources.iter().any(|(traces, _)| *traces)
+            && call_sources.iter().any(|(_, top_level)| *top_level)
+        {
+            bail!(
+                "providers for a chain can not mix the `traces` and the `top_level_calls` features"
+            );
+        }
+
+        // Merged blocks only replace the block stream for subgraphs; the
+        // chain head and eth_calls still need an RPC provider
+        let merged_blocks = self
+            .providers
+            .iter()
+            .filter(|provider| matches!(provider.details, ProviderDetails::MergedBlocks(_)))
+            .count();
+        if merged_blocks > 0 {
+            if self.protocol != BlockchainKind::Ethereum {
+                bail!(
+                    "merged blocks providers are not supported for the {} protocol",
+                    self.protocol
+                );
+            }
+            if merged_blocks > 1 {
+                bail!("a chain can have at most one merged blocks provider");
+            }
+            if !self
+                .providers
+                .iter()
+                .any(|provider| matches!(provider.details, ProviderDetails::Web3(_)))
+            {
+                bail!("a chain with a merged blocks provider also needs an rpc provider");
+            }
+            // Blocks from bundles have no Firehose cursor, but with a
+            // firehose provider, the chain would refetch blocks by cursor
+            if self
+                .providers
+                .iter()
+                .any(|provider| matches!(provider.details, ProviderDetails::Firehose(_)))
+            {
+                bail!("a chain with a merged blocks provider can not have firehose providers");
+            }
+        }
+
         // When using substreams protocol, only substreams endpoints are allowed
         if matches!(self.protocol, BlockchainKind::Substreams) {
             let has_non_substreams_providers = self
@@ -611,6 +749,8 @@ pub enum ProviderDetails {
     Web3(Web3Provider),
     Substreams(FirehoseProvider),
     Web3Call(Web3Provider),
+    #[serde(rename = "merged_blocks")]
+    MergedBlocks(MergedBlocksProvider),
 }
 
 const FIREHOSE_FILTER_FEATURE: &str = "filters";
@@ -647,6 +787,14 @@ impl FirehoseProvider {
     }
 }
 
+/// Firehose `merged-blocks` bundles that subgraphs read their blocks from
+/// instead of using Firehose or RPC
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct MergedBlocksProvider {
+    /// A local directory or an object store URL like `gs://bucket/path`
+    pub url: String,
+}
+
 pub trait Web3Rules {
     fn limit_for(&self, node: &NodeId) -> SubgraphLimit;
 }
@@ -705,22 +853,46 @@ pub struct Web3Provider {
 
     #[serde(default, rename = "match")]
     rules: Vec<Web3Rule>,
+
+    /// Send at most this many requests per second to the provider
+    #[serde(default)]
+    pub max_requests_per_second: Option<u32>,
+    /// Never have more than this many requests to the provider in flight
+    #[serde(default)]
+    pub max_concurrent: Option<usize>,
+    /// If not empty, only send these JSON-RPC methods to the provider
+    #[serde(default)]
+    pub allow_methods: Vec<String>,
+    /// Never send these JSON-RPC methods to the provider
+    #[serde(default)]
+    pub deny_methods: Vec<String>,
 }
 
 impl Web3Provider {
     pub fn node_capabilities(&self) -> NodeCapabilities {
         NodeCapabilities {
             archive: self.features.contains("archive"),
-            traces: self.features.contains("traces"),
+            // Providers that build call triggers from transactions instead
+            // of traces can also serve call handlers
+            traces: self.features.contains("traces") || self.features.contains("top_level_calls"),
         }
     }
 
     pub fn limit_for(&self, node: &NodeId) -> SubgraphLimit {
         self.rules.limit_for(node)
     }
+
+    pub fn request_limits(&self) -> RequestLimits {
+        RequestLimits {
+            max_requests_per_second: self.max_requests_per_second,
+            max_concurrent: self.max_concurrent,
+            allow_methods: self.allow_methods.clone(),
+            deny_methods: self.deny_methods.clone(),
+        }
+    }
 }
 
-const PROVIDER_FEATURES: [&str; 3] = ["traces", "archive", "no_eip1898"];
+const PROVIDER_FEATURES: [&str; 4] = ["traces", "archive", "no_eip1898", "top_level_calls"];
 const DEFAULT_PROVIDER_FEATURES: [&str; 2] = ["traces", "archive"];
 
 impl Provider {
@@ -793,6 +965,33 @@ impl Provider {
                         e
                     )
                 })?;
+
+                if web3.max_requests_per_second == Some(0) || web3.max_concurrent == Some(0) {
+                    bail!(
+                        "`max_requests_per_second` and `max_concurrent` for provider {} must be greater than 0",
+                        label
+                    );
+                }
+                if web3.transport != Transport::Rpc && !web3.request_limits().is_empty() {
+                    bail!(
+                        "request limits for provider {} are only supported with the `rpc` transport",
+                        label
+                    );
+                }
+                if !web3.allow_methods.is_empty() && !web3.deny_methods.is_empty() {
+                    bail!(
+                        "provider {} can not have both `allow_methods` and `deny_methods`",
+                        label
+                    );
+                }
+            }
+
+            ProviderDetails::MergedBlocks(ref mut merged_blocks) => {
+                merged_blocks.url = shellexpand::env(&merged_blocks.url)?.into_owned();
+
+                if merged_blocks.url.is_empty() {
+                    bail!("the url for merged blocks provider {} is empty", self.label);
+                }
             }
         }
 
@@ -901,6 +1100,10 @@ impl<'de> Deserialize<'de> for Provider {
                             .ok_or_else(|| serde::de::Error::missing_field("features"))?,
                         headers: headers.unwrap_or_else(HeaderMap::new),
                         rules: nodes,
+                        max_requests_per_second: None,
+                        max_concurrent: None,
+                        allow_methods: vec![],
+                        deny_methods: vec![],
                     }),
                 };
 
@@ -1213,16 +1416,18 @@ where
 #[cfg(test)]
 mod tests {
 
-    use crate::config::{default_polling_interval, ChainSection, Web3Rule};
+    use crate::config::{default_polling_interval, ApiKeySection, ChainSection, Web3Rule};
 
     use super::{
-        Chain, Config, FirehoseProvider, Provider, ProviderDetails, Transport, Web3Provider,
+        Chain, Config, FirehoseProvider, MergedBlocksProvider, Provider, ProviderDetails,
+        Transport, Web3Provider,
     };
     use graph::blockchain::BlockchainKind;
     use graph::firehose::SubgraphLimit;
     use graph::http::{HeaderMap, HeaderValue};
     use graph::prelude::regex::Regex;
     use graph::prelude::{toml, NodeId};
+    use graph_chain_ethereum::NodeCapabilities;
     use std::collections::BTreeSet;
     use std::fs::read_to_string;
     use std::path::{Path, PathBuf};
@@ -1307,6 +1512,10 @@ mod tests {
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
                     rules: Vec::new(),
+                    max_requests_per_second: None,
+                    max_concurrent: None,
+                    allow_methods: vec![],
+                    deny_methods: vec![],
                 }),
             },
             actual
@@ -1333,6 +1542,10 @@ mod tests {
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
                     rules: Vec::new(),
+                    max_requests_per_second: None,
+                    max_concurrent: None,
+                    allow_methods: vec![],
+                    deny_methods: vec![],
                 }),
             },
             actual
@@ -1415,6 +1628,129 @@ mod tests {
         );
     }
 
+    #[test]
+    fn fails_if_traces_and_top_level_calls_are_mixed() {
+        let mut actual = toml::from_str::<ChainSection>(
+            r#"
+            ingestor = "block_ingestor_node"
+            [calibration]
+            shard = "primary"
+            protocol = "ethereum"
+            provider = [
+              { label = "lotus", details = { type = "web3", url = "http://127.0.0.1:1234", features = ["archive", "top_level_calls"] }},
+              { label = "traced", details = { type = "web3", url = "http://127.0.0.1:8545", features = ["archive", "traces"] }},
+            ]
+        "#,
+        )
+        .unwrap();
+        let err = actual.validate().unwrap_err().to_string();
+
+        assert!(
+            err.contains("can not mix the `traces` and the `top_level_calls` features"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn it_works_on_merged_blocks_provider_from_toml() {
+        let mut actual = toml::from_str::<ChainSection>(
+            r#"
+            ingestor = "block_ingestor_node"
+            [mainnet]
+            shard = "primary"
+            protocol = "ethereum"
+            provider = [
+              { label = "rpc", details = { type = "web3", url = "http://127.0.0.1:8545", features = ["archive"] }},
+              { label = "bundles", details = { type = "merged_blocks", url = "/data/merged-blocks" }},
+            ]
+        "#,
+        )
+        .unwrap();
+        actual.validate().unwrap();
+
+        assert_eq!(
+            ProviderDetails::MergedBlocks(MergedBlocksProvider {
+                url: "/data/merged-blocks".to_owned(),
+            }),
+            actual.chains["mainnet"].providers[1].details
+        );
+    }
+
+    #[test]
+    fn fails_if_merged_blocks_provider_without_rpc_provider() {
+        let mut actual = toml::from_str::<ChainSection>(
+            r#"
+            ingestor = "block_ingestor_node"
+            [mainnet]
+            shard = "primary"
+            protocol = "ethereum"
+            provider = [
+              { label = "bundles", details = { type = "merged_blocks", url = "gs://bucket/merged-blocks" }},
+            ]
+        "#,
+        )
+        .unwrap();
+        let err = actual.validate().unwrap_err().to_string();
+
+        assert!(
+            err.contains("a chain with a merged blocks provider also needs an rpc provider"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn fails_if_merged_blocks_provider_with_firehose_provider() {
+        let mut actual = toml::from_str::<ChainSection>(
+            r#"
+            ingestor = "block_ingestor_node"
+            [mainnet]
+            shard = "primary"
+            protocol = "ethereum"
+            provider = [
+              { label = "rpc", details = { type = "web3", url = "http://127.0.0.1:8545", features = ["archive"] }},
+              { label = "firehose", details = { type = "firehose", url = "http://127.0.0.1:9000", features = [] }},
+              { label = "bundles", details = { type = "merged_blocks", url = "/data/merged-blocks" }},
+            ]
+        "#,
+        )
+        .unwrap();
+        let err = actual.validate().unwrap_err().to_string();
+
+        assert!(
+            err.contains("a chain with a merged blocks provider can not have firehose providers"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn top_level_calls_provide_traces_capability() {
+        let mut actual = toml::from_str::<ChainSection>(
+            r#"
+            ingestor = "block_ingestor_node"
+            [calibration]
+            shard = "primary"
+            protocol = "ethereum"
+            provider = [
+              { label = "lotus", details = { type = "web3", url = "http://127.0.0.1:1234", features = ["archive", "top_level_calls"] }},
+            ]
+        "#,
+        )
+        .unwrap();
+        actual.validate().unwrap();
+
+        let provider = &actual.chains["calibration"].providers[0];
+        let ProviderDetails::Web3(web3) = &provider.details else {
+            panic!("expected a web3 provider");
+        };
+        assert_eq!(
+            NodeCapabilities {
+                archive: true,
+                traces: true
+            },
+            web3.node_capabilities()
+        );
+    }
+
     #[test]
     fn it_works_on_new_web3_provider_from_toml() {
         let actual = toml::from_str(
@@ -1440,6 +1776,10 @@ mod tests {
                     features,
                     headers,
                     rules: Vec::new(),
+                    max_requests_per_second: None,
+                    max_concurrent: None,
+                    allow_methods: vec![],
+                    deny_methods: vec![],
                 }),
             },
             actual
@@ -1465,12 +1805,54 @@ mod tests {
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
                     rules: Vec::new(),
+                    max_requests_per_second: None,
+                    max_concurrent: None,
+                    allow_methods: vec![],
+                    deny_methods: vec![],
                 }),
             },
             actual
         );
     }
 
+    #[test]
+    fn it_works_on_web3_provider_with_request_limits_from_toml() {
+        let mut actual = toml::from_str::<Provider>(
+            r#"
+            label = "public"
+            details = { type = "web3", url = "http://localhost:8545", features = [], max_requests_per_second = 25, max_concurrent = 4, allow_methods = ["eth_call"] }
+        "#,
+        )
+        .unwrap();
+        actual.validate().unwrap();
+
+        let ProviderDetails::Web3(web3) = actual.details else {
+            panic!("expected a web3 provider");
+        };
+        let limits = web3.request_limits();
+        assert_eq!(Some(25), limits.max_requests_per_second);
+        assert_eq!(Some(4), limits.max_concurrent);
+        assert_eq!(vec!["eth_call".to_string()], limits.allow_methods);
+        assert!(limits.deny_methods.is_empty());
+    }
+
+    #[test]
+    fn it_errors_on_web3_provider_with_allow_and_deny_methods() {
+        let mut actual = toml::from_str::<Provider>(
+            r#"
+            label = "public"
+            details = { type = "web3", url = "http://localhost:8545", features = [], allow_methods = ["eth_call"], deny_methods = ["eth_getLogs"] }
+        "#,
+        )
+        .unwrap();
+        let err = actual.validate().unwrap_err().to_string();
+
+        assert!(
+            err.contains("can not have both `allow_methods` and `deny_methods`"),
+            "{err}"
+        );
+    }
+
     #[test]
     fn it_errors_on_new_provider_with_deprecated_fields_from_toml() {
         let actual = toml::from_str::<Provider>(
@@ -1834,6 +2216,10 @@ mod tests {
                     features: BTreeSet::new(),
                     headers: HeaderMap::new(),
                     rules: Vec::new(),
+                    max_requests_per_second: None,
+                    max_concurrent: None,
+                    allow_methods: vec![],
+                    deny_methods: vec![],
                 }),
             },
             actual
@@ -1937,4 +2323,57 @@ mod tests {
             actual.chains.get("mainnet").unwrap().polling_interval
         );
     }
+
+    #[test]
+    fn api_keys() {
+        std::env::set_var("API_KEYS_TEST_SECRET", "s3cret");
+        let mut actual = toml::from_str::<ApiKeySection>(
+            r#"
+            required = true
+            [franky]
+            key = "${API_KEYS_TEST_SECRET}"
+            rate_limit = 10
+            burst = 50
+            monthly_quota = 1_000_000
+            [zoro]
+            key = "other"
+            "#,
+        )
+        .unwrap();
+        actual.validate().unwrap();
+
+        assert!(actual.required);
+        let keys = actual.to_api_keys();
+        assert_eq!(2, keys.len());
+        assert_eq!("franky", keys[0].name);
+        assert_eq!("s3cret", keys[0].key);
+        assert_eq!(Some(10.0), keys[0].rate_limit);
+        assert_eq!(Some(50), keys[0].burst);
+        assert_eq!(Some(1_000_000), keys[0].monthly_quota);
+        assert_eq!(None, keys[1].rate_limit);
+
+        let mut actual = toml::from_str::<ApiKeySection>(
+            r#"
+            [franky]
+            key = "secret"
+            burst = 50
+            "#,
+        )
+        .unwrap();
+        assert!(actual.validate().is_err());
+
+        let mut actual = toml::from_str::<ApiKeySection>(
+            r#"
+            [franky]
+            key = "secret"
+            [zoro]
+            key = "secret"
+            "#,
+        )
+        .unwrap();
+        assert!(actual.validate().is_err());
+
+        let mut actual = toml::from_str::<ApiKeySection>("required = true").unwrap();
+        assert!(actual.validate().is_err());
+    }
 }
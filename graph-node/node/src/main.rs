@@ -24,7 +24,7 @@ use graph_node::config::Config;
 use graph_node::network_setup::Networks;
 use graph_node::opt;
 use graph_node::store_builder::StoreBuilder;
-use graph_server_http::GraphQLServer as GraphQLQueryServer;
+use graph_server_http::{ApiKeys, GraphQLServer as GraphQLQueryServer};
 use graph_server_index_node::IndexNodeServer;
 use graph_server_json_rpc::JsonRpcServer;
 use graph_server_metrics::PrometheusMetricsServer;
@@ -358,13 +358,25 @@ async fn main() {
             load_manager,
             graphql_metrics_registry,
         ));
-        let graphql_server = GraphQLQueryServer::new(&logger_factory, graphql_runner.clone());
+        let api_keys = Arc::new(ApiKeys::new(
+            &metrics_registry,
+            config.api_keys.required,
+            config.api_keys.to_api_keys(),
+        ));
+        let graphql_server = GraphQLQueryServer::new(
+            &logger_factory,
+            graphql_runner.clone(),
+            subscription_manager.cheap_clone(),
+            network_store.subgraph_store().persisted_queries(),
+            api_keys,
+        );
 
         let index_node_server = IndexNodeServer::new(
             &logger_factory,
             blockchain_map.clone(),
             network_store.clone(),
             link_resolver.clone(),
+            metrics_registry.mapping_profiles(),
         );
 
         if !opt.disable_block_ingestor {
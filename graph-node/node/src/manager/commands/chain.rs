@@ -76,6 +76,52 @@ pub async fn clear_call_cache(
     Ok(())
 }
 
+pub async fn clear_log_cache(
+    chain_store: Arc<ChainStore>,
+    from: i32,
+    to: i32,
+) -> Result<(), Error> {
+    println!(
+        "Removing entries for blocks from {from} to {to} from the log cache for `{}`",
+        chain_store.chain
+    );
+    chain_store.clear_log_cache(from, to).await?;
+    Ok(())
+}
+
+pub fn log_cache_info(chain_store: Arc<ChainStore>) -> Result<(), Error> {
+    fn row(label: &str, value: impl std::fmt::Display) {
+        println!("{:<16} | {}", label, value);
+    }
+
+    fn range(min: Option<i32>, max: Option<i32>) -> String {
+        match (min, max) {
+            (Some(min), Some(max)) => format!("{min} - {max}"),
+            _ => "ø".to_string(),
+        }
+    }
+
+    let info = match chain_store.log_cache_info()? {
+        Some(info) => info,
+        None => {
+            println!(
+                "`{}` uses shared storage and does not have a log cache",
+                chain_store.chain
+            );
+            return Ok(());
+        }
+    };
+    row("chain", &chain_store.chain);
+    row("log ranges", info.log_ranges);
+    row("log blocks", range(info.log_min_block, info.log_max_block));
+    row("receipts", info.receipts);
+    row(
+        "receipt blocks",
+        range(info.receipt_min_block, info.receipt_max_block),
+    );
+    Ok(())
+}
+
 pub async fn info(
     primary: ConnectionPool,
     store: Arc<BlockStore>,
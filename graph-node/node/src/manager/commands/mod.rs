@@ -10,12 +10,15 @@ pub mod deployment;
 pub mod drop;
 pub mod index;
 pub mod listen;
+pub mod persisted_query;
 pub mod provider_checks;
 pub mod prune;
 pub mod query;
 pub mod remove;
+pub mod replay;
 pub mod rewind;
 pub mod run;
 pub mod stats;
 pub mod txn_speed;
 pub mod unused_deployments;
+pub mod wasm_cache;
@@ -0,0 +1,77 @@
+use std::fs;
+
+use graph::data::query::persisted_query_hash;
+use graph::prelude::{
+    anyhow::{anyhow, Error},
+    q,
+};
+use graph_store_postgres::{
+    command_support::catalog::{self, PersistedQuery},
+    connection_pool::ConnectionPool,
+};
+
+use crate::manager::display::List;
+
+pub fn list(primary: ConnectionPool, show_query: bool) -> Result<(), Error> {
+    let mut conn = catalog::Connection::new(primary.get()?);
+    let queries = conn.persisted_queries()?;
+
+    if queries.is_empty() {
+        println!("no persisted queries");
+        return Ok(());
+    }
+
+    let headers = if show_query {
+        vec!["hash", "created at", "query"]
+    } else {
+        vec!["hash", "created at"]
+    };
+    let mut list = List::new(headers);
+    for PersistedQuery {
+        hash,
+        query,
+        created_at,
+    } in queries
+    {
+        let mut row = vec![hash, created_at.to_rfc3339()];
+        if show_query {
+            row.push(query);
+        }
+        list.append(row);
+    }
+    list.render();
+    Ok(())
+}
+
+/// Add the query in `file` to the allow-list. If `file` is `-`, read the
+/// query from stdin
+pub fn add(primary: ConnectionPool, file: String) -> Result<(), Error> {
+    let query = if file == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        fs::read_to_string(&file).map_err(|e| anyhow!("failed to read {}: {}", file, e))?
+    };
+
+    // Make sure we only persist queries that we can at least parse
+    q::parse_query(&query).map_err(|e| anyhow!("invalid query: {}", e))?;
+
+    let hash = persisted_query_hash(&query);
+    let mut conn = catalog::Connection::new(primary.get()?);
+    if conn.add_persisted_query(&hash, &query)? {
+        println!("added persisted query {}", hash);
+    } else {
+        println!("persisted query {} already exists", hash);
+    }
+    Ok(())
+}
+
+pub fn remove(primary: ConnectionPool, hash: String) -> Result<(), Error> {
+    let hash = hash.to_lowercase();
+    let mut conn = catalog::Connection::new(primary.get()?);
+    if conn.remove_persisted_query(&hash)? {
+        println!("removed persisted query {}", hash);
+    } else {
+        println!("no persisted query with hash {}", hash);
+    }
+    Ok(())
+}
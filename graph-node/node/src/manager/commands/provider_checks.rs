@@ -8,6 +8,8 @@ use graph::components::network_provider::GenesisHashCheck;
 use graph::components::network_provider::NetworkDetails;
 use graph::components::network_provider::ProviderCheck;
 use graph::components::network_provider::ProviderCheckStatus;
+use graph::components::network_provider::ProviderName;
+use graph::endpoint::EndpointMetrics;
 use graph::prelude::tokio;
 use graph::prelude::Logger;
 use graph_store_postgres::BlockStore;
@@ -20,6 +22,7 @@ pub async fn execute(
     networks: &Networks,
     store: Arc<BlockStore>,
     timeout: Duration,
+    metrics: Option<&EndpointMetrics>,
 ) {
     let chain_name_iter = networks
         .adapters
@@ -30,12 +33,14 @@ pub async fn execute(
 
     for chain_name in chain_name_iter {
         let mut errors = Vec::new();
+        let mut providers: Vec<ProviderName> = Vec::new();
 
         for adapter in networks
             .rpc_provider_manager
             .providers_unchecked(chain_name)
             .unique_by(|x| x.provider_name())
         {
+            providers.push(adapter.provider_name());
             match tokio::time::timeout(
                 timeout,
                 run_checks(logger, chain_name, adapter, store.clone()),
@@ -56,6 +61,7 @@ pub async fn execute(
             .providers_unchecked(chain_name)
             .unique_by(|x| x.provider_name())
         {
+            providers.push(adapter.provider_name());
             match tokio::time::timeout(
                 timeout,
                 run_checks(logger, chain_name, adapter, store.clone()),
@@ -76,6 +82,7 @@ pub async fn execute(
             .providers_unchecked(chain_name)
             .unique_by(|x| x.provider_name())
         {
+            providers.push(adapter.provider_name());
             match tokio::time::timeout(
                 timeout,
                 run_checks(logger, chain_name, adapter, store.clone()),
@@ -93,12 +100,28 @@ pub async fn execute(
 
         if errors.is_empty() {
             println!("Chain: {chain_name}; Status: OK");
-            continue;
+        } else {
+            println!("Chain: {chain_name}; Status: ERROR");
+            for error in errors.into_iter().unique() {
+                println!("ERROR: {error}");
+            }
         }
 
-        println!("Chain: {chain_name}; Status: ERROR");
-        for error in errors.into_iter().unique() {
-            println!("ERROR: {error}");
+        let Some(metrics) = metrics else {
+            continue;
+        };
+        for score in providers
+            .iter()
+            .unique()
+            .filter_map(|provider| metrics.score(provider))
+        {
+            println!(
+                "Provider: {}; Latency: {:.0}ms; Error rate: {:.2}; Circuit: {}",
+                score.provider,
+                score.latency_ms,
+                score.error_rate,
+                if score.circuit_open { "open" } else { "closed" }
+            );
         }
     }
 }
@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::manager::deployment::DeploymentSearch;
+use crate::network_setup::Networks;
+use crate::store_builder::StoreBuilder;
+use graph::cheap_clone::CheapClone;
+use graph::components::link_resolver::{ArweaveClient, FileSizeLimit};
+use graph::endpoint::EndpointMetrics;
+use graph::env::EnvVars;
+use graph::prelude::{
+    anyhow, serde_yaml, BlockNumber, EntityModification, IpfsResolver, LinkResolver, LoggerFactory,
+    MetricsRegistry, NodeId, SubgraphCountMetric, ENV_VARS,
+};
+use graph::slog::{info, Logger};
+use graph_core::polling_monitor::{arweave_service, ipfs_service};
+use graph_core::{BlockReplay, SubgraphInstanceManager};
+
+/// Run the triggers of `block` for the deployment `search` against the
+/// state of the deployment at `block - 1` and print the changes that
+/// indexing would make. Nothing is written to the store.
+pub async fn run(
+    logger: Logger,
+    store_builder: StoreBuilder,
+    ipfs_url: Vec<String>,
+    arweave_url: String,
+    config: Config,
+    metrics_registry: Arc<MetricsRegistry>,
+    node_id: NodeId,
+    search: DeploymentSearch,
+    block: BlockNumber,
+) -> Result<(), anyhow::Error> {
+    let env_vars = Arc::new(EnvVars::from_env().unwrap());
+    let logger_factory = LoggerFactory::new(logger.clone(), None, metrics_registry.clone());
+
+    let primary_pool = store_builder.primary_pool();
+    let loc = search.locate_unique(&primary_pool)?;
+
+    let ipfs_client = graph::ipfs::new_ipfs_client(&ipfs_url, &logger).await?;
+    let ipfs_service = ipfs_service(
+        ipfs_client.cheap_clone(),
+        env_vars.mappings.max_ipfs_file_bytes,
+        env_vars.mappings.ipfs_timeout,
+        env_vars.mappings.ipfs_request_limit,
+    );
+
+    let arweave_resolver = Arc::new(ArweaveClient::new(
+        logger.cheap_clone(),
+        arweave_url.parse().expect("invalid arweave url"),
+    ));
+    let arweave_service = arweave_service(
+        arweave_resolver.cheap_clone(),
+        env_vars.mappings.ipfs_request_limit,
+        match env_vars.mappings.max_ipfs_file_bytes {
+            0 => FileSizeLimit::Unlimited,
+            n => FileSizeLimit::MaxBytes(n as u64),
+        },
+    );
+
+    let link_resolver = Arc::new(IpfsResolver::new(ipfs_client, env_vars.cheap_clone()));
+
+    let endpoint_metrics = Arc::new(EndpointMetrics::new(
+        logger.clone(),
+        &config.chains.providers(),
+        metrics_registry.cheap_clone(),
+    ));
+
+    let chain_head_update_listener = store_builder.chain_head_update_listener();
+    let network_store = store_builder.network_store(config.chain_ids());
+    let block_store = network_store.block_store();
+    let subgraph_store = network_store.subgraph_store();
+
+    let networks = Networks::from_config(
+        logger.cheap_clone(),
+        &config,
+        metrics_registry.cheap_clone(),
+        endpoint_metrics,
+        &[],
+    )
+    .await?;
+
+    let blockchain_map = Arc::new(
+        networks
+            .blockchain_map(
+                &env_vars,
+                &node_id,
+                &logger,
+                block_store,
+                &logger_factory,
+                metrics_registry.cheap_clone(),
+                chain_head_update_listener,
+            )
+            .await,
+    );
+
+    let sg_metrics = Arc::new(SubgraphCountMetric::new(metrics_registry.clone()));
+
+    let instance_manager = SubgraphInstanceManager::new(
+        &logger_factory,
+        env_vars.cheap_clone(),
+        subgraph_store,
+        blockchain_map,
+        sg_metrics,
+        metrics_registry,
+        link_resolver.cheap_clone(),
+        ipfs_service,
+        arweave_service,
+        ENV_VARS.experimental_static_filters,
+    );
+
+    let file_bytes = link_resolver.cat(&logger, &loc.hash.to_ipfs_link()).await?;
+    let manifest: serde_yaml::Mapping = serde_yaml::from_slice(&file_bytes)?;
+
+    info!(logger, "Replaying block {} of {}", block, loc);
+    let replay = instance_manager
+        .replay_block(logger_factory.subgraph_logger(&loc), loc, manifest, block)
+        .await?;
+
+    print_replay(&replay);
+
+    Ok(())
+}
+
+fn print_replay(replay: &BlockReplay) {
+    let BlockReplay {
+        block_ptr,
+        modifications,
+        poi_digests,
+        created_data_sources,
+        removed_data_sources,
+        deterministic_errors,
+    } = replay;
+
+    println!("block: {}", block_ptr);
+
+    println!();
+    println!("entity changes: {}", modifications.len());
+    for m in modifications {
+        let (op, data) = match m {
+            EntityModification::Insert { data, .. } => ("insert", Some(data)),
+            EntityModification::Overwrite { data, .. } => ("overwrite", Some(data)),
+            EntityModification::Remove { .. } => ("remove", None),
+        };
+        let key = m.key();
+        println!("  {:<9} {}[{}]", op, key.entity_type, key.entity_id);
+        if let Some(data) = data {
+            for (name, value) in data.sorted_ref() {
+                println!("      {}: {}", name, value);
+            }
+        }
+    }
+
+    if !created_data_sources.is_empty() || !removed_data_sources.is_empty() {
+        println!();
+        println!(
+            "data sources: {} created, {} removed",
+            created_data_sources.len(),
+            removed_data_sources.len()
+        );
+    }
+
+    if !deterministic_errors.is_empty() {
+        println!();
+        println!("errors: {}", deterministic_errors.len());
+        for e in deterministic_errors {
+            match &e.handler {
+                Some(handler) => println!("  {}: {}", handler, e.message),
+                None => println!("  {}", e.message),
+            }
+        }
+    }
+
+    println!();
+    println!("poi digests:");
+    for (region, digest) in poi_digests {
+        println!("  {}: {}", region, digest);
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::PathBuf;
+
+use graph::env::ENV_VARS;
+use graph::prelude::anyhow::{anyhow, Error};
+use graph_runtime_wasm::module_cache::ModuleCache;
+
+fn cache(dir: Option<PathBuf>) -> Result<(PathBuf, ModuleCache), Error> {
+    let dir = dir
+        .or_else(|| ENV_VARS.mappings.wasm_module_cache_dir.clone())
+        .ok_or_else(|| {
+            anyhow!("no cache directory; pass `--dir` or set `GRAPH_WASM_MODULE_CACHE_DIR`")
+        })?;
+    Ok((dir.clone(), ModuleCache::new(dir)))
+}
+
+pub fn info(dir: Option<PathBuf>) -> Result<(), Error> {
+    let (dir, cache) = cache(dir)?;
+    let (count, bytes) = cache.size()?;
+    println!("directory: {}", dir.display());
+    println!("modules:   {}", count);
+    println!("size:      {:.1} MB", bytes as f64 / 1_000_000.0);
+    Ok(())
+}
+
+pub fn clear(dir: Option<PathBuf>) -> Result<(), Error> {
+    let (dir, cache) = cache(dir)?;
+    let count = cache.clear()?;
+    println!("removed {} modules from {}", count, dir.display());
+    Ok(())
+}
@@ -9,7 +9,10 @@ use graph::components::network_provider::ProviderCheckStrategy;
 use graph::components::network_provider::ProviderManager;
 use graph::{
     anyhow::{self, bail},
-    blockchain::{Blockchain, BlockchainKind, BlockchainMap, ChainIdentifier},
+    blockchain::{
+        merged_blocks_block_stream::MergedBlocksSource, Blockchain, BlockchainKind, BlockchainMap,
+        ChainIdentifier,
+    },
     cheap_clone::CheapClone,
     components::metrics::MetricsRegistry,
     endpoint::EndpointMetrics,
@@ -30,8 +33,8 @@ use graph_store_postgres::{BlockStore, ChainHeadUpdateListener};
 use std::{any::Any, cmp::Ordering, sync::Arc, time::Duration};
 
 use crate::chain::{
-    create_all_ethereum_networks, create_firehose_networks, create_substreams_networks,
-    networks_as_chains,
+    create_all_ethereum_networks, create_firehose_networks, create_merged_blocks_sources,
+    create_substreams_networks, networks_as_chains,
 };
 
 #[derive(Debug, Clone)]
@@ -51,17 +54,26 @@ pub struct FirehoseAdapterConfig {
     pub adapters: Vec<Arc<FirehoseEndpoint>>,
 }
 
+#[derive(Debug, Clone)]
+pub struct MergedBlocksConfig {
+    pub chain_id: ChainName,
+    pub source: Arc<MergedBlocksSource>,
+}
+
 #[derive(Debug, Clone)]
 pub enum AdapterConfiguration {
     Rpc(EthAdapterConfig),
     Firehose(FirehoseAdapterConfig),
     Substreams(FirehoseAdapterConfig),
+    MergedBlocks(MergedBlocksConfig),
 }
 
 impl AdapterConfiguration {
     pub fn blockchain_kind(&self) -> &BlockchainKind {
         match self {
-            AdapterConfiguration::Rpc(_) => &BlockchainKind::Ethereum,
+            AdapterConfiguration::Rpc(_) | AdapterConfiguration::MergedBlocks(_) => {
+                &BlockchainKind::Ethereum
+            }
             AdapterConfiguration::Firehose(fh) | AdapterConfiguration::Substreams(fh) => &fh.kind,
         }
     }
@@ -69,7 +81,8 @@ impl AdapterConfiguration {
         match self {
             AdapterConfiguration::Rpc(EthAdapterConfig { chain_id, .. })
             | AdapterConfiguration::Firehose(FirehoseAdapterConfig { chain_id, .. })
-            | AdapterConfiguration::Substreams(FirehoseAdapterConfig { chain_id, .. }) => chain_id,
+            | AdapterConfiguration::Substreams(FirehoseAdapterConfig { chain_id, .. })
+            | AdapterConfiguration::MergedBlocks(MergedBlocksConfig { chain_id, .. }) => chain_id,
         }
     }
 
@@ -101,6 +114,13 @@ impl AdapterConfiguration {
     pub fn is_substreams(&self) -> bool {
         self.as_substreams().is_none()
     }
+
+    pub fn as_merged_blocks(&self) -> Option<&MergedBlocksConfig> {
+        match self {
+            AdapterConfiguration::MergedBlocks(mb) => Some(mb),
+            _ => None,
+        }
+    }
 }
 
 pub struct Networks {
@@ -208,10 +228,12 @@ impl Networks {
         );
         let substreams =
             create_substreams_networks(logger.cheap_clone(), &config, endpoint_metrics);
+        let merged_blocks = create_merged_blocks_sources(logger.cheap_clone(), &config)?;
         let adapters: Vec<_> = eth
             .into_iter()
             .chain(firehose.into_iter())
             .chain(substreams.into_iter())
+            .chain(merged_blocks.into_iter())
             .collect();
 
         Ok(Networks::new(&logger, adapters, provider_checks))
@@ -392,6 +414,14 @@ impl Networks {
         FirehoseEndpoints::new(chain_id, self.substreams_provider_manager.clone())
     }
 
+    pub fn merged_blocks_source(&self, chain_id: &ChainName) -> Option<Arc<MergedBlocksSource>> {
+        self.adapters
+            .iter()
+            .filter(|a| a.chain_id().eq(chain_id))
+            .find_map(|a| a.as_merged_blocks())
+            .map(|mb| mb.source.cheap_clone())
+    }
+
     pub fn ethereum_rpcs(&self, chain_id: ChainName) -> EthereumNetworkAdapters {
         let eth_adapters = self
             .adapters
@@ -1,4 +1,5 @@
 use graph::blockchain::BlockTime;
+use graph::blockchain::DataSource as _;
 use graph::components::metrics::gas::GasMetrics;
 use graph::components::store::*;
 use graph::data::store::{scalar, Id, IdType};
@@ -21,11 +22,12 @@ use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 use test_store::{LOGGER, STORE};
 use wasmtime::{AsContext, AsContextMut};
-use web3::types::H160;
+use web3::types::{H160, H256};
 
 use crate::common::{mock_context, mock_data_source};
 
 mod abi;
+mod rust_abi;
 
 pub const API_VERSION_0_0_4: Version = Version::new(0, 0, 4);
 pub const API_VERSION_0_0_5: Version = Version::new(0, 0, 5);
@@ -102,7 +104,15 @@ async fn test_valid_module_and_store_with_timeout(
     };
 
     let module = WasmInstance::from_valid_module_with_ctx(
-        Arc::new(ValidModule::new(&logger, data_source.mapping.runtime.as_ref(), timeout).unwrap()),
+        Arc::new(
+            ValidModule::new(
+                &logger,
+                data_source.mapping.runtime.as_ref(),
+                data_source.language(),
+                timeout,
+            )
+            .unwrap(),
+        ),
         mock_context(
             deployment.clone(),
             data_source,
@@ -167,6 +177,16 @@ pub trait WasmInstanceExt {
         T2: ToAscObj<C2> + ?Sized;
     fn invoke_export0_val<V: wasmtime::WasmTy>(&mut self, func: &str) -> V;
     fn invoke_export1_val<V: wasmtime::WasmTy, C, T>(&mut self, func: &str, v: &T) -> V
+    where
+        C: AscType + AscIndexId,
+        T: ToAscObj<C> + ?Sized;
+    fn invoke_export3_val<V: wasmtime::WasmTy, C, T>(
+        &mut self,
+        func: &str,
+        arg0: &T,
+        arg1: &T,
+        arg2: &T,
+    ) -> V
     where
         C: AscType + AscIndexId,
         T: ToAscObj<C> + ?Sized;
@@ -305,6 +325,32 @@ impl WasmInstanceExt for WasmInstance {
             .unwrap()
     }
 
+    fn invoke_export3_val<V: wasmtime::WasmTy, C, T>(
+        &mut self,
+        func: &str,
+        arg0: &T,
+        arg1: &T,
+        arg2: &T,
+    ) -> V
+    where
+        C: AscType + AscIndexId,
+        T: ToAscObj<C> + ?Sized,
+    {
+        let func = self
+            .get_func(func)
+            .typed(&self.store.as_context())
+            .unwrap()
+            .clone();
+        let arg0 = self.asc_new(arg0).unwrap();
+        let arg1 = self.asc_new(arg1).unwrap();
+        let arg2 = self.asc_new(arg2).unwrap();
+        func.call(
+            &mut self.store.as_context_mut(),
+            (arg0.wasm_ptr(), arg1.wasm_ptr(), arg2.wasm_ptr()),
+        )
+        .unwrap()
+    }
+
     fn takes_val_returns_ptr<P>(&mut self, fn_name: &str, val: impl wasmtime::WasmTy) -> AscPtr<P> {
         let func = self
             .get_func(fn_name)
@@ -683,6 +729,128 @@ async fn crypto_keccak256_v0_0_5() {
     test_crypto_keccak256(API_VERSION_0_0_5).await;
 }
 
+async fn crypto_module() -> WasmInstance {
+    test_module(
+        "cryptoExtended",
+        mock_data_source(
+            &wasm_file_path("crypto_extended.wasm", API_VERSION_0_0_5),
+            API_VERSION_0_0_5,
+        ),
+        API_VERSION_0_0_5,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn crypto_hashes() {
+    let mut module = crypto_module().await;
+    let input: &[u8] = "eth".as_ref();
+
+    let hash: AscPtr<Uint8Array> = module.invoke_export1("sha256", input);
+    let hash: Vec<u8> = module.asc_get(hash).unwrap();
+    assert_eq!(
+        hex::encode(hash),
+        "c69ea13227bace6e1f8a06364d93f4a6f04632432b64ca9b1fc4036baea4d34c"
+    );
+
+    let hash: AscPtr<Uint8Array> = module.invoke_export1("blake2b256", input);
+    let hash: Vec<u8> = module.asc_get(hash).unwrap();
+    assert_eq!(
+        hex::encode(hash),
+        "c0ad5f4066fe9b1a96851be82c6504444eddafd0c1e5b42d53d1a3cdc978ef21"
+    );
+}
+
+#[tokio::test]
+async fn crypto_secp256k1_recover() {
+    let mut module = crypto_module().await;
+
+    let secp = secp256k1::Secp256k1::new();
+    let secret = secp256k1::SecretKey::from_slice(&[7u8; 32]).unwrap();
+    let public = secp256k1::PublicKey::from_secret_key(&secp, &secret);
+    let hash = [1u8; 32];
+    let message = secp256k1::Message::from_slice(&hash).unwrap();
+    let (id, signature) = secp
+        .sign_ecdsa_recoverable(&message, &secret)
+        .serialize_compact();
+
+    // Both ways of encoding `v` work
+    for v in [id.to_i32() as u8, id.to_i32() as u8 + 27] {
+        let mut signature = signature.to_vec();
+        signature.push(v);
+        let key: AscPtr<Uint8Array> =
+            module.invoke_export2("secp256k1Recover", &hash[..], signature.as_slice());
+        let key: Vec<u8> = module.asc_get(key).unwrap();
+        assert_eq!(public.serialize_uncompressed().to_vec(), key);
+    }
+
+    // Malformed signatures return `null` instead of failing the handler
+    let key: AscPtr<Uint8Array> =
+        module.invoke_export2("secp256k1Recover", &hash[..], &signature[..]);
+    assert!(key.is_null());
+    let mut signature = signature.to_vec();
+    signature.push(5);
+    let key: AscPtr<Uint8Array> =
+        module.invoke_export2("secp256k1Recover", &hash[..], signature.as_slice());
+    assert!(key.is_null());
+}
+
+#[tokio::test]
+async fn crypto_ed25519_verify() {
+    use ed25519_dalek::Signer;
+
+    let mut module = crypto_module().await;
+
+    let key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+    let message: &[u8] = b"DeviceRegistered";
+    let signature = key.sign(message).to_bytes();
+    let public_key = key.verifying_key().to_bytes();
+
+    let valid: i32 =
+        module.invoke_export3_val("ed25519Verify", message, &signature[..], &public_key[..]);
+    assert_eq!(1, valid);
+
+    let valid: i32 = module.invoke_export3_val(
+        "ed25519Verify",
+        b"DeviceRemoved".as_ref(),
+        &signature[..],
+        &public_key[..],
+    );
+    assert_eq!(0, valid);
+
+    let valid: i32 =
+        module.invoke_export3_val("ed25519Verify", message, &signature[..], &public_key[1..]);
+    assert_eq!(0, valid);
+}
+
+#[tokio::test]
+async fn crypto_bls12381_verify() {
+    const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+    let mut module = crypto_module().await;
+
+    let secret = blst::min_pk::SecretKey::key_gen(&[7u8; 32], &[]).unwrap();
+    let message: &[u8] = b"DeviceRegistered";
+    let signature = secret.sign(message, DST, &[]).to_bytes();
+    let public_key = secret.sk_to_pk().to_bytes();
+
+    let valid: i32 =
+        module.invoke_export3_val("bls12381Verify", message, &signature[..], &public_key[..]);
+    assert_eq!(1, valid);
+
+    let valid: i32 = module.invoke_export3_val(
+        "bls12381Verify",
+        b"DeviceRemoved".as_ref(),
+        &signature[..],
+        &public_key[..],
+    );
+    assert_eq!(0, valid);
+
+    let valid: i32 =
+        module.invoke_export3_val("bls12381Verify", message, &signature[1..], &public_key[..]);
+    assert_eq!(0, valid);
+}
+
 async fn test_big_int_to_hex(api_version: Version, gas_used: u64) {
     let mut instance = test_module(
         "BigIntToHex",
@@ -1267,6 +1435,7 @@ async fn recursion_limit() {
 }
 
 struct Host {
+    deployment: DeploymentLocator,
     ctx: MappingContext,
     host_exports: host_exports::test_support::HostExports,
     stopwatch: StopwatchMetrics,
@@ -1304,6 +1473,7 @@ impl Host {
         let gas = GasCounter::new(gas_metrics);
 
         Host {
+            deployment,
             ctx,
             host_exports,
             stopwatch,
@@ -1355,6 +1525,39 @@ impl Host {
             &self.gas,
         )
     }
+
+    fn store_find_by(
+        &mut self,
+        entity_type: &str,
+        attribute: &str,
+        value: Value,
+        limit: i32,
+    ) -> Result<Vec<Entity>, HostExportError> {
+        self.host_exports.store_find_by(
+            &mut self.ctx.state,
+            entity_type.to_string(),
+            attribute.to_string(),
+            value,
+            limit,
+            &self.gas,
+        )
+    }
+
+    fn store_get_at(
+        &mut self,
+        entity_type: &str,
+        id: &str,
+        block: BlockNumber,
+    ) -> Result<Option<Entity>, HostExportError> {
+        self.host_exports.store_get_at(
+            &mut self.ctx.state,
+            entity_type.to_string(),
+            id.to_string(),
+            block,
+            12, // The same arbitrary block number as `store_setv`
+            &self.gas,
+        )
+    }
 }
 
 #[track_caller]
@@ -1459,6 +1662,150 @@ async fn test_store_set_id() {
     );
 }
 
+/// Test looking up entities by an attribute with `store.findBy`, and that
+/// invalid arguments lead to deterministic errors
+#[tokio::test]
+async fn test_store_find_by() {
+    const USER: &str = "User";
+    let schema = "
+    type User @entity {
+        id: ID!,
+        name: String,
+        tags: [String!]
+    }";
+
+    let mut host = Host::new(schema, "hostStoreFindBy", "boolean.wasm", None).await;
+
+    for (id, name) in [("u3", "alice"), ("u1", "alice"), ("u2", "bob")] {
+        host.store_set(USER, id, vec![("id", id), ("name", name)])
+            .unwrap();
+    }
+
+    let ids = |entities: Vec<Entity>| {
+        entities
+            .into_iter()
+            .map(|entity| entity.id().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let alice = Value::from("alice");
+    let found = host.store_find_by(USER, "name", alice.clone(), 10).unwrap();
+    assert_eq!(vec!["u1", "u3"], ids(found));
+
+    let found = host.store_find_by(USER, "name", alice.clone(), 1).unwrap();
+    assert_eq!(vec!["u1"], ids(found));
+
+    let found = host
+        .store_find_by(USER, "name", Value::from("carol"), 10)
+        .unwrap();
+    assert!(found.is_empty());
+
+    // Changes in the same block are taken into account
+    host.store_set(USER, "u3", vec![("name", "carol")]).unwrap();
+    let found = host.store_find_by(USER, "name", alice.clone(), 10).unwrap();
+    assert_eq!(vec!["u1"], ids(found));
+
+    let deterministic = |res: Result<Vec<Entity>, HostExportError>, exp: &str| match res {
+        Err(HostExportError::Deterministic(e)) => err_says(e, exp),
+        res => panic!("expected a deterministic error containing `{exp}` but got {res:?}"),
+    };
+    deterministic(
+        host.store_find_by(USER, "nickname", alice.clone(), 10),
+        "unknown attribute",
+    );
+    deterministic(
+        host.store_find_by(USER, "tags", alice.clone(), 10),
+        "derived and list attributes can not be used",
+    );
+    deterministic(
+        host.store_find_by(USER, "name", Value::Int(7), 10),
+        "has the wrong type",
+    );
+    deterministic(
+        host.store_find_by(USER, "name", alice.clone(), -1),
+        "the limit must not be negative",
+    );
+    deterministic(
+        host.store_find_by(USER, "name", alice, 1001),
+        "the limit can be at most 1000",
+    );
+}
+
+/// Test looking up earlier versions of entities with `store.getAt`, and
+/// that reading at blocks that are not before the current one or that have
+/// been pruned are deterministic errors
+#[tokio::test]
+async fn test_store_get_at() {
+    const USER: &str = "User";
+    let schema = "
+    type User @entity {
+        id: ID!,
+        name: String,
+    }";
+
+    struct Progress;
+    impl PruneReporter for Progress {}
+
+    let mut host = Host::new(schema, "hostStoreGetAt", "boolean.wasm", None).await;
+    let subgraph_store = STORE.subgraph_store();
+    let input_schema = subgraph_store.input_schema(&host.deployment.hash).unwrap();
+    let key = input_schema
+        .entity_type(USER)
+        .unwrap()
+        .parse_key("u1")
+        .unwrap();
+
+    // Write a new version of the user in each of the blocks 1 to 4
+    for block in 1..5 {
+        let name = format!("user{block}");
+        let data = entity! { input_schema => id: "u1", name: name, vid: block as i64 };
+        let ptr = BlockPtr::from((H256::from_low_u64_be(block as u64), block as BlockNumber));
+        test_store::transact_and_wait(
+            &subgraph_store,
+            &host.deployment,
+            ptr,
+            vec![EntityOperation::Set {
+                key: key.clone(),
+                data,
+            }],
+        )
+        .await
+        .unwrap();
+    }
+
+    let name_at = |host: &mut Host, block| {
+        host.store_get_at(USER, "u1", block)
+            .unwrap()
+            .map(|user| user.get("name").unwrap().as_str().unwrap().to_string())
+    };
+    assert_eq!(None, name_at(&mut host, 0));
+    assert_eq!(Some("user1".to_string()), name_at(&mut host, 1));
+    assert_eq!(Some("user3".to_string()), name_at(&mut host, 3));
+    assert_eq!(Some("user4".to_string()), name_at(&mut host, 11));
+
+    let deterministic = |res: Result<Option<Entity>, HostExportError>, exp: &str| match res {
+        Err(HostExportError::Deterministic(e)) => err_says(e, exp),
+        res => panic!("expected a deterministic error containing `{exp}` but got {res:?}"),
+    };
+    deterministic(
+        host.store_get_at(USER, "u1", 12),
+        "must be before the current block",
+    );
+    deterministic(
+        host.store_get_at(USER, "u1", -1),
+        "must be before the current block",
+    );
+
+    // Keep only the history from block 2 on
+    let req = PruneRequest::new(&host.deployment, 2, 1, 0, 4).unwrap();
+    subgraph_store
+        .prune(Box::new(Progress), &host.deployment, req)
+        .await
+        .unwrap();
+    deterministic(host.store_get_at(USER, "u1", 1), "has been pruned");
+    assert_eq!(Some("user2".to_string()), name_at(&mut host, 2));
+}
+
 /// Test setting fields that are not defined in the schema
 /// This should return an error
 #[tokio::test]
@@ -0,0 +1,83 @@
+use graph::data::store::Value;
+use graph::data_source::common::MappingLanguage;
+use graph_runtime_wasm::rust_abi::FromRustBytes;
+
+use super::*;
+
+fn rust_wasm_file_path(wasm_file: &str) -> String {
+    format!("wasm_test/rust_abi_v1/{}", wasm_file)
+}
+
+fn rust_data_source(wasm_file: &str) -> DataSource {
+    let mut ds = mock_data_source(&rust_wasm_file_path(wasm_file), API_VERSION_0_0_5);
+    ds.mapping.language = MappingLanguage::RUST.to_string();
+    ds
+}
+
+/// Read a value that the host returned to the mapping
+fn read_returned(module: &mut WasmInstance, ptr: u32) -> Vec<u8> {
+    let memory = module
+        .instance
+        .get_memory(&mut module.store, "memory")
+        .unwrap();
+    let mut len = [0u8; 4];
+    memory.read(&module.store, ptr as usize, &mut len).unwrap();
+    let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+    memory
+        .read(&module.store, ptr as usize + 4, &mut bytes)
+        .unwrap();
+    bytes
+}
+
+#[tokio::test]
+async fn rust_store_set_get_remove() {
+    let mut module = test_module(
+        "rustStoreSetGetRemove",
+        rust_data_source("store.wasm"),
+        API_VERSION_0_0_5,
+    )
+    .await;
+
+    module.invoke_export0_void("set_user").unwrap();
+
+    let ptr: u32 = module.invoke_export0_val("get_user");
+    assert_ne!(0, ptr);
+    let entity = Vec::<(String, Value)>::from_rust_bytes(&read_returned(&mut module, ptr)).unwrap();
+    assert_eq!(
+        vec![
+            ("id".to_string(), Value::from("u1")),
+            ("name".to_string(), Value::from("john")),
+        ],
+        entity
+    );
+
+    module.invoke_export0_void("remove_user").unwrap();
+    let ptr: u32 = module.invoke_export0_val("get_user");
+    assert_eq!(0, ptr);
+}
+
+#[test]
+fn rust_mapping_must_use_known_abi_version() {
+    let logger = Logger::root(slog::Discard, o!());
+    let raw_module = std::fs::read(rust_wasm_file_path("wrong_namespace.wasm")).unwrap();
+
+    let err = ValidModule::new(&logger, &raw_module, MappingLanguage::Rust, None)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("graph_rust_v999"), "{}", err);
+
+    // The same module is fine if it claims to be AssemblyScript, it just
+    // fails later when we try to link it
+    assert!(ValidModule::new(&logger, &raw_module, MappingLanguage::AssemblyScript, None).is_ok());
+}
+
+#[test]
+fn rust_mapping_can_not_import_assemblyscript_host_fns() {
+    let logger = Logger::root(slog::Discard, o!());
+    let raw_module = std::fs::read(rust_wasm_file_path("assemblyscript_import.wasm")).unwrap();
+
+    let err = ValidModule::new(&logger, &raw_module, MappingLanguage::Rust, None)
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("store.get"), "{}", err);
+}
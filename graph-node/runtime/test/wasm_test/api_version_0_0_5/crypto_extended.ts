@@ -0,0 +1,38 @@
+export * from './common/global'
+
+declare namespace crypto {
+    function sha256(input: Uint8Array): Uint8Array
+    function blake2b256(input: Uint8Array): Uint8Array
+
+    namespace secp256k1 {
+        function recover(hash: Uint8Array, signature: Uint8Array): Uint8Array | null
+    }
+
+    namespace ed25519 {
+        function verify(message: Uint8Array, signature: Uint8Array, publicKey: Uint8Array): bool
+    }
+
+    namespace bls12381 {
+        function verify(message: Uint8Array, signature: Uint8Array, publicKey: Uint8Array): bool
+    }
+}
+
+export function sha256(input: Uint8Array): Uint8Array {
+    return crypto.sha256(input)
+}
+
+export function blake2b256(input: Uint8Array): Uint8Array {
+    return crypto.blake2b256(input)
+}
+
+export function secp256k1Recover(hash: Uint8Array, signature: Uint8Array): Uint8Array | null {
+    return crypto.secp256k1.recover(hash, signature)
+}
+
+export function ed25519Verify(message: Uint8Array, signature: Uint8Array, publicKey: Uint8Array): bool {
+    return crypto.ed25519.verify(message, signature, publicKey)
+}
+
+export function bls12381Verify(message: Uint8Array, signature: Uint8Array, publicKey: Uint8Array): bool {
+    return crypto.bls12381.verify(message, signature, publicKey)
+}
@@ -0,0 +1,9 @@
+;; A mapping that uses the Rust ABI, but imports a host function of the
+;; AssemblyScript ABI from the namespace of the Rust ABI
+(module
+  (import "graph_rust_v1" "store.get" (func $store_get (param i32 i32 i32)))
+
+  (memory (export "memory") 1)
+
+  (func (export "allocate") (param $size i32) (result i32)
+    (i32.const 1024)))
@@ -0,0 +1,47 @@
+;; A mapping that uses the Rust ABI, written by hand since the test suite
+;; does not need a Rust toolchain for wasm. It stores, loads and removes the
+;; `User` with id `u1` and name `john`.
+(module
+  (import "graph_rust_v1" "store_set"
+    (func $store_set (param i32 i32 i32 i32 i32 i32)))
+  (import "graph_rust_v1" "store_get"
+    (func $store_get (param i32 i32 i32 i32) (result i32)))
+  (import "graph_rust_v1" "store_remove"
+    (func $store_remove (param i32 i32 i32 i32)))
+
+  (memory (export "memory") 1)
+
+  ;; "User"
+  (data (i32.const 0) "User")
+  ;; "u1"
+  (data (i32.const 8) "u1")
+  ;; The entity `[("id", String("u1")), ("name", String("john"))]`
+  (data (i32.const 16)
+    "\02\00\00\00"
+    "\02\00\00\00" "id" "\01" "\02\00\00\00" "u1"
+    "\04\00\00\00" "name" "\01" "\04\00\00\00" "john")
+
+  ;; A bump allocator that never frees anything
+  (global $next (mut i32) (i32.const 1024))
+
+  (func (export "allocate") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $next))
+    (global.set $next (i32.add (global.get $next) (local.get $size)))
+    (local.get $ptr))
+
+  (func (export "set_user")
+    (call $store_set
+      (i32.const 0) (i32.const 4)
+      (i32.const 8) (i32.const 2)
+      (i32.const 16) (i32.const 34)))
+
+  (func (export "get_user") (result i32)
+    (call $store_get
+      (i32.const 0) (i32.const 4)
+      (i32.const 8) (i32.const 2)))
+
+  (func (export "remove_user")
+    (call $store_remove
+      (i32.const 0) (i32.const 4)
+      (i32.const 8) (i32.const 2))))
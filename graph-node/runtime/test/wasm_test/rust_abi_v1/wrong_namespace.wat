@@ -0,0 +1,9 @@
+;; A mapping that uses the Rust ABI, but imports a host function from a
+;; version of the ABI that does not exist
+(module
+  (import "graph_rust_v999" "log" (func $log (param i32 i32 i32)))
+
+  (memory (export "memory") 1)
+
+  (func (export "allocate") (param $size i32) (result i32)
+    (i32.const 1024)))
@@ -2,6 +2,7 @@ use std::{convert::TryInto, num::NonZeroU32};
 
 use graph::runtime::gas::CONST_MAX_GAS_PER_HANDLER;
 use parity_wasm::elements::Instruction;
+use sha2::{Digest, Sha256};
 use wasm_instrument::gas_metering::{MemoryGrowCost, Rules};
 
 pub const GAS_COST_STORE: u32 = 2263;
@@ -9,6 +10,22 @@ pub const GAS_COST_LOAD: u32 = 1573;
 
 pub struct GasRules;
 
+impl GasRules {
+    /// A fingerprint of the gas costs. Gas metering is compiled into
+    /// modules, and compiled modules that were instrumented with different
+    /// costs must not be reused.
+    pub fn fingerprint() -> [u8; 32] {
+        let mut digest = Sha256::new();
+        // The source covers every instruction cost and the constants above
+        digest.update(include_str!("gas_rules.rs"));
+        match GasRules.memory_grow_cost() {
+            MemoryGrowCost::Free => digest.update([0u8]),
+            MemoryGrowCost::Linear(cost) => digest.update(cost.get().to_le_bytes()),
+        }
+        digest.finalize().into()
+    }
+}
+
 impl Rules for GasRules {
     fn instruction_cost(&self, instruction: &Instruction) -> Option<u32> {
         use Instruction::*;
@@ -1,4 +1,5 @@
 use std::cmp::PartialEq;
+use std::sync::atomic::{AtomicI32, Ordering};
 use std::time::Instant;
 
 use async_trait::async_trait;
@@ -9,7 +10,8 @@ use graph::blockchain::{BlockTime, Blockchain, HostFn, RuntimeAdapter};
 use graph::components::store::{EnsLookup, SubgraphFork};
 use graph::components::subgraph::{MappingError, SharedProofOfIndexing};
 use graph::data_source::{
-    DataSource, DataSourceTemplate, MappingTrigger, TriggerData, TriggerWithHandler,
+    common::MappingLanguage, DataSource, DataSourceTemplate, MappingTrigger, TriggerData,
+    TriggerWithHandler,
 };
 use graph::futures01::Sink as _;
 use graph::futures03::compat::Future01CompatExt;
@@ -19,6 +21,7 @@ use graph::prelude::{
 
 use crate::mapping::{MappingContext, WasmRequest};
 use crate::module::ToAscPtr;
+use crate::rust_abi::ToRustTrigger;
 use crate::{host_exports::HostExports, module::ExperimentalFeatures};
 use graph::runtime::gas::Gas;
 
@@ -56,13 +59,14 @@ impl<C: Blockchain> RuntimeHostBuilder<C> {
 
 impl<C: Blockchain> RuntimeHostBuilderTrait<C> for RuntimeHostBuilder<C>
 where
-    <C as Blockchain>::MappingTrigger: ToAscPtr,
+    <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
 {
     type Host = RuntimeHost<C>;
     type Req = WasmRequest<C>;
 
     fn spawn_mapping(
         raw_module: &[u8],
+        language: MappingLanguage,
         logger: Logger,
         subgraph_id: DeploymentHash,
         metrics: Arc<HostMetrics>,
@@ -72,6 +76,7 @@ where
         };
         crate::mapping::spawn_module(
             raw_module,
+            language,
             logger,
             subgraph_id,
             metrics,
@@ -104,12 +109,18 @@ where
     }
 }
 
+/// Marks an onchain data source that has not been removed
+const NOT_REMOVED: BlockNumber = -1;
+
 pub struct RuntimeHost<C: Blockchain> {
     host_fns: Arc<Vec<HostFn>>,
     data_source: DataSource<C>,
     mapping_request_sender: Sender<WasmRequest<C>>,
     host_exports: Arc<HostExports>,
     metrics: Arc<HostMetrics>,
+    /// The block at which a mapping removed this onchain data source, or
+    /// `NOT_REMOVED`
+    removed_at: AtomicI32,
 }
 
 impl<C> RuntimeHost<C>
@@ -150,6 +161,7 @@ where
             mapping_request_sender,
             host_exports,
             metrics,
+            removed_at: AtomicI32::new(NOT_REMOVED),
         })
     }
 
@@ -357,10 +369,14 @@ impl<C: Blockchain> RuntimeHostTrait<C> for RuntimeHost<C> {
     }
 
     /// Offchain data sources track done_at which is set once the
-    /// trigger has been processed.
+    /// trigger has been processed. Onchain data sources are done once a
+    /// mapping removes them.
     fn done_at(&self) -> Option<BlockNumber> {
         match self.data_source() {
-            DataSource::Onchain(_) => None,
+            DataSource::Onchain(_) => match self.removed_at.load(Ordering::SeqCst) {
+                NOT_REMOVED => None,
+                block => Some(block),
+            },
             DataSource::Offchain(ds) => ds.done_at(),
             DataSource::Subgraph(_) => None,
         }
@@ -368,7 +384,9 @@ impl<C: Blockchain> RuntimeHostTrait<C> for RuntimeHost<C> {
 
     fn set_done_at(&self, block: Option<BlockNumber>) {
         match self.data_source() {
-            DataSource::Onchain(_) => {}
+            DataSource::Onchain(_) => self
+                .removed_at
+                .store(block.unwrap_or(NOT_REMOVED), Ordering::SeqCst),
             DataSource::Offchain(ds) => ds.set_done_at(block),
             DataSource::Subgraph(_) => {}
         }
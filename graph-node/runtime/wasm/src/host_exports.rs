@@ -1,27 +1,31 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::str::FromStr;
 use std::time::{Duration, Instant};
 
 use graph::data::subgraph::API_VERSION_0_0_8;
 use graph::data::value::Word;
 
+use blake2::{digest::consts::U32, Blake2b, Digest};
 use graph::futures03::stream::StreamExt;
 use graph::schema::EntityType;
 use never::Never;
+use secp256k1::{Secp256k1, VerifyOnly};
 use semver::Version;
-use web3::types::H160;
+use sha2::Sha256;
+use web3::types::{H160, U256};
 
 use graph::blockchain::BlockTime;
 use graph::blockchain::Blockchain;
-use graph::components::store::{EnsLookup, GetScope, LoadRelatedRequest};
+use graph::components::store::{EnsLookup, FindByQuery, GetScope, LoadRelatedRequest};
 use graph::components::subgraph::{
-    InstanceDSTemplate, PoICausalityRegion, ProofOfIndexingEvent, SharedProofOfIndexing,
+    InstanceDSTemplate, PoICausalityRegion, ProofOfIndexingEvent, RemovedDataSource,
+    SharedProofOfIndexing,
 };
-use graph::data::store::{self};
+use graph::data::store::{self, scalar::RoundingMode};
 use graph::data_source::{CausalityRegion, DataSource, EntityTypeAccess};
 use graph::ensure;
 use graph::prelude::ethabi::param_type::Reader;
-use graph::prelude::ethabi::{decode, encode, Token};
+use graph::prelude::ethabi::{decode, encode, ParamType, Token};
 use graph::prelude::serde_json;
 use graph::prelude::{slog::b, slog::record_static, *};
 use graph::runtime::gas::{self, complexity, Gas, GasCounter};
@@ -32,6 +36,13 @@ use crate::{error::DeterminismLevel, module::IntoTrap};
 
 use super::module::WasmInstanceData;
 
+type Blake2b256 = Blake2b<U32>;
+
+lazy_static! {
+    /// Creating a secp256k1 context is expensive, so we share one
+    static ref SECP256K1: Secp256k1<VerifyOnly> = Secp256k1::verification_only();
+}
+
 impl IntoTrap for HostExportError {
     fn determinism_level(&self) -> DeterminismLevel {
         match self {
@@ -64,6 +75,8 @@ pub struct DataSourceDetails {
     pub entity_type_access: EntityTypeAccess,
     pub templates: Arc<Vec<InstanceDSTemplate>>,
     pub causality_region: CausalityRegion,
+    /// Whether the data source was created from a template
+    pub is_dynamic: bool,
 }
 
 impl DataSourceDetails {
@@ -79,6 +92,7 @@ impl DataSourceDetails {
             entity_type_access: ds.entities(),
             templates,
             causality_region: ds.causality_region(),
+            is_dynamic: ds.creation_block().is_some(),
         }
     }
 }
@@ -414,6 +428,56 @@ impl HostExports {
         Ok(result)
     }
 
+    /// Look up an entity as it was at the end of `block`, which must lie
+    /// before the block that is currently being processed. Reading at a
+    /// block whose history has been pruned is a deterministic error
+    pub(crate) fn store_get_at(
+        &self,
+        state: &mut BlockState,
+        entity_type: String,
+        entity_id: String,
+        block: BlockNumber,
+        current_block: BlockNumber,
+        gas: &GasCounter,
+    ) -> Result<Option<Entity>, HostExportError> {
+        let entity_type = state.entity_cache.schema.entity_type(&entity_type)?;
+        Self::expect_object_type(&entity_type, "get")?;
+
+        let store_key = entity_type.parse_key_in(entity_id, self.data_source.causality_region)?;
+        self.check_entity_type_access(&store_key.entity_type)?;
+
+        if block < 0 || block >= current_block {
+            return Err(HostExportError::Deterministic(anyhow!(
+                "store.getAt: block {} must be before the current block {}",
+                block,
+                current_block
+            )));
+        }
+
+        let result = state
+            .entity_cache
+            .get_at(&store_key, block)
+            .map_err(|e| match e {
+                StoreError::HistoryPruned(..) => {
+                    HostExportError::Deterministic(anyhow!("store.getAt: {}", e))
+                }
+                e => HostExportError::Unknown(e.into()),
+            })?;
+
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::STORE_GET.with_args(complexity::Linear, (&store_key, result.as_ref())),
+            "store_get_at",
+        )?;
+
+        if let Some(ref entity) = result {
+            state.metrics.track_entity_read(&entity_type, entity)
+        }
+
+        Ok(result)
+    }
+
     pub(crate) fn store_load_related(
         &self,
         state: &mut BlockState,
@@ -446,6 +510,51 @@ impl HostExports {
         Ok(result)
     }
 
+    pub(crate) fn store_find_by(
+        &self,
+        state: &mut BlockState,
+        entity_type: String,
+        attribute: String,
+        value: Value,
+        limit: i32,
+        gas: &GasCounter,
+    ) -> Result<Vec<Entity>, HostExportError> {
+        let entity_type = state.entity_cache.schema.entity_type(&entity_type)?;
+        Self::expect_object_type(&entity_type, "findBy")?;
+        self.check_entity_type_access(&entity_type)?;
+
+        let limit = usize::try_from(limit).map_err(|_| {
+            HostExportError::Deterministic(anyhow!(
+                "findBy: the limit must not be negative but is {}",
+                limit
+            ))
+        })?;
+        let query = FindByQuery {
+            entity_type: entity_type.cheap_clone(),
+            attribute: attribute.into(),
+            value,
+            causality_region: self.data_source.causality_region,
+            limit,
+        };
+        query.validate().map_err(HostExportError::Deterministic)?;
+
+        let result = state
+            .entity_cache
+            .find_by(&query)
+            .map_err(HostExportError::Unknown)?;
+
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::STORE_GET.with_args(complexity::Linear, (&query, &result)),
+            "store_find_by",
+        )?;
+
+        state.metrics.track_entity_read_batch(&entity_type, &result);
+
+        Ok(result)
+    }
+
     /// Prints the module of `n` in hex.
     /// Integers are encoded using the least amount of digits (no leading zero digits).
     /// Their encoding may be of uneven length. The number zero encodes as "0x0".
@@ -654,6 +763,138 @@ impl HostExports {
         Ok(tiny_keccak::keccak256(data))
     }
 
+    pub(crate) fn crypto_sha_256(
+        &self,
+        input: Vec<u8>,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<[u8; 32], DeterministicHostError> {
+        let data = &input[..];
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::SHA256.with_args(complexity::Size, data),
+            "crypto_sha_256",
+        )?;
+        Ok(Sha256::digest(data).into())
+    }
+
+    pub(crate) fn crypto_blake2b_256(
+        &self,
+        input: Vec<u8>,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<[u8; 32], DeterministicHostError> {
+        let data = &input[..];
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BLAKE2B256.with_args(complexity::Size, data),
+            "crypto_blake2b_256",
+        )?;
+        Ok(Blake2b256::digest(data).into())
+    }
+
+    /// Recover the uncompressed public key that signed `hash`. The
+    /// `signature` is 65 bytes `r || s || v` where `v` is either 0/1 or
+    /// 27/28 as in Ethereum. Returns `None` if the input is malformed or no
+    /// key can be recovered; since the input usually comes from untrusted
+    /// data, that must not fail the subgraph
+    pub(crate) fn crypto_secp256k1_recover(
+        &self,
+        hash: Vec<u8>,
+        signature: Vec<u8>,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<Option<[u8; 65]>, DeterministicHostError> {
+        use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::SECP256K1_RECOVER,
+            "crypto_secp256k1_recover",
+        )?;
+
+        if signature.len() != 65 {
+            return Ok(None);
+        }
+        let v = match signature[64] {
+            v @ (0 | 1) => v,
+            v @ (27 | 28) => v - 27,
+            _ => return Ok(None),
+        };
+        let key = secp256k1::Message::from_slice(&hash)
+            .and_then(|msg| {
+                let id = RecoveryId::from_i32(v as i32)?;
+                let sig = RecoverableSignature::from_compact(&signature[..64], id)?;
+                SECP256K1.recover_ecdsa(&msg, &sig)
+            })
+            .ok();
+        Ok(key.map(|key| key.serialize_uncompressed()))
+    }
+
+    /// Verify an ed25519 signature. We use strict verification, which
+    /// rejects malleable signatures and weak keys, so that the result
+    /// does not depend on the quirks of a particular implementation
+    pub(crate) fn crypto_ed25519_verify(
+        &self,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+        public_key: Vec<u8>,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<bool, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::ED25519_VERIFY.with_args(complexity::Size, message.as_slice()),
+            "crypto_ed25519_verify",
+        )?;
+
+        let (Ok(signature), Ok(public_key)) = (
+            <[u8; 64]>::try_from(signature.as_slice()),
+            <[u8; 32]>::try_from(public_key.as_slice()),
+        ) else {
+            return Ok(false);
+        };
+        let signature = ed25519_dalek::Signature::from_bytes(&signature);
+        Ok(ed25519_dalek::VerifyingKey::from_bytes(&public_key)
+            .and_then(|key| key.verify_strict(&message, &signature))
+            .is_ok())
+    }
+
+    /// Verify a BLS12-381 signature with 48 byte public keys in G1 and 96
+    /// byte signatures in G2, using the same ciphersuite as Filecoin
+    pub(crate) fn crypto_bls12381_verify(
+        &self,
+        message: Vec<u8>,
+        signature: Vec<u8>,
+        public_key: Vec<u8>,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<bool, DeterministicHostError> {
+        use blst::min_pk::{PublicKey, Signature};
+
+        const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BLS12381_VERIFY.with_args(complexity::Size, message.as_slice()),
+            "crypto_bls12381_verify",
+        )?;
+
+        let (Ok(signature), Ok(public_key)) = (
+            Signature::from_bytes(&signature),
+            PublicKey::from_bytes(&public_key),
+        ) else {
+            return Ok(false);
+        };
+        let result = signature.verify(true, &message, DST, &[], &public_key, true);
+        Ok(result == blst::BLST_ERROR::BLST_SUCCESS)
+    }
+
     pub(crate) fn big_int_plus(
         &self,
         x: BigInt,
@@ -764,6 +1005,57 @@ impl HostExports {
         Ok(x.pow(exp)?)
     }
 
+    pub(crate) fn big_int_sqrt(
+        &self,
+        x: BigInt,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<BigInt, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BIG_MATH_GAS_OP.with_args(complexity::Mul, (&x, &x)),
+            "big_int_sqrt",
+        )?;
+        Ok(x.sqrt()?)
+    }
+
+    /// Each squaring and multiplication works on numbers smaller than the
+    /// modulus, and there are at most two of them per bit of the exponent.
+    pub(crate) fn big_int_mod_pow(
+        &self,
+        x: BigInt,
+        exp: BigInt,
+        modulus: BigInt,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<BigInt, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BIG_MATH_GAS_OP.with_args(complexity::Mul, (&modulus, &modulus))
+                * (2 * exp.bits() + 1),
+            "big_int_mod_pow",
+        )?;
+        Ok(x.mod_pow(exp, modulus)?)
+    }
+
+    pub(crate) fn big_int_gcd(
+        &self,
+        x: BigInt,
+        y: BigInt,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<BigInt, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BIG_MATH_GAS_OP.with_args(complexity::Mul, (&x, &y)),
+            "big_int_gcd",
+        )?;
+        Ok(x.gcd(y))
+    }
+
     pub(crate) fn big_int_from_string(
         &self,
         s: String,
@@ -948,6 +1240,123 @@ impl HostExports {
         Ok(x == y)
     }
 
+    /// Exponentiation by squaring takes at most two multiplications per
+    /// bit of the exponent.
+    pub(crate) fn big_decimal_pow(
+        &self,
+        x: BigDecimal,
+        exp: i32,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<BigDecimal, DeterministicHostError> {
+        let exp_bits = u32::BITS - exp.unsigned_abs().leading_zeros();
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BIG_MATH_GAS_OP.with_args(complexity::Mul, (&x, &x)) * (2 * exp_bits as u64 + 1),
+            "big_decimal_pow",
+        )?;
+        Ok(x.pow(exp)?)
+    }
+
+    pub(crate) fn big_decimal_sqrt(
+        &self,
+        x: BigDecimal,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<BigDecimal, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BIG_MATH_GAS_OP.with_args(complexity::Mul, (&x, &x)),
+            "big_decimal_sqrt",
+        )?;
+        Ok(x.sqrt()?)
+    }
+
+    /// `mode` is the discriminant of a `RoundingMode`.
+    pub(crate) fn big_decimal_round(
+        &self,
+        x: BigDecimal,
+        scale: i32,
+        mode: i32,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<BigDecimal, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BIG_MATH_GAS_OP.with_args(complexity::Size, &x),
+            "big_decimal_round",
+        )?;
+        let scale = Self::big_decimal_scale(scale)?;
+        let mode = RoundingMode::try_from(mode)?;
+        Ok(x.round(scale, mode))
+    }
+
+    pub(crate) fn big_decimal_truncate(
+        &self,
+        x: BigDecimal,
+        scale: i32,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<BigDecimal, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BIG_MATH_GAS_OP.with_args(complexity::Size, &x),
+            "big_decimal_truncate",
+        )?;
+        let scale = Self::big_decimal_scale(scale)?;
+        Ok(x.truncate(scale))
+    }
+
+    /// Check that rounding to `scale` digits after the decimal point
+    /// produces a `BigDecimal` with an exponent in the allowed range
+    fn big_decimal_scale(scale: i32) -> Result<i64, DeterministicHostError> {
+        if scale < -BigDecimal::MAX_EXP || scale > -BigDecimal::MIN_EXP {
+            return Err(DeterministicHostError::from(anyhow!(
+                "the scale {} is outside the range from {} to {}",
+                scale,
+                -BigDecimal::MAX_EXP,
+                -BigDecimal::MIN_EXP
+            )));
+        }
+        Ok(scale as i64)
+    }
+
+    pub(crate) fn big_decimal_min(
+        &self,
+        x: BigDecimal,
+        y: BigDecimal,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<BigDecimal, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BIG_MATH_GAS_OP.with_args(complexity::Min, (&x, &y)),
+            "big_decimal_min",
+        )?;
+        Ok(x.min(y))
+    }
+
+    pub(crate) fn big_decimal_max(
+        &self,
+        x: BigDecimal,
+        y: BigDecimal,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<BigDecimal, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::BIG_MATH_GAS_OP.with_args(complexity::Min, (&x, &y)),
+            "big_decimal_max",
+        )?;
+        Ok(x.max(y))
+    }
+
     pub(crate) fn big_decimal_to_string(
         &self,
         x: BigDecimal,
@@ -1033,6 +1442,93 @@ impl HostExports {
         Ok(())
     }
 
+    pub(crate) fn data_source_remove(
+        &self,
+        logger: &Logger,
+        state: &mut BlockState,
+        gas: &GasCounter,
+    ) -> Result<(), HostExportError> {
+        Self::track_gas_and_ops(gas, state, gas::CREATE_DATA_SOURCE, "data_source_remove")?;
+
+        if !self.data_source.is_dynamic
+            || self.data_source.causality_region != CausalityRegion::ONCHAIN
+        {
+            return Err(HostExportError::Deterministic(anyhow!(
+                "Failed to remove data source `{}`: only onchain data sources \
+                 created from a template can be removed",
+                self.data_source.name
+            )));
+        }
+
+        info!(
+            logger,
+            "Remove data source";
+            "name" => &self.data_source.name,
+            "address" => ::hex::encode(&self.data_source.address)
+        );
+
+        state.push_removed_data_source(RemovedDataSource::Current {
+            name: self.data_source.name.clone(),
+            address: self.data_source.address.clone(),
+        });
+
+        Ok(())
+    }
+
+    pub(crate) fn data_source_remove_template(
+        &self,
+        logger: &Logger,
+        state: &mut BlockState,
+        name: String,
+        params: Vec<String>,
+        creation_block: BlockNumber,
+        gas: &GasCounter,
+    ) -> Result<(), HostExportError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::CREATE_DATA_SOURCE,
+            "data_source_remove_template",
+        )?;
+        info!(
+            logger,
+            "Remove data source";
+            "name" => &name,
+            "params" => format!("{}", params.join(","))
+        );
+
+        let template = self
+            .data_source
+            .templates
+            .iter()
+            .find(|template| template.name().eq(&name))
+            .with_context(|| {
+                format!(
+                    "Failed to remove data source from name `{}`: \
+                     No template with this name in parent data source `{}`.",
+                    name, self.data_source.name,
+                )
+            })
+            .map_err(DeterministicHostError::from)?
+            .clone();
+        if !template.is_onchain() {
+            return Err(HostExportError::Deterministic(anyhow!(
+                "Failed to remove data source from name `{}`: \
+                 only data sources from onchain templates can be removed",
+                name
+            )));
+        }
+
+        state.push_removed_data_source(RemovedDataSource::Template(InstanceDSTemplateInfo {
+            template,
+            params,
+            context: None,
+            creation_block,
+        }));
+
+        Ok(())
+    }
+
     pub(crate) fn ens_name_by_hash(
         &self,
         hash: &str,
@@ -1222,6 +1718,67 @@ impl HostExports {
             .context("Failed to decode")
     }
 
+    pub(crate) fn ethereum_encode_packed(
+        &self,
+        types: String,
+        tokens: Vec<Token>,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<Vec<u8>, DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::DEFAULT_GAS_OP.with_args(complexity::Size, &types),
+            "ethereum_encode_packed",
+        )?;
+
+        // The types are given like for `ethereum.decode`, e.g.,
+        // `(uint8,address)`; a single type does not need parentheses
+        let types = match Reader::read(&types)
+            .map_err(|e| anyhow!("encodePacked: failed to read types: {}", e))?
+        {
+            ParamType::Tuple(types) => types,
+            kind => vec![kind],
+        };
+        let encoded = encode_packed(&types, &tokens)?;
+
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::DEFAULT_GAS_OP.with_args(complexity::Size, &encoded),
+            "ethereum_encode_packed",
+        )?;
+
+        Ok(encoded)
+    }
+
+    pub(crate) fn ethereum_hash_typed_data(
+        &self,
+        domain: Token,
+        types: String,
+        message: Token,
+        gas: &GasCounter,
+        state: &mut BlockState,
+    ) -> Result<[u8; 32], DeterministicHostError> {
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::DEFAULT_GAS_OP.with_args(complexity::Size, &types),
+            "ethereum_hash_typed_data",
+        )?;
+
+        let (hash, hashed) = hash_typed_data(domain, &types, message)?;
+
+        Self::track_gas_and_ops(
+            gas,
+            state,
+            gas::TYPED_DATA_HASH_BYTE * hashed,
+            "ethereum_hash_typed_data",
+        )?;
+
+        Ok(hash)
+    }
+
     pub(crate) fn yaml_from_bytes(
         &self,
         bytes: &[u8],
@@ -1280,6 +1837,318 @@ fn bytes_to_string(logger: &Logger, bytes: Vec<u8>) -> String {
     s.trim_end_matches('\u{0000}').to_string()
 }
 
+/// Encode `tokens` like Solidity's `abi.encodePacked` does for arguments
+/// of the given `types`. Values take up as many bytes as their type
+/// declares, e.g., one byte for a `uint8`, and are not padded, except for
+/// the elements of arrays, which are padded to 32 bytes
+fn encode_packed(types: &[ParamType], tokens: &[Token]) -> Result<Vec<u8>, DeterministicHostError> {
+    if types.len() != tokens.len() {
+        return Err(DeterministicHostError::from(anyhow!(
+            "encodePacked: got {} types but {} values",
+            types.len(),
+            tokens.len()
+        )));
+    }
+
+    let mut out = Vec::new();
+    for (kind, token) in types.iter().zip(tokens) {
+        match (kind, token) {
+            (ParamType::String, Token::String(s)) => out.extend_from_slice(s.as_bytes()),
+            (ParamType::Bytes, Token::Bytes(bytes)) => out.extend_from_slice(bytes),
+            (ParamType::Array(elem_kind), Token::Array(elems)) => {
+                encode_packed_elems(elem_kind, elems, &mut out)?
+            }
+            (ParamType::FixedArray(elem_kind, len), Token::FixedArray(elems))
+                if elems.len() == *len =>
+            {
+                encode_packed_elems(elem_kind, elems, &mut out)?
+            }
+            (kind, token) => {
+                check_packed(kind, token)?;
+                match (kind, token) {
+                    (
+                        ParamType::Int(size) | ParamType::Uint(size),
+                        Token::Int(n) | Token::Uint(n),
+                    ) => {
+                        let word = encode(&[Token::Uint(*n)]);
+                        out.extend_from_slice(&word[32 - size / 8..]);
+                    }
+                    (_, Token::Address(address)) => out.extend_from_slice(address.as_bytes()),
+                    (_, Token::FixedBytes(bytes)) => out.extend_from_slice(bytes),
+                    (_, Token::Bool(b)) => out.push(*b as u8),
+                    // `check_packed` only accepts the tokens above
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Encode the elements of an array; each element is padded to 32 bytes
+fn encode_packed_elems(
+    kind: &ParamType,
+    elems: &[Token],
+    out: &mut Vec<u8>,
+) -> Result<(), DeterministicHostError> {
+    for elem in elems {
+        check_packed(kind, elem).map_err(|_| {
+            DeterministicHostError::from(anyhow!(
+                "encodePacked: arrays of `{}` can not be encoded from `{}`",
+                kind,
+                elem
+            ))
+        })?;
+        out.extend(encode(&[elem.clone()]));
+    }
+    Ok(())
+}
+
+/// Check that `token` is a value of the static type `kind`. Integers must
+/// fit into the size of `kind`
+fn check_packed(kind: &ParamType, token: &Token) -> Result<(), DeterministicHostError> {
+    let valid_size = |size: usize| size > 0 && size <= 256 && size % 8 == 0;
+    let ok = match (kind, token) {
+        (ParamType::Address, Token::Address(_)) | (ParamType::Bool, Token::Bool(_)) => true,
+        (ParamType::FixedBytes(size), Token::FixedBytes(bytes)) => bytes.len() == *size,
+        (ParamType::Uint(size), Token::Uint(n)) => valid_size(*size) && n.bits() <= *size,
+        (ParamType::Int(size), Token::Int(n)) if valid_size(*size) => {
+            // `n` is in two's complement and fits if all bits above the
+            // sign bit of `size` are copies of it
+            let high = *n >> (*size - 1);
+            high.is_zero() || high == U256::MAX >> (*size - 1)
+        }
+        _ => false,
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(DeterministicHostError::from(anyhow!(
+            "encodePacked: `{}` is not a valid `{}`",
+            token,
+            kind
+        )))
+    }
+}
+
+/// A field of a struct in EIP-712 typed data
+struct TypedDataField {
+    name: String,
+    kind: String,
+}
+
+const EIP712_DOMAIN: &str = "EIP712Domain";
+
+/// Compute the EIP-712 digest that is signed for `message` in `domain`.
+/// `types` are the struct definitions in JSON, in the same format as for
+/// `eth_signTypedData_v4`, and must include `EIP712Domain`. The primary
+/// type of `message` is the one struct that no other struct refers to.
+/// `domain` and `message` are tuples whose components are in the order of
+/// the fields of their type.
+///
+/// Returns the digest and the number of bytes that were hashed to compute
+/// it
+fn hash_typed_data(
+    domain: Token,
+    types: &str,
+    message: Token,
+) -> Result<([u8; 32], usize), DeterministicHostError> {
+    let types: BTreeMap<String, Vec<BTreeMap<String, String>>> =
+        serde_json::from_str(types).map_err(|e| anyhow!("hashTypedData: invalid types: {}", e))?;
+    let types = types
+        .into_iter()
+        .map(|(name, fields)| {
+            let fields = fields
+                .into_iter()
+                .map(
+                    |mut field| match (field.remove("name"), field.remove("type")) {
+                        (Some(name), Some(kind)) => Ok(TypedDataField { name, kind }),
+                        _ => Err(anyhow!(
+                            "hashTypedData: the fields of `{}` must have a name and a type",
+                            name
+                        )),
+                    },
+                )
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok((name, fields))
+        })
+        .collect::<Result<BTreeMap<_, _>, anyhow::Error>>()?;
+    if !types.contains_key(EIP712_DOMAIN) {
+        return Err(DeterministicHostError::from(anyhow!(
+            "hashTypedData: the types do not define `{}`",
+            EIP712_DOMAIN
+        )));
+    }
+
+    let referenced: HashSet<&str> = types
+        .values()
+        .flatten()
+        .map(|field| base_type(&field.kind))
+        .collect();
+    let primary: Vec<_> = types
+        .keys()
+        .filter(|name| *name != EIP712_DOMAIN && !referenced.contains(name.as_str()))
+        .collect();
+    let primary = match primary.as_slice() {
+        [primary] => primary.to_string(),
+        _ => {
+            return Err(DeterministicHostError::from(anyhow!(
+                "hashTypedData: the types must have exactly one primary type but have {}",
+                primary.len()
+            )))
+        }
+    };
+
+    let mut hasher = TypedDataHasher { types, hashed: 0 };
+    let mut data = vec![0x19, 0x01];
+    data.extend(hasher.hash_struct(EIP712_DOMAIN, domain)?);
+    data.extend(hasher.hash_struct(&primary, message)?);
+    Ok((hasher.keccak(&data), hasher.hashed))
+}
+
+/// The type `kind` without any array suffixes
+fn base_type(kind: &str) -> &str {
+    kind.split('[').next().unwrap_or(kind)
+}
+
+struct TypedDataHasher {
+    types: BTreeMap<String, Vec<TypedDataField>>,
+    /// The number of bytes we hashed so far
+    hashed: usize,
+}
+
+impl TypedDataHasher {
+    fn keccak(&mut self, data: &[u8]) -> [u8; 32] {
+        self.hashed += data.len();
+        tiny_keccak::keccak256(data)
+    }
+
+    fn fields(&self, name: &str) -> Result<&Vec<TypedDataField>, DeterministicHostError> {
+        self.types.get(name).ok_or_else(|| {
+            DeterministicHostError::from(anyhow!("hashTypedData: unknown type `{}`", name))
+        })
+    }
+
+    /// The `encodeType` of struct `name`: its own definition followed by
+    /// the definitions of all structs it refers to, sorted by name
+    fn encode_type(&self, name: &str) -> Result<String, DeterministicHostError> {
+        let mut deps = BTreeSet::new();
+        let mut queue = vec![name];
+        while let Some(next) = queue.pop() {
+            for field in self.fields(next)? {
+                let dep = base_type(&field.kind);
+                if dep != name && self.types.contains_key(dep) && deps.insert(dep) {
+                    queue.push(dep);
+                }
+            }
+        }
+
+        let mut encoded = String::new();
+        for name in std::iter::once(name).chain(deps) {
+            let fields = self
+                .fields(name)?
+                .iter()
+                .map(|field| format!("{} {}", field.kind, field.name))
+                .collect::<Vec<_>>();
+            encoded.push_str(&format!("{}({})", name, fields.join(",")));
+        }
+        Ok(encoded)
+    }
+
+    fn hash_struct(
+        &mut self,
+        name: &str,
+        value: Token,
+    ) -> Result<[u8; 32], DeterministicHostError> {
+        let values = match value {
+            Token::Tuple(values) => values,
+            value => {
+                return Err(DeterministicHostError::from(anyhow!(
+                    "hashTypedData: expected a tuple for `{}` but got `{}`",
+                    name,
+                    value
+                )))
+            }
+        };
+        let kinds: Vec<_> = self
+            .fields(name)?
+            .iter()
+            .map(|field| field.kind.clone())
+            .collect();
+        if kinds.len() != values.len() {
+            return Err(DeterministicHostError::from(anyhow!(
+                "hashTypedData: `{}` has {} fields but the tuple has {} components",
+                name,
+                kinds.len(),
+                values.len()
+            )));
+        }
+
+        let type_hash = self.encode_type(name)?;
+        let mut data = self.keccak(type_hash.as_bytes()).to_vec();
+        for (kind, value) in kinds.iter().zip(values) {
+            data.extend(self.encode_value(kind, value)?);
+        }
+        Ok(self.keccak(&data))
+    }
+
+    /// Encode one field of a struct as a 32 byte word
+    fn encode_value(
+        &mut self,
+        kind: &str,
+        value: Token,
+    ) -> Result<[u8; 32], DeterministicHostError> {
+        if let Some(elem_kind) = kind
+            .strip_suffix(']')
+            .and_then(|kind| kind.rsplit_once('['))
+            .map(|(elem_kind, _)| elem_kind)
+        {
+            let elems = match value {
+                Token::Array(elems) | Token::FixedArray(elems) => elems,
+                value => return Err(mismatch(kind, &value)),
+            };
+            let mut data = Vec::with_capacity(elems.len() * 32);
+            for elem in elems {
+                data.extend(self.encode_value(elem_kind, elem)?);
+            }
+            return Ok(self.keccak(&data));
+        }
+
+        if self.types.contains_key(kind) {
+            return self.hash_struct(kind, value);
+        }
+
+        let matches = match (&value, kind) {
+            (Token::String(s), "string") => return Ok(self.keccak(s.as_bytes())),
+            (Token::Bytes(b), "bytes") => return Ok(self.keccak(b)),
+            (Token::Address(_), "address") | (Token::Bool(_), "bool") => true,
+            (Token::Uint(_), kind) => kind.starts_with("uint"),
+            (Token::Int(_), kind) => kind.starts_with("int"),
+            (Token::FixedBytes(b), kind) => {
+                kind.strip_prefix("bytes")
+                    .and_then(|size| size.parse::<usize>().ok())
+                    == Some(b.len())
+            }
+            _ => false,
+        };
+        if !matches {
+            return Err(mismatch(kind, &value));
+        }
+
+        let mut word = [0u8; 32];
+        word.copy_from_slice(&encode(&[value]));
+        Ok(word)
+    }
+}
+
+fn mismatch(kind: &str, value: &Token) -> DeterministicHostError {
+    DeterministicHostError::from(anyhow!(
+        "hashTypedData: `{}` is not a valid `{}`",
+        value,
+        kind
+    ))
+}
+
 /// Expose some host functions for testing only
 #[cfg(debug_assertions)]
 pub mod test_support {
@@ -1348,6 +2217,32 @@ pub mod test_support {
             self.host_exports
                 .store_get(state, entity_type, entity_id, gas, GetScope::Store)
         }
+
+        pub fn store_find_by(
+            &self,
+            state: &mut BlockState,
+            entity_type: String,
+            attribute: String,
+            value: Value,
+            limit: i32,
+            gas: &GasCounter,
+        ) -> Result<Vec<Entity>, HostExportError> {
+            self.host_exports
+                .store_find_by(state, entity_type, attribute, value, limit, gas)
+        }
+
+        pub fn store_get_at(
+            &self,
+            state: &mut BlockState,
+            entity_type: String,
+            entity_id: String,
+            block: BlockNumber,
+            current_block: BlockNumber,
+            gas: &GasCounter,
+        ) -> Result<Option<Entity>, HostExportError> {
+            self.host_exports
+                .store_get_at(state, entity_type, entity_id, block, current_block, gas)
+        }
     }
 }
 #[test]
@@ -1379,3 +2274,127 @@ fn bytes_to_string_is_lossy() {
         )
     )
 }
+
+#[test]
+fn encode_packed_does_not_pad() {
+    let address = H160::from_str("A16081F360e3847006dB660bae1c6d1b2e17eC2A").unwrap();
+    let types = vec![
+        ParamType::Address,
+        ParamType::Bool,
+        ParamType::String,
+        ParamType::FixedBytes(2),
+        ParamType::Uint(256),
+        ParamType::Array(Box::new(ParamType::Uint(8))),
+    ];
+    let tokens = vec![
+        Token::Address(address),
+        Token::Bool(true),
+        Token::String("ab".to_string()),
+        Token::FixedBytes(vec![0xff, 0xee]),
+        Token::Uint(1.into()),
+        Token::Array(vec![Token::Uint(2.into()), Token::Uint(0.into())]),
+    ];
+    let packed = encode_packed(&types, &tokens).unwrap();
+
+    let mut expected = address.as_bytes().to_vec();
+    expected.extend([0x01, b'a', b'b', 0xff, 0xee]);
+    expected.extend([0; 31]);
+    expected.push(0x01);
+    expected.extend([0; 31]);
+    expected.push(0x02);
+    expected.extend([0; 32]);
+    assert_eq!(expected, packed);
+
+    assert!(encode_packed(&[ParamType::Tuple(vec![])], &[Token::Tuple(vec![])]).is_err());
+    assert!(encode_packed(
+        &[ParamType::Array(Box::new(ParamType::String))],
+        &[Token::Array(vec![Token::String("a".to_string())])]
+    )
+    .is_err());
+    assert!(encode_packed(&[ParamType::Bool], &[]).is_err());
+    assert!(encode_packed(&[ParamType::Bool], &[Token::Uint(1.into())]).is_err());
+}
+
+#[test]
+fn encode_packed_uses_declared_int_sizes() {
+    let minus_one = Token::Int(U256::MAX);
+    let types = vec![
+        ParamType::Uint(8),
+        ParamType::Uint(16),
+        ParamType::Int(8),
+        ParamType::Int(32),
+        ParamType::Array(Box::new(ParamType::Int(8))),
+    ];
+    let tokens = vec![
+        Token::Uint(0xab.into()),
+        Token::Uint(0x0102.into()),
+        minus_one.clone(),
+        Token::Int(5.into()),
+        Token::Array(vec![minus_one.clone()]),
+    ];
+    let packed = encode_packed(&types, &tokens).unwrap();
+
+    // Matches `abi.encodePacked(uint8(0xab), uint16(0x0102), int8(-1),
+    // int32(5), [int8(-1)])`
+    let mut expected = vec![0xab, 0x01, 0x02, 0xff, 0x00, 0x00, 0x00, 0x05];
+    expected.extend([0xff; 32]);
+    assert_eq!(expected, packed);
+
+    // Values that do not fit into their type are rejected
+    assert!(encode_packed(&[ParamType::Uint(8)], &[Token::Uint(256.into())]).is_err());
+    assert!(encode_packed(&[ParamType::Int(8)], &[Token::Int(128.into())]).is_err());
+    assert!(encode_packed(&[ParamType::Int(8)], &[Token::Int(U256::MAX - 128)]).is_err());
+    assert!(encode_packed(&[ParamType::Int(8)], &[Token::Int(U256::MAX - 127)]).is_ok());
+    assert!(encode_packed(&[ParamType::Uint(7)], &[Token::Uint(1.into())]).is_err());
+}
+
+#[test]
+fn hash_typed_data_matches_eip712_example() {
+    // The example from the EIP-712 specification
+    let types = r#"{
+        "EIP712Domain": [
+            { "name": "name", "type": "string" },
+            { "name": "version", "type": "string" },
+            { "name": "chainId", "type": "uint256" },
+            { "name": "verifyingContract", "type": "address" }
+        ],
+        "Person": [
+            { "name": "name", "type": "string" },
+            { "name": "wallet", "type": "address" }
+        ],
+        "Mail": [
+            { "name": "from", "type": "Person" },
+            { "name": "to", "type": "Person" },
+            { "name": "contents", "type": "string" }
+        ]
+    }"#;
+    let address = |s: &str| Token::Address(H160::from_str(s).unwrap());
+    let person = |name: &str, wallet: &str| {
+        Token::Tuple(vec![Token::String(name.to_string()), address(wallet)])
+    };
+    let domain = Token::Tuple(vec![
+        Token::String("Ether Mail".to_string()),
+        Token::String("1".to_string()),
+        Token::Uint(1.into()),
+        address("CcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"),
+    ]);
+    let message = Token::Tuple(vec![
+        person("Cow", "CD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"),
+        person("Bob", "bBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"),
+        Token::String("Hello, Bob!".to_string()),
+    ]);
+
+    let (hash, _) = hash_typed_data(domain.clone(), types, message.clone()).unwrap();
+    assert_eq!(
+        "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2",
+        hex::encode(hash)
+    );
+
+    // The message does not match its type
+    let bad_message = Token::Tuple(vec![Token::String("Hello, Bob!".to_string())]);
+    assert!(hash_typed_data(domain.clone(), types, bad_message).is_err());
+
+    // Without `EIP712Domain`, we can't hash the domain
+    let types = r#"{ "Mail": [{ "name": "contents", "type": "string" }] }"#;
+    assert!(hash_typed_data(domain, types, message).is_err());
+}
@@ -1,5 +1,8 @@
 pub mod asc_abi;
 
+/// Native ABI for mappings written in Rust.
+pub mod rust_abi;
+
 mod host;
 pub mod to_from;
 
@@ -17,6 +20,9 @@ pub mod host_exports;
 pub mod error;
 mod gas_rules;
 
+/// Cache of compiled modules on disk.
+pub mod module_cache;
+
 pub use host::RuntimeHostBuilder;
 pub use host_exports::HostExports;
 pub use mapping::{MappingContext, ValidModule};
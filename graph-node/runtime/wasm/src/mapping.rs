@@ -1,8 +1,11 @@
 use crate::gas_rules::GasRules;
 use crate::module::{ExperimentalFeatures, ToAscPtr, WasmInstance};
+use crate::module_cache::ModuleCache;
+use crate::rust_abi::{self, ToRustTrigger};
 use graph::blockchain::{BlockTime, Blockchain, HostFn};
 use graph::components::store::SubgraphFork;
 use graph::components::subgraph::{MappingError, SharedProofOfIndexing};
+use graph::data_source::common::MappingLanguage;
 use graph::data_source::{MappingTrigger, TriggerWithHandler};
 use graph::futures01::sync::mpsc;
 use graph::futures01::{Future as _, Stream as _};
@@ -19,6 +22,7 @@ use std::{panic, thread};
 /// Spawn a wasm module in its own thread.
 pub fn spawn_module<C: Blockchain>(
     raw_module: &[u8],
+    language: MappingLanguage,
     logger: Logger,
     subgraph_id: DeploymentHash,
     host_metrics: Arc<HostMetrics>,
@@ -27,11 +31,11 @@ pub fn spawn_module<C: Blockchain>(
     experimental_features: ExperimentalFeatures,
 ) -> Result<mpsc::Sender<WasmRequest<C>>, anyhow::Error>
 where
-    <C as Blockchain>::MappingTrigger: ToAscPtr,
+    <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
 {
     static THREAD_COUNT: AtomicUsize = AtomicUsize::new(0);
 
-    let valid_module = Arc::new(ValidModule::new(&logger, raw_module, timeout)?);
+    let valid_module = Arc::new(ValidModule::new(&logger, raw_module, language, timeout)?);
 
     // Create channel for event handling requests
     let (mapping_request_sender, mapping_request_receiver) = mpsc::channel(100);
@@ -118,7 +122,7 @@ fn instantiate_module<C: Blockchain>(
     experimental_features: ExperimentalFeatures,
 ) -> Result<WasmInstance, anyhow::Error>
 where
-    <C as Blockchain>::MappingTrigger: ToAscPtr,
+    <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
 {
     // Start the WASM module runtime.
     let _section = host_metrics.stopwatch.start_section("module_init");
@@ -138,7 +142,7 @@ fn handle_trigger<C: Blockchain>(
     host_metrics: Arc<HostMetrics>,
 ) -> Result<(BlockState, Gas), MappingError>
 where
-    <C as Blockchain>::MappingTrigger: ToAscPtr,
+    <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
 {
     let logger = logger.cheap_clone();
 
@@ -230,6 +234,11 @@ impl MappingContext {
 // See the start_index comment below for more information.
 const GN_START_FUNCTION_NAME: &str = "gn::start";
 
+/// Bump this whenever `ValidModule::compile` changes how it instruments
+/// modules, e.g., how it injects gas metering, so that modules that were
+/// compiled before are not taken from the `ModuleCache`
+pub(crate) const INSTRUMENTATION_VERSION: u32 = 1;
+
 /// A pre-processed and valid WASM module, ready to be started as a WasmModule.
 pub struct ValidModule {
     pub module: wasmtime::Module,
@@ -250,6 +259,9 @@ pub struct ValidModule {
     // the ship has sailed.
     pub import_name_to_modules: BTreeMap<String, Vec<String>>,
 
+    // The language of the mapping, which determines the ABI we use to talk to it.
+    pub language: MappingLanguage,
+
     // The timeout for the module.
     pub timeout: Option<Duration>,
 
@@ -262,47 +274,9 @@ impl ValidModule {
     pub fn new(
         logger: &Logger,
         raw_module: &[u8],
+        language: MappingLanguage,
         timeout: Option<Duration>,
     ) -> Result<Self, anyhow::Error> {
-        // Add the gas calls here. Module name "gas" must match. See also
-        // e3f03e62-40e4-4f8c-b4a1-d0375cca0b76. We do this by round-tripping the module through
-        // parity - injecting gas then serializing again.
-        let parity_module = parity_wasm::elements::Module::from_bytes(raw_module)?;
-        let mut parity_module = match parity_module.parse_names() {
-            Ok(module) => module,
-            Err((errs, module)) => {
-                for (index, err) in errs {
-                    warn!(
-                        logger,
-                        "unable to parse function name for index {}: {}",
-                        index,
-                        err.to_string()
-                    );
-                }
-
-                module
-            }
-        };
-
-        let start_function = parity_module.start_section().map(|index| {
-            let name = GN_START_FUNCTION_NAME.to_string();
-
-            parity_module.clear_start_section();
-            parity_module
-                .export_section_mut()
-                .unwrap()
-                .entries_mut()
-                .push(ExportEntry::new(
-                    name.clone(),
-                    parity_wasm::elements::Internal::Function(index),
-                ));
-
-            name
-        });
-        let parity_module = wasm_instrument::gas_metering::inject(parity_module, &GasRules, "gas")
-            .map_err(|_| anyhow!("Failed to inject gas counter"))?;
-        let raw_module = parity_module.into_bytes()?;
-
         // We currently use Cranelift as a compilation engine. Cranelift is an optimizing compiler,
         // but that should not cause determinism issues since it adheres to the Wasm spec. Still we
         // turn off optional optimizations to be conservative.
@@ -314,7 +288,26 @@ impl ValidModule {
         config.max_wasm_stack(ENV_VARS.mappings.max_stack_size);
 
         let engine = &wasmtime::Engine::new(&config)?;
-        let module = wasmtime::Module::from_binary(engine, &raw_module)?;
+
+        let module = match ModuleCache::from_env() {
+            Some(cache) => {
+                let key = ModuleCache::key(raw_module, engine);
+                match cache.load(logger, engine, &key) {
+                    Some(module) => module,
+                    None => {
+                        let module = Self::compile(logger, engine, raw_module)?;
+                        cache.store(logger, &key, &module);
+                        module
+                    }
+                }
+            }
+            None => Self::compile(logger, engine, raw_module)?,
+        };
+
+        // `compile` exports the start function under this name
+        let start_function = module
+            .get_export(GN_START_FUNCTION_NAME)
+            .map(|_| GN_START_FUNCTION_NAME.to_string());
 
         let mut import_name_to_modules: BTreeMap<String, Vec<String>> = BTreeMap::new();
 
@@ -329,6 +322,39 @@ impl ValidModule {
                 .push(module.to_string());
         }
 
+        // The version of the Rust ABI is determined by the module that host
+        // functions are imported from, so we make sure that the mapping does
+        // not expect some other version. Host functions are linked by name,
+        // and the mapping must also not import any that are not part of the
+        // Rust ABI.
+        if language == MappingLanguage::Rust {
+            for (name, modules) in &import_name_to_modules {
+                for module in modules {
+                    match module.as_str() {
+                        rust_abi::NAMESPACE if rust_abi::HOST_FNS.contains(&name.as_str()) => {}
+                        "gas" if name == "gas" => {}
+                        rust_abi::NAMESPACE | "gas" => {
+                            return Err(anyhow!(
+                                "Rust mappings can not import `{}` from `{}` since it is not a \
+                                 host function of the Rust ABI",
+                                name,
+                                module
+                            ));
+                        }
+                        _ => {
+                            return Err(anyhow!(
+                                "Rust mappings must import host functions from `{}`, \
+                                 but `{}` is imported from `{}`",
+                                rust_abi::NAMESPACE,
+                                name,
+                                module
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
         let mut epoch_counter_abort_handle = None;
         if let Some(timeout) = timeout {
             let timeout = timeout.clone();
@@ -350,6 +376,7 @@ impl ValidModule {
         Ok(ValidModule {
             module,
             import_name_to_modules,
+            language,
             start_function,
             timeout,
             epoch_counter_abort_handle,
@@ -357,6 +384,53 @@ impl ValidModule {
     }
 }
 
+impl ValidModule {
+    /// Inject gas metering into `raw_module`, export its start function so
+    /// that we can run it ourselves, and compile it
+    fn compile(
+        logger: &Logger,
+        engine: &wasmtime::Engine,
+        raw_module: &[u8],
+    ) -> Result<wasmtime::Module, anyhow::Error> {
+        // Add the gas calls here. Module name "gas" must match. See also
+        // e3f03e62-40e4-4f8c-b4a1-d0375cca0b76. We do this by round-tripping the module through
+        // parity - injecting gas then serializing again.
+        let parity_module = parity_wasm::elements::Module::from_bytes(raw_module)?;
+        let mut parity_module = match parity_module.parse_names() {
+            Ok(module) => module,
+            Err((errs, module)) => {
+                for (index, err) in errs {
+                    warn!(
+                        logger,
+                        "unable to parse function name for index {}: {}",
+                        index,
+                        err.to_string()
+                    );
+                }
+
+                module
+            }
+        };
+
+        if let Some(index) = parity_module.start_section() {
+            parity_module.clear_start_section();
+            parity_module
+                .export_section_mut()
+                .unwrap()
+                .entries_mut()
+                .push(ExportEntry::new(
+                    GN_START_FUNCTION_NAME.to_string(),
+                    parity_wasm::elements::Internal::Function(index),
+                ));
+        }
+        let parity_module = wasm_instrument::gas_metering::inject(parity_module, &GasRules, "gas")
+            .map_err(|_| anyhow!("Failed to inject gas counter"))?;
+        let raw_module = parity_module.into_bytes()?;
+
+        wasmtime::Module::from_binary(engine, &raw_module)
+    }
+}
+
 impl Drop for ValidModule {
     fn drop(&mut self) {
         if let Some(handle) = self.epoch_counter_abort_handle.take() {
@@ -21,6 +21,7 @@ use crate::asc_abi::class::AscEntity;
 use crate::asc_abi::class::AscString;
 use crate::mapping::MappingContext;
 use crate::mapping::ValidModule;
+use crate::rust_abi::RustHeapCtx;
 use crate::ExperimentalFeatures;
 use graph::prelude::*;
 use graph::runtime::AscPtr;
@@ -97,6 +98,9 @@ pub struct WasmInstanceData {
     // This option is needed to break the cyclic dependency between, instance, store, and context.
     // during execution it should always be populated.
     asc_heap: Option<AscHeapCtx>,
+
+    // The counterpart of `asc_heap` for mappings that use the Rust ABI.
+    rust_heap: Option<RustHeapCtx>,
 }
 
 impl WasmInstanceData {
@@ -108,6 +112,7 @@ impl WasmInstanceData {
     ) -> Self {
         WasmInstanceData {
             asc_heap: None,
+            rust_heap: None,
             ctx,
             valid_module,
             host_metrics,
@@ -128,6 +133,14 @@ impl WasmInstanceData {
         self.asc_heap.as_mut().unwrap()
     }
 
+    pub fn set_rust_heap(&mut self, rust_heap: RustHeapCtx) {
+        self.rust_heap = Some(rust_heap);
+    }
+
+    pub fn rust_heap_ref(&self) -> &RustHeapCtx {
+        self.rust_heap.as_ref().unwrap()
+    }
+
     pub fn take_state(mut self) -> BlockState {
         let state = &mut self.ctx.state;
 
@@ -336,6 +349,49 @@ impl WasmInstanceContext<'_> {
         self.store_get_scoped(gas, entity_ptr, id_ptr, GetScope::InBlock)
     }
 
+    /// function store.getAt(entity: string, id: string, block: i32): Entity | null
+    pub fn store_get_at(
+        &mut self,
+        gas: &GasCounter,
+        entity_ptr: AscPtr<AscString>,
+        id_ptr: AscPtr<AscString>,
+        block: u32,
+    ) -> Result<AscPtr<AscEntity>, HostExportError> {
+        // Wasm passes the `i32` as its bit pattern
+        let block = block as i32;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let _timer = self
+            .as_ref()
+            .host_metrics
+            .cheap_clone()
+            .time_host_fn_execution_region("store_get_at");
+
+        let entity_type: String = asc_get(self, entity_ptr, gas)?;
+        let id: String = asc_get(self, id_ptr, gas)?;
+        let current_block = self.as_ref().ctx.block_ptr.number;
+        let entity_option = host_exports.store_get_at(
+            &mut self.as_mut().ctx.state,
+            entity_type.clone(),
+            id.clone(),
+            block,
+            current_block,
+            gas,
+        )?;
+
+        if self.as_ref().ctx.instrument {
+            debug!(self.as_ref().ctx.logger, "store_get_at";
+                    "type" => &entity_type,
+                    "id" => &id,
+                    "block" => block,
+                    "found" => entity_option.is_some());
+        }
+
+        match entity_option {
+            Some(entity) => asc_new(self, &entity.sorted_ref(), gas),
+            None => Ok(AscPtr::null()),
+        }
+    }
+
     /// function store.loadRelated(entity_type: string, id: string, field: string): Array<Entity>
     pub fn store_load_related(
         &mut self,
@@ -363,6 +419,36 @@ impl WasmInstanceContext<'_> {
         Ok(ret)
     }
 
+    /// function store.findBy(entity_type: string, attribute: string, value: Value, limit: i32): Array<Entity>
+    pub fn store_find_by(
+        &mut self,
+        gas: &GasCounter,
+        entity_type_ptr: AscPtr<AscString>,
+        attribute_ptr: AscPtr<AscString>,
+        value_ptr: AscPtr<AscEnum<StoreValueKind>>,
+        limit: u32,
+    ) -> Result<AscPtr<Array<AscPtr<AscEntity>>>, HostExportError> {
+        // Wasm passes the `i32` as its bit pattern
+        let limit = limit as i32;
+        let entity_type: String = asc_get(self, entity_type_ptr, gas)?;
+        let attribute: String = asc_get(self, attribute_ptr, gas)?;
+        let value: Value = asc_get(self, value_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let entities = host_exports.store_find_by(
+            &mut self.as_mut().ctx.state,
+            entity_type,
+            attribute,
+            value,
+            limit,
+            gas,
+        )?;
+
+        let entities: Vec<Vec<(Word, Value)>> =
+            entities.into_iter().map(|entity| entity.sorted()).collect();
+        let ret = asc_new(self, &entities, gas)?;
+        Ok(ret)
+    }
+
     /// function typeConversion.bytesToString(bytes: Bytes): string
     pub fn bytes_to_string(
         &mut self,
@@ -704,6 +790,90 @@ impl WasmInstanceContext<'_> {
         asc_new(self, input.as_ref(), gas)
     }
 
+    /// function crypto.sha256(input: Bytes): Bytes
+    pub fn crypto_sha_256(
+        &mut self,
+        gas: &GasCounter,
+        input_ptr: AscPtr<Uint8Array>,
+    ) -> Result<AscPtr<Uint8Array>, HostExportError> {
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let input = asc_get(self, input_ptr, gas)?;
+        let ctx = &mut self.as_mut().ctx;
+
+        let hash = host_exports.crypto_sha_256(input, gas, &mut ctx.state)?;
+        asc_new(self, hash.as_ref(), gas)
+    }
+
+    /// function crypto.blake2b256(input: Bytes): Bytes
+    pub fn crypto_blake2b_256(
+        &mut self,
+        gas: &GasCounter,
+        input_ptr: AscPtr<Uint8Array>,
+    ) -> Result<AscPtr<Uint8Array>, HostExportError> {
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let input = asc_get(self, input_ptr, gas)?;
+        let ctx = &mut self.as_mut().ctx;
+
+        let hash = host_exports.crypto_blake2b_256(input, gas, &mut ctx.state)?;
+        asc_new(self, hash.as_ref(), gas)
+    }
+
+    /// function crypto.secp256k1.recover(hash: Bytes, signature: Bytes): Bytes | null
+    pub fn crypto_secp256k1_recover(
+        &mut self,
+        gas: &GasCounter,
+        hash_ptr: AscPtr<Uint8Array>,
+        signature_ptr: AscPtr<Uint8Array>,
+    ) -> Result<AscPtr<Uint8Array>, HostExportError> {
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let hash = asc_get(self, hash_ptr, gas)?;
+        let signature = asc_get(self, signature_ptr, gas)?;
+        let ctx = &mut self.as_mut().ctx;
+
+        let key = host_exports.crypto_secp256k1_recover(hash, signature, gas, &mut ctx.state)?;
+        // return `null` if no key can be recovered
+        key.map(|key| asc_new(self, key.as_ref(), gas))
+            .unwrap_or(Ok(AscPtr::null()))
+    }
+
+    /// function crypto.ed25519.verify(message: Bytes, signature: Bytes, publicKey: Bytes): bool
+    pub fn crypto_ed25519_verify(
+        &mut self,
+        gas: &GasCounter,
+        message_ptr: AscPtr<Uint8Array>,
+        signature_ptr: AscPtr<Uint8Array>,
+        public_key_ptr: AscPtr<Uint8Array>,
+    ) -> Result<bool, HostExportError> {
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let message = asc_get(self, message_ptr, gas)?;
+        let signature = asc_get(self, signature_ptr, gas)?;
+        let public_key = asc_get(self, public_key_ptr, gas)?;
+        let ctx = &mut self.as_mut().ctx;
+
+        host_exports
+            .crypto_ed25519_verify(message, signature, public_key, gas, &mut ctx.state)
+            .map_err(Into::into)
+    }
+
+    /// function crypto.bls12381.verify(message: Bytes, signature: Bytes, publicKey: Bytes): bool
+    pub fn crypto_bls12381_verify(
+        &mut self,
+        gas: &GasCounter,
+        message_ptr: AscPtr<Uint8Array>,
+        signature_ptr: AscPtr<Uint8Array>,
+        public_key_ptr: AscPtr<Uint8Array>,
+    ) -> Result<bool, HostExportError> {
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let message = asc_get(self, message_ptr, gas)?;
+        let signature = asc_get(self, signature_ptr, gas)?;
+        let public_key = asc_get(self, public_key_ptr, gas)?;
+        let ctx = &mut self.as_mut().ctx;
+
+        host_exports
+            .crypto_bls12381_verify(message, signature, public_key, gas, &mut ctx.state)
+            .map_err(Into::into)
+    }
+
     /// function bigInt.plus(x: BigInt, y: BigInt): BigInt
     pub fn big_int_plus(
         &mut self,
@@ -824,6 +994,54 @@ impl WasmInstanceContext<'_> {
         asc_new(self, &result, gas)
     }
 
+    /// function bigInt.sqrt(x: BigInt): BigInt
+    pub fn big_int_sqrt(
+        &mut self,
+        gas: &GasCounter,
+        x_ptr: AscPtr<AscBigInt>,
+    ) -> Result<AscPtr<AscBigInt>, HostExportError> {
+        let x = asc_get(self, x_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let result = host_exports.big_int_sqrt(x, gas, &mut ctx.state)?;
+        asc_new(self, &result, gas)
+    }
+
+    /// function bigInt.modPow(x: BigInt, exp: BigInt, m: BigInt): BigInt
+    pub fn big_int_mod_pow(
+        &mut self,
+        gas: &GasCounter,
+        x_ptr: AscPtr<AscBigInt>,
+        exp_ptr: AscPtr<AscBigInt>,
+        m_ptr: AscPtr<AscBigInt>,
+    ) -> Result<AscPtr<AscBigInt>, HostExportError> {
+        let x = asc_get(self, x_ptr, gas)?;
+        let exp = asc_get(self, exp_ptr, gas)?;
+        let m = asc_get(self, m_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let result = host_exports.big_int_mod_pow(x, exp, m, gas, &mut ctx.state)?;
+        asc_new(self, &result, gas)
+    }
+
+    /// function bigInt.gcd(x: BigInt, y: BigInt): BigInt
+    pub fn big_int_gcd(
+        &mut self,
+        gas: &GasCounter,
+        x_ptr: AscPtr<AscBigInt>,
+        y_ptr: AscPtr<AscBigInt>,
+    ) -> Result<AscPtr<AscBigInt>, HostExportError> {
+        let x = asc_get(self, x_ptr, gas)?;
+        let y = asc_get(self, y_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let result = host_exports.big_int_gcd(x, y, gas, &mut ctx.state)?;
+        asc_new(self, &result, gas)
+    }
+
     /// function bigInt.bitOr(x: BigInt, y: BigInt): BigInt
     pub fn big_int_bit_or(
         &mut self,
@@ -1012,6 +1230,102 @@ impl WasmInstanceContext<'_> {
         host_exports.big_decimal_equals(x, y, gas, &mut ctx.state)
     }
 
+    /// function bigDecimal.pow(x: BigDecimal, exp: i32): BigDecimal
+    pub fn big_decimal_pow(
+        &mut self,
+        gas: &GasCounter,
+        x_ptr: AscPtr<AscBigDecimal>,
+        exp: u32,
+    ) -> Result<AscPtr<AscBigDecimal>, HostExportError> {
+        // Wasm passes the `i32` as its bit pattern
+        let exp = exp as i32;
+        let x = asc_get(self, x_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let result = host_exports.big_decimal_pow(x, exp, gas, &mut ctx.state)?;
+        asc_new(self, &result, gas)
+    }
+
+    /// function bigDecimal.sqrt(x: BigDecimal): BigDecimal
+    pub fn big_decimal_sqrt(
+        &mut self,
+        gas: &GasCounter,
+        x_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<AscPtr<AscBigDecimal>, HostExportError> {
+        let x = asc_get(self, x_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let result = host_exports.big_decimal_sqrt(x, gas, &mut ctx.state)?;
+        asc_new(self, &result, gas)
+    }
+
+    /// function bigDecimal.round(x: BigDecimal, scale: i32, mode: i32): BigDecimal
+    pub fn big_decimal_round(
+        &mut self,
+        gas: &GasCounter,
+        x_ptr: AscPtr<AscBigDecimal>,
+        scale: u32,
+        mode: u32,
+    ) -> Result<AscPtr<AscBigDecimal>, HostExportError> {
+        let (scale, mode) = (scale as i32, mode as i32);
+        let x = asc_get(self, x_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let result = host_exports.big_decimal_round(x, scale, mode, gas, &mut ctx.state)?;
+        asc_new(self, &result, gas)
+    }
+
+    /// function bigDecimal.truncate(x: BigDecimal, scale: i32): BigDecimal
+    pub fn big_decimal_truncate(
+        &mut self,
+        gas: &GasCounter,
+        x_ptr: AscPtr<AscBigDecimal>,
+        scale: u32,
+    ) -> Result<AscPtr<AscBigDecimal>, HostExportError> {
+        let scale = scale as i32;
+        let x = asc_get(self, x_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let result = host_exports.big_decimal_truncate(x, scale, gas, &mut ctx.state)?;
+        asc_new(self, &result, gas)
+    }
+
+    /// function bigDecimal.min(x: BigDecimal, y: BigDecimal): BigDecimal
+    pub fn big_decimal_min(
+        &mut self,
+        gas: &GasCounter,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<AscPtr<AscBigDecimal>, HostExportError> {
+        let x = asc_get(self, x_ptr, gas)?;
+        let y = asc_get(self, y_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let result = host_exports.big_decimal_min(x, y, gas, &mut ctx.state)?;
+        asc_new(self, &result, gas)
+    }
+
+    /// function bigDecimal.max(x: BigDecimal, y: BigDecimal): BigDecimal
+    pub fn big_decimal_max(
+        &mut self,
+        gas: &GasCounter,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<AscPtr<AscBigDecimal>, HostExportError> {
+        let x = asc_get(self, x_ptr, gas)?;
+        let y = asc_get(self, y_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        let result = host_exports.big_decimal_max(x, y, gas, &mut ctx.state)?;
+        asc_new(self, &result, gas)
+    }
+
     /// function dataSource.create(name: string, params: Array<string>): void
     pub fn data_source_create(
         &mut self,
@@ -1062,6 +1376,35 @@ impl WasmInstanceContext<'_> {
         )
     }
 
+    /// function dataSource.remove(): void
+    pub fn data_source_remove(&mut self, gas: &GasCounter) -> Result<(), HostExportError> {
+        let logger = self.as_ref().ctx.logger.cheap_clone();
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        host_exports.data_source_remove(&logger, &mut self.as_mut().ctx.state, gas)
+    }
+
+    /// function dataSource.removeTemplate(name: string, params: Array<string>): void
+    pub fn data_source_remove_template(
+        &mut self,
+        gas: &GasCounter,
+        name_ptr: AscPtr<AscString>,
+        params_ptr: AscPtr<Array<AscPtr<AscString>>>,
+    ) -> Result<(), HostExportError> {
+        let logger = self.as_ref().ctx.logger.cheap_clone();
+        let block_number = self.as_ref().ctx.block_ptr.number;
+        let name: String = asc_get(self, name_ptr, gas)?;
+        let params: Vec<String> = asc_get(self, params_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        host_exports.data_source_remove_template(
+            &logger,
+            &mut self.as_mut().ctx.state,
+            name,
+            params,
+            block_number,
+            gas,
+        )
+    }
+
     /// function dataSource.address(): Bytes
     pub fn data_source_address(
         &mut self,
@@ -1167,6 +1510,39 @@ impl WasmInstanceContext<'_> {
             .unwrap_or(Ok(AscPtr::null()))
     }
 
+    /// function encodePacked(types: String, tokens: Array<ethereum.Value>): Bytes
+    pub fn ethereum_encode_packed(
+        &mut self,
+        gas: &GasCounter,
+        types_ptr: AscPtr<AscString>,
+        tokens_ptr: AscPtr<Array<AscPtr<AscEnum<EthereumValueKind>>>>,
+    ) -> Result<AscPtr<Uint8Array>, HostExportError> {
+        let types = asc_get(self, types_ptr, gas)?;
+        let tokens = asc_get(self, tokens_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+        let data = host_exports.ethereum_encode_packed(types, tokens, gas, &mut ctx.state)?;
+        asc_new(self, &*data, gas)
+    }
+
+    /// function hashTypedData(domain: ethereum.Value, types: string, message: ethereum.Value): Bytes
+    pub fn ethereum_hash_typed_data(
+        &mut self,
+        gas: &GasCounter,
+        domain_ptr: AscPtr<AscEnum<EthereumValueKind>>,
+        types_ptr: AscPtr<AscString>,
+        message_ptr: AscPtr<AscEnum<EthereumValueKind>>,
+    ) -> Result<AscPtr<Uint8Array>, HostExportError> {
+        let domain = asc_get(self, domain_ptr, gas)?;
+        let types = asc_get(self, types_ptr, gas)?;
+        let message = asc_get(self, message_ptr, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+        let hash =
+            host_exports.ethereum_hash_typed_data(domain, types, message, gas, &mut ctx.state)?;
+        asc_new(self, hash.as_ref(), gas)
+    }
+
     /// function arweave.transactionData(txId: string): Bytes | null
     pub fn arweave_transaction_data(
         &self,
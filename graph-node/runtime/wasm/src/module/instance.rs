@@ -10,6 +10,7 @@ use wasmtime::{AsContextMut, Linker, Store, Trap};
 use graph::blockchain::{Blockchain, HostFnCtx};
 use graph::data::store;
 use graph::data::subgraph::schema::SubgraphError;
+use graph::data_source::common::MappingLanguage;
 use graph::data_source::{MappingTrigger, TriggerWithHandler};
 use graph::prelude::*;
 use graph::runtime::{
@@ -25,6 +26,7 @@ use crate::error::DeterminismLevel;
 use crate::mapping::MappingContext;
 use crate::mapping::ValidModule;
 use crate::module::WasmInstanceData;
+use crate::rust_abi::{RustHeapCtx, ToRustTrigger};
 use crate::ExperimentalFeatures;
 
 use super::{is_trap_deterministic, AscHeapCtx, ToAscPtr};
@@ -112,12 +114,17 @@ impl WasmInstance {
         block_data: Box<[u8]>,
     ) -> Result<(BlockState, Gas), MappingError> {
         let gas = self.gas.clone();
+        let language = self.store.data().valid_module.language;
         let mut ctx = self.instance_ctx();
-        let obj = block_data.to_vec().to_asc_obj(&mut ctx, &gas)?;
-
-        let obj = AscPtr::alloc_obj(obj, &mut ctx, &gas)?;
+        let ptr = match language {
+            MappingLanguage::AssemblyScript => {
+                let obj = block_data.to_vec().to_asc_obj(&mut ctx, &gas)?;
+                AscPtr::alloc_obj(obj, &mut ctx, &gas)?.wasm_ptr()
+            }
+            MappingLanguage::Rust => ctx.rust_write(&block_data, &gas)?,
+        };
 
-        self.invoke_handler(handler_name, obj, Arc::new(o!()), None)
+        self.invoke_handler(handler_name, ptr, Arc::new(o!()), None)
     }
 
     pub(crate) fn handle_trigger<C: Blockchain>(
@@ -125,16 +132,23 @@ impl WasmInstance {
         trigger: TriggerWithHandler<MappingTrigger<C>>,
     ) -> Result<(BlockState, Gas), MappingError>
     where
-        <C as Blockchain>::MappingTrigger: ToAscPtr,
+        <C as Blockchain>::MappingTrigger: ToAscPtr + ToRustTrigger,
     {
         let handler_name = trigger.handler_name().to_owned();
         let gas = self.gas.clone();
         let logging_extras = trigger.logging_extras().cheap_clone();
         let error_context = trigger.trigger.error_context();
+        let language = self.store.data().valid_module.language;
         let mut ctx = self.instance_ctx();
-        let asc_trigger = trigger.to_asc_ptr(&mut ctx, &gas)?;
+        let ptr = match language {
+            MappingLanguage::AssemblyScript => trigger.to_asc_ptr(&mut ctx, &gas)?.wasm_ptr(),
+            MappingLanguage::Rust => {
+                let bytes = trigger.to_rust_trigger()?;
+                ctx.rust_write(&bytes, &gas)?
+            }
+        };
 
-        self.invoke_handler(&handler_name, asc_trigger, logging_extras, error_context)
+        self.invoke_handler(&handler_name, ptr, logging_extras, error_context)
     }
 
     pub fn take_ctx(self) -> WasmInstanceData {
@@ -157,13 +171,16 @@ impl WasmInstance {
         self.gas.get().value()
     }
 
-    fn invoke_handler<T>(
+    /// Call `handler` with `arg`, a pointer to the trigger in the memory of
+    /// the mapping
+    fn invoke_handler(
         mut self,
         handler: &str,
-        arg: AscPtr<T>,
+        arg: u32,
         logging_extras: Arc<dyn SendSyncRefUnwindSafeKV>,
         error_context: Option<String>,
     ) -> Result<(BlockState, Gas), MappingError> {
+        let start = Instant::now();
         let func = self
             .instance
             .get_func(self.store.as_context_mut(), handler)
@@ -176,46 +193,50 @@ impl WasmInstance {
         // Caution: Make sure all exit paths from this function call `exit_handler`.
         self.instance_ctx().as_mut().ctx.state.enter_handler();
 
+        let result = func.call(self.store.as_context_mut(), arg);
+        let block = self.instance_ctx().as_ref().ctx.block_ptr.number;
+        self.gas
+            .record_profile(block, handler, start.elapsed(), result.is_err());
+
         // This `match` will return early if there was a non-deterministic trap.
-        let deterministic_error: Option<Error> =
-            match func.call(self.store.as_context_mut(), arg.wasm_ptr()) {
-                Ok(()) => {
-                    assert!(self.instance_ctx().as_ref().possible_reorg == false);
-                    assert!(self.instance_ctx().as_ref().deterministic_host_trap == false);
-                    None
-                }
-                Err(trap) if self.instance_ctx().as_ref().possible_reorg => {
-                    self.instance_ctx().as_mut().ctx.state.exit_handler();
-                    return Err(MappingError::PossibleReorg(trap.into()));
-                }
+        let deterministic_error: Option<Error> = match result {
+            Ok(()) => {
+                assert!(self.instance_ctx().as_ref().possible_reorg == false);
+                assert!(self.instance_ctx().as_ref().deterministic_host_trap == false);
+                None
+            }
+            Err(trap) if self.instance_ctx().as_ref().possible_reorg => {
+                self.instance_ctx().as_mut().ctx.state.exit_handler();
+                return Err(MappingError::PossibleReorg(trap.into()));
+            }
 
-                // Treat timeouts anywhere in the error chain as a special case to have a better error
-                // message. Any `TrapCode::Interrupt` is assumed to be a timeout.
-                // See also: runtime-timeouts
-                Err(trap)
-                    if trap
-                        .chain()
-                        .any(|e| e.downcast_ref::<Trap>() == Some(&Trap::Interrupt)) =>
-                {
-                    self.instance_ctx().as_mut().ctx.state.exit_handler();
-                    return Err(MappingError::Unknown(Error::from(trap).context(format!(
+            // Treat timeouts anywhere in the error chain as a special case to have a better error
+            // message. Any `TrapCode::Interrupt` is assumed to be a timeout.
+            // See also: runtime-timeouts
+            Err(trap)
+                if trap
+                    .chain()
+                    .any(|e| e.downcast_ref::<Trap>() == Some(&Trap::Interrupt)) =>
+            {
+                self.instance_ctx().as_mut().ctx.state.exit_handler();
+                return Err(MappingError::Unknown(Error::from(trap).context(format!(
                         "Handler '{}' hit the timeout of '{}' seconds",
                         handler,
                         self.instance_ctx().as_ref().valid_module.timeout.unwrap().as_secs()
                     ))));
-                }
-                Err(trap) => {
-                    let trap_is_deterministic = is_trap_deterministic(&trap)
-                        || self.instance_ctx().as_ref().deterministic_host_trap;
-                    match trap_is_deterministic {
-                        true => Some(trap),
-                        false => {
-                            self.instance_ctx().as_mut().ctx.state.exit_handler();
-                            return Err(MappingError::Unknown(trap));
-                        }
+            }
+            Err(trap) => {
+                let trap_is_deterministic = is_trap_deterministic(&trap)
+                    || self.instance_ctx().as_ref().deterministic_host_trap;
+                match trap_is_deterministic {
+                    true => Some(trap),
+                    false => {
+                        self.instance_ctx().as_mut().ctx.state.exit_handler();
+                        return Err(MappingError::Unknown(trap));
                     }
                 }
-            };
+            }
+        };
 
         if let Some(deterministic_error) = deterministic_error {
             let deterministic_error = match error_context {
@@ -399,6 +420,19 @@ impl WasmInstance {
 
         link!("ethereum.encode", ethereum_encode, params_ptr);
         link!("ethereum.decode", ethereum_decode, params_ptr, data_ptr);
+        link!(
+            "ethereum.encodePacked",
+            ethereum_encode_packed,
+            types_ptr,
+            tokens_ptr
+        );
+        link!(
+            "ethereum.hashTypedData",
+            ethereum_hash_typed_data,
+            domain_ptr,
+            types_ptr,
+            message_ptr
+        );
 
         link!("abort", abort, message_ptr, file_name_ptr, line, column);
 
@@ -411,6 +445,15 @@ impl WasmInstance {
             id,
             field
         );
+        link!(
+            "store.findBy",
+            store_find_by,
+            "host_export_store_find_by",
+            entity,
+            attribute,
+            value,
+            limit
+        );
         link!(
             "store.get_in_block",
             store_get_in_block,
@@ -418,6 +461,14 @@ impl WasmInstance {
             entity,
             id
         );
+        link!(
+            "store.getAt",
+            store_get_at,
+            "host_export_store_get_at",
+            entity,
+            id,
+            block
+        );
         link!(
             "store.set",
             store_set,
@@ -472,6 +523,28 @@ impl WasmInstance {
         link!("yaml.try_fromBytes", yaml_try_from_bytes, ptr);
 
         link!("crypto.keccak256", crypto_keccak_256, ptr);
+        link!("crypto.sha256", crypto_sha_256, ptr);
+        link!("crypto.blake2b256", crypto_blake2b_256, ptr);
+        link!(
+            "crypto.secp256k1.recover",
+            crypto_secp256k1_recover,
+            hash_ptr,
+            signature_ptr
+        );
+        link!(
+            "crypto.ed25519.verify",
+            crypto_ed25519_verify,
+            message_ptr,
+            signature_ptr,
+            public_key_ptr
+        );
+        link!(
+            "crypto.bls12381.verify",
+            crypto_bls12381_verify,
+            message_ptr,
+            signature_ptr,
+            public_key_ptr
+        );
 
         link!("bigInt.plus", big_int_plus, x_ptr, y_ptr);
         link!("bigInt.minus", big_int_minus, x_ptr, y_ptr);
@@ -485,6 +558,9 @@ impl WasmInstance {
         link!("bigInt.bitAnd", big_int_bit_and, x_ptr, y_ptr);
         link!("bigInt.leftShift", big_int_left_shift, x_ptr, bits);
         link!("bigInt.rightShift", big_int_right_shift, x_ptr, bits);
+        link!("bigInt.sqrt", big_int_sqrt, x_ptr);
+        link!("bigInt.modPow", big_int_mod_pow, x_ptr, exp_ptr, m_ptr);
+        link!("bigInt.gcd", big_int_gcd, x_ptr, y_ptr);
 
         link!("bigDecimal.toString", big_decimal_to_string, ptr);
         link!("bigDecimal.fromString", big_decimal_from_string, ptr);
@@ -493,6 +569,12 @@ impl WasmInstance {
         link!("bigDecimal.times", big_decimal_times, x_ptr, y_ptr);
         link!("bigDecimal.dividedBy", big_decimal_divided_by, x, y);
         link!("bigDecimal.equals", big_decimal_equals, x_ptr, y_ptr);
+        link!("bigDecimal.pow", big_decimal_pow, x_ptr, exp);
+        link!("bigDecimal.sqrt", big_decimal_sqrt, x_ptr);
+        link!("bigDecimal.round", big_decimal_round, x_ptr, scale, mode);
+        link!("bigDecimal.truncate", big_decimal_truncate, x_ptr, scale);
+        link!("bigDecimal.min", big_decimal_min, x_ptr, y_ptr);
+        link!("bigDecimal.max", big_decimal_max, x_ptr, y_ptr);
 
         link!("dataSource.create", data_source_create, name, params);
         link!(
@@ -502,6 +584,13 @@ impl WasmInstance {
             params,
             context
         );
+        link!("dataSource.remove", data_source_remove,);
+        link!(
+            "dataSource.removeTemplate",
+            data_source_remove_template,
+            name,
+            params
+        );
         link!("dataSource.address", data_source_address,);
         link!("dataSource.network", data_source_network,);
         link!("dataSource.context", data_source_context,);
@@ -516,6 +605,41 @@ impl WasmInstance {
             link!("box.profile", box_profile, ptr);
         }
 
+        // Host functions of the native Rust ABI, see `crate::rust_abi`. `ValidModule` makes sure
+        // that Rust mappings import them from `rust_abi::NAMESPACE`.
+        if valid_module.language == MappingLanguage::Rust {
+            link!(
+                "store_set",
+                rust_store_set,
+                "host_export_store_set",
+                entity_ptr,
+                entity_len,
+                id_ptr,
+                id_len,
+                data_ptr,
+                data_len
+            );
+            link!(
+                "store_get",
+                rust_store_get,
+                "host_export_store_get",
+                entity_ptr,
+                entity_len,
+                id_ptr,
+                id_len
+            );
+            link!(
+                "store_remove",
+                rust_store_remove,
+                entity_ptr,
+                entity_len,
+                id_ptr,
+                id_len
+            );
+            link!("log", rust_log, level, msg_ptr, msg_len);
+            link!("panic", rust_panic, msg_ptr, msg_len);
+        }
+
         // link the `gas` function
         // See also e3f03e62-40e4-4f8c-b4a1-d0375cca0b76
         {
@@ -537,12 +661,21 @@ impl WasmInstance {
 
         let instance = linker.instantiate(store.as_context_mut(), &valid_module.module)?;
 
-        let asc_heap = AscHeapCtx::new(
-            &instance,
-            &mut WasmInstanceContext::new(&mut store),
-            api_version.clone(),
-        )?;
-        store.data_mut().set_asc_heap(asc_heap);
+        match valid_module.language {
+            MappingLanguage::AssemblyScript => {
+                let asc_heap = AscHeapCtx::new(
+                    &instance,
+                    &mut WasmInstanceContext::new(&mut store),
+                    api_version.clone(),
+                )?;
+                store.data_mut().set_asc_heap(asc_heap);
+            }
+            MappingLanguage::Rust => {
+                let rust_heap =
+                    RustHeapCtx::new(&instance, &mut WasmInstanceContext::new(&mut store))?;
+                store.data_mut().set_rust_heap(rust_heap);
+            }
+        }
 
         // See start_function comment for more information
         // TL;DR; we need the wasmtime::Instance to create the heap, therefore
@@ -555,7 +688,9 @@ impl WasmInstance {
                 .call(store.as_context_mut(), ())?;
         }
 
+        // Rust mappings don't have an AssemblyScript `_start`
         match api_version {
+            _ if valid_module.language == MappingLanguage::Rust => {}
             version if version <= Version::new(0, 0, 4) => {}
             _ => {
                 instance
@@ -31,6 +31,13 @@ impl IntoWasmRet for i32 {
     }
 }
 
+impl IntoWasmRet for u32 {
+    type Ret = Self;
+    fn into_wasm_ret(self) -> Self {
+        self
+    }
+}
+
 impl IntoWasmRet for i64 {
     type Ret = Self;
     fn into_wasm_ret(self) -> Self {
@@ -205,7 +205,7 @@ impl AscHeapCtx {
     }
 }
 
-fn host_export_error_from_trap(trap: Error, context: String) -> HostExportError {
+pub(crate) fn host_export_error_from_trap(trap: Error, context: String) -> HostExportError {
     let trap_is_deterministic = is_trap_deterministic(&trap);
     let e = Error::from(trap).context(context);
     match trap_is_deterministic {
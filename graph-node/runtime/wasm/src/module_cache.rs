@@ -0,0 +1,244 @@
+//! A cache of compiled WASM modules on disk. Compiling a mapping with
+//! Cranelift is by far the most expensive part of starting a subgraph, and
+//! since mappings never change, we can reuse the compiled code across
+//! restarts of the node.
+//!
+//! Entries are keyed by a hash of the raw module, the version of
+//! graph-node, the configuration of the `wasmtime::Engine` and how we
+//! instrument modules, including the gas costs, so that an upgrade of
+//! graph-node or a change in configuration or gas costs simply leads to
+//! cache misses. Each file starts with a checksum of the serialized module
+//! so that we never hand truncated or otherwise corrupted files to
+//! wasmtime; such files are removed and the module is compiled again.
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Error};
+use graph::env::ENV_VARS;
+use graph::prelude::{debug, warn, Logger};
+use sha2::{Digest, Sha256};
+
+use crate::gas_rules::GasRules;
+use crate::mapping::INSTRUMENTATION_VERSION;
+
+/// Bump this whenever the layout of cache files changes
+const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// The extension of cache files
+const EXTENSION: &str = "cwasm";
+
+/// The size of the checksum at the start of each cache file
+const CHECKSUM_LEN: usize = 32;
+
+pub struct ModuleCache {
+    dir: PathBuf,
+}
+
+impl ModuleCache {
+    pub fn new(dir: PathBuf) -> Self {
+        ModuleCache { dir }
+    }
+
+    /// The cache in the directory configured with
+    /// `GRAPH_WASM_MODULE_CACHE_DIR`, or `None` if caching is disabled
+    pub fn from_env() -> Option<Self> {
+        ENV_VARS
+            .mappings
+            .wasm_module_cache_dir
+            .clone()
+            .map(ModuleCache::new)
+    }
+
+    /// The key under which the compiled version of `raw_module` is stored
+    /// when it is compiled with `engine`
+    pub fn key(raw_module: &[u8], engine: &wasmtime::Engine) -> String {
+        // `precompile_compatibility_hash` only implements `Hash`; the
+        // `DefaultHasher` is stable for a given build of graph-node, and
+        // the key includes the version anyway
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        engine.precompile_compatibility_hash().hash(&mut hasher);
+
+        let mut digest = Sha256::new();
+        digest.update(CACHE_FORMAT_VERSION.to_le_bytes());
+        digest.update(INSTRUMENTATION_VERSION.to_le_bytes());
+        digest.update(GasRules::fingerprint());
+        digest.update(env!("CARGO_PKG_VERSION").as_bytes());
+        digest.update(hasher.finish().to_le_bytes());
+        digest.update((ENV_VARS.mappings.max_stack_size as u64).to_le_bytes());
+        digest.update(raw_module);
+        hex::encode(digest.finalize())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, EXTENSION))
+    }
+
+    /// Load the module stored under `key`. Returns `None` if there is no
+    /// such module or if it can not be used, in which case we also remove
+    /// it from the cache
+    pub fn load(
+        &self,
+        logger: &Logger,
+        engine: &wasmtime::Engine,
+        key: &str,
+    ) -> Option<wasmtime::Module> {
+        let path = self.path(key);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!(logger, "Failed to read cached WASM module";
+                    "path" => path.display().to_string(), "error" => e.to_string());
+                return None;
+            }
+        };
+
+        match Self::deserialize(engine, &bytes) {
+            Ok(module) => {
+                debug!(logger, "Loaded WASM module from cache"; "key" => key);
+                Some(module)
+            }
+            Err(e) => {
+                warn!(logger, "Removing unusable WASM module from cache";
+                    "path" => path.display().to_string(), "error" => e.to_string());
+                if let Err(e) = fs::remove_file(&path) {
+                    warn!(logger, "Failed to remove cached WASM module";
+                        "path" => path.display().to_string(), "error" => e.to_string());
+                }
+                None
+            }
+        }
+    }
+
+    fn deserialize(engine: &wasmtime::Engine, bytes: &[u8]) -> Result<wasmtime::Module, Error> {
+        if bytes.len() < CHECKSUM_LEN {
+            return Err(anyhow!("the file is truncated"));
+        }
+        let (checksum, module) = bytes.split_at(CHECKSUM_LEN);
+        if Sha256::digest(module).as_slice() != checksum {
+            return Err(anyhow!("the checksum does not match"));
+        }
+
+        // Safety: `Module::deserialize` runs the native code in `module`
+        // without verifying it, so the bytes must come from
+        // `Module::serialize`. The checksum only catches accidental damage
+        // like truncated writes, not deliberate changes; the cache
+        // directory must only be writable by graph-node. wasmtime rejects
+        // modules from a different wasmtime version or an incompatible
+        // engine configuration.
+        unsafe { wasmtime::Module::deserialize(engine, module) }
+    }
+
+    /// Store `module` under `key`. Failing to do that is not fatal, we
+    /// just log a warning
+    pub fn store(&self, logger: &Logger, key: &str, module: &wasmtime::Module) {
+        if let Err(e) = self.try_store(key, module) {
+            warn!(logger, "Failed to store WASM module in cache";
+                "key" => key, "error" => e.to_string());
+        }
+    }
+
+    fn try_store(&self, key: &str, module: &wasmtime::Module) -> Result<(), Error> {
+        let serialized = module.serialize()?;
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("failed to create {}", self.dir.display()))?;
+
+        // Write to a temporary file and rename it so that other processes
+        // sharing the directory never see a partially written file
+        let tmp = self.dir.join(format!("{}.{}.tmp", key, std::process::id()));
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&Sha256::digest(&serialized))?;
+        file.write_all(&serialized)?;
+        file.sync_all()?;
+        fs::rename(&tmp, self.path(key)).map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            Error::from(e)
+        })
+    }
+
+    /// The number of entries in the cache and their total size in bytes
+    pub fn size(&self) -> Result<(usize, u64), Error> {
+        let mut count = 0;
+        let mut bytes = 0;
+        for path in self.entries()? {
+            count += 1;
+            bytes += fs::metadata(&path)?.len();
+        }
+        Ok((count, bytes))
+    }
+
+    /// Remove all entries from the cache, including leftover temporary
+    /// files. Returns the number of modules that were removed
+    pub fn clear(&self) -> Result<usize, Error> {
+        let mut count = 0;
+        for path in self.entries()? {
+            fs::remove_file(&path)
+                .with_context(|| format!("failed to remove {}", path.display()))?;
+            if has_extension(&path, EXTENSION) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// All files in the cache directory that we created
+    fn entries(&self) -> Result<Vec<PathBuf>, Error> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => {
+                return Err(Error::from(e).context(format!("failed to read {}", self.dir.display())))
+            }
+        };
+        let mut entries = Vec::new();
+        for entry in dir {
+            let path = entry?.path();
+            if has_extension(&path, EXTENSION) || has_extension(&path, "tmp") {
+                entries.push(path);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension().is_some_and(|e| e == ext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stores_loads_and_clears_modules() {
+        let logger = graph::log::logger(true);
+        let dir = std::env::temp_dir().join(format!("graph-module-cache-{}", std::process::id()));
+        let cache = ModuleCache::new(dir.clone());
+        let engine = wasmtime::Engine::default();
+        let module = wasmtime::Module::new(&engine, "(module (func (export \"f\")))").unwrap();
+        let key = ModuleCache::key(b"raw module", &engine);
+
+        assert!(cache.load(&logger, &engine, &key).is_none());
+        cache.store(&logger, &key, &module);
+        let loaded = cache.load(&logger, &engine, &key).unwrap();
+        assert!(loaded.get_export("f").is_some());
+        assert_eq!(1, cache.size().unwrap().0);
+
+        // A corrupted entry is a cache miss and gets removed
+        let path = cache.path(&key);
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&path, bytes).unwrap();
+        assert!(cache.load(&logger, &engine, &key).is_none());
+        assert!(!path.exists());
+
+        cache.store(&logger, &key, &module);
+        assert_eq!(1, cache.clear().unwrap());
+        assert_eq!(0, cache.size().unwrap().0);
+
+        fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -0,0 +1,372 @@
+//! The binary encoding of the Rust ABI. Integers are little-endian, and
+//! variable-length data (byte strings, strings and lists) is prefixed with
+//! its length as a `u32`. Optional values and enums start with a one-byte
+//! tag.
+use std::str;
+
+use anyhow::anyhow;
+use graph::runtime::DeterministicHostError;
+
+/// How deeply values that a mapping passes to the host can be nested. Like
+/// for AssemblyScript mappings, this is plenty for any subgraph while
+/// keeping a malicious mapping from overflowing the stack of the host
+const MAX_RECURSION_DEPTH: usize = 128;
+
+/// Builds the encoding of a value that is passed to a mapping
+#[derive(Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn i32(&mut self, value: i32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn i64(&mut self, value: i64) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// The length of a byte string, string or list
+    pub fn len(&mut self, len: usize) {
+        // Wasm memory can not hold more than `u32::MAX` bytes, so there is
+        // no point in passing anything longer to a mapping
+        self.u32(u32::try_from(len).expect("values passed to mappings fit into wasm memory"));
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.len(bytes.len());
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn str(&mut self, s: &str) {
+        self.bytes(s.as_bytes());
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Reads a value that a mapping passed to the host. Since the data comes
+/// from the mapping, all errors are deterministic
+pub struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Decoder {
+            buf,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    /// Decode a value that is nested inside the current one with
+    /// `decode`. Fails if values are nested more than
+    /// `MAX_RECURSION_DEPTH` levels deep
+    pub fn nested<T>(
+        &mut self,
+        decode: impl FnOnce(&mut Self) -> Result<T, DeterministicHostError>,
+    ) -> Result<T, DeterministicHostError> {
+        if self.depth >= MAX_RECURSION_DEPTH {
+            return Err(DeterministicHostError::from(anyhow!(
+                "recursion limit reached: values can be nested at most {} levels deep",
+                MAX_RECURSION_DEPTH
+            )));
+        }
+        self.depth += 1;
+        let value = decode(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DeterministicHostError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or_else(|| {
+                DeterministicHostError::from(anyhow!(
+                    "unexpected end of input: needed {} bytes at offset {} but only {} are left",
+                    len,
+                    self.pos,
+                    self.buf.len() - self.pos
+                ))
+            })?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], DeterministicHostError> {
+        // Unwrap: `take` returns exactly `N` bytes
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, DeterministicHostError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, DeterministicHostError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub fn u64(&mut self) -> Result<u64, DeterministicHostError> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    pub fn i32(&mut self) -> Result<i32, DeterministicHostError> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    pub fn i64(&mut self) -> Result<i64, DeterministicHostError> {
+        self.array().map(i64::from_le_bytes)
+    }
+
+    /// The length of a byte string, string or list
+    pub fn len(&mut self) -> Result<usize, DeterministicHostError> {
+        self.u32().map(|len| len as usize)
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], DeterministicHostError> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    pub fn str(&mut self) -> Result<&'a str, DeterministicHostError> {
+        let bytes = self.bytes()?;
+        str::from_utf8(bytes)
+            .map_err(|e| DeterministicHostError::from(anyhow!("invalid UTF-8 string: {}", e)))
+    }
+
+    /// Check that all of the input was used
+    pub fn finish(self) -> Result<(), DeterministicHostError> {
+        match self.buf.len() - self.pos {
+            0 => Ok(()),
+            left => Err(DeterministicHostError::from(anyhow!(
+                "{} bytes of trailing data after the end of the value",
+                left
+            ))),
+        }
+    }
+}
+
+/// Types that can be passed to a mapping that uses the Rust ABI
+pub trait ToRustBytes {
+    fn encode(&self, enc: &mut Encoder);
+
+    fn to_rust_bytes(&self) -> Vec<u8> {
+        let mut enc = Encoder::new();
+        self.encode(&mut enc);
+        enc.finish()
+    }
+}
+
+/// Types that a mapping that uses the Rust ABI can pass to the host
+pub trait FromRustBytes: Sized {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError>;
+
+    fn from_rust_bytes(bytes: &[u8]) -> Result<Self, DeterministicHostError> {
+        let mut dec = Decoder::new(bytes);
+        let value = Self::decode(&mut dec)?;
+        dec.finish()?;
+        Ok(value)
+    }
+}
+
+impl ToRustBytes for bool {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.u8(*self as u8)
+    }
+}
+
+impl FromRustBytes for bool {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        match dec.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(DeterministicHostError::from(anyhow!(
+                "invalid boolean `{}`",
+                b
+            ))),
+        }
+    }
+}
+
+macro_rules! impl_int {
+    ($($t:ident),*) => {
+        $(
+            impl ToRustBytes for $t {
+                fn encode(&self, enc: &mut Encoder) {
+                    enc.$t(*self)
+                }
+            }
+
+            impl FromRustBytes for $t {
+                fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+                    dec.$t()
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(u32, u64, i32, i64);
+
+impl ToRustBytes for str {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.str(self)
+    }
+}
+
+impl ToRustBytes for String {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.str(self)
+    }
+}
+
+impl FromRustBytes for String {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        dec.str().map(str::to_string)
+    }
+}
+
+impl<T: ToRustBytes + ?Sized> ToRustBytes for &T {
+    fn encode(&self, enc: &mut Encoder) {
+        (*self).encode(enc)
+    }
+}
+
+impl<T: ToRustBytes> ToRustBytes for [T] {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.len(self.len());
+        for item in self {
+            item.encode(enc);
+        }
+    }
+}
+
+impl<T: ToRustBytes> ToRustBytes for Vec<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        self.as_slice().encode(enc)
+    }
+}
+
+impl<T: FromRustBytes> FromRustBytes for Vec<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        let len = dec.len()?;
+        // Don't trust `len` for preallocating, decoding runs out of input
+        // long before we would run out of memory
+        let mut items = Vec::new();
+        for _ in 0..len {
+            items.push(T::decode(dec)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: ToRustBytes> ToRustBytes for Option<T> {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            None => enc.u8(0),
+            Some(value) => {
+                enc.u8(1);
+                value.encode(enc);
+            }
+        }
+    }
+}
+
+impl<T: FromRustBytes> FromRustBytes for Option<T> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        match dec.u8()? {
+            0 => Ok(None),
+            1 => T::decode(dec).map(Some),
+            tag => Err(DeterministicHostError::from(anyhow!(
+                "invalid tag `{}` for an optional value",
+                tag
+            ))),
+        }
+    }
+}
+
+impl<A: ToRustBytes, B: ToRustBytes> ToRustBytes for (A, B) {
+    fn encode(&self, enc: &mut Encoder) {
+        self.0.encode(enc);
+        self.1.encode(enc);
+    }
+}
+
+impl<A: FromRustBytes, B: FromRustBytes> FromRustBytes for (A, B) {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        Ok((A::decode(dec)?, B::decode(dec)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T: ToRustBytes + FromRustBytes + PartialEq + std::fmt::Debug>(value: T) {
+        let bytes = value.to_rust_bytes();
+        assert_eq!(value, T::from_rust_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn encodes_values() {
+        assert_eq!(vec![1], true.to_rust_bytes());
+        assert_eq!(vec![1, 2, 0, 0], 0x0201u32.to_rust_bytes());
+        assert_eq!(vec![0xff; 8], (-1i64).to_rust_bytes());
+        assert_eq!(vec![2, 0, 0, 0, b'h', b'i'], "hi".to_rust_bytes());
+        assert_eq!(vec![0], None::<u32>.to_rust_bytes());
+        assert_eq!(vec![1, 7, 0, 0, 0], Some(7u32).to_rust_bytes());
+        assert_eq!(
+            vec![2, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0],
+            vec![1u32, 2].to_rust_bytes()
+        );
+
+        roundtrip(false);
+        roundtrip(u32::MAX);
+        roundtrip(u64::MAX);
+        roundtrip(i32::MIN);
+        roundtrip(i64::MIN);
+        roundtrip("grüße".to_string());
+        roundtrip(vec![Some("a".to_string()), None]);
+        roundtrip(("x".to_string(), vec![(1u32, true)]));
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        // Not enough input
+        assert!(u32::from_rust_bytes(&[1, 2, 3]).is_err());
+        assert!(String::from_rust_bytes(&[5, 0, 0, 0, b'a']).is_err());
+        // A length that is way bigger than the input
+        assert!(Vec::<u32>::from_rust_bytes(&[0xff, 0xff, 0xff, 0xff]).is_err());
+        // Trailing data
+        assert!(u32::from_rust_bytes(&[1, 0, 0, 0, 0]).is_err());
+        // Invalid tags and strings
+        assert!(bool::from_rust_bytes(&[2]).is_err());
+        assert!(Option::<u32>::from_rust_bytes(&[2, 1, 0, 0, 0]).is_err());
+        assert!(String::from_rust_bytes(&[1, 0, 0, 0, 0xff]).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+//! The host functions of the Rust ABI. They decode their arguments, call
+//! the same `HostExports` as the AssemblyScript host functions, and encode
+//! their results.
+use std::collections::HashMap;
+
+use anyhow::anyhow;
+use graph::components::store::GetScope;
+use graph::data::store::Value;
+use graph::data::value::Word;
+use graph::prelude::*;
+use graph::runtime::gas::{Gas, GasCounter};
+use graph::runtime::{DeterministicHostError, HostExportError};
+use never::Never;
+use wasmtime::AsContextMut;
+
+use super::codec::{FromRustBytes, ToRustBytes};
+use crate::asc_abi::class::LogLevel;
+use crate::gas_rules::{GAS_COST_LOAD, GAS_COST_STORE};
+use crate::module::{host_export_error_from_trap, WasmInstanceContext};
+
+impl WasmInstanceContext<'_> {
+    /// Read `len` bytes at `ptr` from the memory of the mapping
+    pub(crate) fn rust_read(
+        &self,
+        ptr: u32,
+        len: u32,
+        gas: &GasCounter,
+    ) -> Result<Vec<u8>, DeterministicHostError> {
+        let out_of_bounds = || {
+            DeterministicHostError::from(anyhow!(
+                "Heap access out of bounds. Offset: {} Size: {}",
+                ptr,
+                len
+            ))
+        };
+
+        // Check the bounds before allocating so that the mapping can not
+        // make us allocate more than it has memory
+        let memory = self.as_ref().rust_heap_ref().memory;
+        if ptr as u64 + len as u64 > memory.data_size(self) as u64 {
+            return Err(out_of_bounds());
+        }
+
+        // Same cost as for the AssemblyScript heap
+        gas.consume_host_fn_with_metrics(Gas::new(GAS_COST_LOAD as u64 * len as u64), "rust_read")?;
+
+        let mut bytes = vec![0; len as usize];
+        memory
+            .read(self, ptr as usize, &mut bytes)
+            .map_err(|_| out_of_bounds())?;
+        Ok(bytes)
+    }
+
+    fn rust_read_str(
+        &self,
+        ptr: u32,
+        len: u32,
+        gas: &GasCounter,
+    ) -> Result<String, DeterministicHostError> {
+        String::from_utf8(self.rust_read(ptr, len, gas)?)
+            .map_err(|e| DeterministicHostError::from(anyhow!("invalid UTF-8 string: {}", e)))
+    }
+
+    /// Copy `bytes`, prefixed with their length, into memory that the
+    /// mapping allocated and return a pointer to it
+    pub(crate) fn rust_write(
+        &mut self,
+        bytes: &[u8],
+        gas: &GasCounter,
+    ) -> Result<u32, HostExportError> {
+        let len = u32::try_from(bytes.len()).map_err(|_| {
+            HostExportError::Deterministic(anyhow!(
+                "value of {} bytes is too big to pass to the mapping",
+                bytes.len()
+            ))
+        })?;
+        let size = len.checked_add(4).ok_or_else(|| {
+            HostExportError::Deterministic(anyhow!(
+                "value of {} bytes is too big to pass to the mapping",
+                len
+            ))
+        })?;
+        gas.consume_host_fn_with_metrics(
+            Gas::new(GAS_COST_STORE as u64 * size as u64),
+            "rust_write",
+        )?;
+
+        let allocate = self.as_ref().rust_heap_ref().allocate.clone();
+        let ptr = allocate.call(self.as_context_mut(), size).map_err(|trap| {
+            host_export_error_from_trap(
+                trap,
+                format!("Failed to allocate {} bytes in the mapping", size),
+            )
+        })?;
+
+        let memory = self.as_ref().rust_heap_ref().memory;
+        let mut buf = Vec::with_capacity(size as usize);
+        buf.extend_from_slice(&len.to_le_bytes());
+        buf.extend_from_slice(bytes);
+        memory
+            .write(self.as_context_mut(), ptr as usize, &buf)
+            .map_err(|_| {
+                HostExportError::Deterministic(anyhow!(
+                    "`allocate` returned a pointer outside of memory: {}",
+                    ptr
+                ))
+            })?;
+        Ok(ptr)
+    }
+
+    /// fn store_set(entity: &str, id: &str, data: Entity)
+    pub fn rust_store_set(
+        &mut self,
+        gas: &GasCounter,
+        entity_ptr: u32,
+        entity_len: u32,
+        id_ptr: u32,
+        id_len: u32,
+        data_ptr: u32,
+        data_len: u32,
+    ) -> Result<(), HostExportError> {
+        let stopwatch = self.as_ref().host_metrics.stopwatch.cheap_clone();
+        let logger = self.as_ref().ctx.logger.cheap_clone();
+        let block_number = self.as_ref().ctx.block_ptr.block_number();
+        stopwatch.start_section("host_export_store_set__wasm_instance_context_store_set");
+
+        let entity = self.rust_read_str(entity_ptr, entity_len, gas)?;
+        let id = self.rust_read_str(id_ptr, id_len, gas)?;
+        let data =
+            HashMap::<Word, Value>::from_rust_bytes(&self.rust_read(data_ptr, data_len, gas)?)?;
+
+        if self.as_ref().ctx.instrument {
+            debug!(self.as_ref().ctx.logger, "store_set";
+                    "type" => &entity,
+                    "id" => &id);
+        }
+
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+
+        host_exports.store_set(
+            &logger,
+            block_number,
+            &mut ctx.state,
+            &ctx.proof_of_indexing,
+            ctx.timestamp,
+            entity,
+            id,
+            data,
+            &stopwatch,
+            gas,
+        )
+    }
+
+    /// fn store_get(entity: &str, id: &str) -> Option<Entity>
+    pub fn rust_store_get(
+        &mut self,
+        gas: &GasCounter,
+        entity_ptr: u32,
+        entity_len: u32,
+        id_ptr: u32,
+        id_len: u32,
+    ) -> Result<u32, HostExportError> {
+        let _timer = self
+            .as_ref()
+            .host_metrics
+            .cheap_clone()
+            .time_host_fn_execution_region("store_get");
+
+        let entity_type = self.rust_read_str(entity_ptr, entity_len, gas)?;
+        let id = self.rust_read_str(id_ptr, id_len, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let entity = host_exports.store_get(
+            &mut self.as_mut().ctx.state,
+            entity_type.clone(),
+            id.clone(),
+            gas,
+            GetScope::Store,
+        )?;
+
+        if self.as_ref().ctx.instrument {
+            debug!(self.as_ref().ctx.logger, "store_get";
+                    "type" => &entity_type,
+                    "id" => &id,
+                    "found" => entity.is_some());
+        }
+
+        match entity {
+            Some(entity) => self.rust_write(&entity.to_rust_bytes(), gas),
+            None => Ok(0),
+        }
+    }
+
+    /// fn store_remove(entity: &str, id: &str)
+    pub fn rust_store_remove(
+        &mut self,
+        gas: &GasCounter,
+        entity_ptr: u32,
+        entity_len: u32,
+        id_ptr: u32,
+        id_len: u32,
+    ) -> Result<(), HostExportError> {
+        let logger = self.as_ref().ctx.logger.cheap_clone();
+
+        let entity = self.rust_read_str(entity_ptr, entity_len, gas)?;
+        let id = self.rust_read_str(id_ptr, id_len, gas)?;
+        if self.as_ref().ctx.instrument {
+            debug!(self.as_ref().ctx.logger, "store_remove";
+                    "type" => &entity,
+                    "id" => &id);
+        }
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+        host_exports.store_remove(
+            &logger,
+            &mut ctx.state,
+            &ctx.proof_of_indexing,
+            entity,
+            id,
+            gas,
+        )
+    }
+
+    /// fn log(level: u32, msg: &str)
+    ///
+    /// The levels are the same as for AssemblyScript mappings, from 0 for
+    /// critical to 4 for debug
+    pub fn rust_log(
+        &mut self,
+        gas: &GasCounter,
+        level: u32,
+        msg_ptr: u32,
+        msg_len: u32,
+    ) -> Result<(), DeterministicHostError> {
+        let level = LogLevel::from(level).into();
+        let msg = self.rust_read_str(msg_ptr, msg_len, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+        host_exports.log_log(&ctx.mapping_logger, level, msg, gas, &mut ctx.state)
+    }
+
+    /// fn panic(msg: &str) -> !
+    pub fn rust_panic(
+        &mut self,
+        gas: &GasCounter,
+        msg_ptr: u32,
+        msg_len: u32,
+    ) -> Result<Never, DeterministicHostError> {
+        let msg = self.rust_read_str(msg_ptr, msg_len, gas)?;
+        let host_exports = self.as_ref().ctx.host_exports.cheap_clone();
+        let ctx = &mut self.as_mut().ctx;
+        host_exports.abort(Some(msg), None, None, None, gas, &mut ctx.state)
+    }
+}
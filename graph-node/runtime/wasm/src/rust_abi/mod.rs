@@ -0,0 +1,80 @@
+//! The native ABI for mappings written in Rust, used for mappings whose
+//! `language` in the manifest is `wasm/rust`.
+//!
+//! Unlike the AssemblyScript ABI, the host does not need to know anything
+//! about the memory layout of the guest. All values are passed as byte
+//! strings in the encoding defined in `codec`:
+//!
+//! - The module must export its `memory` and a function
+//!   `allocate(size: u32) -> u32` that returns a pointer to `size` bytes
+//!   that the host may write to.
+//! - Handlers have the signature `handler(ptr: u32)`. `ptr` points to the
+//!   encoded trigger, prefixed with its length as a `u32`.
+//! - The mapping passes values to the host as a pointer and a length.
+//!   Values returned by the host are allocated with `allocate` and prefixed
+//!   with their length; a null pointer stands for a missing value.
+//! - All host functions are imported from the module `NAMESPACE`. When the
+//!   ABI changes in an incompatible way, it gets a new namespace, and the
+//!   runtime keeps supporting the old ones.
+//!
+//! The host functions of version 1 are
+//!
+//! - `store_set(entity_ptr, entity_len, id_ptr, id_len, data_ptr, data_len)`
+//! - `store_get(entity_ptr, entity_len, id_ptr, id_len) -> ptr`
+//! - `store_remove(entity_ptr, entity_len, id_ptr, id_len)`
+//! - `log(level, msg_ptr, msg_len)`
+//! - `panic(msg_ptr, msg_len)`
+//!
+//! Entity types, ids and log messages are passed as plain UTF-8 strings.
+use anyhow::Context;
+use graph::runtime::HostExportError;
+use wasmtime::{AsContext, AsContextMut, Memory};
+
+use crate::module::WasmInstanceContext;
+
+pub mod codec;
+mod context;
+mod to_from;
+
+pub use codec::{Decoder, Encoder, FromRustBytes, ToRustBytes};
+
+/// The module from which mappings import the host functions of the Rust
+/// ABI
+pub const NAMESPACE: &str = "graph_rust_v1";
+
+/// The names of the host functions in `NAMESPACE`. Rust mappings can not
+/// import anything else, in particular not the host functions of the
+/// AssemblyScript ABI, which would fail when called
+pub const HOST_FNS: &[&str] = &["store_set", "store_get", "store_remove", "log", "panic"];
+
+/// Encode a trigger for a mapping that uses the Rust ABI. Chains that do not
+/// support Rust mappings can rely on the default implementation
+pub trait ToRustTrigger {
+    fn to_rust_trigger(&self) -> Result<Vec<u8>, HostExportError> {
+        Err(HostExportError::Deterministic(anyhow::anyhow!(
+            "the triggers of this chain can not be passed to Rust mappings"
+        )))
+    }
+}
+
+/// The parts of a Rust module that the host needs to exchange data with it
+pub struct RustHeapCtx {
+    memory: Memory,
+    allocate: wasmtime::TypedFunc<u32, u32>,
+}
+
+impl RustHeapCtx {
+    pub(crate) fn new(
+        instance: &wasmtime::Instance,
+        ctx: &mut WasmInstanceContext<'_>,
+    ) -> anyhow::Result<RustHeapCtx> {
+        let memory = instance
+            .get_memory(ctx.as_context_mut(), "memory")
+            .context("Failed to find memory export in the WASM module")?;
+        let allocate = instance
+            .get_func(ctx.as_context_mut(), "allocate")
+            .context("`allocate` function not found")?
+            .typed(ctx.as_context())?;
+        Ok(RustHeapCtx { memory, allocate })
+    }
+}
@@ -0,0 +1,308 @@
+//! Implementations of `ToRustBytes` and `FromRustBytes` for the types of
+//! the store, and of `ToRustTrigger` for the triggers that are not specific
+//! to a chain.
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use graph::blockchain::Blockchain;
+use graph::data::store::scalar::{BigDecimal, BigInt, Bytes, Timestamp};
+use graph::data::store::Value;
+use graph::data::value::Word;
+use graph::data_source::{offchain, subgraph, MappingTrigger, TriggerWithHandler};
+use graph::prelude::Entity;
+use graph::runtime::{DeterministicHostError, HostExportError};
+
+use super::codec::{Decoder, Encoder, FromRustBytes, ToRustBytes};
+use super::ToRustTrigger;
+
+// The tags of the variants of `Value`. They are part of the ABI and must
+// never change.
+const VALUE_NULL: u8 = 0;
+const VALUE_STRING: u8 = 1;
+const VALUE_INT: u8 = 2;
+const VALUE_INT8: u8 = 3;
+const VALUE_TIMESTAMP: u8 = 4;
+const VALUE_BIG_DECIMAL: u8 = 5;
+const VALUE_BOOL: u8 = 6;
+const VALUE_LIST: u8 = 7;
+const VALUE_BYTES: u8 = 8;
+const VALUE_BIG_INT: u8 = 9;
+
+impl ToRustBytes for Bytes {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.bytes(self.as_slice())
+    }
+}
+
+impl FromRustBytes for Bytes {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        dec.bytes().map(Bytes::from)
+    }
+}
+
+/// Big integers are passed as their two's complement in little-endian
+/// order
+impl ToRustBytes for BigInt {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.bytes(&self.to_signed_bytes_le())
+    }
+}
+
+impl FromRustBytes for BigInt {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        BigInt::from_signed_bytes_le(dec.bytes()?).map_err(DeterministicHostError::from)
+    }
+}
+
+/// Big decimals are passed as strings in the same format that
+/// `BigDecimal.toString` produces for AssemblyScript mappings
+impl ToRustBytes for BigDecimal {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.str(&self.to_string())
+    }
+}
+
+impl FromRustBytes for BigDecimal {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        let s = dec.str()?;
+        BigDecimal::from_str(s)
+            .map_err(|e| DeterministicHostError::from(anyhow!("invalid BigDecimal `{}`: {}", s, e)))
+    }
+}
+
+/// Timestamps are passed as microseconds since the epoch
+impl ToRustBytes for Timestamp {
+    fn encode(&self, enc: &mut Encoder) {
+        enc.i64(self.as_microseconds_since_epoch())
+    }
+}
+
+impl FromRustBytes for Timestamp {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        Timestamp::from_microseconds_since_epoch(dec.i64()?)
+            .map_err(|e| DeterministicHostError::from(anyhow!("invalid timestamp: {}", e)))
+    }
+}
+
+impl ToRustBytes for Value {
+    fn encode(&self, enc: &mut Encoder) {
+        match self {
+            Value::Null => enc.u8(VALUE_NULL),
+            Value::String(s) => {
+                enc.u8(VALUE_STRING);
+                enc.str(s);
+            }
+            Value::Int(i) => {
+                enc.u8(VALUE_INT);
+                enc.i32(*i);
+            }
+            Value::Int8(i) => {
+                enc.u8(VALUE_INT8);
+                enc.i64(*i);
+            }
+            Value::Timestamp(ts) => {
+                enc.u8(VALUE_TIMESTAMP);
+                ts.encode(enc);
+            }
+            Value::BigDecimal(d) => {
+                enc.u8(VALUE_BIG_DECIMAL);
+                d.encode(enc);
+            }
+            Value::Bool(b) => {
+                enc.u8(VALUE_BOOL);
+                b.encode(enc);
+            }
+            Value::List(values) => {
+                enc.u8(VALUE_LIST);
+                values.encode(enc);
+            }
+            Value::Bytes(bytes) => {
+                enc.u8(VALUE_BYTES);
+                bytes.encode(enc);
+            }
+            Value::BigInt(n) => {
+                enc.u8(VALUE_BIG_INT);
+                n.encode(enc);
+            }
+        }
+    }
+}
+
+impl FromRustBytes for Value {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        let value = match dec.u8()? {
+            VALUE_NULL => Value::Null,
+            VALUE_STRING => Value::String(String::decode(dec)?),
+            VALUE_INT => Value::Int(dec.i32()?),
+            VALUE_INT8 => Value::Int8(dec.i64()?),
+            VALUE_TIMESTAMP => Value::Timestamp(Timestamp::decode(dec)?),
+            VALUE_BIG_DECIMAL => Value::BigDecimal(BigDecimal::decode(dec)?),
+            VALUE_BOOL => Value::Bool(bool::decode(dec)?),
+            VALUE_LIST => Value::List(dec.nested(Vec::decode)?),
+            VALUE_BYTES => Value::Bytes(Bytes::decode(dec)?),
+            VALUE_BIG_INT => Value::BigInt(BigInt::decode(dec)?),
+            tag => {
+                return Err(DeterministicHostError::from(anyhow!(
+                    "invalid tag `{}` for a store value",
+                    tag
+                )))
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// Entities are passed as a list of `(attribute, value)` pairs, sorted by
+/// attribute name
+impl ToRustBytes for Entity {
+    fn encode(&self, enc: &mut Encoder) {
+        self.sorted_ref().encode(enc)
+    }
+}
+
+/// The data of an entity that a mapping wants to store, encoded in the same
+/// way as an entity
+impl FromRustBytes for HashMap<Word, Value> {
+    fn decode(dec: &mut Decoder<'_>) -> Result<Self, DeterministicHostError> {
+        let fields: Vec<(String, Value)> = Vec::decode(dec)?;
+        let mut data = HashMap::with_capacity(fields.len());
+        for (name, value) in fields {
+            if data.insert(Word::from(name.clone()), value).is_some() {
+                return Err(DeterministicHostError::from(anyhow!(
+                    "attribute `{}` appears more than once",
+                    name
+                )));
+            }
+        }
+        Ok(data)
+    }
+}
+
+/// File data sources receive the content of the file
+impl ToRustTrigger for offchain::TriggerData {
+    fn to_rust_trigger(&self) -> Result<Vec<u8>, HostExportError> {
+        let mut enc = Encoder::new();
+        enc.bytes(self.data.as_ref());
+        Ok(enc.finish())
+    }
+}
+
+/// Subgraph data sources receive the entity of the source subgraph
+impl ToRustTrigger for subgraph::MappingEntityTrigger {
+    fn to_rust_trigger(&self) -> Result<Vec<u8>, HostExportError> {
+        Ok(self.data.entity.entity.to_rust_bytes())
+    }
+}
+
+impl<C: Blockchain> ToRustTrigger for MappingTrigger<C>
+where
+    C::MappingTrigger: ToRustTrigger,
+{
+    fn to_rust_trigger(&self) -> Result<Vec<u8>, HostExportError> {
+        match self {
+            MappingTrigger::Onchain(trigger) => trigger.to_rust_trigger(),
+            MappingTrigger::Offchain(trigger) => trigger.to_rust_trigger(),
+            MappingTrigger::Subgraph(trigger) => trigger.to_rust_trigger(),
+        }
+    }
+}
+
+impl<T: ToRustTrigger> ToRustTrigger for TriggerWithHandler<T> {
+    fn to_rust_trigger(&self) -> Result<Vec<u8>, HostExportError> {
+        self.trigger.to_rust_trigger()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graph::data::value::Word;
+    use graph::entity;
+    use graph::ipfs::ContentPath;
+    use graph::prelude::DeploymentHash;
+    use graph::schema::InputSchema;
+
+    use super::*;
+
+    #[test]
+    fn store_values_roundtrip() {
+        let values = vec![
+            Value::Null,
+            Value::String("hello".to_string()),
+            Value::Int(-17),
+            Value::Int8(i64::MAX),
+            Value::Timestamp(Timestamp::from_microseconds_since_epoch(1_700_000_000).unwrap()),
+            Value::BigDecimal(BigDecimal::from_str("-1.25").unwrap()),
+            Value::Bool(true),
+            Value::List(vec![Value::Int(1), Value::Null]),
+            Value::Bytes(Bytes::from([0xde, 0xad].as_slice())),
+            Value::BigInt(BigInt::from(-256i64)),
+        ];
+        for value in values {
+            let bytes = value.to_rust_bytes();
+            assert_eq!(value, Value::from_rust_bytes(&bytes).unwrap());
+        }
+
+        // Big integers are two's complement, little-endian
+        assert_eq!(
+            vec![VALUE_BIG_INT, 2, 0, 0, 0, 0x00, 0xff],
+            Value::BigInt(BigInt::from(-256i64)).to_rust_bytes()
+        );
+        assert!(Value::from_rust_bytes(&[42]).is_err());
+    }
+
+    #[test]
+    fn nesting_of_lists_is_limited() {
+        // A list nested `depth` levels deep whose innermost list is empty
+        fn nested_list(depth: usize) -> Vec<u8> {
+            let mut bytes = [VALUE_LIST, 1, 0, 0, 0].repeat(depth - 1);
+            bytes.extend_from_slice(&[VALUE_LIST, 0, 0, 0, 0]);
+            bytes
+        }
+
+        assert!(Value::from_rust_bytes(&nested_list(128)).is_ok());
+
+        let err = Value::from_rust_bytes(&nested_list(129)).unwrap_err();
+        assert!(err.to_string().contains("recursion limit"), "{err}");
+        // Deep enough to overflow the stack without the limit
+        assert!(Value::from_rust_bytes(&nested_list(1_000_000)).is_err());
+    }
+
+    #[test]
+    fn entities_are_sorted_by_attribute() {
+        let schema = InputSchema::parse_latest(
+            "type User @entity { id: String!, name: String!, age: Int! }",
+            DeploymentHash::new("rustAbi").unwrap(),
+        )
+        .unwrap();
+        let user = entity! { schema => id: "u1", name: "john", age: 42 };
+
+        let fields = Vec::<(String, Value)>::from_rust_bytes(&user.to_rust_bytes()).unwrap();
+        let names: Vec<_> = fields.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(vec!["age", "id", "name"], names);
+
+        let data = HashMap::<Word, Value>::from_rust_bytes(&user.to_rust_bytes()).unwrap();
+        assert_eq!(Some(&Value::from("john")), data.get("name"));
+
+        let duplicate = vec![
+            ("id".to_string(), Value::from("u1")),
+            ("id".to_string(), Value::from("u2")),
+        ];
+        assert!(HashMap::<Word, Value>::from_rust_bytes(&duplicate.to_rust_bytes()).is_err());
+    }
+
+    #[test]
+    fn file_triggers_pass_the_content() {
+        let trigger = offchain::TriggerData {
+            source: offchain::Source::Ipfs(
+                ContentPath::new("QmVkvoPGi9jvvuxsHDVJDgzPEzagBaWSZRYoRDzU244HjZ").unwrap(),
+            ),
+            data: std::sync::Arc::new(graph::bytes::Bytes::from_static(b"content")),
+        };
+        let bytes = trigger.to_rust_trigger().unwrap();
+        assert_eq!(
+            b"content".to_vec(),
+            Bytes::from_rust_bytes(&bytes).unwrap().to_vec()
+        );
+    }
+}
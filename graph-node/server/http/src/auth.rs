@@ -0,0 +1,347 @@
+//! API keys for the query server. Operators configure keys in the
+//! `[api_keys]` section of the configuration file, and clients send them
+//! as a bearer token in the `Authorization` header. Each key can have a
+//! rate limit, enforced with a token bucket, and a monthly quota. Usage is
+//! tracked in memory, i.e., separately on each query node, and quotas start
+//! over when a node restarts.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use graph::components::metrics::MetricsRegistry;
+use graph::components::server::query::ServerResponse;
+use graph::http_body_util::Full;
+use graph::hyper::header::{
+    ACCESS_CONTROL_ALLOW_ORIGIN, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER, WWW_AUTHENTICATE,
+};
+use graph::hyper::{HeaderMap, Response, StatusCode};
+use graph::prelude::chrono::{DateTime, Datelike, TimeZone, Utc};
+use graph::prelude::serde_json::{self, json};
+use graph::prometheus::{Counter, Gauge, IntCounter};
+
+/// The configuration of one API key
+#[derive(Clone, Debug)]
+pub struct ApiKey {
+    /// The name under which usage of the key is reported in metrics
+    pub name: String,
+    /// The secret that clients send as their bearer token
+    pub key: String,
+    /// The number of requests per second the key can make on average
+    pub rate_limit: Option<f64>,
+    /// The number of requests the key can make in a burst. Defaults to
+    /// `rate_limit`
+    pub burst: Option<u32>,
+    /// The number of requests the key can make per calendar month (UTC)
+    pub monthly_quota: Option<u64>,
+}
+
+/// Why a request was not admitted
+#[derive(Debug, PartialEq)]
+pub(crate) enum Rejection {
+    MissingKey,
+    UnknownKey,
+    RateLimited(Duration),
+    QuotaExceeded(Duration),
+}
+
+impl Rejection {
+    pub fn as_response(&self) -> ServerResponse {
+        let (status, msg, retry_after) = match self {
+            Rejection::MissingKey => (
+                StatusCode::UNAUTHORIZED,
+                "An API key is required to query this server",
+                None,
+            ),
+            Rejection::UnknownKey => (StatusCode::UNAUTHORIZED, "Invalid API key", None),
+            Rejection::RateLimited(wait) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Rate limit exceeded for this API key",
+                Some(wait),
+            ),
+            Rejection::QuotaExceeded(wait) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Monthly quota exceeded for this API key",
+                Some(wait),
+            ),
+        };
+        let body = serde_json::to_string(&json!({ "error": msg })).unwrap();
+
+        let mut response = Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*");
+        if status == StatusCode::UNAUTHORIZED {
+            response = response.header(WWW_AUTHENTICATE, "Bearer");
+        }
+        if let Some(wait) = retry_after {
+            // Round up so that clients don't retry too early
+            let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            response = response.header(RETRY_AFTER, secs.max(1));
+        }
+        response.body(Full::from(body)).unwrap()
+    }
+}
+
+/// The usage of a key that we need to track to enforce its limits
+struct Usage {
+    tokens: f64,
+    refilled_at: Instant,
+    /// The month that `used` counts requests for
+    month: (i32, u32),
+    used: u64,
+}
+
+struct KeyState {
+    /// The refill rate and capacity of the token bucket
+    rate_limit: Option<(f64, f64)>,
+    monthly_quota: Option<u64>,
+    usage: Mutex<Usage>,
+    admitted: IntCounter,
+    rate_limited: IntCounter,
+    quota_exceeded: IntCounter,
+    quota_used: Gauge,
+}
+
+impl KeyState {
+    fn admit(&self, now: Instant, today: DateTime<Utc>) -> Result<(), Rejection> {
+        let mut usage = self.usage.lock().unwrap();
+
+        let month = (today.year(), today.month());
+        if usage.month != month {
+            usage.month = month;
+            usage.used = 0;
+        }
+        if let Some(quota) = self.monthly_quota {
+            if usage.used >= quota {
+                self.quota_exceeded.inc();
+                return Err(Rejection::QuotaExceeded(until_next_month(today)));
+            }
+        }
+
+        if let Some((rate, burst)) = self.rate_limit {
+            let elapsed = now.saturating_duration_since(usage.refilled_at);
+            usage.tokens = (usage.tokens + elapsed.as_secs_f64() * rate).min(burst);
+            usage.refilled_at = now;
+            if usage.tokens < 1.0 {
+                self.rate_limited.inc();
+                let wait = Duration::from_secs_f64((1.0 - usage.tokens) / rate);
+                return Err(Rejection::RateLimited(wait));
+            }
+            usage.tokens -= 1.0;
+        }
+
+        usage.used += 1;
+        self.admitted.inc();
+        self.quota_used.set(usage.used as f64);
+        Ok(())
+    }
+}
+
+fn until_next_month(today: DateTime<Utc>) -> Duration {
+    let (year, month) = match today.month() {
+        12 => (today.year() + 1, 1),
+        month => (today.year(), month + 1),
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .single()
+        .and_then(|next| (next - today).to_std().ok())
+        .unwrap_or_default()
+}
+
+/// The API keys that the query server accepts
+pub struct ApiKeys {
+    /// Whether requests without an API key should be rejected
+    required: bool,
+    /// The configured keys, indexed by the hash of their secret so that
+    /// looking up a key does not leak its secret through timing
+    keys: HashMap<blake3::Hash, KeyState>,
+    unauthorized: Box<Counter>,
+}
+
+impl ApiKeys {
+    pub fn new(registry: &MetricsRegistry, required: bool, keys: Vec<ApiKey>) -> Self {
+        let requests = registry
+            .new_int_counter_vec(
+                "query_api_key_requests",
+                "Counts query requests made with each API key by whether they were admitted",
+                &["key", "result"],
+            )
+            .expect("failed to create `query_api_key_requests` counter");
+        let quota_used = registry
+            .new_gauge_vec(
+                "query_api_key_quota_used",
+                "The number of requests each API key has made this month",
+                vec!["key".to_string()],
+            )
+            .expect("failed to create `query_api_key_quota_used` gauge");
+        let unauthorized = registry
+            .new_counter(
+                "query_api_key_unauthorized",
+                "Counts query requests rejected because of a missing or unknown API key",
+            )
+            .expect("failed to create `query_api_key_unauthorized` counter");
+
+        let now = Instant::now();
+        let today = Utc::now();
+        let keys = keys
+            .into_iter()
+            .map(|key| {
+                let rate_limit = key
+                    .rate_limit
+                    .map(|rate| (rate, key.burst.map(f64::from).unwrap_or(rate).max(1.0)));
+                let state = KeyState {
+                    rate_limit,
+                    monthly_quota: key.monthly_quota,
+                    usage: Mutex::new(Usage {
+                        tokens: rate_limit.map(|(_, burst)| burst).unwrap_or_default(),
+                        refilled_at: now,
+                        month: (today.year(), today.month()),
+                        used: 0,
+                    }),
+                    admitted: requests.with_label_values(&[&key.name, "admitted"]),
+                    rate_limited: requests.with_label_values(&[&key.name, "rate_limited"]),
+                    quota_exceeded: requests.with_label_values(&[&key.name, "quota_exceeded"]),
+                    quota_used: quota_used.with_label_values(&[&key.name]),
+                };
+                (blake3::hash(key.key.as_bytes()), state)
+            })
+            .collect();
+
+        ApiKeys {
+            required,
+            keys,
+            unauthorized,
+        }
+    }
+
+    /// Check whether the request with these `headers` may be run, and
+    /// count it against the limits of its API key if it may
+    pub(crate) fn admit(&self, headers: &HeaderMap) -> Result<(), Rejection> {
+        self.admit_at(headers, Instant::now(), Utc::now())
+    }
+
+    fn admit_at(
+        &self,
+        headers: &HeaderMap,
+        now: Instant,
+        today: DateTime<Utc>,
+    ) -> Result<(), Rejection> {
+        if self.keys.is_empty() && !self.required {
+            return Ok(());
+        }
+
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|header| header.as_bytes().strip_prefix(b"Bearer "));
+        let result = match token {
+            None if self.required => Err(Rejection::MissingKey),
+            None => Ok(()),
+            Some(token) => match self.keys.get(&blake3::hash(token)) {
+                Some(key) => return key.admit(now, today),
+                None => Err(Rejection::UnknownKey),
+            },
+        };
+        if result.is_err() {
+            self.unauthorized.inc();
+        }
+        result
+    }
+}
+
+impl Default for ApiKeys {
+    /// No API keys; all requests are admitted
+    fn default() -> Self {
+        ApiKeys::new(&MetricsRegistry::mock(), false, vec![])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use graph::hyper::header::HeaderValue;
+
+    use super::*;
+
+    fn key(name: &str, rate_limit: Option<f64>, burst: Option<u32>, quota: Option<u64>) -> ApiKey {
+        ApiKey {
+            name: name.to_string(),
+            key: format!("secret-{}", name),
+            rate_limit,
+            burst,
+            monthly_quota: quota,
+        }
+    }
+
+    fn headers(name: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(name) = name {
+            let value = format!("Bearer secret-{}", name);
+            headers.insert(AUTHORIZATION, HeaderValue::from_str(&value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn checks_keys() {
+        let keys = vec![key("franky", None, None, None)];
+
+        let api_keys = ApiKeys::new(&MetricsRegistry::mock(), false, keys.clone());
+        assert_eq!(Ok(()), api_keys.admit(&headers(None)));
+        assert_eq!(Ok(()), api_keys.admit(&headers(Some("franky"))));
+        assert_eq!(
+            Err(Rejection::UnknownKey),
+            api_keys.admit(&headers(Some("zoro")))
+        );
+
+        let api_keys = ApiKeys::new(&MetricsRegistry::mock(), true, keys);
+        assert_eq!(Err(Rejection::MissingKey), api_keys.admit(&headers(None)));
+        assert_eq!(Ok(()), api_keys.admit(&headers(Some("franky"))));
+
+        // Without any keys, we don't look at the `Authorization` header
+        let api_keys = ApiKeys::default();
+        assert_eq!(Ok(()), api_keys.admit(&headers(Some("zoro"))));
+    }
+
+    #[test]
+    fn enforces_rate_limit() {
+        let keys = vec![key("franky", Some(2.0), Some(3), None)];
+        let api_keys = ApiKeys::new(&MetricsRegistry::mock(), true, keys);
+        let headers = headers(Some("franky"));
+        let start = Instant::now();
+        let today = Utc::now();
+
+        // We can use up the burst right away
+        for _ in 0..3 {
+            assert_eq!(Ok(()), api_keys.admit_at(&headers, start, today));
+        }
+        assert_eq!(
+            Err(Rejection::RateLimited(Duration::from_millis(500))),
+            api_keys.admit_at(&headers, start, today)
+        );
+
+        // After a second, two more requests are allowed
+        let later = start + Duration::from_secs(1);
+        assert_eq!(Ok(()), api_keys.admit_at(&headers, later, today));
+        assert_eq!(Ok(()), api_keys.admit_at(&headers, later, today));
+        assert!(api_keys.admit_at(&headers, later, today).is_err());
+    }
+
+    #[test]
+    fn enforces_monthly_quota() {
+        let keys = vec![key("franky", None, None, Some(2))];
+        let api_keys = ApiKeys::new(&MetricsRegistry::mock(), true, keys);
+        let headers = headers(Some("franky"));
+        let now = Instant::now();
+        let today = Utc.with_ymd_and_hms(2024, 12, 31, 23, 0, 0).unwrap();
+
+        assert_eq!(Ok(()), api_keys.admit_at(&headers, now, today));
+        assert_eq!(Ok(()), api_keys.admit_at(&headers, now, today));
+        assert_eq!(
+            Err(Rejection::QuotaExceeded(Duration::from_secs(3600))),
+            api_keys.admit_at(&headers, now, today)
+        );
+
+        // The quota starts over with the new month
+        let next_month = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(Ok(()), api_keys.admit_at(&headers, now, next_month));
+    }
+}
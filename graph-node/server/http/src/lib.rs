@@ -2,11 +2,15 @@ extern crate graph;
 extern crate graph_graphql;
 extern crate serde;
 
+mod auth;
+mod live;
+mod persisted;
 mod request;
 mod server;
 mod service;
 
+pub use self::auth::{ApiKey, ApiKeys};
 pub use self::server::GraphQLServer;
-pub use self::service::GraphQLService;
+pub use self::service::{GraphQLResponse, GraphQLService};
 
 pub mod test_utils;
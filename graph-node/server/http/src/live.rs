@@ -0,0 +1,238 @@
+//! Live queries: a query that is sent with `Accept: text/event-stream` is
+//! rerun whenever the head of the deployment it targets moves, and every
+//! result that differs from the previous one is pushed to the client as a
+//! Server-Sent Event.
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use graph::cheap_clone::CheapClone;
+use graph::components::graphql::GraphQlRunner;
+use graph::components::server::query::ServerResponse;
+use graph::components::store::{DeploymentId, StoreEvent, SubscriptionManager};
+use graph::data::query::{Query, QueryTarget};
+use graph::env::ENV_VARS;
+use graph::futures03::compat::Stream01CompatExt;
+use graph::futures03::StreamExt;
+use graph::http_body_util::{Full, StreamBody};
+use graph::hyper::body::{Bytes, Frame};
+use graph::hyper::header::{ACCESS_CONTROL_ALLOW_ORIGIN, CONTENT_TYPE};
+use graph::hyper::{Response, StatusCode};
+use graph::prelude::serde_json::{self, json};
+use graph::prelude::BlockNumber;
+use graph::slog::{debug, Logger};
+use graph::tokio::{self, sync::mpsc};
+use graph::tokio_stream::wrappers::ReceiverStream;
+
+pub type LiveQueryBody = StreamBody<ReceiverStream<Result<Frame<Bytes>, Infallible>>>;
+
+/// Keeps track of how many live queries are active so that we can enforce
+/// `GRAPH_GRAPHQL_MAX_LIVE_QUERIES`
+#[derive(Debug)]
+pub struct LiveQueries {
+    active: Arc<AtomicUsize>,
+    max: usize,
+}
+
+impl Default for LiveQueries {
+    fn default() -> Self {
+        Self::new(ENV_VARS.graphql.max_live_queries)
+    }
+}
+
+impl LiveQueries {
+    pub fn new(max: usize) -> Self {
+        Self {
+            active: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    /// Reserve a slot for a new live query. Returns `None` if the maximum
+    /// number of live queries is already active. The slot is released when
+    /// the returned guard is dropped
+    pub fn acquire(&self) -> Option<LiveQueryGuard> {
+        let active = self.active.fetch_add(1, Ordering::SeqCst);
+        let guard = LiveQueryGuard(self.active.cheap_clone());
+        if active >= self.max {
+            return None;
+        }
+        Some(guard)
+    }
+}
+
+/// The response for a live query that is rejected because the maximum
+/// number of live queries is already active
+pub fn unavailable() -> ServerResponse {
+    let body = serde_json::to_string(&json!({
+        "error": "Too many live queries, try again later"
+    }))
+    .unwrap();
+    Response::builder()
+        .status(StatusCode::SERVICE_UNAVAILABLE)
+        .header(CONTENT_TYPE, "application/json")
+        .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(Full::from(body))
+        .unwrap()
+}
+
+pub struct LiveQueryGuard(Arc<AtomicUsize>);
+
+impl Drop for LiveQueryGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Start running `query` as a live query and return the body of the
+/// response through which results are streamed. The live query stops as
+/// soon as the client goes away
+pub fn start<Q: GraphQlRunner>(
+    logger: Logger,
+    runner: Arc<Q>,
+    subscription_manager: Arc<dyn SubscriptionManager>,
+    guard: LiveQueryGuard,
+    query: Query,
+    target: QueryTarget,
+) -> LiveQueryBody {
+    let (sender, receiver) = mpsc::channel(1);
+
+    graph::spawn(async move {
+        let _guard = guard;
+        run(&logger, runner, subscription_manager, query, target, sender).await;
+        debug!(logger, "Live query finished");
+    });
+
+    StreamBody::new(ReceiverStream::new(receiver))
+}
+
+async fn run<Q: GraphQlRunner>(
+    logger: &Logger,
+    runner: Arc<Q>,
+    subscription_manager: Arc<dyn SubscriptionManager>,
+    query: Query,
+    target: QueryTarget,
+    sender: mpsc::Sender<Result<Frame<Bytes>, Infallible>>,
+) {
+    // Store events signal changes to deployment assignments, for example
+    // when a new version of a subgraph becomes current. They do not say
+    // which entities changed, and are therefore only used to notice
+    // sooner that the deployment the query reads from changed
+    let mut events = subscription_manager.subscribe().compat();
+    let mut events_done = false;
+
+    let mut interval = tokio::time::interval(ENV_VARS.graphql.live_query_poll_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The first tick completes immediately
+    interval.tick().await;
+
+    let mut head = runner.cheap_clone().deployment_head(target.clone()).await;
+    let mut last_result: Option<String> = None;
+    let mut rerun = true;
+
+    loop {
+        if rerun {
+            let result = runner
+                .cheap_clone()
+                .run_query(query.clone(), target.clone())
+                .await;
+            let result = serde_json::to_string(&result).unwrap();
+            if last_result.as_ref() != Some(&result) {
+                if sender.send(Ok(event("next", &result))).await.is_err() {
+                    return;
+                }
+                last_result = Some(result);
+            }
+        }
+
+        tokio::select! {
+            _ = sender.closed() => return,
+            event = events.next(), if !events_done => {
+                match event {
+                    Some(Ok(event)) => {
+                        rerun = affects(&event, &target)
+                            && head_moved(&runner, &target, &mut head).await;
+                    }
+                    Some(Err(())) | None => {
+                        debug!(logger, "Store event stream ended, live query relies on polling only");
+                        events_done = true;
+                        rerun = false;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                rerun = head_moved(&runner, &target, &mut head).await;
+            }
+        }
+    }
+}
+
+/// Whether `event` can change what `target` resolves to. A query by name
+/// is affected by any assignment change since it might be for a new
+/// version of the subgraph
+fn affects(event: &StoreEvent, target: &QueryTarget) -> bool {
+    match target {
+        QueryTarget::Name(_, _) => true,
+        QueryTarget::Deployment(hash, _) => event
+            .changes
+            .iter()
+            .any(|change| &change.deployment().hash == hash),
+    }
+}
+
+/// Check whether the deployment `target` resolves to or its head changed
+/// since `head` was determined, and remember the new head
+async fn head_moved<Q: GraphQlRunner>(
+    runner: &Arc<Q>,
+    target: &QueryTarget,
+    head: &mut Option<(DeploymentId, BlockNumber)>,
+) -> bool {
+    let new_head = runner.cheap_clone().deployment_head(target.clone()).await;
+    let moved = new_head.is_none() || new_head != *head;
+    *head = new_head;
+    moved
+}
+
+/// Format a Server-Sent Event. `data` must not contain newlines, which is
+/// always true for compact JSON
+fn event(name: &str, data: &str) -> Frame<Bytes> {
+    Frame::data(Bytes::from(format!("event: {}\ndata: {}\n\n", name, data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use graph::components::store::{AssignmentChange, DeploymentLocator};
+    use graph::components::versions::ApiVersion;
+    use graph::data::query::QueryTarget;
+    use graph::prelude::{DeploymentHash, SubgraphName};
+
+    use super::*;
+
+    #[test]
+    fn live_queries_are_limited() {
+        let live_queries = LiveQueries::new(1);
+        let guard = live_queries
+            .acquire()
+            .expect("first live query is admitted");
+        assert!(live_queries.acquire().is_none());
+        drop(guard);
+        assert!(live_queries.acquire().is_some());
+    }
+
+    #[test]
+    fn events_only_affect_their_deployment() {
+        let hash = DeploymentHash::new("QmLiveQueries").unwrap();
+        let other = DeploymentHash::new("QmOther").unwrap();
+        let event = StoreEvent::new(vec![AssignmentChange::set(DeploymentLocator::new(
+            DeploymentId(1),
+            other.clone(),
+        ))]);
+
+        let by_id = QueryTarget::Deployment(hash, ApiVersion::default());
+        assert!(!affects(&event, &by_id));
+        let by_id = QueryTarget::Deployment(other, ApiVersion::default());
+        assert!(affects(&event, &by_id));
+        let by_name = QueryTarget::Name(SubgraphName::new("users").unwrap(), ApiVersion::default());
+        assert!(affects(&event, &by_name));
+    }
+}
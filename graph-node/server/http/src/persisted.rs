@@ -0,0 +1,146 @@
+//! Support for persisted queries. Clients can register queries as
+//! automatic persisted queries (APQ) by sending their text together with
+//! its hash, and can later run them by sending just the hash. The operator
+//! can also register queries in an allow-list with `graphman
+//! persisted-query`; when `GRAPH_GRAPHQL_PERSISTED_QUERIES_ONLY` is set,
+//! only queries in that list can be run.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+use graph::components::server::query::ServerError;
+use graph::components::store::PersistedQueryStore;
+use graph::data::query::{persisted_query_hash, QueryError, QueryExecutionError};
+use graph::env::ENV_VARS;
+use graph::slog::{warn, Logger};
+use graph::util::lfu_cache::LfuCache;
+
+use crate::request::GraphQLRequest;
+
+/// The allow-list as of the last time we loaded it from the store
+#[derive(Default)]
+struct AllowList {
+    loaded_at: Option<Instant>,
+    queries: Arc<HashMap<String, String>>,
+}
+
+pub struct PersistedQueries {
+    logger: Logger,
+    store: Arc<dyn PersistedQueryStore>,
+    allow_list: RwLock<AllowList>,
+    /// Queries that clients registered as automatic persisted queries
+    registered: Mutex<LfuCache<String, String>>,
+}
+
+impl PersistedQueries {
+    pub fn new(logger: Logger, store: Arc<dyn PersistedQueryStore>) -> Self {
+        Self {
+            logger,
+            store,
+            allow_list: RwLock::new(AllowList::default()),
+            registered: Mutex::new(LfuCache::new()),
+        }
+    }
+
+    /// Fill in the text of the query for requests that only contain the
+    /// hash of a persisted query, register queries that clients send with
+    /// their hash, and enforce the allow-list if only persisted queries
+    /// can be run
+    pub async fn resolve(&self, request: GraphQLRequest) -> Result<GraphQLRequest, ServerError> {
+        let only_persisted = ENV_VARS.graphql.persisted_queries_only;
+
+        match (&request.query, &request.persisted_hash) {
+            (Some(query), hash) => {
+                let actual = persisted_query_hash(query);
+                if hash.as_ref().is_some_and(|hash| hash != &actual) {
+                    return Err(ServerError::ClientError(
+                        "The \"sha256Hash\" of the persisted query does not match the query"
+                            .to_string(),
+                    ));
+                }
+
+                if only_persisted {
+                    if !self.allow_list().await?.contains_key(&actual) {
+                        return Err(not_allowed());
+                    }
+                } else if hash.is_some() {
+                    let mut registered = self.registered.lock().unwrap();
+                    registered.insert(actual, query.clone());
+                    registered.evict(ENV_VARS.graphql.apq_cache_max_mem);
+                }
+                Ok(request)
+            }
+            (None, Some(hash)) => {
+                let query = if only_persisted {
+                    None
+                } else {
+                    self.registered.lock().unwrap().get(hash).cloned()
+                };
+                let query = match query {
+                    Some(query) => Some(query),
+                    None => self.allow_list().await?.get(hash).cloned(),
+                };
+
+                match query {
+                    Some(query) => Ok(GraphQLRequest {
+                        query: Some(query),
+                        ..request
+                    }),
+                    None => Err(not_found()),
+                }
+            }
+            (None, None) => Ok(request),
+        }
+    }
+
+    /// Return the allow-list, reloading it from the store if it is older
+    /// than the refresh interval. If reloading fails, we keep using the
+    /// list we loaded previously
+    async fn allow_list(&self) -> Result<Arc<HashMap<String, String>>, ServerError> {
+        let stale = {
+            let list = self.allow_list.read().unwrap();
+            match list.loaded_at {
+                Some(loaded_at)
+                    if loaded_at.elapsed()
+                        < ENV_VARS.graphql.persisted_queries_refresh_interval =>
+                {
+                    return Ok(list.queries.clone());
+                }
+                Some(_) => Some(list.queries.clone()),
+                None => None,
+            }
+        };
+
+        match self.store.persisted_queries().await {
+            Ok(queries) => {
+                let queries = Arc::new(queries.into_iter().collect::<HashMap<_, _>>());
+                let mut list = self.allow_list.write().unwrap();
+                list.loaded_at = Some(Instant::now());
+                list.queries = queries.clone();
+                Ok(queries)
+            }
+            Err(e) => match stale {
+                Some(queries) => {
+                    warn!(
+                        self.logger,
+                        "Failed to reload persisted queries";
+                        "error" => e.to_string()
+                    );
+                    Ok(queries)
+                }
+                None => Err(ServerError::InternalError(format!(
+                    "failed to load persisted queries: {}",
+                    e
+                ))),
+            },
+        }
+    }
+}
+
+fn not_found() -> ServerError {
+    QueryError::from(QueryExecutionError::PersistedQueryNotFound).into()
+}
+
+fn not_allowed() -> ServerError {
+    QueryError::from(QueryExecutionError::PersistedQueryNotAllowed).into()
+}
@@ -3,8 +3,37 @@ use graph::prelude::serde_json;
 use graph::components::server::query::ServerError;
 use graph::hyper::body::Bytes;
 use graph::prelude::*;
+use graph::url::form_urlencoded;
 
-pub fn parse_graphql_request(body: &Bytes, trace: bool) -> Result<Query, ServerError> {
+/// A GraphQL request as sent by a client. The text of the query can be
+/// missing if the client only sent the hash of a persisted query
+#[derive(Debug)]
+pub struct GraphQLRequest {
+    pub query: Option<String>,
+    pub variables: Option<QueryVariables>,
+    /// The `sha256Hash` from the `persistedQuery` extension
+    pub persisted_hash: Option<String>,
+}
+
+impl GraphQLRequest {
+    /// Parse the text of the query and turn this request into a `Query`
+    pub fn into_query(self, trace: bool) -> Result<Query, ServerError> {
+        let query_string = self.query.ok_or_else(|| {
+            ServerError::ClientError(String::from(
+                "The \"query\" field is missing in request data",
+            ))
+        })?;
+
+        // Parse the "query" field of the JSON body
+        let document = q::parse_query(&query_string)
+            .map_err(|e| ServerError::from(QueryError::ParseError(Arc::new(e.into()))))?
+            .into_static();
+
+        Ok(Query::new(document, self.variables, trace))
+    }
+}
+
+pub fn parse_graphql_request(body: &Bytes) -> Result<GraphQLRequest, ServerError> {
     // Parse request body as JSON
     let json: serde_json::Value =
         serde_json::from_slice(body).map_err(|e| ServerError::ClientError(format!("{}", e)))?;
@@ -14,22 +43,59 @@ pub fn parse_graphql_request(body: &Bytes, trace: bool) -> Result<Query, ServerE
         .as_object()
         .ok_or_else(|| ServerError::ClientError(String::from("Request data is not an object")))?;
 
-    // Ensure the JSON data has a "query" field
-    let query_value = obj.get("query").ok_or_else(|| {
-        ServerError::ClientError(String::from(
-            "The \"query\" field is missing in request data",
-        ))
-    })?;
+    parse_graphql_object(obj)
+}
+
+/// Parse a GraphQL request that was passed in the `query`, `variables` and
+/// `extensions` parameters of a URL query string, as is done for `GET`
+/// requests. The `variables` and `extensions` parameters must be JSON
+/// objects
+pub fn parse_graphql_query_string(query: &str) -> Result<GraphQLRequest, ServerError> {
+    let mut obj = serde_json::Map::new();
+    for (key, value) in form_urlencoded::parse(query.as_bytes()) {
+        match key.as_ref() {
+            "query" => {
+                obj.insert(
+                    "query".to_string(),
+                    serde_json::Value::String(value.into_owned()),
+                );
+            }
+            "variables" | "extensions" => {
+                let json = serde_json::from_str(&value)
+                    .map_err(|e| ServerError::ClientError(format!("{}", e)))?;
+                obj.insert(key.into_owned(), json);
+            }
+            _ => { /* ignore other parameters like `api-version` */ }
+        }
+    }
+
+    parse_graphql_object(&obj)
+}
 
-    // Ensure the "query" field is a string
-    let query_string = query_value.as_str().ok_or_else(|| {
-        ServerError::ClientError(String::from("The \"query\" field is not a string"))
-    })?;
+fn parse_graphql_object(
+    obj: &serde_json::Map<String, serde_json::Value>,
+) -> Result<GraphQLRequest, ServerError> {
+    let persisted_hash = parse_persisted_query(obj)?;
 
-    // Parse the "query" field of the JSON body
-    let document = q::parse_query(query_string)
-        .map_err(|e| ServerError::from(QueryError::ParseError(Arc::new(e.into()))))?
-        .into_static();
+    // Ensure the JSON data has a "query" field, unless the client asks for
+    // a persisted query
+    let query = match (obj.get("query"), &persisted_hash) {
+        (None, Some(_)) | (Some(serde_json::Value::Null), Some(_)) => None,
+        (None, None) => {
+            return Err(ServerError::ClientError(String::from(
+                "The \"query\" field is missing in request data",
+            )))
+        }
+        // Ensure the "query" field is a string
+        (Some(query_value), _) => Some(
+            query_value
+                .as_str()
+                .ok_or_else(|| {
+                    ServerError::ClientError(String::from("The \"query\" field is not a string"))
+                })?
+                .to_string(),
+        ),
+    };
 
     // Parse the "variables" field of the JSON body, if present
     let variables = match obj.get("variables") {
@@ -42,7 +108,41 @@ pub fn parse_graphql_request(body: &Bytes, trace: bool) -> Result<Query, ServerE
         )),
     }?;
 
-    Ok(Query::new(document, variables, trace))
+    Ok(GraphQLRequest {
+        query,
+        variables,
+        persisted_hash,
+    })
+}
+
+/// Extract the hash from an `extensions` field of the form
+/// `{ "persistedQuery": { "version": 1, "sha256Hash": "<hash>" } }`
+fn parse_persisted_query(
+    obj: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Option<String>, ServerError> {
+    let persisted = match obj
+        .get("extensions")
+        .and_then(|extensions| extensions.get("persistedQuery"))
+    {
+        None | Some(serde_json::Value::Null) => return Ok(None),
+        Some(persisted) => persisted,
+    };
+
+    if persisted.get("version").and_then(|v| v.as_i64()) != Some(1) {
+        return Err(ServerError::ClientError(
+            "Unsupported persisted query version".to_string(),
+        ));
+    }
+    let hash = persisted
+        .get("sha256Hash")
+        .and_then(|hash| hash.as_str())
+        .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| {
+            ServerError::ClientError(
+                "The \"sha256Hash\" of a persisted query must be a SHA-256 hash in hex".to_string(),
+            )
+        })?;
+    Ok(Some(hash.to_lowercase()))
 }
 
 #[cfg(test)]
@@ -59,7 +159,17 @@ mod tests {
         prelude::*,
     };
 
-    use super::parse_graphql_request;
+    use graph::components::server::query::ServerError;
+
+    use super::{parse_graphql_query_string, parse_graphql_request};
+
+    fn parse_request(body: &Bytes) -> Result<Query, ServerError> {
+        parse_graphql_request(body)?.into_query(false)
+    }
+
+    fn parse_query_string(query: &str) -> Result<Query, ServerError> {
+        parse_graphql_query_string(query)?.into_query(false)
+    }
 
     lazy_static! {
         static ref TARGET: QueryTarget = QueryTarget::Name(
@@ -70,32 +180,31 @@ mod tests {
 
     #[test]
     fn rejects_invalid_json() {
-        let request = parse_graphql_request(&Bytes::from("!@#)%"), false);
+        let request = parse_request(&Bytes::from("!@#)%"));
         request.expect_err("Should reject invalid JSON");
     }
 
     #[test]
     fn rejects_json_without_query_field() {
-        let request = parse_graphql_request(&Bytes::from("{}"), false);
+        let request = parse_request(&Bytes::from("{}"));
         request.expect_err("Should reject JSON without query field");
     }
 
     #[test]
     fn rejects_json_with_non_string_query_field() {
-        let request = parse_graphql_request(&Bytes::from("{\"query\": 5}"), false);
+        let request = parse_request(&Bytes::from("{\"query\": 5}"));
         request.expect_err("Should reject JSON with a non-string query field");
     }
 
     #[test]
     fn rejects_broken_queries() {
-        let request = parse_graphql_request(&Bytes::from("{\"query\": \"foo\"}"), false);
+        let request = parse_request(&Bytes::from("{\"query\": \"foo\"}"));
         request.expect_err("Should reject broken queries");
     }
 
     #[test]
     fn accepts_valid_queries() {
-        let request =
-            parse_graphql_request(&Bytes::from("{\"query\": \"{ user { name } }\"}"), false);
+        let request = parse_request(&Bytes::from("{\"query\": \"{ user { name } }\"}"));
         let query = request.expect("Should accept valid queries");
         assert_eq!(
             query.document,
@@ -105,16 +214,13 @@ mod tests {
 
     #[test]
     fn accepts_null_variables() {
-        let request = parse_graphql_request(
-            &Bytes::from(
-                "\
+        let request = parse_request(&Bytes::from(
+            "\
                  {\
                  \"query\": \"{ user { name } }\", \
                  \"variables\": null \
                  }",
-            ),
-            false,
-        );
+        ));
         let query = request.expect("Should accept null variables");
 
         let expected_query = q::parse_query("{ user { name } }").unwrap().into_static();
@@ -124,33 +230,27 @@ mod tests {
 
     #[test]
     fn rejects_non_map_variables() {
-        let request = parse_graphql_request(
-            &Bytes::from(
-                "\
+        let request = parse_request(&Bytes::from(
+            "\
                  {\
                  \"query\": \"{ user { name } }\", \
                  \"variables\": 5 \
                  }",
-            ),
-            false,
-        );
+        ));
         request.expect_err("Should reject non-map variables");
     }
 
     #[test]
     fn parses_variables() {
-        let request = parse_graphql_request(
-            &Bytes::from(
-                "\
+        let request = parse_request(&Bytes::from(
+            "\
                  {\
                  \"query\": \"{ user { name } }\", \
                  \"variables\": { \
                  \"string\": \"s\", \"map\": {\"k\": \"v\"}, \"int\": 5 \
                  } \
                  }",
-            ),
-            false,
-        );
+        ));
         let query = request.expect("Should accept valid queries");
 
         let expected_query = q::parse_query("{ user { name } }").unwrap().into_static();
@@ -171,4 +271,51 @@ mod tests {
         assert_eq!(query.document, expected_query);
         assert_eq!(query.variables, Some(expected_variables));
     }
+
+    #[test]
+    fn parses_query_strings() {
+        let query = parse_query_string("query=%7B%20user%20%7B%20name%20%7D%20%7D&variables=%7B%22int%22%3A5%7D&api-version=0.0.4")
+        .expect("Should accept valid query strings");
+
+        let expected_query = q::parse_query("{ user { name } }").unwrap().into_static();
+        let expected_variables = QueryVariables::new(HashMap::from_iter(
+            vec![(String::from("int"), r::Value::Int(5))].into_iter(),
+        ));
+
+        assert_eq!(query.document, expected_query);
+        assert_eq!(query.variables, Some(expected_variables));
+
+        parse_query_string("variables=%7B%7D")
+            .expect_err("Should reject query strings without a query");
+        parse_query_string("query=%7B%20user%20%7D&variables=5")
+            .expect_err("Should reject non-map variables");
+    }
+
+    #[test]
+    fn parses_persisted_queries() {
+        const HASH: &str = "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38";
+
+        fn body(version: i32, hash: &str) -> Bytes {
+            Bytes::from(format!(
+                "{{\"extensions\": {{\"persistedQuery\": \
+                 {{\"version\": {version}, \"sha256Hash\": \"{hash}\"}}}}}}"
+            ))
+        }
+
+        let request =
+            parse_graphql_request(&body(1, HASH)).expect("Should accept a persisted query hash");
+        assert_eq!(request.query, None);
+        assert_eq!(request.persisted_hash.as_deref(), Some(HASH));
+        parse_request(&body(1, HASH)).expect_err("Should require the query text to run a query");
+
+        parse_graphql_request(&body(2, HASH)).expect_err("Should reject unknown versions");
+        parse_graphql_request(&body(1, "not-a-hash")).expect_err("Should reject invalid hashes");
+
+        let request = parse_graphql_query_string(&format!(
+            "extensions=%7B%22persistedQuery%22%3A%7B%22version%22%3A1%2C%22sha256Hash%22%3A%22{}%22%7D%7D",
+            HASH.to_uppercase()
+        ))
+        .expect("Should accept a persisted query hash in query strings");
+        assert_eq!(request.persisted_hash.as_deref(), Some(HASH));
+    }
 }
@@ -3,9 +3,11 @@ use std::sync::Arc;
 use graph::anyhow;
 use graph::cheap_clone::CheapClone;
 use graph::components::server::server::{start, ServerHandle};
+use graph::components::store::{PersistedQueryStore, SubscriptionManager};
 use graph::log::factory::{ComponentLoggerConfig, ElasticComponentLoggerConfig};
 use graph::slog::info;
 
+use crate::auth::ApiKeys;
 use crate::service::GraphQLService;
 use graph::prelude::{GraphQlRunner, Logger, LoggerFactory};
 
@@ -13,11 +15,20 @@ use graph::prelude::{GraphQlRunner, Logger, LoggerFactory};
 pub struct GraphQLServer<Q> {
     logger: Logger,
     graphql_runner: Arc<Q>,
+    subscription_manager: Arc<dyn SubscriptionManager>,
+    persisted_queries: Arc<dyn PersistedQueryStore>,
+    api_keys: Arc<ApiKeys>,
 }
 
 impl<Q: GraphQlRunner> GraphQLServer<Q> {
     /// Creates a new GraphQL server.
-    pub fn new(logger_factory: &LoggerFactory, graphql_runner: Arc<Q>) -> Self {
+    pub fn new(
+        logger_factory: &LoggerFactory,
+        graphql_runner: Arc<Q>,
+        subscription_manager: Arc<dyn SubscriptionManager>,
+        persisted_queries: Arc<dyn PersistedQueryStore>,
+        api_keys: Arc<ApiKeys>,
+    ) -> Self {
         let logger = logger_factory.component_logger(
             "GraphQLServer",
             Some(ComponentLoggerConfig {
@@ -29,6 +40,9 @@ impl<Q: GraphQlRunner> GraphQLServer<Q> {
         GraphQLServer {
             logger,
             graphql_runner,
+            subscription_manager,
+            persisted_queries,
+            api_keys,
         }
     }
 
@@ -42,11 +56,17 @@ impl<Q: GraphQlRunner> GraphQLServer<Q> {
 
         let graphql_runner = self.graphql_runner.clone();
 
-        let service = Arc::new(GraphQLService::new(logger.clone(), graphql_runner));
+        let service = Arc::new(GraphQLService::new(
+            logger.clone(),
+            graphql_runner,
+            self.subscription_manager.cheap_clone(),
+            self.persisted_queries.cheap_clone(),
+            self.api_keys.cheap_clone(),
+        ));
 
         start(logger, port, move |req| {
             let service = service.cheap_clone();
-            async move { Ok::<_, _>(service.cheap_clone().call(req).await) }
+            async move { Ok::<_, _>(service.cheap_clone().serve(req).await) }
         })
         .await
     }
@@ -1,4 +1,4 @@
-use std::convert::TryFrom;
+use std::convert::{Infallible, TryFrom};
 use std::env;
 use std::sync::Arc;
 use std::time::Instant;
@@ -7,15 +7,18 @@ use graph::cheap_clone::CheapClone;
 use graph::components::graphql::GraphQlRunner;
 use graph::components::server::query::ServerResponse;
 use graph::components::server::query::ServerResult;
+use graph::components::store::{PersistedQueryStore, SubscriptionManager};
 use graph::components::versions::ApiVersion;
 use graph::data::query::QueryResult;
 use graph::data::subgraph::DeploymentHash;
 use graph::data::subgraph::SubgraphName;
 use graph::env::ENV_VARS;
+use graph::http_body_util::combinators::UnsyncBoxBody;
 use graph::http_body_util::{BodyExt, Full};
+use graph::hyper::body::Bytes;
 use graph::hyper::header::{
-    ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
-    CONTENT_LENGTH, CONTENT_TYPE, LOCATION,
+    ACCEPT, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+    ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE, LOCATION,
 };
 use graph::hyper::{body::Body, header::HeaderValue};
 use graph::hyper::{Method, Request, Response, StatusCode};
@@ -27,7 +30,14 @@ use graph::slog::Logger;
 use graph::url::form_urlencoded;
 use graph::{components::server::query::ServerError, data::query::QueryTarget};
 
-use crate::request::parse_graphql_request;
+use crate::auth::ApiKeys;
+use crate::live::{self, LiveQueries, LiveQueryBody, LiveQueryGuard};
+use crate::persisted::PersistedQueries;
+use crate::request::{parse_graphql_query_string, parse_graphql_request};
+
+/// The response to a request that might have been a live query, whose body
+/// is streamed
+pub type GraphQLResponse = Response<UnsyncBoxBody<Bytes, Infallible>>;
 
 fn client_error(msg: impl Into<String>) -> ServerResponse {
     let response_obj = json!({
@@ -43,11 +53,15 @@ fn client_error(msg: impl Into<String>) -> ServerResponse {
         .unwrap()
 }
 
-/// A Hyper Service that serves GraphQL over a POST / endpoint.
-#[derive(Debug)]
+/// A Hyper Service that serves GraphQL over a POST / endpoint, and live
+/// queries as Server-Sent Events.
 pub struct GraphQLService<Q> {
     logger: Logger,
     graphql_runner: Arc<Q>,
+    subscription_manager: Arc<dyn SubscriptionManager>,
+    live_queries: LiveQueries,
+    persisted_queries: PersistedQueries,
+    api_keys: Arc<ApiKeys>,
 }
 
 impl<Q> GraphQLService<Q>
@@ -55,10 +69,21 @@ where
     Q: GraphQlRunner,
 {
     /// Creates a new GraphQL service.
-    pub fn new(logger: Logger, graphql_runner: Arc<Q>) -> Self {
+    pub fn new(
+        logger: Logger,
+        graphql_runner: Arc<Q>,
+        subscription_manager: Arc<dyn SubscriptionManager>,
+        persisted_queries: Arc<dyn PersistedQueryStore>,
+        api_keys: Arc<ApiKeys>,
+    ) -> Self {
+        let persisted_queries = PersistedQueries::new(logger.clone(), persisted_queries);
         GraphQLService {
             logger,
             graphql_runner,
+            subscription_manager,
+            live_queries: LiveQueries::default(),
+            persisted_queries,
+            api_keys,
         }
     }
 
@@ -120,18 +145,39 @@ where
         Ok(version)
     }
 
-    async fn handle_graphql_query_by_name<T: Body>(
+    fn target_by_name<T>(
         &self,
         subgraph_name: String,
-        request: Request<T>,
-    ) -> ServerResult {
-        let version = self.resolve_api_version(&request)?;
+        request: &Request<T>,
+    ) -> Result<QueryTarget, ServerError> {
+        let version = self.resolve_api_version(request)?;
         let subgraph_name = SubgraphName::new(subgraph_name.as_str()).map_err(|()| {
             ServerError::ClientError(format!("Invalid subgraph name {:?}", subgraph_name))
         })?;
 
-        self.handle_graphql_query(QueryTarget::Name(subgraph_name, version), request)
-            .await
+        Ok(QueryTarget::Name(subgraph_name, version))
+    }
+
+    fn target_by_id<T>(
+        &self,
+        id: String,
+        request: &Request<T>,
+    ) -> Result<QueryTarget, ServerError> {
+        let id = DeploymentHash::new(id)
+            .map_err(|id| ServerError::ClientError(format!("Invalid subgraph id `{}`", id)))?;
+        let version = self.resolve_api_version(request)?;
+
+        Ok(QueryTarget::Deployment(id, version))
+    }
+
+    async fn handle_graphql_query_by_name<T: Body>(
+        &self,
+        subgraph_name: String,
+        request: Request<T>,
+    ) -> ServerResult {
+        let target = self.target_by_name(subgraph_name, &request)?;
+
+        self.handle_graphql_query(target, request).await
     }
 
     async fn handle_graphql_query_by_id<T: Body>(
@@ -139,12 +185,91 @@ where
         id: String,
         request: Request<T>,
     ) -> ServerResult {
-        let id = DeploymentHash::new(id)
-            .map_err(|id| ServerError::ClientError(format!("Invalid subgraph id `{}`", id)))?;
-        let version = self.resolve_api_version(&request)?;
+        let target = self.target_by_id(id, &request)?;
 
-        self.handle_graphql_query(QueryTarget::Deployment(id, version), request)
-            .await
+        self.handle_graphql_query(target, request).await
+    }
+
+    /// If `request` asks for a live query, i.e., is a `GET` or `POST`
+    /// request for a subgraph's query endpoint that accepts
+    /// `text/event-stream`, return the target of the query
+    fn live_query_target<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Option<Result<QueryTarget, ServerError>> {
+        let accepts_event_stream = request
+            .headers()
+            .get(ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .map(|accept| {
+                accept
+                    .split(',')
+                    .any(|media_type| media_type.trim().starts_with("text/event-stream"))
+            })
+            .unwrap_or(false);
+        if !accepts_event_stream
+            || (request.method() != Method::GET && request.method() != Method::POST)
+        {
+            return None;
+        }
+
+        let path_segments = request
+            .uri()
+            .path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        match path_segments.as_slice() {
+            ["subgraphs", "id", id] => Some(self.target_by_id(id.to_string(), request)),
+            ["subgraphs", "name", name @ ..]
+                if !name.is_empty() && name.last() != Some(&"graphql") =>
+            {
+                Some(self.target_by_name(name.join("/"), request))
+            }
+            _ => None,
+        }
+    }
+
+    async fn handle_live_query<T: Body>(
+        &self,
+        target: QueryTarget,
+        guard: LiveQueryGuard,
+        request: Request<T>,
+    ) -> Result<Response<LiveQueryBody>, ServerError> {
+        // Live queries are never traced since the trace would make every
+        // result differ from the previous one
+        let request = if request.method() == Method::GET {
+            parse_graphql_query_string(request.uri().query().unwrap_or_default())?
+        } else {
+            let body = request
+                .collect()
+                .await
+                .map_err(|_| ServerError::InternalError("Failed to read request body".into()))?
+                .to_bytes();
+            parse_graphql_request(&body)?
+        };
+        let query = self
+            .persisted_queries
+            .resolve(request)
+            .await?
+            .into_query(false)?;
+
+        let body = live::start(
+            self.logger.clone(),
+            self.graphql_runner.cheap_clone(),
+            self.subscription_manager.cheap_clone(),
+            guard,
+            query,
+            target,
+        );
+
+        Ok(Response::builder()
+            .status(200)
+            .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+            .header(CONTENT_TYPE, "text/event-stream")
+            .header(CACHE_CONTROL, "no-cache")
+            .body(body)
+            .unwrap())
     }
 
     async fn handle_graphql_query<T: Body>(
@@ -152,6 +277,10 @@ where
         target: QueryTarget,
         request: Request<T>,
     ) -> ServerResult {
+        if let Err(rejection) = self.api_keys.admit(request.headers()) {
+            return Ok(rejection.as_response());
+        }
+
         let start = Instant::now();
         let trace = {
             !ENV_VARS.graphql.query_trace_token.is_empty()
@@ -170,7 +299,14 @@ where
             .await
             .map_err(|_| ServerError::InternalError("Failed to read request body".into()))?
             .to_bytes();
-        let query = parse_graphql_request(&body, trace);
+        let query = match parse_graphql_request(&body) {
+            Ok(request) => self
+                .persisted_queries
+                .resolve(request)
+                .await
+                .and_then(|request| request.into_query(trace)),
+            Err(e) => Err(e),
+        };
         let query_parsing_time = start.elapsed();
 
         let mut result = match query {
@@ -200,7 +336,10 @@ where
         Ok(Response::builder()
             .status(200)
             .header(ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-            .header(ACCESS_CONTROL_ALLOW_HEADERS, "Content-Type, User-Agent")
+            .header(
+                ACCESS_CONTROL_ALLOW_HEADERS,
+                "Accept, Authorization, Content-Type, User-Agent",
+            )
             .header(ACCESS_CONTROL_ALLOW_METHODS, "GET, OPTIONS, POST")
             .header(CONTENT_TYPE, "text/html; charset=utf-8")
             .body(Full::from(""))
@@ -344,6 +483,36 @@ where
         }
     }
 
+    /// Like `call`, but also serves live queries, whose results are
+    /// streamed to the client
+    pub async fn serve<T: Body + std::fmt::Debug>(&self, req: Request<T>) -> GraphQLResponse {
+        let target = match self.live_query_target(&req) {
+            Some(target) => target,
+            None => return self.call(req).await.map(BodyExt::boxed_unsync),
+        };
+
+        // A live query counts as a single request against the limits of
+        // its API key, no matter how many results it streams
+        if let Err(rejection) = self.api_keys.admit(req.headers()) {
+            return rejection.as_response().map(BodyExt::boxed_unsync);
+        }
+
+        let guard = match self.live_queries.acquire() {
+            Some(guard) => guard,
+            None => return live::unavailable().map(BodyExt::boxed_unsync),
+        };
+
+        let result = match target {
+            Ok(target) => self.handle_live_query(target, guard, req).await,
+            Err(err) => Err(err),
+        };
+
+        match result {
+            Ok(response) => response.map(BodyExt::boxed_unsync),
+            Err(err) => self.error_response(err).map(BodyExt::boxed_unsync),
+        }
+    }
+
     pub async fn call<T: Body + std::fmt::Debug>(&self, req: Request<T>) -> ServerResponse {
         // Returning Err here will prevent the client from receiving any response.
         // Instead, we generate a Response with an error code and return Ok
@@ -351,7 +520,13 @@ where
 
         match result {
             Ok(response) => response,
-            Err(err @ ServerError::ClientError(_)) => {
+            Err(err) => self.error_response(err),
+        }
+    }
+
+    fn error_response(&self, err: ServerError) -> ServerResponse {
+        match err {
+            err @ ServerError::ClientError(_) => {
                 let response_obj = json!({
                     "error": err.to_string()
                 });
@@ -364,7 +539,7 @@ where
                     .body(Full::from(response_str))
                     .unwrap()
             }
-            Err(err @ ServerError::QueryError(_)) => {
+            err @ ServerError::QueryError(_) => {
                 error!(self.logger, "GraphQLService call failed: {}", err);
 
                 let response_obj = json!({
@@ -379,7 +554,7 @@ where
                     .body(Full::from(response_str))
                     .unwrap()
             }
-            Err(err @ ServerError::InternalError(_)) => {
+            err @ ServerError::InternalError(_) => {
                 error!(self.logger, "GraphQLService call failed: {}", err);
 
                 Response::builder()
@@ -398,7 +573,7 @@ mod tests {
     use graph::data::value::{Object, Word};
     use graph::http_body_util::{BodyExt, Full};
     use graph::hyper::body::Bytes;
-    use graph::hyper::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use graph::hyper::header::{ACCEPT, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, RETRY_AFTER};
     use graph::hyper::{Method, Request, StatusCode};
     use graph::prelude::serde_json::json;
 
@@ -406,6 +581,7 @@ mod tests {
     use graph::prelude::*;
 
     use crate::test_utils;
+    use crate::{ApiKey, ApiKeys};
 
     use super::GraphQLService;
 
@@ -456,7 +632,13 @@ mod tests {
         let logger = Logger::root(slog::Discard, o!());
         let graphql_runner = Arc::new(TestGraphQlRunner);
 
-        let service = GraphQLService::new(logger, graphql_runner);
+        let service = GraphQLService::new(
+            logger,
+            graphql_runner,
+            Arc::new(test_utils::TestSubscriptionManager),
+            Arc::new(test_utils::TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
 
         let request: Request<Full<Bytes>> = Request::builder()
             .method(Method::GET)
@@ -488,7 +670,13 @@ mod tests {
         let subgraph_id = USERS.clone();
         let graphql_runner = Arc::new(TestGraphQlRunner);
 
-        let service = GraphQLService::new(logger, graphql_runner);
+        let service = GraphQLService::new(
+            logger,
+            graphql_runner,
+            Arc::new(test_utils::TestSubscriptionManager),
+            Arc::new(test_utils::TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
 
         let request: Request<Full<Bytes>> = Request::builder()
             .method(Method::POST)
@@ -520,7 +708,13 @@ mod tests {
         let subgraph_id = USERS.clone();
         let graphql_runner = Arc::new(TestGraphQlRunner);
 
-        let service = GraphQLService::new(logger, graphql_runner);
+        let service = GraphQLService::new(
+            logger,
+            graphql_runner,
+            Arc::new(test_utils::TestSubscriptionManager),
+            Arc::new(test_utils::TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
 
         let request: Request<Full<Bytes>> = Request::builder()
             .method(Method::POST)
@@ -546,4 +740,175 @@ mod tests {
             .expect("Query result field \"name\" is not a string");
         assert_eq!(name, "Jordi".to_string());
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn live_queries_stream_results_as_events() {
+        let logger = Logger::root(slog::Discard, o!());
+        let graphql_runner = Arc::new(TestGraphQlRunner);
+
+        let service = GraphQLService::new(
+            logger,
+            graphql_runner,
+            Arc::new(test_utils::TestSubscriptionManager),
+            Arc::new(test_utils::TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
+
+        let request: Request<Full<Bytes>> = Request::builder()
+            .method(Method::GET)
+            .header(ACCEPT, "text/event-stream")
+            .uri("http://localhost:8000/subgraphs/name/users?query=%7B%20name%20%7D")
+            .body(Full::from(""))
+            .unwrap();
+
+        let response = service.serve(request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+
+        let mut body = response.into_body();
+        let frame = body
+            .frame()
+            .await
+            .expect("Live query stream ended")
+            .unwrap()
+            .into_data()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(frame.to_vec()).unwrap(),
+            "event: next\ndata: {\"data\":{\"name\":\"Jordi\"}}\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn get_without_event_stream_still_redirects() {
+        let logger = Logger::root(slog::Discard, o!());
+        let graphql_runner = Arc::new(TestGraphQlRunner);
+
+        let service = GraphQLService::new(
+            logger,
+            graphql_runner,
+            Arc::new(test_utils::TestSubscriptionManager),
+            Arc::new(test_utils::TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
+
+        let request: Request<Full<Bytes>> = Request::builder()
+            .method(Method::GET)
+            .uri("http://localhost:8000/subgraphs/name/users")
+            .body(Full::from(""))
+            .unwrap();
+
+        let response = service.serve(request).await;
+        assert_eq!(response.status(), StatusCode::FOUND);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn automatic_persisted_queries_can_be_run_by_hash() {
+        let logger = Logger::root(slog::Discard, o!());
+        let graphql_runner = Arc::new(TestGraphQlRunner);
+
+        let service = GraphQLService::new(
+            logger,
+            graphql_runner,
+            Arc::new(test_utils::TestSubscriptionManager),
+            Arc::new(test_utils::TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
+
+        let hash = graph::data::query::persisted_query_hash("{ name }");
+        let post = |body: String| -> Request<Full<Bytes>> {
+            Request::builder()
+                .method(Method::POST)
+                .header(CONTENT_TYPE, "application/json")
+                .uri(format!(
+                    "http://localhost:8000/subgraphs/id/{}",
+                    USERS.clone()
+                ))
+                .body(Full::from(body))
+                .unwrap()
+        };
+        let extensions = format!(
+            "{{\"persistedQuery\": {{\"version\": 1, \"sha256Hash\": \"{}\"}}}}",
+            hash
+        );
+
+        // The query is not known yet
+        let response = service
+            .call(post(format!("{{\"extensions\": {}}}", extensions)))
+            .await;
+        let errors = test_utils::assert_error_response(response, StatusCode::OK, true).await;
+        assert_eq!(errors[0]["message"], "PersistedQueryNotFound");
+
+        // Register the query by sending it together with its hash
+        let response = service
+            .call(post(format!(
+                "{{\"query\": \"{{ name }}\", \"extensions\": {}}}",
+                extensions
+            )))
+            .await;
+        test_utils::assert_successful_response(response).await;
+
+        // Now the hash alone is enough
+        let response = service
+            .call(post(format!("{{\"extensions\": {}}}", extensions)))
+            .await;
+        let data = test_utils::assert_successful_response(response).await;
+        assert_eq!(data.get("name").unwrap(), "Jordi");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn queries_require_configured_api_key() {
+        let logger = Logger::root(slog::Discard, o!());
+        let graphql_runner = Arc::new(TestGraphQlRunner);
+        let key = ApiKey {
+            name: "franky".to_string(),
+            key: "secret".to_string(),
+            rate_limit: None,
+            burst: None,
+            monthly_quota: Some(1),
+        };
+        let api_keys = ApiKeys::new(&MetricsRegistry::mock(), true, vec![key]);
+
+        let service = GraphQLService::new(
+            logger,
+            graphql_runner,
+            Arc::new(test_utils::TestSubscriptionManager),
+            Arc::new(test_utils::TestPersistedQueryStore::default()),
+            Arc::new(api_keys),
+        );
+
+        let request = |key: Option<&str>| -> Request<Full<Bytes>> {
+            let mut builder = Request::builder()
+                .method(Method::POST)
+                .header(CONTENT_TYPE, "application/json")
+                .header(CONTENT_LENGTH, 100)
+                .uri(format!(
+                    "http://localhost:8000/subgraphs/id/{}",
+                    USERS.clone()
+                ));
+            if let Some(key) = key {
+                builder = builder.header(AUTHORIZATION, format!("Bearer {}", key));
+            }
+            builder
+                .body(Full::from("{\"query\": \"{ name }\"}"))
+                .unwrap()
+        };
+
+        let response = service.call(request(None)).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = service.call(request(Some("wrong"))).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = service.call(request(Some("secret"))).await;
+        test_utils::assert_successful_response(response).await;
+
+        // The quota of one request per month is used up
+        let response = service.call(request(Some("secret"))).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(RETRY_AFTER));
+    }
 }
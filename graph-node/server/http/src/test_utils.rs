@@ -1,6 +1,30 @@
+use graph::components::store::{
+    PersistedQueryStore, StoreError, StoreEventStream, StoreEventStreamBox, SubscriptionManager,
+};
+use graph::futures01;
 use graph::http_body_util::{BodyExt, Full};
 use graph::hyper::{body::Bytes, header::ACCESS_CONTROL_ALLOW_ORIGIN, Response, StatusCode};
-use graph::prelude::serde_json;
+use graph::prelude::{async_trait, serde_json};
+
+/// A subscription manager that never produces any store events.
+pub struct TestSubscriptionManager;
+
+impl SubscriptionManager for TestSubscriptionManager {
+    fn subscribe(&self) -> StoreEventStreamBox {
+        StoreEventStream::new(Box::new(futures01::stream::empty()))
+    }
+}
+
+/// A persisted query store with a fixed list of `(hash, query)` pairs.
+#[derive(Default)]
+pub struct TestPersistedQueryStore(pub Vec<(String, String)>);
+
+#[async_trait]
+impl PersistedQueryStore for TestPersistedQueryStore {
+    async fn persisted_queries(&self) -> Result<Vec<(String, String)>, StoreError> {
+        Ok(self.0.clone())
+    }
+}
 
 /// Asserts that the response is a successful GraphQL response; returns its `"data"` field.
 pub async fn assert_successful_response(
@@ -6,7 +6,8 @@ use graph::data::{
     value::{Object, Word},
 };
 use graph::prelude::*;
-use graph_server_http::GraphQLServer as HyperGraphQLServer;
+use graph_server_http::test_utils::{TestPersistedQueryStore, TestSubscriptionManager};
+use graph_server_http::{ApiKeys, GraphQLServer as HyperGraphQLServer};
 
 use tokio::time::sleep;
 
@@ -163,7 +164,13 @@ mod test {
         let logger_factory = LoggerFactory::new(logger, None, Arc::new(MetricsRegistry::mock()));
         let id = USERS.clone();
         let query_runner = Arc::new(TestGraphQlRunner);
-        let server = HyperGraphQLServer::new(&logger_factory, query_runner);
+        let server = HyperGraphQLServer::new(
+            &logger_factory,
+            query_runner,
+            Arc::new(TestSubscriptionManager),
+            Arc::new(TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
         let server_handle = server
             .start(8007)
             .await
@@ -195,7 +202,13 @@ mod test {
         let logger_factory = LoggerFactory::new(logger, None, Arc::new(MetricsRegistry::mock()));
         let id = USERS.clone();
         let query_runner = Arc::new(TestGraphQlRunner);
-        let server = HyperGraphQLServer::new(&logger_factory, query_runner);
+        let server = HyperGraphQLServer::new(
+            &logger_factory,
+            query_runner,
+            Arc::new(TestSubscriptionManager),
+            Arc::new(TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
         let server_handle = server
             .start(8002)
             .await
@@ -267,7 +280,13 @@ mod test {
         let logger_factory = LoggerFactory::new(logger, None, Arc::new(MetricsRegistry::mock()));
         let id = USERS.clone();
         let query_runner = Arc::new(TestGraphQlRunner);
-        let server = HyperGraphQLServer::new(&logger_factory, query_runner);
+        let server = HyperGraphQLServer::new(
+            &logger_factory,
+            query_runner,
+            Arc::new(TestSubscriptionManager),
+            Arc::new(TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
         let server_handle = server
             .start(8003)
             .await
@@ -304,7 +323,13 @@ mod test {
         let logger_factory = LoggerFactory::new(logger, None, Arc::new(MetricsRegistry::mock()));
         let id = USERS.clone();
         let query_runner = Arc::new(TestGraphQlRunner);
-        let server = HyperGraphQLServer::new(&logger_factory, query_runner);
+        let server = HyperGraphQLServer::new(
+            &logger_factory,
+            query_runner,
+            Arc::new(TestSubscriptionManager),
+            Arc::new(TestPersistedQueryStore::default()),
+            Arc::new(ApiKeys::default()),
+        );
         let server_handle = server
             .start(8005)
             .await
@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 use std::convert::TryInto;
+use std::ops::RangeInclusive;
 
 use graph::data::query::Trace;
 use graph::data::store::Id;
@@ -8,6 +9,7 @@ use web3::types::Address;
 
 use git_testament::{git_testament, CommitKind};
 use graph::blockchain::{Blockchain, BlockchainKind, BlockchainMap};
+use graph::components::metrics::gas::MappingProfiles;
 use graph::components::store::{BlockPtrForNumber, BlockStore, QueryPermit, Store};
 use graph::components::versions::VERSIONS;
 use graph::data::graphql::{object, IntoValue, ObjectOrInterface, ValueMap};
@@ -101,6 +103,7 @@ pub struct IndexNodeResolver<S: Store> {
     #[allow(dead_code)]
     link_resolver: Arc<dyn LinkResolver>,
     bearer_token: Option<String>,
+    mapping_profiles: Arc<MappingProfiles>,
 }
 
 impl<S: Store> IndexNodeResolver<S> {
@@ -110,6 +113,7 @@ impl<S: Store> IndexNodeResolver<S> {
         link_resolver: Arc<dyn LinkResolver>,
         bearer_token: Option<String>,
         blockchain_map: Arc<BlockchainMap>,
+        mapping_profiles: Arc<MappingProfiles>,
     ) -> Self {
         let logger = logger.new(o!("component" => "IndexNodeResolver"));
 
@@ -119,6 +123,7 @@ impl<S: Store> IndexNodeResolver<S> {
             store,
             link_resolver,
             bearer_token,
+            mapping_profiles,
         }
     }
 
@@ -187,6 +192,26 @@ impl<S: Store> IndexNodeResolver<S> {
         Ok(entity_changes_to_graphql(entity_changes))
     }
 
+    fn resolve_mapping_profile(&self, field: &a::Field) -> Result<r::Value, QueryExecutionError> {
+        let subgraph_id = field
+            .get_required::<DeploymentHash>("subgraphId")
+            .expect("Valid subgraphId required");
+        let from_block = field
+            .get_optional::<BlockNumber>("fromBlock")
+            .expect("fromBlock must be an Int")
+            .unwrap_or(0);
+        let to_block = field
+            .get_optional::<BlockNumber>("toBlock")
+            .expect("toBlock must be an Int")
+            .unwrap_or(BLOCK_NUMBER_MAX);
+
+        Ok(mapping_profile(
+            &self.mapping_profiles,
+            &subgraph_id,
+            from_block..=to_block,
+        ))
+    }
+
     async fn resolve_block_data(&self, field: &a::Field) -> Result<r::Value, QueryExecutionError> {
         let network = field
             .get_required::<String>("network")
@@ -864,6 +889,7 @@ impl<S: Store> Resolver for IndexNodeResolver<S> {
             (None, "entityChangesInBlock") => self.resolve_entity_changes_in_block(field),
             // The top-level `subgraphVersions` field
             (None, "apiVersions") => self.resolve_api_versions(field),
+            (None, "mappingProfile") => self.resolve_mapping_profile(field),
             (None, "version") => self.version(),
 
             // Resolve fields of `Object` values (e.g. the `latestBlock` field of `EthereumBlock`)
@@ -871,3 +897,130 @@ impl<S: Store> Resolver for IndexNodeResolver<S> {
         }
     }
 }
+
+/// The `mappingProfile` of `subgraph_id`, summed over the blocks in `range`
+fn mapping_profile(
+    profiles: &MappingProfiles,
+    subgraph_id: &DeploymentHash,
+    range: RangeInclusive<BlockNumber>,
+) -> r::Value {
+    let summary = profiles
+        .get(subgraph_id)
+        .and_then(|profile| profile.summarize(range));
+    let (first, last, handlers) = match summary {
+        Some(summary) => summary,
+        None => return r::Value::Null,
+    };
+
+    let mut handlers: Vec<_> = handlers.into_iter().collect();
+    handlers.sort_by(|(_, a), (_, b)| b.wall_time.cmp(&a.wall_time));
+    let handlers: Vec<_> = handlers
+        .into_iter()
+        .map(|(handler, profile)| {
+            let mut host_fns: Vec<_> = profile.host_fns.into_iter().collect();
+            host_fns.sort_by(|(_, a), (_, b)| b.gas.cmp(&a.gas));
+            let host_fns: Vec<_> = host_fns
+                .into_iter()
+                .map(|(name, host_fn)| {
+                    object! {
+                        __typename: "HostFunctionProfile",
+                        name: name,
+                        calls: format!("{}", host_fn.calls),
+                        gas: format!("{}", host_fn.gas),
+                    }
+                })
+                .collect();
+            object! {
+                __typename: "HandlerProfile",
+                handler: handler,
+                calls: format!("{}", profile.calls),
+                failures: format!("{}", profile.failures),
+                wallTimeMicros: format!("{}", profile.wall_time.as_micros()),
+                gas: format!("{}", profile.gas),
+                hostFunctions: host_fns,
+            }
+        })
+        .collect();
+
+    object! {
+        __typename: "MappingProfile",
+        fromBlock: first,
+        toBlock: last,
+        handlers: handlers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use graph::components::metrics::gas::{HandlerProfile, HostFnProfile};
+
+    use super::*;
+
+    fn profile(wall_time: u64, gas: u64, failures: u64) -> HandlerProfile {
+        HandlerProfile {
+            calls: 1,
+            failures,
+            wall_time: Duration::from_micros(wall_time),
+            gas,
+            host_fns: HashMap::from([(
+                "store.set".to_string(),
+                HostFnProfile {
+                    calls: 2,
+                    gas: gas / 2,
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn mapping_profile_sums_blocks_in_range() {
+        let id = DeploymentHash::new("QmProfile").unwrap();
+        let profiles = MappingProfiles::default();
+        let deployment = profiles.deployment(&id);
+        deployment.record(1, "handleTransfer", &profile(100, 10, 0));
+        deployment.record(2, "handleTransfer", &profile(100, 10, 1));
+        deployment.record(3, "handleTransfer", &profile(100, 10, 0));
+        deployment.record(3, "handleApproval", &profile(50, 30, 0));
+
+        let host_fn = |calls: &str, gas: &str| {
+            object! {
+                __typename: "HostFunctionProfile",
+                name: "store.set",
+                calls: calls,
+                gas: gas,
+            }
+        };
+        let expected = object! {
+            __typename: "MappingProfile",
+            fromBlock: 2,
+            toBlock: 3,
+            handlers: vec![
+                object! {
+                    __typename: "HandlerProfile",
+                    handler: "handleTransfer",
+                    calls: "2",
+                    failures: "1",
+                    wallTimeMicros: "200",
+                    gas: "20",
+                    hostFunctions: vec![host_fn("4", "10")],
+                },
+                object! {
+                    __typename: "HandlerProfile",
+                    handler: "handleApproval",
+                    calls: "1",
+                    failures: "0",
+                    wallTimeMicros: "50",
+                    gas: "30",
+                    hostFunctions: vec![host_fn("2", "15")],
+                },
+            ],
+        };
+        assert_eq!(expected, mapping_profile(&profiles, &id, 2..=10));
+
+        assert_eq!(r::Value::Null, mapping_profile(&profiles, &id, 4..=10));
+        let unknown = DeploymentHash::new("QmUnknown").unwrap();
+        assert_eq!(r::Value::Null, mapping_profile(&profiles, &unknown, 0..=10));
+    }
+}
@@ -1,6 +1,7 @@
 use graph::{
     blockchain::BlockchainMap,
     components::{
+        metrics::gas::MappingProfiles,
         server::server::{start, ServerHandle},
         store::Store,
     },
@@ -15,6 +16,7 @@ pub struct IndexNodeServer<S> {
     blockchain_map: Arc<BlockchainMap>,
     store: Arc<S>,
     link_resolver: Arc<dyn LinkResolver>,
+    mapping_profiles: Arc<MappingProfiles>,
 }
 
 impl<S> IndexNodeServer<S>
@@ -27,6 +29,7 @@ where
         blockchain_map: Arc<BlockchainMap>,
         store: Arc<S>,
         link_resolver: Arc<dyn LinkResolver>,
+        mapping_profiles: Arc<MappingProfiles>,
     ) -> Self {
         let logger = logger_factory.component_logger(
             "IndexNodeServer",
@@ -42,6 +45,7 @@ where
             blockchain_map,
             store,
             link_resolver,
+            mapping_profiles,
         }
     }
 
@@ -62,6 +66,7 @@ where
             self.blockchain_map.clone(),
             store,
             self.link_resolver.clone(),
+            self.mapping_profiles.clone(),
         ));
 
         start(logger_for_service.clone(), port, move |req| {
@@ -5,6 +5,7 @@ use graph::blockchain::BlockchainMap;
 use graph::cheap_clone::CheapClone;
 use graph::components::graphql::GraphQLMetrics;
 use graph::components::link_resolver::LinkResolver;
+use graph::components::metrics::gas::MappingProfiles;
 use graph::components::server::query::{ServerResponse, ServerResult};
 use graph::data::subgraph::DeploymentHash;
 use graph::http_body_util::{BodyExt, Full};
@@ -45,6 +46,7 @@ pub struct IndexNodeService<S> {
     store: Arc<S>,
     explorer: Arc<Explorer<S>>,
     link_resolver: Arc<dyn LinkResolver>,
+    mapping_profiles: Arc<MappingProfiles>,
 }
 
 impl<S> IndexNodeService<S>
@@ -56,6 +58,7 @@ where
         blockchain_map: Arc<BlockchainMap>,
         store: Arc<S>,
         link_resolver: Arc<dyn LinkResolver>,
+        mapping_profiles: Arc<MappingProfiles>,
     ) -> Self {
         let explorer = Arc::new(Explorer::new(store.clone()));
 
@@ -65,6 +68,7 @@ where
             store,
             explorer,
             link_resolver,
+            mapping_profiles,
         }
     }
 
@@ -140,6 +144,7 @@ where
                 self.link_resolver.clone(),
                 validated.bearer_token,
                 self.blockchain_map.clone(),
+                self.mapping_profiles.clone(),
             );
             let options = QueryExecutionOptions {
                 resolver,
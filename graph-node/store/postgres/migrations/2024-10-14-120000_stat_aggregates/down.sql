@@ -0,0 +1,14 @@
+set search_path = public;
+drop function sketch_quantile(numeric[], numeric);
+drop aggregate sketch_merge(numeric[]);
+drop function sketch_comb(numeric[], numeric[]);
+drop function sketch_of(numeric[]);
+drop function sketch_value(numeric);
+drop function sketch_key(numeric);
+drop function moments_stddev(numeric[]);
+drop function moments_variance(numeric[]);
+drop function moments_avg(numeric[]);
+drop aggregate moments_merge(numeric[]);
+drop aggregate moments(numeric);
+drop function moments_comb(numeric[], numeric[]);
+drop function moments_acc(numeric[], numeric);
@@ -0,0 +1,127 @@
+-- Functions and aggregates that support the statistical aggregation
+-- functions `avg`, `variance`, `stddev`, and approximate percentiles like
+-- `p95`. These aggregations can not be combined directly from their
+-- values. Rollups therefore also store some state alongside the value
+-- from which the value can be computed and which can be merged with the
+-- state of other buckets
+set search_path = public;
+
+-- The state for `avg`, `variance`, and `stddev` is an array
+-- `[count, sum, sum of squares]` of the values that were aggregated
+create function moments_acc(state numeric[], x numeric)
+  returns numeric[]
+  language sql immutable strict parallel safe as
+'select array[state[1] + 1, state[2] + x, state[3] + x * x]';
+
+create function moments_comb(a numeric[], b numeric[])
+  returns numeric[]
+  language sql immutable strict parallel safe as
+'select array[a[1] + b[1], a[2] + b[2], a[3] + b[3]]';
+
+create aggregate moments(numeric) (
+  sfunc       = moments_acc,
+  stype       = numeric[],
+  combinefunc = moments_comb,
+  initcond    = '{0,0,0}',
+  parallel    = safe
+);
+
+comment on aggregate moments(numeric) is
+'Compute the state [count, sum, sum of squares] for avg, variance, and stddev';
+
+create aggregate moments_merge(numeric[]) (
+  sfunc       = moments_comb,
+  stype       = numeric[],
+  combinefunc = moments_comb,
+  initcond    = '{0,0,0}',
+  parallel    = safe
+);
+
+comment on aggregate moments_merge(numeric[]) is
+'Merge states produced by moments into one state';
+
+create function moments_avg(state numeric[])
+  returns numeric
+  language sql immutable strict parallel safe as
+'select case when state[1] = 0 then null else state[2] / state[1] end';
+
+create function moments_variance(state numeric[])
+  returns numeric
+  language sql immutable strict parallel safe as
+'select case when state[1] = 0 then null
+             else greatest((state[3] - state[2] * state[2] / state[1]) / state[1], 0) end';
+
+create function moments_stddev(state numeric[])
+  returns numeric
+  language sql immutable strict parallel safe as
+'select sqrt(moments_variance(state))';
+
+-- The state for percentiles is a sketch: a logarithmic histogram whose
+-- bucket boundaries grow by a factor of 1.02, so that any value computed
+-- from the sketch is within about 1% of an actual value. Values are mapped
+-- to keys so that the order of keys is the order of values, with 0 for
+-- the value 0. The sketch is stored as an array of pairs
+-- `[key1, count1, key2, count2, ..]` ordered by key
+create function sketch_key(x numeric)
+  returns numeric
+  language sql immutable strict parallel safe as
+'select case when x = 0 then 0
+             when x > 0 then 1000000000 + ceil(ln(x) / ln(1.02))
+             else -(1000000000 + ceil(ln(-x) / ln(1.02))) end';
+
+create function sketch_value(k numeric)
+  returns numeric
+  language sql immutable strict parallel safe as
+'select case when k = 0 then 0
+             else sign(k) * 2 * power(1.02, abs(k) - 1000000000) / 2.02 end';
+
+create function sketch_of(keys numeric[])
+  returns numeric[]
+  language sql immutable strict parallel safe as
+'select coalesce(array_agg(v order by k, pos), ''{}'')
+   from (select k, count(*) as c
+           from unnest(keys) as k
+          where k is not null
+          group by k) as h
+        cross join lateral (values (1, h.k), (2, h.c)) as p(pos, v)';
+
+comment on function sketch_of(numeric[]) is
+'For ''select sketch_of(array_agg(sketch_key(x))) from ..'' return the sketch for all values x';
+
+create function sketch_comb(a numeric[], b numeric[])
+  returns numeric[]
+  language sql immutable strict parallel safe as
+'select coalesce(array_agg(v order by k, pos), ''{}'')
+   from (select k, sum(c) as c
+           from (select a[i] as k, a[i + 1] as c
+                   from generate_series(1, coalesce(array_length(a, 1), 0), 2) as i
+                 union all
+                 select b[i] as k, b[i + 1] as c
+                   from generate_series(1, coalesce(array_length(b, 1), 0), 2) as i) as u
+          group by k) as h
+        cross join lateral (values (1, h.k), (2, h.c)) as p(pos, v)';
+
+create aggregate sketch_merge(numeric[]) (
+  sfunc       = sketch_comb,
+  stype       = numeric[],
+  combinefunc = sketch_comb,
+  initcond    = '{}',
+  parallel    = safe
+);
+
+comment on aggregate sketch_merge(numeric[]) is
+'Merge sketches produced by sketch_of into one sketch';
+
+create function sketch_quantile(sketch numeric[], q numeric)
+  returns numeric
+  language sql immutable strict parallel safe as
+'select sketch_value(k)
+   from (select k, sum(c) over (order by k) as cum, sum(c) over () as total
+           from (select sketch[i] as k, sketch[i + 1] as c
+                   from generate_series(1, coalesce(array_length(sketch, 1), 0), 2) as i) as p) as h
+  where cum >= q * total
+  order by k
+  limit 1';
+
+comment on function sketch_quantile(numeric[], numeric) is
+'Return an approximation of the q-th quantile of the values in the sketch';
@@ -0,0 +1,8 @@
+-- The allow-list of persisted queries; only used in the primary. Queries
+-- are identified by the hex-encoded SHA-256 hash of their text
+create table public.persisted_queries
+(
+    hash       varchar primary key,
+    query      text                     not null,
+    created_at timestamp with time zone not null default now()
+);
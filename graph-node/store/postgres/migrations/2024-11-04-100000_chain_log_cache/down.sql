@@ -0,0 +1,18 @@
+do $$
+declare
+    tables cursor for select namespace
+                        from ethereum_networks
+                       where namespace != 'public';
+begin
+	for table_record in tables loop
+		execute
+			'drop table if exists '
+			|| table_record.namespace
+			|| '.log_cache';
+		execute
+			'drop table if exists '
+			|| table_record.namespace
+			|| '.receipt_cache';
+	end loop;
+end;
+$$;
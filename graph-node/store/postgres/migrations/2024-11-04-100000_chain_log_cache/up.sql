@@ -0,0 +1,35 @@
+-- Cache for eth_getLogs responses and transaction receipts of final
+-- blocks. Chains with shared storage in the public schema do not use the
+-- cache
+do $$
+declare
+    tables cursor for select namespace
+                        from ethereum_networks
+                       where namespace != 'public';
+begin
+	for table_record in tables loop
+		execute
+			'create table if not exists '
+			|| table_record.namespace
+			|| '.log_cache (
+			  filter_id  bytea not null,
+			  from_block int4  not null,
+			  to_block   int4  not null,
+			  logs       jsonb not null,
+			  primary key (filter_id, from_block, to_block)
+			)';
+		execute
+			'create table if not exists '
+			|| table_record.namespace
+			|| '.receipt_cache (
+			  transaction_hash bytea not null primary key,
+			  block_number     int4  not null,
+			  receipt          jsonb not null
+			)';
+		execute
+			'create index if not exists receipt_cache_block_number_idx on '
+			|| table_record.namespace
+			|| '.receipt_cache(block_number)';
+	end loop;
+end;
+$$;
@@ -24,11 +24,11 @@ use std::{
 
 use graph::blockchain::{Block, BlockHash, ChainIdentifier, ExtendedBlockPtr};
 use graph::cheap_clone::CheapClone;
-use graph::prelude::web3::types::{H256, U256};
+use graph::prelude::web3::types::{Log, TransactionReceipt, H256, U256};
 use graph::prelude::{
     async_trait, serde_json as json, transaction_receipt::LightTransactionReceipt, BlockNumber,
     BlockPtr, CachedEthereumCall, CancelableError, ChainStore as ChainStoreTrait, Error,
-    EthereumCallCache, StoreError,
+    EthereumCallCache, StoreError, BLOCK_NUMBER_MAX,
 };
 use graph::{constraint_violation, ensure};
 
@@ -79,7 +79,7 @@ mod public {
     }
 }
 
-pub use data::Storage;
+pub use data::{LogCacheInfo, Storage};
 
 /// Encapuslate access to the blocks table for a chain.
 mod data {
@@ -295,12 +295,76 @@ mod data {
         }
     }
 
+    /// The table that caches `eth_getLogs` responses for final blocks.
+    /// Since we only ever access it with `sql_query`, we only need its name
+    #[derive(Clone, Debug)]
+    struct LogCacheTable {
+        qname: String,
+    }
+
+    impl LogCacheTable {
+        const TABLE_NAME: &'static str = "log_cache";
+
+        fn new(namespace: &str) -> Self {
+            LogCacheTable {
+                qname: format!("{}.{}", namespace, Self::TABLE_NAME),
+            }
+        }
+    }
+
+    /// The table that caches transaction receipts for final blocks
+    #[derive(Clone, Debug)]
+    struct ReceiptCacheTable {
+        qname: String,
+    }
+
+    impl ReceiptCacheTable {
+        const TABLE_NAME: &'static str = "receipt_cache";
+
+        fn new(namespace: &str) -> Self {
+            ReceiptCacheTable {
+                qname: format!("{}.{}", namespace, Self::TABLE_NAME),
+            }
+        }
+    }
+
+    #[derive(QueryableByName)]
+    struct CachedLogs {
+        #[diesel(sql_type = Jsonb)]
+        logs: json::Value,
+    }
+
+    #[derive(QueryableByName)]
+    struct CachedReceipt {
+        #[diesel(sql_type = Jsonb)]
+        receipt: json::Value,
+    }
+
+    /// Summary of what the log and receipt cache of a chain holds
+    #[derive(QueryableByName)]
+    pub struct LogCacheInfo {
+        #[diesel(sql_type = BigInt)]
+        pub log_ranges: i64,
+        #[diesel(sql_type = Nullable<Integer>)]
+        pub log_min_block: Option<i32>,
+        #[diesel(sql_type = Nullable<Integer>)]
+        pub log_max_block: Option<i32>,
+        #[diesel(sql_type = BigInt)]
+        pub receipts: i64,
+        #[diesel(sql_type = Nullable<Integer>)]
+        pub receipt_min_block: Option<i32>,
+        #[diesel(sql_type = Nullable<Integer>)]
+        pub receipt_max_block: Option<i32>,
+    }
+
     #[derive(Clone, Debug)]
     pub struct Schema {
         name: String,
         blocks: BlocksTable,
         call_meta: CallMetaTable,
         call_cache: CallCacheTable,
+        log_cache: LogCacheTable,
+        receipt_cache: ReceiptCacheTable,
     }
 
     impl Schema {
@@ -308,11 +372,15 @@ mod data {
             let blocks = BlocksTable::new(&name);
             let call_meta = CallMetaTable::new(&name);
             let call_cache = CallCacheTable::new(&name);
+            let log_cache = LogCacheTable::new(&name);
+            let receipt_cache = ReceiptCacheTable::new(&name);
             Self {
                 name,
                 blocks,
                 call_meta,
                 call_cache,
+                log_cache,
+                receipt_cache,
             }
         }
     }
@@ -402,6 +470,21 @@ mod data {
                     contract_address bytea not null primary key,
                     accessed_at      date  not null
                 );
+
+                create table {nsp}.log_cache (
+                  filter_id  bytea not null,
+                  from_block int4  not null,
+                  to_block   int4  not null,
+                  logs       jsonb not null,
+                  primary key (filter_id, from_block, to_block)
+                );
+
+                create table {nsp}.receipt_cache (
+                  transaction_hash bytea not null primary key,
+                  block_number     int4  not null,
+                  receipt          jsonb not null
+                );
+                create index receipt_cache_block_number_idx ON {nsp}.receipt_cache(block_number);
             ",
                     nsp = nsp
                 )
@@ -1524,6 +1607,194 @@ mod data {
             result.map(|_| ()).map_err(Error::from)
         }
 
+        /// Return the logs for `filter_id` from the smallest cached block
+        /// range that contains `from..=to`. Chains with shared storage do
+        /// not have a log cache
+        pub(super) fn cached_logs(
+            &self,
+            conn: &mut PgConnection,
+            filter_id: &[u8],
+            from: BlockNumber,
+            to: BlockNumber,
+        ) -> Result<Option<json::Value>, Error> {
+            let log_cache = match self {
+                Storage::Shared => return Ok(None),
+                Storage::Private(Schema { log_cache, .. }) => log_cache,
+            };
+            let query = format!(
+                "select logs from {} \
+                  where filter_id = $1 and from_block <= $2 and to_block >= $3 \
+                  order by to_block - from_block \
+                  limit 1",
+                log_cache.qname
+            );
+            sql_query(query)
+                .bind::<Bytea, _>(filter_id)
+                .bind::<Integer, _>(from)
+                .bind::<Integer, _>(to)
+                .get_result::<CachedLogs>(conn)
+                .optional()
+                .map(|row| row.map(|row| row.logs))
+                .map_err(Error::from)
+        }
+
+        pub(super) fn set_cached_logs(
+            &self,
+            conn: &mut PgConnection,
+            filter_id: &[u8],
+            from: BlockNumber,
+            to: BlockNumber,
+            logs: &json::Value,
+        ) -> Result<(), Error> {
+            let log_cache = match self {
+                Storage::Shared => return Ok(()),
+                Storage::Private(Schema { log_cache, .. }) => log_cache,
+            };
+            let query = format!(
+                "insert into {}(filter_id, from_block, to_block, logs) \
+                 values ($1, $2, $3, $4) \
+                 on conflict do nothing",
+                log_cache.qname
+            );
+            sql_query(query)
+                .bind::<Bytea, _>(filter_id)
+                .bind::<Integer, _>(from)
+                .bind::<Integer, _>(to)
+                .bind::<Jsonb, _>(logs)
+                .execute(conn)
+                .map(|_| ())
+                .map_err(Error::from)
+        }
+
+        pub(super) fn cached_receipts(
+            &self,
+            conn: &mut PgConnection,
+            hashes: &[&[u8]],
+        ) -> Result<Vec<json::Value>, Error> {
+            let receipt_cache = match self {
+                Storage::Shared => return Ok(vec![]),
+                Storage::Private(Schema { receipt_cache, .. }) => receipt_cache,
+            };
+            let query = format!(
+                "select receipt from {} where transaction_hash = any($1)",
+                receipt_cache.qname
+            );
+            sql_query(query)
+                .bind::<Array<Bytea>, _>(hashes)
+                .load::<CachedReceipt>(conn)
+                .map(|rows| rows.into_iter().map(|row| row.receipt).collect())
+                .map_err(Error::from)
+        }
+
+        /// Cache receipts, given as `(transaction_hash, block_number,
+        /// receipt)`
+        pub(super) fn set_cached_receipts(
+            &self,
+            conn: &mut PgConnection,
+            receipts: Vec<(Vec<u8>, BlockNumber, json::Value)>,
+        ) -> Result<(), Error> {
+            let receipt_cache = match self {
+                Storage::Shared => return Ok(()),
+                Storage::Private(Schema { receipt_cache, .. }) => receipt_cache,
+            };
+            let mut hashes = Vec::with_capacity(receipts.len());
+            let mut numbers = Vec::with_capacity(receipts.len());
+            let mut values = Vec::with_capacity(receipts.len());
+            for (hash, number, value) in receipts {
+                hashes.push(hash);
+                numbers.push(number);
+                values.push(value);
+            }
+            let query = format!(
+                "insert into {}(transaction_hash, block_number, receipt) \
+                 select * from unnest($1::bytea[], $2::int4[], $3::jsonb[]) \
+                 on conflict do nothing",
+                receipt_cache.qname
+            );
+            sql_query(query)
+                .bind::<Array<Bytea>, _>(hashes)
+                .bind::<Array<Integer>, _>(numbers)
+                .bind::<Array<Jsonb>, _>(values)
+                .execute(conn)
+                .map(|_| ())
+                .map_err(Error::from)
+        }
+
+        /// Remove cached logs for any block range that overlaps
+        /// `from..=to`, and cached receipts for blocks in `from..=to`. The
+        /// `head` is only used to detect that the entire cache is removed
+        pub(super) fn clear_log_cache(
+            &self,
+            conn: &mut PgConnection,
+            head: Option<BlockNumber>,
+            from: BlockNumber,
+            to: BlockNumber,
+        ) -> Result<(), Error> {
+            let (log_cache, receipt_cache) = match self {
+                Storage::Shared => return Ok(()),
+                Storage::Private(Schema {
+                    log_cache,
+                    receipt_cache,
+                    ..
+                }) => (log_cache, receipt_cache),
+            };
+            if from <= 0 && (to == BLOCK_NUMBER_MAX || head.map_or(false, |head| to >= head)) {
+                // We are removing the entire cache. Truncating is much
+                // faster in that case
+                conn.batch_execute(&format!(
+                    "truncate table {}, {}",
+                    log_cache.qname, receipt_cache.qname
+                ))?;
+                return Ok(());
+            }
+            sql_query(format!(
+                "delete from {} where from_block <= $2 and to_block >= $1",
+                log_cache.qname
+            ))
+            .bind::<Integer, _>(from)
+            .bind::<Integer, _>(to)
+            .execute(conn)?;
+            sql_query(format!(
+                "delete from {} where block_number >= $1 and block_number <= $2",
+                receipt_cache.qname
+            ))
+            .bind::<Integer, _>(from)
+            .bind::<Integer, _>(to)
+            .execute(conn)?;
+            Ok(())
+        }
+
+        pub(super) fn log_cache_info(
+            &self,
+            conn: &mut PgConnection,
+        ) -> Result<Option<LogCacheInfo>, Error> {
+            let (log_cache, receipt_cache) = match self {
+                Storage::Shared => return Ok(None),
+                Storage::Private(Schema {
+                    log_cache,
+                    receipt_cache,
+                    ..
+                }) => (log_cache, receipt_cache),
+            };
+            let query = format!(
+                "select l.log_ranges, l.log_min_block, l.log_max_block, \
+                        r.receipts, r.receipt_min_block, r.receipt_max_block \
+                   from (select count(*) as log_ranges, \
+                                min(from_block) as log_min_block, \
+                                max(to_block) as log_max_block \
+                           from {}) l, \
+                        (select count(*) as receipts, \
+                                min(block_number) as receipt_min_block, \
+                                max(block_number) as receipt_max_block \
+                           from {}) r",
+                log_cache.qname, receipt_cache.qname
+            );
+            sql_query(query)
+                .get_result::<LogCacheInfo>(conn)
+                .map(Some)
+                .map_err(Error::from)
+        }
+
         #[cfg(debug_assertions)]
         // used by `super::set_chain` for test support
         pub(super) fn remove_chain(&self, conn: &mut PgConnection, chain_name: &str) {
@@ -1545,9 +1816,17 @@ mod data {
                     blocks,
                     call_meta,
                     call_cache,
+                    log_cache,
+                    receipt_cache,
                     ..
                 }) => {
-                    for qname in &[&blocks.qname, &call_meta.qname, &call_cache.qname] {
+                    for qname in &[
+                        &blocks.qname,
+                        &call_meta.qname,
+                        &call_cache.qname,
+                        &log_cache.qname,
+                        &receipt_cache.qname,
+                    ] {
                         let query = format!("delete from {}", qname);
                         sql_query(query)
                             .execute(conn)
@@ -1905,6 +2184,13 @@ impl ChainStore {
         self.storage.remove_cursor(&mut conn, chain)
     }
 
+    /// Summarize the contents of the log and receipt cache. Returns `None`
+    /// for chains with shared storage since they do not have that cache
+    pub fn log_cache_info(&self) -> Result<Option<LogCacheInfo>, Error> {
+        let mut conn = self.get_conn()?;
+        self.storage.log_cache_info(&mut conn)
+    }
+
     pub fn truncate_block_cache(&self) -> Result<(), StoreError> {
         let mut conn = self.get_conn()?;
         self.storage.truncate_block_cache(&mut conn)?;
@@ -2482,6 +2768,110 @@ impl ChainStoreTrait for ChainStore {
         Ok(())
     }
 
+    async fn cached_logs(
+        &self,
+        filter_id: &[u8],
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Option<Vec<Log>>, Error> {
+        let storage = self.storage.clone();
+        let filter_id = filter_id.to_vec();
+        let logs = self
+            .pool
+            .with_conn(move |conn, _| {
+                storage
+                    .cached_logs(conn, &filter_id, from, to)
+                    .map_err(|e| StoreError::from(e).into())
+            })
+            .await?;
+        let logs = match logs {
+            Some(logs) => json::from_value::<Vec<Log>>(logs)?,
+            None => return Ok(None),
+        };
+        // The cached range can be larger than the one we were asked for
+        let range = (from as i64)..=(to as i64);
+        Ok(Some(
+            logs.into_iter()
+                .filter(|log| {
+                    log.block_number
+                        .map_or(false, |number| range.contains(&(number.as_u64() as i64)))
+                })
+                .collect(),
+        ))
+    }
+
+    async fn set_cached_logs(
+        &self,
+        filter_id: &[u8],
+        from: BlockNumber,
+        to: BlockNumber,
+        logs: &[Log],
+    ) -> Result<(), Error> {
+        let storage = self.storage.clone();
+        let filter_id = filter_id.to_vec();
+        let logs = json::to_value(logs)?;
+        self.pool
+            .with_conn(move |conn, _| {
+                storage
+                    .set_cached_logs(conn, &filter_id, from, to, &logs)
+                    .map_err(|e| StoreError::from(e).into())
+            })
+            .await
+            .map_err(Error::from)
+    }
+
+    async fn cached_receipts(&self, hashes: &[H256]) -> Result<Vec<TransactionReceipt>, Error> {
+        let storage = self.storage.clone();
+        let hashes: Vec<Vec<u8>> = hashes.iter().map(|hash| hash.as_bytes().to_vec()).collect();
+        let receipts = self
+            .pool
+            .with_conn(move |conn, _| {
+                let hashes: Vec<&[u8]> = hashes.iter().map(|hash| hash.as_slice()).collect();
+                storage
+                    .cached_receipts(conn, &hashes)
+                    .map_err(|e| StoreError::from(e).into())
+            })
+            .await?;
+        receipts
+            .into_iter()
+            .map(|receipt| json::from_value(receipt).map_err(Error::from))
+            .collect()
+    }
+
+    async fn set_cached_receipts(&self, receipts: &[Arc<TransactionReceipt>]) -> Result<(), Error> {
+        let storage = self.storage.clone();
+        let receipts = receipts
+            .iter()
+            .filter_map(|receipt| {
+                // Receipts for pending transactions have no block number;
+                // they can not be final and are never cached
+                let number = receipt.block_number?.as_u64();
+                let number = BlockNumber::try_from(number).ok()?;
+                Some((receipt.transaction_hash, number, receipt))
+            })
+            .map(|(hash, number, receipt)| {
+                json::to_value(receipt).map(|value| (hash.as_bytes().to_vec(), number, value))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if receipts.is_empty() {
+            return Ok(());
+        }
+        self.pool
+            .with_conn(move |conn, _| {
+                storage
+                    .set_cached_receipts(conn, receipts)
+                    .map_err(|e| StoreError::from(e).into())
+            })
+            .await
+            .map_err(Error::from)
+    }
+
+    async fn clear_log_cache(&self, from: BlockNumber, to: BlockNumber) -> Result<(), Error> {
+        let head = self.chain_head_block(&self.chain)?;
+        let mut conn = self.get_conn()?;
+        self.storage.clear_log_cache(&mut conn, head, from, to)
+    }
+
     async fn transaction_receipts_in_block(
         &self,
         block_hash: &H256,
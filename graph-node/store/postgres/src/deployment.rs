@@ -1259,6 +1259,39 @@ pub fn clear_entity_count(conn: &mut PgConnection, site: &Site) -> Result<(), St
     Ok(())
 }
 
+/// Check that reading the state of `site` at `block` does not need any
+/// history that pruning has removed. The `earliest_block_number` starts
+/// out as the `start_block_number` of the manifest, but a deployment that
+/// was not grafted has no state before that block, and reading it is
+/// fine. Reads that should see a consistent state must run in the same
+/// transaction as this check
+pub fn check_history(
+    conn: &mut PgConnection,
+    site: &Site,
+    block: BlockNumber,
+) -> Result<(), StoreError> {
+    use subgraph_deployment as d;
+    use subgraph_manifest as m;
+
+    let (earliest_block, graft_base) = d::table
+        .filter(d::id.eq(site.id))
+        .select((d::earliest_block_number, d::graft_base))
+        .first::<(BlockNumber, Option<String>)>(conn)?;
+    if block >= earliest_block {
+        return Ok(());
+    }
+
+    let start_block = m::table
+        .filter(m::id.eq(site.id))
+        .select(m::start_block_number)
+        .first::<Option<BlockNumber>>(conn)?
+        .unwrap_or(0);
+    if graft_base.is_none() && block < start_block {
+        return Ok(());
+    }
+    Err(StoreError::HistoryPruned(block, earliest_block))
+}
+
 /// Set the earliest block of `site` to the larger of `earliest_block` and
 /// the current value. This means that the `earliest_block_number` can never
 /// go backwards, only forward. This is important so that copying into
@@ -8,8 +8,9 @@ use graph::blockchain::block_stream::{EntitySourceOperation, FirehoseCursor};
 use graph::blockchain::BlockTime;
 use graph::components::store::write::RowGroup;
 use graph::components::store::{
-    Batch, DeploymentLocator, DerivedEntityQuery, PrunePhase, PruneReporter, PruneRequest,
-    PruningStrategy, QueryPermit, StoredDynamicDataSource, VersionStats,
+    Batch, DeploymentLocator, DerivedEntityQuery, EntityAggregate, FindByQuery, PrunePhase,
+    PruneReporter, PruneRequest, PruningStrategy, QueryPermit, StoredDynamicDataSource,
+    VersionStats,
 };
 use graph::components::versions::VERSIONS;
 use graph::data::query::Trace;
@@ -290,6 +291,18 @@ impl DeploymentStore {
         layout.query(&logger, conn, query)
     }
 
+    pub(crate) fn execute_aggregate_query(
+        &self,
+        conn: &mut PgConnection,
+        site: Arc<Site>,
+        query: EntityQuery,
+        aggregates: &[EntityAggregate],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Value>, QueryExecutionError> {
+        let layout = self.layout(conn, site)?;
+        layout.aggregate_query(conn, query, aggregates, timeout)
+    }
+
     fn check_intf_uniqueness(
         &self,
         conn: &mut PgConnection,
@@ -904,6 +917,21 @@ impl DeploymentStore {
         .await
     }
 
+    /// Fail with `StoreError::HistoryPruned` if reading the state of
+    /// `site` at `block` needs history that pruning has removed
+    pub(crate) async fn check_history(
+        &self,
+        site: Arc<Site>,
+        block: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.with_conn(move |conn, cancel| {
+            cancel.check_cancel()?;
+
+            deployment::check_history(conn, &site, block).map_err(Into::into)
+        })
+        .await
+    }
+
     pub(crate) fn block_time(
         &self,
         site: Arc<Site>,
@@ -1032,6 +1060,28 @@ impl DeploymentStore {
         layout.find(&mut conn, key, block)
     }
 
+    /// Like `get`, but fail with `StoreError::HistoryPruned` if the
+    /// versions for `block` might have been removed by pruning
+    pub(crate) fn get_at(
+        &self,
+        site: Arc<Site>,
+        key: &EntityKey,
+        block: BlockNumber,
+    ) -> Result<Option<Entity>, StoreError> {
+        let mut conn = self.get_conn()?;
+        let layout = self.layout(&mut conn, site.cheap_clone())?;
+        // Pruning first moves the earliest block and then removes
+        // versions; with a repeatable read transaction, the lookup sees the
+        // versions that existed when we checked the earliest block
+        conn.build_transaction()
+            .repeatable_read()
+            .read_only()
+            .run(|conn| {
+                deployment::check_history(conn, &site, block)?;
+                layout.find(conn, key, block)
+            })
+    }
+
     /// Retrieve all the entities matching `ids_for_type`, both the type and causality region, from
     /// the deployment `site`. Only consider entities as of the given `block`
     pub(crate) fn get_many(
@@ -1073,6 +1123,17 @@ impl DeploymentStore {
         layout.find_derived(&mut conn, derived_query, block, excluded_keys)
     }
 
+    pub(crate) fn find_by(
+        &self,
+        site: Arc<Site>,
+        query: &FindByQuery,
+        block: BlockNumber,
+    ) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        let mut conn = self.get_conn()?;
+        let layout = self.layout(&mut conn, site)?;
+        layout.find_by(&mut conn, query, block)
+    }
+
     pub(crate) fn get_changes(
         &self,
         site: Arc<Site>,
@@ -1129,7 +1190,7 @@ impl DeploymentStore {
 
                 dynds::insert(conn, &site, &batch.data_sources, manifest_idx_and_name)?;
 
-                dynds::update_offchain_status(conn, &site, &batch.offchain_to_remove)?;
+                dynds::remove(conn, &site, &batch.data_sources_to_remove)?;
 
                 if !batch.deterministic_errors.is_empty() {
                     deployment::insert_subgraph_errors(
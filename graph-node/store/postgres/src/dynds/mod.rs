@@ -47,7 +47,9 @@ pub(crate) fn revert(
     }
 }
 
-pub(crate) fn update_offchain_status(
+/// Mark offchain data sources as done and end the block range of onchain
+/// data sources that mappings removed
+pub(crate) fn remove(
     conn: &mut PgConnection,
     site: &Site,
     data_sources: &write::DataSources,
@@ -57,11 +59,9 @@ pub(crate) fn update_offchain_status(
     }
 
     match site.schema_version.private_data_sources() {
-        true => {
-            DataSourcesTable::new(site.namespace.clone()).update_offchain_status(conn, data_sources)
-        }
+        true => DataSourcesTable::new(site.namespace.clone()).remove(conn, data_sources),
         false => Err(constraint_violation!(
-            "shared schema does not support data source offchain_found",
+            "shared schema does not support removing data sources",
         )),
     }
 }
@@ -80,9 +80,13 @@ impl DataSourcesTable {
         )
     }
 
-    // Query to load the data sources which are live at `block`. Ordering by the creation block and
-    // `vid` makes sure they are in insertion order which is important for the correctness of
-    // reverts and the execution order of triggers. See also 8f1bca33-d3b7-4035-affc-fd6161a12448.
+    // Query to load the data sources which were created at or before `block`. Ordering by the
+    // creation block and `vid` makes sure they are in insertion order which is important for the
+    // correctness of reverts and the execution order of triggers. See also
+    // 8f1bca33-d3b7-4035-affc-fd6161a12448.
+    //
+    // Onchain data sources that a mapping removed are included so that the removal can be
+    // reverted; their `done_at` is the last block whose triggers they handled.
     pub(super) fn load(
         &self,
         conn: &mut PgConnection,
@@ -99,7 +103,7 @@ impl DataSourcesTable {
         let tuples = self
             .table
             .clone()
-            .filter(diesel::dsl::sql::<Bool>("block_range @> ").bind::<Integer, _>(block))
+            .filter(diesel::dsl::sql::<Bool>("lower(block_range) <= ").bind::<Integer, _>(block))
             .select((
                 &self.block_range,
                 &self.manifest_idx,
@@ -124,6 +128,15 @@ impl DataSourcesTable {
                         }
                     };
 
+                    // Onchain data sources that were removed after `block` are still active
+                    // as of `block`
+                    let done_at = match block_range.1 {
+                        Bound::Excluded(end) if causality_region == CausalityRegion::ONCHAIN => {
+                            Some(end - 1).filter(|removed_at| *removed_at <= block)
+                        }
+                        _ => done_at,
+                    };
+
                     StoredDynamicDataSource {
                         manifest_idx: manifest_idx as u32,
                         param: param.map(|p| p.into()),
@@ -200,14 +213,20 @@ impl DataSourcesTable {
     ) -> Result<(), StoreError> {
         // Use the 'does not extend to the left of' operator `&>` to leverage the gist index, this
         // is equivalent to lower(block_range) >= $1.
-        //
-        // This assumes all ranges are of the form [x, +inf), and thefore no range needs to be
-        // unclamped.
         let query = format!(
             "delete from {} where block_range &> int4range($1, null)",
             self.qname
         );
         sql_query(query).bind::<Integer, _>(block).execute(conn)?;
+
+        // Onchain data sources that a mapping removed in one of the reverted blocks become
+        // active again
+        let query = format!(
+            "update {} set block_range = int4range(lower(block_range), null) \
+              where not upper_inf(block_range) and upper(block_range) > $1",
+            self.qname
+        );
+        sql_query(query).bind::<Integer, _>(block).execute(conn)?;
         Ok(())
     }
 
@@ -273,31 +292,19 @@ impl DataSourcesTable {
         Ok(count)
     }
 
-    // Remove offchain data sources by checking the causality region, which currently uniquely
-    // identifies an offchain data source.
-    pub(super) fn update_offchain_status(
+    /// Mark offchain data sources as done, and end the block range of
+    /// onchain data sources that mappings removed
+    pub(super) fn remove(
         &self,
         conn: &mut PgConnection,
         data_sources: &write::DataSources,
     ) -> Result<(), StoreError> {
         for (_, dss) in &data_sources.entries {
             for ds in dss {
-                let query = format!(
-                    "update {} set done_at = $1 where causality_region = $2",
-                    self.qname
-                );
-
-                let count = sql_query(query)
-                    .bind::<Nullable<Integer>, _>(ds.done_at)
-                    .bind::<Integer, _>(ds.causality_region)
-                    .execute(conn)?;
-
-                if count > 1 {
-                    return Err(constraint_violation!(
-                    "expected to remove at most one offchain data source but would remove {}, causality region: {}",
-                    count,
-                    ds.causality_region
-                ));
+                if ds.causality_region == CausalityRegion::ONCHAIN {
+                    self.close_onchain(conn, ds)?;
+                } else {
+                    self.update_offchain_status(conn, ds)?;
                 }
             }
         }
@@ -305,6 +312,73 @@ impl DataSourcesTable {
         Ok(())
     }
 
+    // Onchain data sources all share the same causality region; they are identified by their
+    // template, their parameter and their creation block. The data source handles triggers up to
+    // and including the block at which it was removed.
+    fn close_onchain(
+        &self,
+        conn: &mut PgConnection,
+        ds: &StoredDynamicDataSource,
+    ) -> Result<(), StoreError> {
+        let (Some(creation_block), Some(done_at)) = (ds.creation_block, ds.done_at) else {
+            return Err(constraint_violation!(
+                "removing onchain data source with manifest idx {} requires a creation block and a removal block",
+                ds.manifest_idx
+            ));
+        };
+
+        let query = format!(
+            "update {} set block_range = int4range(lower(block_range), $1) \
+              where causality_region = 0 and manifest_idx = $2 \
+                and param is not distinct from $3 \
+                and lower(block_range) = $4 and upper_inf(block_range)",
+            self.qname
+        );
+        let count = sql_query(query)
+            .bind::<Integer, _>(done_at + 1)
+            .bind::<Integer, _>(ds.manifest_idx as i32)
+            .bind::<Nullable<Binary>, _>(ds.param.as_ref().map(|p| &**p))
+            .bind::<Integer, _>(creation_block)
+            .execute(conn)?;
+
+        if count == 0 {
+            return Err(constraint_violation!(
+                "no active onchain data source with manifest idx {} created at block {} to remove",
+                ds.manifest_idx,
+                creation_block
+            ));
+        }
+        Ok(())
+    }
+
+    // Remove offchain data sources by checking the causality region, which currently uniquely
+    // identifies an offchain data source.
+    fn update_offchain_status(
+        &self,
+        conn: &mut PgConnection,
+        ds: &StoredDynamicDataSource,
+    ) -> Result<(), StoreError> {
+        let query = format!(
+            "update {} set done_at = $1 where causality_region = $2",
+            self.qname
+        );
+
+        let count = sql_query(query)
+            .bind::<Nullable<Integer>, _>(ds.done_at)
+            .bind::<Integer, _>(ds.causality_region)
+            .execute(conn)?;
+
+        if count > 1 {
+            return Err(constraint_violation!(
+                "expected to remove at most one offchain data source but would remove {}, causality region: {}",
+                count,
+                ds.causality_region
+            ));
+        }
+
+        Ok(())
+    }
+
     /// The current causality sequence according to the store, which is infered to be the maximum
     /// value existing in the table.
     pub(super) fn causality_region_curr_val(
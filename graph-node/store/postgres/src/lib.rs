@@ -77,7 +77,7 @@ pub mod command_support {
         pub use crate::copy::{copy_state, copy_table_state};
         pub use crate::primary::{
             active_copies, deployment_schemas, ens_names, subgraph, subgraph_deployment_assignment,
-            subgraph_version, Site,
+            subgraph_version, PersistedQuery, Site,
         };
         pub use crate::primary::{Connection, Mirror};
     }
@@ -185,6 +185,16 @@ table! {
     }
 }
 
+table! {
+    /// The allow-list of persisted queries, keyed by the hex-encoded
+    /// SHA-256 hash of the query text
+    public.persisted_queries(hash) {
+        hash -> Varchar,
+        query -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
 table! {
     public.db_version(version) {
         #[sql_name = "db_version"]
@@ -238,6 +248,14 @@ pub struct UnusedDeployment {
     pub synced_at_block_number: Option<i32>,
 }
 
+#[derive(Clone, Queryable, Debug)]
+#[diesel(table_name = persisted_queries)]
+pub struct PersistedQuery {
+    pub hash: String,
+    pub query: String,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, AsExpression, FromSqlRow)]
 #[diesel(sql_type = Text)]
 /// A namespace (schema) in the database
@@ -1803,6 +1821,39 @@ impl<'a> Connection<'a> {
             .map_err(|e| anyhow!("error if ens table is empty: {}", e).into())
     }
 
+    pub fn persisted_queries(&mut self) -> Result<Vec<PersistedQuery>, StoreError> {
+        use persisted_queries as pq;
+
+        pq::table
+            .order_by(pq::created_at)
+            .load::<PersistedQuery>(self.conn.as_mut())
+            .map_err(StoreError::from)
+    }
+
+    /// Add `query` to the allow-list of persisted queries under `hash`.
+    /// Return `false` if a query with that hash was already in the list
+    pub fn add_persisted_query(&mut self, hash: &str, query: &str) -> Result<bool, StoreError> {
+        use persisted_queries as pq;
+
+        insert_into(pq::table)
+            .values((pq::hash.eq(hash), pq::query.eq(query)))
+            .on_conflict_do_nothing()
+            .execute(self.conn.as_mut())
+            .map(|count| count > 0)
+            .map_err(StoreError::from)
+    }
+
+    /// Remove the query with `hash` from the allow-list of persisted
+    /// queries. Return `false` if there was no such query
+    pub fn remove_persisted_query(&mut self, hash: &str) -> Result<bool, StoreError> {
+        use persisted_queries as pq;
+
+        delete(pq::table.find(hash))
+            .execute(self.conn.as_mut())
+            .map(|count| count > 0)
+            .map_err(StoreError::from)
+    }
+
     pub fn record_active_copy(&mut self, src: &Site, dst: &Site) -> Result<(), StoreError> {
         use active_copies as cp;
 
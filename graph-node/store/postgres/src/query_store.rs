@@ -57,6 +57,21 @@ impl QueryStoreTrait for QueryStore {
             })
     }
 
+    fn aggregate_query_values(
+        &self,
+        query: EntityQuery,
+        aggregates: &[EntityAggregate],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Value>, QueryExecutionError> {
+        assert_eq!(&self.site.deployment, &query.subgraph_id);
+        let mut conn = self
+            .store
+            .get_replica_conn(self.replica_id)
+            .map_err(|e| QueryExecutionError::StoreError(e.into()))?;
+        self.store
+            .execute_aggregate_query(&mut conn, self.site.clone(), query, aggregates, timeout)
+    }
+
     /// Return true if the deployment with the given id is fully synced,
     /// and return false otherwise. Errors from the store are passed back up
     async fn is_deployment_synced(&self) -> Result<bool, Error> {
@@ -40,7 +40,7 @@ use graph::data::value::Word;
 use graph::data_source::CausalityRegion;
 use graph::prelude::{q, EntityQuery, StopwatchMetrics, ENV_VARS};
 use graph::schema::{
-    EntityKey, EntityType, Field, FulltextConfig, FulltextDefinition, InputSchema,
+    Aggregate, EntityKey, EntityType, Field, FulltextConfig, FulltextDefinition, InputSchema,
 };
 use graph::slog::warn;
 use index::IndexList;
@@ -58,8 +58,9 @@ use std::time::{Duration, Instant};
 
 use crate::relational::value::{FromOidRow, OidRow};
 use crate::relational_queries::{
-    ConflictingEntitiesData, ConflictingEntitiesQuery, EntityDataExt, FindChangesQuery,
-    FindDerivedQuery, FindPossibleDeletionsQuery, ReturnedEntityData,
+    AggregateData, AggregateQuery, ConflictingEntitiesData, ConflictingEntitiesQuery,
+    EntityDataExt, FindByAttributeQuery, FindChangesQuery, FindDerivedQuery,
+    FindPossibleDeletionsQuery, ReturnedEntityData,
 };
 use crate::{
     primary::{Namespace, Site},
@@ -68,12 +69,14 @@ use crate::{
         FindRangeQuery, InsertQuery, RevertClampQuery, RevertRemoveQuery,
     },
 };
-use graph::components::store::{AttributeNames, DerivedEntityQuery};
+use graph::components::store::{
+    AttributeNames, DerivedEntityQuery, EntityAggregate, EntityFilter, FindByQuery,
+};
 use graph::data::store::{Id, IdList, IdType, BYTES_SCALAR};
 use graph::data::subgraph::schema::POI_TABLE;
 use graph::prelude::{
     anyhow, info, BlockNumber, DeploymentHash, Entity, EntityOperation, Logger,
-    QueryExecutionError, StoreError, ValueType,
+    QueryExecutionError, StoreError, Value, ValueType,
 };
 
 use crate::block_range::{BoundSide, BLOCK_COLUMN, BLOCK_RANGE_COLUMN};
@@ -679,6 +682,35 @@ impl Layout {
         Ok(entities)
     }
 
+    pub fn find_by(
+        &self,
+        conn: &mut PgConnection,
+        query: &FindByQuery,
+        block: BlockNumber,
+    ) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        let table = self.table_for_entity(&query.entity_type)?;
+        let filter = EntityFilter::Equal(query.attribute.to_string(), query.value.clone());
+        let query = FindByAttributeQuery::new(
+            self,
+            table,
+            &filter,
+            query.causality_region,
+            query.limit,
+            block,
+        )?;
+
+        let mut entities = BTreeMap::new();
+        for data in query.load::<EntityData>(conn)? {
+            let entity_type = data.entity_type(&self.input_schema);
+            let entity_data: Entity = data.deserialize_with_layout(self, None)?;
+            let key =
+                entity_type.key_in(entity_data.id(), CausalityRegion::from_entity(&entity_data));
+
+            entities.insert(key, entity_data);
+        }
+        Ok(entities)
+    }
+
     pub fn find_changes(
         &self,
         conn: &mut PgConnection,
@@ -835,14 +867,19 @@ impl Layout {
 
         let trace = query.trace;
 
-        let filter_collection =
-            FilterCollection::new(self, query.collection, query.filter.as_ref(), query.block)?;
+        let filter_collection = FilterCollection::new(
+            self,
+            query.collection,
+            query.filter.as_ref(),
+            query.block,
+            query.current,
+        )?;
         let query = FilterQuery::new(
             &filter_collection,
             self,
             query.filter.as_ref(),
             query.order,
-            query.range,
+            &query.range,
             query.block,
             query.query_id,
             &self.site,
@@ -898,6 +935,74 @@ impl Layout {
             .map(|values| (values, trace))
     }
 
+    /// Compute `aggregates` over all entities that `query` matches. Only
+    /// queries against a single entity type without windowing are
+    /// supported. The query is aborted if it runs for longer than
+    /// `timeout` or the statement timeout configured for GraphQL queries,
+    /// whichever is shorter
+    pub fn aggregate_query(
+        &self,
+        conn: &mut PgConnection,
+        query: EntityQuery,
+        aggregates: &[EntityAggregate],
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Value>, QueryExecutionError> {
+        if aggregates.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let filter_collection = FilterCollection::new(
+            self,
+            query.collection,
+            query.filter.as_ref(),
+            query.block,
+            query.current,
+        )?;
+        let query = AggregateQuery::new(&filter_collection, aggregates)?;
+        let query_clone = query.clone();
+
+        let timeout = match (timeout, ENV_VARS.graphql.sql_statement_timeout) {
+            (Some(timeout), Some(sql_timeout)) => Some(timeout.min(sql_timeout)),
+            (timeout, sql_timeout) => timeout.or(sql_timeout),
+        };
+        let timeout_sql = timeout
+            .map(|timeout| format!("set local statement_timeout={}", timeout.as_millis().max(1)));
+
+        let data = conn
+            .transaction(|conn| {
+                if let Some(timeout_sql) = &timeout_sql {
+                    conn.batch_execute(timeout_sql)?;
+                }
+                query.get_result::<AggregateData>(conn)
+            })
+            .map_err(|e| {
+                use diesel::result::Error::*;
+                match e {
+                    DatabaseError(_, ref info)
+                        if info
+                            .message()
+                            .contains("canceling statement due to statement timeout") =>
+                    {
+                        QueryExecutionError::Timeout
+                    }
+                    _ => {
+                        let mut query_text = String::new();
+                        match write!(query_text, "{}", debug_query(&query_clone)) {
+                            Ok(()) => (),
+                            Err(_) => {
+                                write!(query_text, "{query_clone}").ok();
+                            }
+                        };
+                        QueryExecutionError::ResolveEntitiesError(format!(
+                            "{e}, query = {query_text}",
+                        ))
+                    }
+                }
+            })?;
+
+        query_clone.values(data).map_err(|e| e.into())
+    }
+
     pub fn update<'a>(
         &'a self,
         conn: &mut PgConnection,
@@ -1054,8 +1159,10 @@ impl Layout {
     /// Revert the metadata (dynamic data sources and related entities) for
     /// the given `subgraph`.
     ///
-    /// For metadata, reversion always means deletion since the metadata that
-    /// is subject to reversion is only ever created but never updated
+    /// For metadata, reversion mostly means deletion since the metadata
+    /// that is subject to reversion is mostly created but never updated.
+    /// The exception are dynamic data sources that mappings removed, which
+    /// become active again
     pub fn revert_metadata(
         logger: &Logger,
         conn: &mut PgConnection,
@@ -1153,6 +1260,16 @@ impl Layout {
         Rollup::last_rollup(&self.rollups, conn)
     }
 
+    /// Return the SQL that adds the current, partially filled bucket to
+    /// the rows of `table`, or `None` if `table` does not store an
+    /// aggregation
+    pub(crate) fn current_bucket_sql(&self, table: &Table) -> Option<&str> {
+        self.rollups
+            .iter()
+            .find(|rollup| rollup.agg_table().name == table.name)
+            .map(|rollup| rollup.current_sql())
+    }
+
     /// Construct `Rolllup` for each of the aggregation mappings
     /// `schema.agg_mappings()` and return them in the same order as the
     /// aggregation mappings
@@ -1251,17 +1368,13 @@ impl Layout {
                 // that, which will roll up the bucket `t5 <= b2 < t6`. So
                 // there's no need to worry about the buckets starting at
                 // `t2`, `t3`, and `t4`.
-                match buckets.first() {
-                    None => {
-                        // The rollups are in increasing order of interval size, so
-                        // if a smaller interval doesn't have a bucket between
-                        // last_rollup and block_time, a larger one can't either and
-                        // we are done with this rollup.
-                        break;
-                    }
-                    Some(bucket) => {
-                        rollup.insert(conn, &bucket, *block)?;
-                    }
+                //
+                // If there is no bucket for this interval, we can not
+                // conclude that larger intervals have nothing to roll up
+                // either since custom intervals, e.g., `m45`, need not
+                // divide larger ones evenly
+                if let Some(bucket) = buckets.first() {
+                    rollup.insert(conn, &bucket, *block)?;
                 }
             }
             last_rollup = *block_time;
@@ -1504,6 +1617,37 @@ impl Column {
         }
     }
 
+    /// Create the column that stores the state for `aggregate` in an
+    /// aggregation table. The state is needed to combine aggregates like
+    /// `avg` across buckets and is never exposed as a field of the
+    /// aggregation
+    fn new_aggregate_state(aggregate: &Aggregate) -> Column {
+        let name = SqlName::verbatim(format!(
+            "{}{}",
+            SqlName::from(aggregate.name.as_str()),
+            AGGREGATE_STATE_SUFFIX
+        ));
+        let field = Word::from(format!("{}{}", aggregate.name, AGGREGATE_STATE_SUFFIX));
+        let field_type = q::Type::NonNullType(Box::new(q::Type::ListType(Box::new(
+            q::Type::NonNullType(Box::new(q::Type::NamedType("BigDecimal".to_string()))),
+        ))));
+        Column {
+            name,
+            field,
+            field_type,
+            column_type: ColumnType::BigDecimal,
+            fulltext_fields: None,
+            is_reference: false,
+            use_prefix_comparison: false,
+        }
+    }
+
+    /// Whether this column holds the state of an aggregate. Such columns
+    /// do not correspond to any field in the schema
+    pub fn is_aggregate_state(&self) -> bool {
+        self.field.ends_with(AGGREGATE_STATE_SUFFIX)
+    }
+
     fn new_fulltext(def: &FulltextDefinition) -> Result<Column, StoreError> {
         SqlName::check_valid_identifier(&def.name, "attribute")?;
         let sql_name = SqlName::from(def.name.as_str());
@@ -1589,6 +1733,11 @@ pub(crate) const PRIMARY_KEY_COLUMN: &str = "id";
 /// synthetic primary key. This is the name of the column we use.
 pub(crate) const VID_COLUMN: &str = "vid";
 
+/// Aggregates like `avg` store some state alongside their value in a
+/// column whose name is the name of the aggregate's column with this
+/// suffix
+pub(crate) const AGGREGATE_STATE_SUFFIX: &str = "$state";
+
 #[derive(Debug, Clone)]
 pub struct Table {
     /// The reference to the underlying type in the input schema. For
@@ -1644,13 +1793,26 @@ impl Table {
         })?;
 
         let table_name = SqlName::from(defn.as_str());
-        let columns = object_type
+        let mut columns = object_type
             .fields
             .iter()
             .filter(|field| !field.is_derived())
             .map(|field| Column::new(schema, &table_name, field, catalog))
             .chain(fulltexts.iter().map(Column::new_fulltext))
             .collect::<Result<Vec<Column>, StoreError>>()?;
+        if let Some(aggregation) = schema
+            .agg_mappings()
+            .find(|mapping| &mapping.agg_type(schema) == defn)
+            .map(|mapping| mapping.aggregation(schema))
+        {
+            columns.extend(
+                aggregation
+                    .aggregates
+                    .iter()
+                    .filter(|aggregate| aggregate.func.has_state())
+                    .map(Column::new_aggregate_state),
+            );
+        }
         let qualified_name = SqlName::qualified_name(&catalog.site.namespace, &table_name);
         let immutable = defn.is_immutable();
         let nsp = catalog.site.namespace.clone();
@@ -1696,7 +1858,7 @@ impl Table {
             .iter()
             .filter(|column| match column.column_type {
                 ColumnType::TSVector(_) => false,
-                _ => true,
+                _ => !column.is_aggregate_state(),
             })
             .find(|column| &column.name == name)
     }
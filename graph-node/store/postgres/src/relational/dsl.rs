@@ -218,6 +218,17 @@ impl<'a> Table<'a> {
         AtBlock::new(*self, block)
     }
 
+    /// Return a `from` clause that adds the rows for the current,
+    /// partially filled bucket at `block` to the rows of this table.
+    /// `current` must be the SQL from `Rollup::current_sql` for the
+    /// aggregation that this table stores
+    pub fn from_current(&self, current: &'a str, block: BlockNumber) -> FromTable<'a> {
+        FromTable {
+            table: *self,
+            current: Some((current, block)),
+        }
+    }
+
     /// The block column for this table for places where the just the
     /// qualified name is needed
     pub fn block_column(&self) -> BlockColumn<'a> {
@@ -273,8 +284,9 @@ impl<'a> Table<'a> {
         };
 
         // NB: Exclude full-text search columns from selection. These columns are used for indexing
-        // and searching but are not part of the entity's data model.
-        cols.retain(|c| !c.is_fulltext());
+        // and searching but are not part of the entity's data model. The
+        // same goes for the state of aggregates
+        cols.retain(|c| !c.is_fulltext() && !c.is_aggregate_state());
 
         if T::WITH_INTERNAL_KEYS {
             match parent_type {
@@ -371,7 +383,13 @@ impl<'a> Table<'a> {
 /// Generate the SQL to use a table in the `from` clause, complete with
 /// giving the table an alias
 #[derive(Debug, Clone, Copy)]
-pub struct FromTable<'a>(Table<'a>);
+pub struct FromTable<'a> {
+    table: Table<'a>,
+    /// For aggregations, the SQL from `Rollup::current_sql` and the block
+    /// at which to compute the current bucket when the current bucket
+    /// should be included in the rows of the table
+    current: Option<(&'a str, BlockNumber)>,
+}
 
 impl<'a, DB> QueryFragment<DB> for FromTable<'a>
 where
@@ -380,11 +398,22 @@ where
     fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, DB>) -> QueryResult<()> {
         out.unsafe_to_cache_prepared();
 
-        out.push_identifier(self.0.meta.nsp.as_str())?;
-        out.push_sql(".");
-        out.push_identifier(&self.0.meta.name)?;
+        match self.current {
+            None => {
+                out.push_identifier(self.table.meta.nsp.as_str())?;
+                out.push_sql(".");
+                out.push_identifier(&self.table.meta.name)?;
+            }
+            Some((current, block)) => {
+                out.push_sql("(with params as (select ");
+                out.push_sql(&block.to_string());
+                out.push_sql("::int4 as block), ");
+                out.push_sql(current);
+                out.push_sql(")");
+            }
+        }
         out.push_sql(" as ");
-        out.push_sql(self.0.alias.as_str());
+        out.push_sql(self.table.alias.as_str());
         Ok(())
     }
 }
@@ -397,7 +426,7 @@ impl std::fmt::Display for Table<'_> {
 
 impl std::fmt::Display for FromTable<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.table)
     }
 }
 
@@ -406,7 +435,10 @@ impl<'a> QuerySource for Table<'a> {
     type DefaultSelection = DummyExpression;
 
     fn from_clause(&self) -> FromTable<'a> {
-        FromTable(*self)
+        FromTable {
+            table: *self,
+            current: None,
+        }
     }
 
     fn default_selection(&self) -> Self::DefaultSelection {
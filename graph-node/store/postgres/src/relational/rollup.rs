@@ -72,7 +72,8 @@ use graph::sqlparser::ast as p;
 use graph::sqlparser::parser::ParserError;
 use itertools::Itertools;
 
-use crate::relational::Table;
+use crate::block_range::BLOCK_COLUMN;
+use crate::relational::{Table, AGGREGATE_STATE_SUFFIX};
 
 use super::{Column, SqlName};
 
@@ -146,6 +147,8 @@ pub(crate) struct Agg<'a> {
     src_columns: Vec<&'a str>,
     expr: String,
     agg_column: &'a Column,
+    /// The column holding the state for aggregates that have state
+    state_column: Option<&'a Column>,
 }
 
 impl<'a> Agg<'a> {
@@ -156,11 +159,18 @@ impl<'a> Agg<'a> {
     ) -> Result<Self, StoreError> {
         let (expr, src_columns) = rewrite(src_table, &aggregate.arg)?;
         let agg_column = agg_table.column_for_field(&aggregate.name)?;
+        let state_column = if aggregate.func.has_state() {
+            let state = format!("{}{}", aggregate.name, AGGREGATE_STATE_SUFFIX);
+            Some(agg_table.column_for_field(&state)?)
+        } else {
+            None
+        };
         Ok(Self {
             aggregate,
             src_columns,
             expr,
             agg_column,
+            state_column,
         })
     }
 
@@ -180,10 +190,48 @@ impl<'a> Agg<'a> {
                 write!(w, "arg_max_{}(({}, {time}))", sql_type, src)?
             }
             Count => write!(w, "count(*)")?,
+            Avg | Variance | Stddev => return self.from_state(&format!("moments({src})"), w),
+            Percentile(_) => {
+                return self.from_state(&format!("sketch_of(array_agg(sketch_key({src})))"), w)
+            }
         }
         write!(w, " as \"{}\"", self.agg_column.name)
     }
 
+    /// Generate a SQL fragment `final(state) as agg_column, state as
+    /// state_column` for aggregates that have state, where `state` is an
+    /// expression that produces the state for the aggregate and `final`
+    /// computes the value of the aggregate from that state. The functions
+    /// that we use here are defined in the `stat_aggregates` migration
+    fn from_state(&self, state: &str, w: &mut dyn fmt::Write) -> fmt::Result {
+        use AggregateFn::*;
+
+        match self.aggregate.func {
+            Avg => write!(w, "moments_avg({state})")?,
+            Variance => write!(w, "moments_variance({state})")?,
+            Stddev => write!(w, "moments_stddev({state})")?,
+            Percentile(p) => write!(w, "sketch_quantile({state}, 0.{p:02})")?,
+            Sum | Max | Min | First | Last | Count => {
+                unreachable!("aggregate {} has no state", self.aggregate.func)
+            }
+        }
+        write!(w, " as \"{}\"", self.agg_column.name)?;
+        if let Some(state_column) = self.state_column {
+            write!(w, ", {state} as \"{}\"", state_column.name)?;
+        }
+        Ok(())
+    }
+
+    /// Write the names of the columns that hold this aggregate, i.e., the
+    /// column for its value and the column for its state if it has one
+    fn columns(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        write!(w, "\"{}\"", self.agg_column.name)?;
+        if let Some(state_column) = self.state_column {
+            write!(w, ", \"{}\"", state_column.name)?;
+        }
+        Ok(())
+    }
+
     /// Generate a SQL fragment `func(expr) as agg_column` where
     /// `func` is the aggregation function. The `time` parameter is the name
     /// of the column with respect to which `first` and `last` should decide
@@ -207,6 +255,16 @@ impl<'a> Agg<'a> {
                 return self.aggregate_over(&name, time, w);
             }
             Count => write!(w, "sum(\"{}\")", self.agg_column.name)?,
+            Avg | Variance | Stddev | Percentile(_) => {
+                let merge = match self.aggregate.func {
+                    Percentile(_) => "sketch_merge",
+                    _ => "moments_merge",
+                };
+                let state_column = self
+                    .state_column
+                    .expect("aggregates with state have a state column");
+                return self.from_state(&format!("{merge}(\"{}\")", state_column.name), w);
+            }
         }
         write!(w, " as \"{}\"", self.agg_column.name)
     }
@@ -216,24 +274,34 @@ impl<'a> Agg<'a> {
     /// `null` when it is not
     fn prev_agg(&self, w: &mut dyn fmt::Write) -> fmt::Result {
         if self.aggregate.cumulative {
-            write!(w, "prev.\"{}\"", self.agg_column.name)
+            write!(w, "prev.\"{}\"", self.agg_column.name)?;
+            if let Some(state_column) = self.state_column {
+                write!(w, ", prev.\"{}\"", state_column.name)?;
+            }
         } else {
             let sql_type = self.agg_column.column_type.sql_type();
-            write!(w, "null::{sql_type} as \"{}\"", self.agg_column.name)
+            write!(w, "null::{sql_type} as \"{}\"", self.agg_column.name)?;
+            if let Some(state_column) = self.state_column {
+                write!(w, ", null::numeric[] as \"{}\"", state_column.name)?;
+            }
         }
+        Ok(())
     }
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct Rollup {
     pub(crate) interval: AggregationInterval,
-    #[allow(dead_code)]
     agg_table: Arc<Table>,
     insert_sql: String,
     /// A query that determines the last time a rollup was done. The query
     /// finds the latest timestamp in the aggregation table and adds the
     /// length of the aggregation interval to deduce the last rollup time
     last_rollup_sql: String,
+    /// A query that returns the rows of the aggregation table together
+    /// with rows for the buckets that have not been rolled up yet. See
+    /// `RollupSql::current` for details
+    current_sql: String,
 }
 
 impl Rollup {
@@ -262,14 +330,31 @@ impl Rollup {
         let mut insert_sql = String::new();
         sql.insert(&mut insert_sql)?;
         let last_rollup_sql = sql.last_rollup();
+        let mut current_sql = String::new();
+        sql.current(&mut current_sql)?;
         Ok(Self {
             interval,
             agg_table,
             insert_sql,
             last_rollup_sql,
+            current_sql,
         })
     }
 
+    /// The aggregation table that this rollup fills
+    pub(crate) fn agg_table(&self) -> &Table {
+        &self.agg_table
+    }
+
+    /// The SQL for a query that returns the rows of the aggregation table
+    /// together with rows for the current, partially filled bucket that
+    /// are computed on the fly from the timeseries. The SQL must be
+    /// preceded by `with params as (select {block}::int4 as block), ` so
+    /// that it can see the rows that the timeseries had at `block`
+    pub(crate) fn current_sql(&self) -> &str {
+        &self.current_sql
+    }
+
     pub(crate) fn insert(
         &self,
         conn: &mut PgConnection,
@@ -310,12 +395,46 @@ impl Rollup {
     }
 }
 
+/// SQL expressions for the time range that a rollup covers and for the
+/// block number that the resulting rows are marked with
+#[derive(Clone, Copy)]
+struct Bounds {
+    /// The beginning of the time range (inclusive)
+    start: &'static str,
+    /// The end of the time range (exclusive)
+    end: &'static str,
+    block: &'static str,
+    /// Whether rows from the source need to be restricted to the ones that
+    /// existed at `block`
+    at_block: bool,
+}
+
+impl Bounds {
+    /// Bounds for a rollup that are passed in as bind variables
+    const PARAMS: Bounds = Bounds {
+        start: "$1",
+        end: "$2",
+        block: "$3",
+        at_block: false,
+    };
+
+    /// Bounds for computing the current bucket. They refer to the
+    /// `bounds` table that `RollupSql::current` sets up
+    const CURRENT: Bounds = Bounds {
+        start: "(select start from bounds)",
+        end: "'infinity'::timestamptz",
+        block: "(select block from bounds)",
+        at_block: true,
+    };
+}
+
 struct RollupSql<'a> {
     interval: AggregationInterval,
     src_table: &'a SqlName,
     agg_table: &'a Table,
     dimensions: &'a [&'a Column],
     aggregates: &'a [Agg<'a>],
+    bounds: Bounds,
 }
 
 impl<'a> RollupSql<'a> {
@@ -332,6 +451,18 @@ impl<'a> RollupSql<'a> {
             agg_table,
             dimensions,
             aggregates,
+            bounds: Bounds::PARAMS,
+        }
+    }
+
+    fn with_bounds(&self, bounds: Bounds) -> Self {
+        Self {
+            interval: self.interval,
+            src_table: self.src_table,
+            agg_table: self.agg_table,
+            dimensions: self.dimensions,
+            aggregates: self.aggregates,
+            bounds,
         }
     }
 
@@ -367,15 +498,13 @@ impl<'a> RollupSql<'a> {
         };
         write!(w, "select {max_id} as id, timestamp")?;
         if with_block {
-            write!(w, ", $3")?;
+            write!(w, ", {}", self.bounds.block)?;
         }
         write_dims(self.dimensions, w)?;
         comma_sep(self.aggregates, w, |w, agg| agg.aggregate("id", w))?;
-        let secs = self.interval.as_duration().as_secs();
-        write!(
-            w,
-            " from (select id, date_bin('{secs}s', timestamp, 'epoch'::timestamptz) as timestamp"
-        )?;
+        write!(w, " from (select id, ")?;
+        bucket_start(self.interval, w)?;
+        write!(w, " as timestamp")?;
         write_dims(self.dimensions, w)?;
         let agg_srcs: Vec<&str> = {
             let mut agg_srcs: Vec<_> = self
@@ -392,9 +521,19 @@ impl<'a> RollupSql<'a> {
         comma_sep(agg_srcs, w, |w, col: &str| write!(w, "\"{}\"", col))?;
         write!(
             w,
-            " from {src_table} where {src_table}.timestamp >= $1 and {src_table}.timestamp < $2",
-            src_table = self.src_table
+            " from {src_table} where {src_table}.timestamp >= {start} and {src_table}.timestamp < {end}",
+            src_table = self.src_table,
+            start = self.bounds.start,
+            end = self.bounds.end,
         )?;
+        if self.bounds.at_block {
+            write!(
+                w,
+                " and {src_table}.{BLOCK_COLUMN} <= {block}",
+                src_table = self.src_table,
+                block = self.bounds.block
+            )?;
+        }
         write!(
             w,
             " order by {src_table}.timestamp) data group by timestamp",
@@ -418,9 +557,7 @@ impl<'a> RollupSql<'a> {
             self.agg_table.qualified_name
         )?;
         write_dims(self.dimensions, w)?;
-        comma_sep(self.aggregates, w, |w, agg| {
-            write!(w, "\"{}\"", agg.agg_column.name)
-        })?;
+        comma_sep(self.aggregates, w, |w, agg| agg.columns(w))?;
         write!(w, ") ")
     }
 
@@ -445,7 +582,7 @@ impl<'a> RollupSql<'a> {
         comma_sep(self.aggregates, w, |w, agg| agg.prev_agg(w))?;
         write!(w, " from bucket cross join lateral (")?;
         write!(w, "select * from {} prev", self.agg_table.qualified_name)?;
-        write!(w, " where prev.timestamp < $1")?;
+        write!(w, " where prev.timestamp < {}", self.bounds.start)?;
         for dim in self.dimensions {
             write!(
                 w,
@@ -476,7 +613,14 @@ impl<'a> RollupSql<'a> {
     ///      prev as (<select_prev>),
     ///      combined as (<select_combined>)
     fn select_cte(&self, w: &mut dyn fmt::Write) -> fmt::Result {
-        write!(w, "with bucket as (")?;
+        write!(w, "with ")?;
+        self.cte_queries(w)
+    }
+
+    /// Generate the queries of the common table expression from
+    /// `select_cte` without the leading `with`
+    fn cte_queries(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        write!(w, "bucket as (")?;
         self.select_bucket(false, w)?;
         write!(w, "), prev as (")?;
         self.select_prev(w)?;
@@ -497,11 +641,9 @@ impl<'a> RollupSql<'a> {
         self.select_cte(w)?;
         write!(w, " ")?;
         self.insert_into(w)?;
-        write!(w, "select id, timestamp, $3 as block$")?;
+        write!(w, "select id, timestamp, {} as block$", self.bounds.block)?;
         write_dims(self.dimensions, w)?;
-        comma_sep(self.aggregates, w, |w, agg| {
-            write!(w, "\"{}\"", agg.agg_column.name)
-        })?;
+        comma_sep(self.aggregates, w, |w, agg| agg.columns(w))?;
         write!(w, " from combined")
     }
 
@@ -513,20 +655,99 @@ impl<'a> RollupSql<'a> {
         }
     }
 
+    /// Generate a query that returns all rows of the aggregation table and
+    /// adds rows for the buckets that have not been rolled up yet. Those
+    /// are computed from the rows in the timeseries that come after the
+    /// last bucket in the aggregation table, exactly like a rollup would
+    /// compute them. The query expects a table `params` with the block
+    /// at which to run it
+    ///
+    /// bounds as (select <end of last bucket> as start, block from params),
+    /// [bucket as (..), prev as (..), combined as (..)]
+    /// select vid, id, timestamp, block$, <dimensions>, <aggregates>
+    ///   from <aggregation>
+    ///  union all
+    /// select null, id, timestamp, block, <dimensions>, <aggregates>
+    ///   from (<select>)
+    fn current(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        let sql = self.with_bounds(Bounds::CURRENT);
+        write!(
+            w,
+            "bounds as (select coalesce((select max(timestamp) from {agg_table} \
+              where {BLOCK_COLUMN} <= params.block) + {length}, \
+              '-infinity'::timestamptz) as start, params.block from params)",
+            agg_table = self.agg_table.qualified_name,
+            length = IntervalLength(self.interval, 0)
+        )?;
+        if self.has_cumulative_aggregates() {
+            write!(w, ", ")?;
+            sql.cte_queries(w)?;
+        }
+        write!(w, " select vid, id, timestamp, {BLOCK_COLUMN}")?;
+        write_dims(self.dimensions, w)?;
+        comma_sep(self.aggregates, w, |w, agg| agg.columns(w))?;
+        write!(w, " from {}", self.agg_table.qualified_name)?;
+        write!(w, " union all select null::int8 as vid, ")?;
+        if self.has_cumulative_aggregates() {
+            write!(w, "id, timestamp, {}", Bounds::CURRENT.block)?;
+            write_dims(self.dimensions, w)?;
+            comma_sep(self.aggregates, w, |w, agg| agg.columns(w))?;
+            write!(w, " from combined")
+        } else {
+            write!(w, "cur.* from (")?;
+            sql.select(w)?;
+            write!(w, ") cur")
+        }
+    }
+
     /// Generate a query that selects the timestamp of the last rollup
     fn last_rollup(&self) -> String {
         // The timestamp column contains the timestamp of the start of the
         // last bucket. The last rollup was therefore at least
         // `self.interval` after that. We add 1 second to make sure we are
         // well within the next bucket
-        let secs = self.interval.as_duration().as_secs() + 1;
         format!(
-            "select max(timestamp) + '{} s'::interval as last_rollup from {}",
-            secs, self.agg_table.qualified_name
+            "select max(timestamp) + {} as last_rollup from {}",
+            IntervalLength(self.interval, 1),
+            self.agg_table.qualified_name
         )
     }
 }
 
+/// Write an expression that computes the start of the bucket for
+/// `interval` that contains the `timestamp` column
+fn bucket_start(interval: AggregationInterval, w: &mut dyn fmt::Write) -> fmt::Result {
+    match interval.fixed_length() {
+        Some((length, origin)) if origin.is_zero() => write!(
+            w,
+            "date_bin('{}s', timestamp, 'epoch'::timestamptz)",
+            length.as_secs()
+        ),
+        Some((length, origin)) => write!(
+            w,
+            "date_bin('{}s', timestamp, 'epoch'::timestamptz + '{} s'::interval)",
+            length.as_secs(),
+            origin.as_secs()
+        ),
+        None => write!(w, "date_trunc('month', timestamp, 'UTC')"),
+    }
+}
+
+/// Format the length of buckets for an interval as a Postgres `interval`,
+/// adding the given number of seconds to it
+struct IntervalLength(AggregationInterval, u64);
+
+impl fmt::Display for IntervalLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let IntervalLength(interval, extra) = self;
+        match interval.fixed_length() {
+            Some((length, _)) => write!(f, "'{} s'::interval", length.as_secs() + extra),
+            None if *extra == 0 => write!(f, "'1 month'::interval"),
+            None => write!(f, "'1 month {} s'::interval", extra),
+        }
+    }
+}
+
 /// Write the elements in `list` separated by commas into `w`. The list
 /// elements are written by calling `out` with each of them.
 fn comma_sep<T, F>(list: impl IntoIterator<Item = T>, w: &mut dyn fmt::Write, out: F) -> fmt::Result
@@ -555,7 +776,7 @@ mod tests {
 
     use crate::{
         layout_for_tests::{make_dummy_site, Namespace},
-        relational::{rollup::Rollup, Catalog, Layout},
+        relational::{rollup::Rollup, Catalog, Layout, SqlName},
     };
 
     // Check that the two strings are the same after replacing runs of
@@ -704,6 +925,60 @@ mod tests {
              order by "sgd007"."data".timestamp) data \
         group by timestamp"#;
 
+        const STATS_HOUR_CURRENT_SQL: &str = r#"\
+        bounds as (select coalesce((select max(timestamp) from "sgd007"."stats_hour" \
+                                     where block$ <= params.block) + '3600 s'::interval, \
+                                   '-infinity'::timestamptz) as start, params.block from params) \
+        select vid, id, timestamp, block$, "token", "sum", "max" from "sgd007"."stats_hour" \
+        union all \
+        select null::int8 as vid, cur.* from (\
+            select max(id) as id, timestamp, (select block from bounds), "token", \
+                   sum("price") as "sum", max("amount") as "max" from (\
+                select id, date_bin('3600s', timestamp, 'epoch'::timestamptz) as timestamp, "token", "amount", "price" \
+                  from "sgd007"."data" \
+                 where "sgd007"."data".timestamp >= (select start from bounds) \
+                   and "sgd007"."data".timestamp < 'infinity'::timestamptz \
+                   and "sgd007"."data".block$ <= (select block from bounds) \
+                 order by "sgd007"."data".timestamp) data \
+            group by timestamp, "token") cur"#;
+
+        const LIFETIME_CURRENT_SQL: &str = r#"\
+        bounds as (select coalesce((select max(timestamp) from "sgd007"."lifetime_day"
+                                     where block$ <= params.block) + '86400 s'::interval,
+                                   '-infinity'::timestamptz) as start, params.block from params),
+             bucket as (
+            select max(id) as id, timestamp, count(*) as "count",
+                   sum("amount") as "sum", count(*) as "total_count",
+                   sum("amount") as "total_sum"
+              from (select id, date_bin('86400s', timestamp, 'epoch'::timestamptz) as timestamp, "amount"
+                      from "sgd007"."data"
+                     where "sgd007"."data".timestamp >= (select start from bounds)
+                       and "sgd007"."data".timestamp < 'infinity'::timestamptz
+                       and "sgd007"."data".block$ <= (select block from bounds)
+                     order by "sgd007"."data".timestamp) data
+              group by timestamp),
+             prev as (select bucket.id, bucket.timestamp,
+                             null::int8 as "count", null::numeric as "sum",
+                             prev."total_count", prev."total_sum"
+                        from bucket cross join lateral (
+                             select * from "sgd007"."lifetime_day" prev
+                              where prev.timestamp < (select start from bounds)
+                              order by prev.timestamp desc limit 1) prev),
+             combined as (select id, timestamp,
+                                 sum("count") as "count", sum("sum") as "sum",
+                                 sum("total_count") as "total_count",
+                                 sum("total_sum") as "total_sum" from (
+                            select *, 1 as seq from prev
+                            union all
+                            select *, 2 as seq from bucket) u
+                          group by id, timestamp)
+        select vid, id, timestamp, block$, "count", "sum", "total_count", "total_sum"
+          from "sgd007"."lifetime_day"
+         union all
+        select null::int8 as vid, id, timestamp, (select block from bounds),
+               "count", "sum", "total_count", "total_sum" from combined
+        "#;
+
         #[track_caller]
         fn rollup_for<'a>(layout: &'a Layout, table_name: &str) -> &'a Rollup {
             layout
@@ -741,5 +1016,169 @@ mod tests {
 
         let count_only = rollup_for(&layout, "count_only_day");
         check_eqv(COUNT_ONLY_SQL, &count_only.insert_sql);
+
+        // The current bucket is computed the same way as a rollup
+        check_eqv(STATS_HOUR_CURRENT_SQL, stats_hour.current_sql());
+        check_eqv(LIFETIME_CURRENT_SQL, lifetime.current_sql());
+    }
+
+    #[test]
+    fn rollup_stats() {
+        const SCHEMA: &str = r#"
+    type Data @entity(timeseries: true) {
+        id: Int8!
+        timestamp: Timestamp!
+        price: BigDecimal!
+      }
+
+      type Stats @aggregation(intervals: ["hour"], source: "Data") {
+        id: Int8!
+        timestamp: Timestamp!
+        avg: BigDecimal! @aggregate(fn: "avg", arg: "price")
+        p95: BigDecimal! @aggregate(fn: "p95", arg: "price")
+      }
+
+      type Running @aggregation(intervals: ["day"], source: "Data") {
+        id: Int8!
+        timestamp: Timestamp!
+        stddev: BigDecimal! @aggregate(fn: "stddev", arg: "price", cumulative: true)
+      }
+      "#;
+
+        const STATS_SQL: &str = r#"\
+        insert into "sgd007"."stats_hour"(id, timestamp, block$, "avg", "avg$state", "p95", "p95$state") \
+        select max(id) as id, timestamp, $3, \
+               moments_avg(moments("price")) as "avg", moments("price") as "avg$state", \
+               sketch_quantile(sketch_of(array_agg(sketch_key("price"))), 0.95) as "p95", \
+               sketch_of(array_agg(sketch_key("price"))) as "p95$state" from (\
+            select id, date_bin('3600s', timestamp, 'epoch'::timestamptz) as timestamp, "price" \
+              from "sgd007"."data" \
+             where "sgd007"."data".timestamp >= $1 and "sgd007"."data".timestamp < $2 \
+             order by "sgd007"."data".timestamp) data \
+        group by timestamp"#;
+
+        const RUNNING_SQL: &str = r#"\
+        with bucket as (
+            select max(id) as id, timestamp,
+                   moments_stddev(moments("price")) as "stddev",
+                   moments("price") as "stddev$state"
+              from (select id, date_bin('86400s', timestamp, 'epoch'::timestamptz) as timestamp, "price"
+                      from "sgd007"."data"
+                     where "sgd007"."data".timestamp >= $1
+                       and "sgd007"."data".timestamp < $2
+                     order by "sgd007"."data".timestamp) data
+              group by timestamp),
+             prev as (select bucket.id, bucket.timestamp,
+                             prev."stddev", prev."stddev$state"
+                        from bucket cross join lateral (
+                             select * from "sgd007"."running_day" prev
+                              where prev.timestamp < $1
+                              order by prev.timestamp desc limit 1) prev),
+             combined as (select id, timestamp,
+                                 moments_stddev(moments_merge("stddev$state")) as "stddev",
+                                 moments_merge("stddev$state") as "stddev$state" from (
+                            select *, 1 as seq from prev
+                            union all
+                            select *, 2 as seq from bucket) u
+                          group by id, timestamp)
+        insert into "sgd007"."running_day"(id, timestamp, block$, "stddev", "stddev$state")
+        select id, timestamp, $3 as block$, "stddev", "stddev$state" from combined
+        "#;
+
+        let hash = DeploymentHash::new("rollup").unwrap();
+        let nsp = Namespace::new("sgd007".to_string()).unwrap();
+        let schema = InputSchema::parse_latest(SCHEMA, hash.clone()).unwrap();
+        let site = Arc::new(make_dummy_site(hash, nsp, "rollup".to_string()));
+        let catalog = Catalog::for_tests(site.clone(), BTreeSet::new()).unwrap();
+        let layout = Layout::new(site, &schema, catalog).unwrap();
+        assert_eq!(2, layout.rollups.len());
+
+        let stats = layout
+            .rollups
+            .iter()
+            .find(|rollup| rollup.agg_table.name.as_str() == "stats_hour")
+            .unwrap();
+        check_eqv(STATS_SQL, &stats.insert_sql);
+
+        let running = layout
+            .rollups
+            .iter()
+            .find(|rollup| rollup.agg_table.name.as_str() == "running_day")
+            .unwrap();
+        check_eqv(RUNNING_SQL, &running.insert_sql);
+
+        // The state is not visible as a column for any field
+        assert!(stats
+            .agg_table
+            .column(&SqlName::verbatim("avg$state".to_string()))
+            .is_none());
+        assert!(stats.agg_table.column_for_field("avg$state").is_ok());
+    }
+
+    #[test]
+    fn rollup_intervals() {
+        const SCHEMA: &str = r#"
+    type Data @entity(timeseries: true) {
+        id: Int8!
+        timestamp: Timestamp!
+        price: BigDecimal!
+      }
+
+      type Stats @aggregation(intervals: ["month", "week", "m15"], source: "Data") {
+        id: Int8!
+        timestamp: Timestamp!
+        sum: BigDecimal! @aggregate(fn: "sum", arg: "price")
+      }
+      "#;
+
+        const STATS_M15_SQL: &str = r#"        insert into "sgd007"."stats_m15"(id, timestamp, block$, "sum") \
+        select max(id) as id, timestamp, $3, sum("price") as "sum" from (\
+            select id, date_bin('900s', timestamp, 'epoch'::timestamptz) as timestamp, "price" \
+              from "sgd007"."data" \
+             where "sgd007"."data".timestamp >= $1 and "sgd007"."data".timestamp < $2 \
+             order by "sgd007"."data".timestamp) data \
+        group by timestamp"#;
+
+        const STATS_WEEK_SQL: &str = r#"        insert into "sgd007"."stats_week"(id, timestamp, block$, "sum") \
+        select max(id) as id, timestamp, $3, sum("price") as "sum" from (\
+            select id, date_bin('604800s', timestamp, 'epoch'::timestamptz + '345600 s'::interval) as timestamp, "price" \
+              from "sgd007"."data" \
+             where "sgd007"."data".timestamp >= $1 and "sgd007"."data".timestamp < $2 \
+             order by "sgd007"."data".timestamp) data \
+        group by timestamp"#;
+
+        const STATS_MONTH_SQL: &str = r#"        insert into "sgd007"."stats_month"(id, timestamp, block$, "sum") \
+        select max(id) as id, timestamp, $3, sum("price") as "sum" from (\
+            select id, date_trunc('month', timestamp, 'UTC') as timestamp, "price" \
+              from "sgd007"."data" \
+             where "sgd007"."data".timestamp >= $1 and "sgd007"."data".timestamp < $2 \
+             order by "sgd007"."data".timestamp) data \
+        group by timestamp"#;
+
+        const STATS_MONTH_LAST_ROLLUP_SQL: &str = r#"        select max(timestamp) + '1 month 1 s'::interval as last_rollup \
+          from "sgd007"."stats_month""#;
+
+        let hash = DeploymentHash::new("rollup").unwrap();
+        let nsp = Namespace::new("sgd007".to_string()).unwrap();
+        let schema = InputSchema::parse_latest(SCHEMA, hash.clone()).unwrap();
+        let site = Arc::new(make_dummy_site(hash, nsp, "rollup".to_string()));
+        let catalog = Catalog::for_tests(site.clone(), BTreeSet::new()).unwrap();
+        let layout = Layout::new(site, &schema, catalog).unwrap();
+        assert_eq!(3, layout.rollups.len());
+
+        let names: Vec<_> = layout
+            .rollups
+            .iter()
+            .map(|rollup| rollup.agg_table.name.as_str())
+            .collect();
+        assert_eq!(vec!["stats_m15", "stats_week", "stats_month"], names);
+
+        check_eqv(STATS_M15_SQL, &layout.rollups[0].insert_sql);
+        check_eqv(STATS_WEEK_SQL, &layout.rollups[1].insert_sql);
+        check_eqv(STATS_MONTH_SQL, &layout.rollups[2].insert_sql);
+        check_eqv(
+            STATS_MONTH_LAST_ROLLUP_SQL,
+            &layout.rollups[2].last_rollup_sql,
+        );
     }
 }
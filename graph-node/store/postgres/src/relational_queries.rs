@@ -10,7 +10,9 @@ use diesel::query_builder::{AstPass, Query, QueryFragment, QueryId};
 use diesel::query_dsl::RunQueryDsl;
 use diesel::result::{Error as DieselError, QueryResult};
 use diesel::sql_types::Untyped;
-use diesel::sql_types::{Array, BigInt, Binary, Bool, Int8, Integer, Jsonb, Text, Timestamptz};
+use diesel::sql_types::{
+    Array, BigInt, Binary, Bool, Int8, Integer, Jsonb, Nullable, Text, Timestamptz,
+};
 use diesel::QuerySource as _;
 use graph::components::store::write::{EntityWrite, RowGroup, WriteChunk};
 use graph::components::store::{Child as StoreChild, DerivedEntityQuery};
@@ -19,11 +21,14 @@ use graph::data::store::{IdList, IdRef, QueryObject};
 use graph::data::value::{Object, Word};
 use graph::data_source::CausalityRegion;
 use graph::prelude::{
-    anyhow, r, serde_json, BlockNumber, ChildMultiplicity, Entity, EntityCollection, EntityFilter,
-    EntityLink, EntityOrder, EntityOrderByChild, EntityOrderByChildInfo, EntityRange, EntityWindow,
-    ParentLink, QueryExecutionError, StoreError, Value, ENV_VARS,
+    anyhow, r, serde_json, BlockNumber, ChildMultiplicity, Entity, EntityAggregate,
+    EntityCollection, EntityCursor, EntityFilter, EntityLink, EntityOrder, EntityOrderByChild,
+    EntityOrderByChildInfo, EntityRange, EntityWindow, ParentLink, QueryExecutionError, StoreError,
+    Value, ENV_VARS,
+};
+use graph::schema::{
+    AggregationCurrent, EntityType, FulltextAlgorithm, FulltextConfig, InputSchema,
 };
-use graph::schema::{EntityType, FulltextAlgorithm, FulltextConfig, InputSchema};
 use graph::{components::store::AttributeNames, data::store::scalar};
 use inflector::Inflector;
 use itertools::Itertools;
@@ -652,7 +657,7 @@ impl std::fmt::Display for SqlValue<'_> {
 
 /// A `QueryValue` makes it possible to bind a `Value` into a SQL query
 /// using the metadata from Column
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct QueryValue<'a> {
     value: SqlValue<'a>,
     column_type: &'a ColumnType,
@@ -1304,6 +1309,20 @@ pub enum Filter<'a> {
         op: &'static str,
         pattern: String,
     },
+    /// Match a string column against a regular expression; `op` is one of
+    /// Postgres' regex operators `~` and `~*`
+    Matches {
+        column: dsl::Column<'a>,
+        op: &'static str,
+        pattern: String,
+    },
+    /// Compare a list column to an array of values with one of Postgres'
+    /// array operators `&&` (overlaps) and `<@` (is contained by)
+    ListCmp {
+        column: dsl::Column<'a>,
+        op: &'static str,
+        values: QueryValue<'a>,
+    },
     ChangeBlockGte(dsl::ChangedSince<'a>),
     Child(Box<QueryChild<'a>>),
     /// The value is never null for fulltext queries
@@ -1377,6 +1396,60 @@ impl<'a> Filter<'a> {
             }
         }
 
+        fn matches<'s>(
+            table: dsl::Table<'s>,
+            attr: &String,
+            value: &Value,
+            nocase: bool,
+        ) -> Result<Filter<'s>, StoreError> {
+            let column = table.column_for_field(attr)?;
+            let (op, filter) = if nocase {
+                (" ~* ", "matches_nocase")
+            } else {
+                (" ~ ", "matches")
+            };
+
+            match (value, column.column_type()) {
+                (Value::String(s), ColumnType::String) if !column.is_list() => {
+                    Ok(Filter::Matches {
+                        column,
+                        op,
+                        pattern: s.clone(),
+                    })
+                }
+                _ => Err(StoreError::UnsupportedFilter(
+                    filter.to_owned(),
+                    value.to_string(),
+                )),
+            }
+        }
+
+        fn list_cmp<'s>(
+            table: dsl::Table<'s>,
+            attr: &String,
+            value: &'s Value,
+            op: &'static str,
+            filter: &'static str,
+        ) -> Result<Filter<'s>, StoreError> {
+            let column = table.column_for_field(attr)?;
+            if !column.is_list() {
+                return Err(StoreError::UnsupportedFilter(
+                    filter.to_owned(),
+                    value.to_string(),
+                ));
+            }
+            let values = QueryValue::new(value, column.column_type())?;
+            match values.value {
+                SqlValue::List(_) | SqlValue::Numerics(_) => {
+                    Ok(Filter::ListCmp { column, op, values })
+                }
+                _ => Err(StoreError::UnsupportedFilter(
+                    filter.to_owned(),
+                    value.to_string(),
+                )),
+            }
+        }
+
         fn cmp<'s>(
             table: dsl::Table<'s>,
             attr: &String,
@@ -1504,6 +1577,10 @@ impl<'a> Filter<'a> {
             NotEndsWithNoCase(attr, value) => {
                 starts_or_ends_with(table, attr, value, " not ilike ", false)
             }
+            Matches(attr, value) => matches(table, attr, value, false),
+            MatchesNoCase(attr, value) => matches(table, attr, value, true),
+            Overlaps(attr, value) => list_cmp(table, attr, value, " && ", "overlaps"),
+            ContainedBy(attr, value) => list_cmp(table, attr, value, " <@ ", "contained_by"),
 
             ChangeBlockGte(num) => Ok(F::ChangeBlockGte(table.changed_since(*num))),
             Child(child) => {
@@ -1779,9 +1856,15 @@ impl<'a> fmt::Display for Filter<'a> {
                 column,
                 op,
                 pattern,
+            }
+            | Matches {
+                column,
+                op,
+                pattern,
             } => {
                 write!(f, "{column} {op} '{pattern}'")
             }
+            ListCmp { column, op, values } => write!(f, "{column} {op} {values}"),
             ChangeBlockGte(b) => write!(f, "{}", b),
             Child(child /* a, et, cf, _ */) => write!(
                 f,
@@ -1817,11 +1900,21 @@ impl<'a> QueryFragment<Pg> for Filter<'a> {
                 column,
                 op,
                 pattern,
+            }
+            | Matches {
+                column,
+                op,
+                pattern,
             } => {
                 column.walk_ast(out.reborrow())?;
                 out.push_sql(op);
                 out.push_bind_param::<Text, _>(pattern)?;
             }
+            ListCmp { column, op, values } => {
+                column.walk_ast(out.reborrow())?;
+                out.push_sql(op);
+                values.walk_ast(out)?;
+            }
             ChangeBlockGte(changed_since) => changed_since.walk_ast(out.reborrow())?,
             Child(child) => child.walk_ast(out)?,
         }
@@ -2194,6 +2287,90 @@ impl<'a> Query for FindDerivedQuery<'a> {
 
 impl<'a, Conn> RunQueryDsl<Conn> for FindDerivedQuery<'a> {}
 
+/// A query that finds the entities whose attribute equals a given value,
+/// ordered by id. Used during indexing for `store.findBy`. The comparison
+/// is written so that Postgres can use the attribute index on the column
+#[derive(Debug)]
+pub struct FindByAttributeQuery<'a> {
+    table: dsl::Table<'a>,
+    from_table: dsl::FromTable<'a>,
+    primary_key: dsl::Column<'a>,
+    filter: Filter<'a>,
+    at_block: AtBlock<'a>,
+    causality_region: CausalityRegion,
+    limit: usize,
+}
+
+impl<'a> FindByAttributeQuery<'a> {
+    pub fn new(
+        layout: &'a Layout,
+        table: &'a Table,
+        filter: &'a EntityFilter,
+        causality_region: CausalityRegion,
+        limit: usize,
+        block: BlockNumber,
+    ) -> Result<Self, StoreError> {
+        let table = table.dsl_table();
+        let filter = Filter::main(layout, table, filter, block)?;
+        let at_block = table.at_block(block);
+        Ok(Self {
+            table,
+            from_table: table.from_clause(),
+            primary_key: table.primary_key(),
+            filter,
+            at_block,
+            causality_region,
+            limit,
+        })
+    }
+}
+
+impl<'a> QueryFragment<Pg> for FindByAttributeQuery<'a> {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+
+        // Generate
+        //    select '..' as entity, to_jsonb(c.*) as data
+        //      from schema.table c
+        //     where attr = $1 and block_range @> $block
+        //     order by c.id
+        //     limit $limit
+        out.push_sql("select ");
+        out.push_bind_param::<Text, _>(self.table.meta.object.as_str())?;
+        out.push_sql(" as entity, to_jsonb(");
+        self.table.walk_ast(out.reborrow())?;
+        out.push_sql(".*) as data\n  from ");
+        self.from_table.walk_ast(out.reborrow())?;
+        out.push_sql("\n where ");
+        self.filter.walk_ast(out.reborrow())?;
+        out.push_sql(" and ");
+        if self.table.meta.has_causality_region {
+            self.table.walk_ast(out.reborrow())?;
+            out.push_sql(".causality_region = ");
+            out.push_bind_param::<Integer, _>(&self.causality_region)?;
+            out.push_sql(" and ");
+        }
+        self.at_block.walk_ast(out.reborrow())?;
+        out.push_sql("\n order by ");
+        self.primary_key.walk_ast(out.reborrow())?;
+        out.push_sql("\n limit ");
+        out.push_sql(&self.limit.to_string());
+        Ok(())
+    }
+}
+
+impl<'a> QueryId for FindByAttributeQuery<'a> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<'a> Query for FindByAttributeQuery<'a> {
+    type SqlType = Untyped;
+}
+
+impl<'a, Conn> RunQueryDsl<Conn> for FindByAttributeQuery<'a> {}
+
 /// One value for inserting into a column of a table
 #[derive(Debug)]
 enum InsertValue<'a> {
@@ -2544,13 +2721,25 @@ struct ParentLimit<'a> {
     /// Limit children by sorting and picking top n
     sort_key: SortKey<'a>,
     range: FilterRange,
+    /// Only pick children that come after this cursor
+    cursor: Option<KeysetCursor<'a>>,
 }
 
 impl<'a> ParentLimit<'a> {
-    fn filter(&self, is_outer: bool, out: &mut AstPass<'_, 'a, Pg>) {
+    fn filter(&'a self, is_outer: bool, out: &mut AstPass<'_, 'a, Pg>) -> QueryResult<()> {
         if is_outer {
             out.push_sql(" and q.id = p.id")
         }
+        self.after_cursor(out)
+    }
+
+    /// Generate ` and {keyset condition}` if there is a cursor
+    fn after_cursor(&'a self, out: &mut AstPass<'_, 'a, Pg>) -> QueryResult<()> {
+        if let Some(cursor) = &self.cursor {
+            out.push_sql(" and ");
+            cursor.walk_ast(out.reborrow())?;
+        }
+        Ok(())
     }
 
     fn restrict(&'a self, is_outer: bool, out: &mut AstPass<'_, 'a, Pg>) -> QueryResult<()> {
@@ -2678,7 +2867,7 @@ impl<'a> FilterWindow<'a> {
         self.from_table.walk_ast(out.reborrow())?;
         out.push_sql(" where ");
         self.at_block.walk_ast(out.reborrow())?;
-        limit.filter(is_outer, out);
+        limit.filter(is_outer, out)?;
         out.push_sql(" and p.id = any(");
         column.walk_ast(out.reborrow())?;
         out.push_sql(")");
@@ -2714,7 +2903,7 @@ impl<'a> FilterWindow<'a> {
         self.from_table.walk_ast(out.reborrow())?;
         out.push_sql(" where ");
         self.at_block.walk_ast(out.reborrow())?;
-        limit.filter(is_outer, out);
+        limit.filter(is_outer, out)?;
         out.push_sql(" and ");
         column.walk_ast(out.reborrow())?;
         out.push_sql(" @> array[p.id]");
@@ -2757,7 +2946,7 @@ impl<'a> FilterWindow<'a> {
         self.from_table.walk_ast(out.reborrow())?;
         out.push_sql(" where ");
         self.at_block.walk_ast(out.reborrow())?;
-        limit.filter(is_outer, out);
+        limit.filter(is_outer, out)?;
         out.push_sql(" and p.id = ");
         column.walk_ast(out.reborrow())?;
         self.and_filter(out)?;
@@ -2787,7 +2976,7 @@ impl<'a> FilterWindow<'a> {
         self.from_table.walk_ast(out.reborrow())?;
         out.push_sql(" where ");
         self.at_block.walk_ast(out.reborrow())?;
-        limit.filter(is_outer, out);
+        limit.filter(is_outer, out)?;
         out.push_sql(" and p.id = ");
         column.walk_ast(out.reborrow())?;
         self.and_filter(out)?;
@@ -2841,7 +3030,7 @@ impl<'a> FilterWindow<'a> {
             self.from_table.walk_ast(out.reborrow())?;
             out.push_sql(" where ");
             self.at_block.walk_ast(out.reborrow())?;
-            limit.filter(is_outer, out);
+            limit.filter(is_outer, out)?;
             out.push_sql(" and c.id = any(p.child_ids)");
             self.and_filter(out)?;
             limit.restrict(is_outer, out)?;
@@ -2883,7 +3072,7 @@ impl<'a> FilterWindow<'a> {
         self.from_table.walk_ast(out.reborrow())?;
         out.push_sql(" where ");
         self.at_block.walk_ast(out.reborrow())?;
-        limit.filter(is_outer, out);
+        limit.filter(is_outer, out)?;
 
         // Include a constraint on the child IDs as a set if the size of the set
         // is below the threshold set by environment variable. Set it to
@@ -2976,11 +3165,19 @@ impl<'a> WholeTable<'a> {
         entity_filter: Option<&'a EntityFilter>,
         column_names: AttributeNames,
         block: BlockNumber,
+        current: AggregationCurrent,
     ) -> Result<Self, QueryExecutionError> {
         let table = layout
             .table_for_entity(entity_type)
             .map(|rc| rc.as_ref())?
             .dsl_table();
+        let from_table = match current {
+            AggregationCurrent::Ignore => table.from_clause(),
+            AggregationCurrent::Include => match layout.current_bucket_sql(table.meta) {
+                Some(current) => table.from_current(current, block),
+                None => table.from_clause(),
+            },
+        };
         let filter = entity_filter
             .map(|filter| Filter::main(layout, table, filter, block))
             .transpose()?;
@@ -2992,7 +3189,7 @@ impl<'a> WholeTable<'a> {
         let at_block = table.at_block(block).filters_by_id(filters_by_id);
         Ok(WholeTable {
             table,
-            from_table: table.from_clause(),
+            from_table,
             filter,
             column_names,
             at_block,
@@ -3107,6 +3304,7 @@ impl<'a> FilterCollection<'a> {
         collection: EntityCollection,
         filter: Option<&'a EntityFilter>,
         block: BlockNumber,
+        current: AggregationCurrent,
     ) -> Result<Self, QueryExecutionError> {
         match collection {
             EntityCollection::All(entities) => {
@@ -3117,7 +3315,14 @@ impl<'a> FilterCollection<'a> {
                 let entities = entities
                     .iter()
                     .map(|(entity, column_names)| {
-                        WholeTable::new(layout, entity, filter, column_names.clone(), block)
+                        WholeTable::new(
+                            layout,
+                            entity,
+                            filter,
+                            column_names.clone(),
+                            block,
+                            current,
+                        )
                     })
                     .collect::<Result<Vec<_>, _>>()?;
                 Ok(FilterCollection::All(entities))
@@ -3274,6 +3479,11 @@ pub enum SortKey<'a> {
     },
     /// Order by some other column; `column` will never be `id`
     ChildKey(ChildKey<'a>),
+    /// Order by several columns in turn and then by `id` in the direction
+    /// of the last one. Each entry is a `SortKey::Id`, a `SortKey::Key`
+    /// that is not a fulltext column, or a `SortKey::ChildKey`, none of
+    /// which use the block column
+    Multi(Vec<SortKey<'a>>),
 }
 
 /// String representation that is useful for debugging when `walk_ast` fails
@@ -3344,6 +3554,31 @@ impl<'a> fmt::Display for SortKey<'a> {
                     )
                 }
             },
+            SortKey::Multi(keys) => {
+                for key in keys {
+                    match key {
+                        SortKey::Key {
+                            column, direction, ..
+                        } => write!(f, "{}{}, ", column, direction)?,
+                        SortKey::ChildKey(ChildKey::Single(details)) => {
+                            write!(f, "{}{}, ", details.sort_by_column, details.direction)?
+                        }
+                        SortKey::ChildKey(ChildKey::Many(_, details)) => {
+                            write!(f, "coalesce(")?;
+                            for details in details {
+                                write!(f, "{} ", details.sort_by_column)?;
+                            }
+                            write!(f, "){}, ", key.direction())?
+                        }
+                        _ => write!(f, "{}, ", key)?,
+                    }
+                }
+                let direction = keys
+                    .last()
+                    .map(SortKey::direction)
+                    .unwrap_or(SortDirection::Asc);
+                write!(f, "{}{}", PRIMARY_KEY_COLUMN, direction)
+            }
         }
     }
 }
@@ -3436,8 +3671,9 @@ impl<'a> SortKey<'a> {
             attribute: String,
             use_block_column: UseBlockColumn,
             direction: SortDirection,
+            child_idx: u8,
         ) -> Result<SortKey<'a>, QueryExecutionError> {
-            let child_table = child_table.child(1);
+            let child_table = child_table.child(child_idx);
             let sort_by_column = child_table.column_for_field(&attribute)?;
             if sort_by_column.is_fulltext() {
                 Err(QueryExecutionError::NotSupported(
@@ -3490,11 +3726,11 @@ impl<'a> SortKey<'a> {
                     )));
                 }
 
-                let child_table = child_table.child(1);
+                let child_table = child_table.child(child_idx);
                 let child_at_block = child_table.at_block(block);
                 let child_from = child_table.from_clause();
                 Ok(SortKey::ChildKey(ChildKey::Single(ChildKeyDetails {
-                    child_table: child_table.child(1),
+                    child_table: child_table.child(child_idx),
                     child_from,
                     parent_join_column: parent_column,
                     child_join_column: child_column,
@@ -3513,8 +3749,9 @@ impl<'a> SortKey<'a> {
             entity_types: Vec<EntityType>,
             child: EntityOrderByChildInfo,
             direction: SortDirection,
+            child_idx: u8,
         ) -> Result<Vec<ChildKeyAndIdSharedDetails<'a>>, QueryExecutionError> {
-            assert!(entity_types.len() < 255);
+            assert!(entity_types.len() + (child_idx as usize) < 256);
             return entity_types
                 .iter()
                 .enumerate()
@@ -3522,7 +3759,7 @@ impl<'a> SortKey<'a> {
                     let child_table = layout
                         .table_for_entity(entity_type)?
                         .dsl_table()
-                        .child((i + 1) as u8);
+                        .child(i as u8 + child_idx);
                     let sort_by_column = child_table.column_for_field(&child.sort_by_attribute)?;
                     if sort_by_column.is_fulltext() {
                         Err(QueryExecutionError::NotSupported(
@@ -3584,6 +3821,7 @@ impl<'a> SortKey<'a> {
             entity_types: Vec<EntityType>,
             use_block_column: UseBlockColumn,
             direction: SortDirection,
+            child_idx: u8,
         ) -> Result<SortKey<'a>, QueryExecutionError> {
             if entity_types.is_empty() {
                 return Err(QueryExecutionError::ConstraintViolation(
@@ -3609,6 +3847,7 @@ impl<'a> SortKey<'a> {
                         entity_types,
                         child,
                         direction,
+                        child_idx,
                     )?
                     .iter()
                     .map(|details| ChildIdDetails {
@@ -3633,6 +3872,7 @@ impl<'a> SortKey<'a> {
                         entity_types,
                         child,
                         direction,
+                        child_idx,
                     )?
                     .iter()
                     .map(|details| ChildKeyDetails {
@@ -3663,56 +3903,139 @@ impl<'a> SortKey<'a> {
             UseBlockColumn::No
         };
 
-        use SortDirection::*;
+        let with_order = |order: EntityOrder,
+                          use_block_column: UseBlockColumn,
+                          child_idx: u8|
+         -> Result<SortKey<'a>, QueryExecutionError> {
+            use SortDirection::*;
+            match order {
+                EntityOrder::Ascending(attr, _) => {
+                    with_key(table, attr, filter, Asc, use_block_column)
+                }
+                EntityOrder::Descending(attr, _) => {
+                    with_key(table, attr, filter, Desc, use_block_column)
+                }
+                EntityOrder::Default => Ok(SortKey::Id(Asc, use_block_column.block_column(table))),
+                EntityOrder::Unordered => Ok(SortKey::None),
+                EntityOrder::ChildAscending(kind) => match kind {
+                    EntityOrderByChild::Object(child, entity_type) => with_child_object_key(
+                        block,
+                        table,
+                        layout.table_for_entity(&entity_type)?.dsl_table(),
+                        child.join_attribute,
+                        child.derived,
+                        child.sort_by_attribute,
+                        use_block_column,
+                        Asc,
+                        child_idx,
+                    ),
+                    EntityOrderByChild::Interface(child, entity_types) => with_child_interface_key(
+                        layout,
+                        block,
+                        table,
+                        child,
+                        entity_types,
+                        use_block_column,
+                        Asc,
+                        child_idx,
+                    ),
+                },
+                EntityOrder::ChildDescending(kind) => match kind {
+                    EntityOrderByChild::Object(child, entity_type) => with_child_object_key(
+                        block,
+                        table,
+                        layout.table_for_entity(&entity_type)?.dsl_table(),
+                        child.join_attribute,
+                        child.derived,
+                        child.sort_by_attribute,
+                        use_block_column,
+                        Desc,
+                        child_idx,
+                    ),
+                    EntityOrderByChild::Interface(child, entity_types) => with_child_interface_key(
+                        layout,
+                        block,
+                        table,
+                        child,
+                        entity_types,
+                        use_block_column,
+                        Desc,
+                        child_idx,
+                    ),
+                },
+                EntityOrder::Multi(_) => Err(QueryExecutionError::ConstraintViolation(
+                    "EntityOrder::Multi can not be nested".to_string(),
+                )),
+            }
+        };
+
         match order {
-            EntityOrder::Ascending(attr, _) => with_key(table, attr, filter, Asc, use_block_column),
-            EntityOrder::Descending(attr, _) => {
-                with_key(table, attr, filter, Desc, use_block_column)
-            }
-            EntityOrder::Default => Ok(SortKey::Id(Asc, use_block_column.block_column(table))),
-            EntityOrder::Unordered => Ok(SortKey::None),
-            EntityOrder::ChildAscending(kind) => match kind {
-                EntityOrderByChild::Object(child, entity_type) => with_child_object_key(
-                    block,
-                    table,
-                    layout.table_for_entity(&entity_type)?.dsl_table(),
-                    child.join_attribute,
-                    child.derived,
-                    child.sort_by_attribute,
-                    use_block_column,
-                    Asc,
-                ),
-                EntityOrderByChild::Interface(child, entity_types) => with_child_interface_key(
-                    layout,
-                    block,
-                    table,
-                    child,
-                    entity_types,
-                    use_block_column,
-                    Asc,
-                ),
-            },
-            EntityOrder::ChildDescending(kind) => match kind {
-                EntityOrderByChild::Object(child, entity_type) => with_child_object_key(
-                    block,
-                    table,
-                    layout.table_for_entity(&entity_type)?.dsl_table(),
-                    child.join_attribute,
-                    child.derived,
-                    child.sort_by_attribute,
-                    use_block_column,
-                    Desc,
-                ),
-                EntityOrderByChild::Interface(child, entity_types) => with_child_interface_key(
-                    layout,
-                    block,
-                    table,
-                    child,
-                    entity_types,
-                    use_block_column,
-                    Desc,
-                ),
+            EntityOrder::Multi(orders) => {
+                // Every child table that we join with needs its own alias
+                let mut child_idx: usize = 1;
+                let mut keys = Vec::with_capacity(orders.len());
+                for order in orders {
+                    let key = with_order(order, UseBlockColumn::No, child_idx as u8)?;
+                    match &key {
+                        SortKey::Key { value: Some(_), .. } => {
+                            return Err(QueryExecutionError::NotSupported(
+                                "Sorting by fulltext fields together with other fields".to_string(),
+                            ))
+                        }
+                        SortKey::ChildKey(ChildKey::Single(_))
+                        | SortKey::ChildKey(ChildKey::Id(..)) => child_idx += 1,
+                        SortKey::ChildKey(ChildKey::Many(_, children)) => {
+                            child_idx += children.len()
+                        }
+                        SortKey::ChildKey(ChildKey::ManyId(_, children, _)) => {
+                            child_idx += children.len()
+                        }
+                        SortKey::None
+                        | SortKey::Id(..)
+                        | SortKey::Key { .. }
+                        | SortKey::Multi(_) => {}
+                    }
+                    if child_idx > u8::MAX as usize {
+                        return Err(QueryExecutionError::NotSupported(
+                            "Sorting by attributes of that many child entities".to_string(),
+                        ));
+                    }
+                    keys.push(key);
+                }
+                Ok(SortKey::Multi(keys))
+            }
+            order => with_order(order, use_block_column, 1),
+        }
+    }
+
+    /// The direction of this sort key; the direction of a `SortKey::Multi`
+    /// is that of its last entry since that is also used for `id`
+    fn direction(&self) -> SortDirection {
+        match self {
+            SortKey::None => SortDirection::Asc,
+            SortKey::Id(direction, _) => *direction,
+            SortKey::Key { direction, .. } => *direction,
+            SortKey::ChildKey(child) => match child {
+                ChildKey::Single(details) => details.direction,
+                ChildKey::Many(_, details) => details
+                    .first()
+                    .map(|details| details.direction)
+                    .unwrap_or(SortDirection::Asc),
+                ChildKey::Id(direction, _, _) | ChildKey::ManyId(direction, _, _) => *direction,
             },
+            SortKey::Multi(keys) => keys
+                .last()
+                .map(SortKey::direction)
+                .unwrap_or(SortDirection::Asc),
+        }
+    }
+
+    /// Whether any part of this sort key needs a join with a child table
+    fn has_child(&self) -> bool {
+        match self {
+            SortKey::ChildKey(_) => true,
+            SortKey::Multi(keys) => keys.iter().any(SortKey::has_child),
+            SortKey::None | SortKey::Id(..) | SortKey::Key { .. } => false,
         }
     }
 
@@ -3806,6 +4129,17 @@ impl<'a> SortKey<'a> {
                     out.push_sql(SORT_KEY_COLUMN);
                 }
             }
+            SortKey::Multi(keys) => {
+                for (i, key) in keys.iter().enumerate() {
+                    out.push_sql(", ");
+                    if let SelectStatementLevel::InnerStatement = select_statement_level {
+                        SortKey::multi_entry_expr(key, out)?;
+                        out.push_sql(" as ");
+                    }
+                    out.push_sql(SORT_KEY_COLUMN);
+                    out.push_sql(&i.to_string());
+                }
+            }
         }
         Ok(())
     }
@@ -3877,6 +4211,10 @@ impl<'a> SortKey<'a> {
                     }
                 }
             }
+            SortKey::Multi(keys) => {
+                out.push_sql("order by ");
+                SortKey::multi_expr(keys, use_sort_key_alias, out)
+            }
         }
     }
 
@@ -3915,6 +4253,15 @@ impl<'a> SortKey<'a> {
             SortKey::ChildKey(_) => Err(diesel::result::Error::QueryBuilderError(
                 "SortKey::ChildKey cannot be used for parent ordering (yet)".into(),
             )),
+            SortKey::Multi(keys) => {
+                if self.has_child() {
+                    return Err(diesel::result::Error::QueryBuilderError(
+                        "SortKey::ChildKey cannot be used for parent ordering (yet)".into(),
+                    ));
+                }
+                order_by_parent_id(out);
+                SortKey::multi_expr(keys, use_sort_key_alias, out)
+            }
         }
     }
 
@@ -4068,6 +4415,84 @@ impl<'a> SortKey<'a> {
         Ok(())
     }
 
+    /// Generate
+    ///   name1 direction1, name2 direction2, .., id direction
+    /// where `id` is sorted in the direction of the last entry
+    fn multi_expr<'b>(
+        keys: &'b [SortKey<'b>],
+        use_sort_key_alias: bool,
+        out: &mut AstPass<'_, 'b, Pg>,
+    ) -> QueryResult<()> {
+        let mut direction = SortDirection::Asc;
+        let mut has_child = false;
+        for (i, key) in keys.iter().enumerate() {
+            if use_sort_key_alias {
+                out.push_sql(SORT_KEY_COLUMN);
+                out.push_sql(&i.to_string());
+            } else {
+                SortKey::multi_entry_expr(key, out)?;
+            }
+            direction = key.direction();
+            has_child = has_child || key.has_child();
+            out.push_sql(direction.as_sql());
+            out.push_sql(", ");
+        }
+        if !use_sort_key_alias && has_child {
+            out.push_sql("c.");
+        }
+        out.push_identifier(PRIMARY_KEY_COLUMN)?;
+        out.push_sql(direction.as_sql());
+        Ok(())
+    }
+
+    /// Generate the expression for one entry of a `SortKey::Multi`,
+    /// without a direction
+    fn multi_entry_expr<'b>(
+        key: &'b SortKey<'b>,
+        out: &mut AstPass<'_, 'b, Pg>,
+    ) -> QueryResult<()> {
+        fn coalesce<'b>(
+            columns: impl Iterator<Item = &'b dsl::Column<'b>>,
+            out: &mut AstPass<'_, 'b, Pg>,
+        ) -> QueryResult<()> {
+            out.push_sql("coalesce(");
+            for (i, column) in columns.enumerate() {
+                if i > 0 {
+                    out.push_sql(", ");
+                }
+                column.walk_ast(out.reborrow())?;
+            }
+            out.push_sql(")");
+            Ok(())
+        }
+
+        match key {
+            SortKey::Id(_, _) => {
+                out.push_sql("c.");
+                out.push_identifier(PRIMARY_KEY_COLUMN)
+            }
+            SortKey::Key {
+                column,
+                value: None,
+                direction: _,
+            } => column.walk_ast(out.reborrow()),
+            SortKey::ChildKey(child) => match child {
+                ChildKey::Single(child) => child.sort_by_column.walk_ast(out.reborrow()),
+                ChildKey::Many(_, children) => {
+                    coalesce(children.iter().map(|child| &child.sort_by_column), out)
+                }
+                ChildKey::Id(_, child, _) => child.child_pk.walk_ast(out.reborrow()),
+                ChildKey::ManyId(_, children, _) => {
+                    coalesce(children.iter().map(|child| &child.child_join_column), out)
+                }
+            },
+            SortKey::None | SortKey::Key { .. } | SortKey::Multi(_) => Err(constraint_violation!(
+                "multi_entry_expr called with unsupported sort key {}",
+                key
+            )),
+        }
+    }
+
     fn add_child<'b>(&'b self, out: &mut AstPass<'_, 'b, Pg>) -> QueryResult<()> {
         fn add<'b>(
             child_from: &'b dsl::FromTable<'b>,
@@ -4149,12 +4574,156 @@ impl<'a> SortKey<'a> {
                     )?;
                 }
             },
+            SortKey::Multi(keys) => {
+                for key in keys {
+                    key.add_child(out)?;
+                }
+            }
             _ => {}
         }
         Ok(())
     }
 }
 
+/// Restrict a query to the entities that come after a cursor in the order
+/// given by the sort key, i.e., generate
+///   (c.{sort_key}, c.id) > ($value, $id)
+/// or `<` for a descending sort. Postgres sorts nulls last in ascending
+/// and first in descending order, and since a row comparison with a null
+/// is never true, rows where the sort key is null need to be handled
+/// separately
+#[derive(Debug, Clone)]
+struct KeysetCursor<'a> {
+    /// The name of the column we sort by and the cursor's value for it;
+    /// `None` if we sort by `id`
+    key: Option<(&'a str, QueryValue<'a>)>,
+    id: QueryValue<'a>,
+    direction: SortDirection,
+}
+
+impl<'a> KeysetCursor<'a> {
+    fn new(
+        sort_key: &SortKey<'a>,
+        collection: &'a FilterCollection,
+        cursor: &'a EntityCursor,
+    ) -> Result<Self, QueryExecutionError> {
+        let table = collection
+            .first_table()
+            .expect("an entity query always contains at least one entity type/table");
+        let id = QueryValue::new(&cursor.id, table.primary_key().column_type())
+            .map_err(StoreError::from)?;
+
+        match (sort_key, &cursor.value) {
+            (SortKey::Id(direction, _), None) => Ok(KeysetCursor {
+                key: None,
+                id,
+                direction: *direction,
+            }),
+            (
+                SortKey::Key {
+                    column,
+                    value: None,
+                    direction,
+                },
+                Some(value),
+            ) => {
+                let value =
+                    QueryValue::new(value, column.column_type()).map_err(StoreError::from)?;
+                Ok(KeysetCursor {
+                    key: Some((column.name(), value)),
+                    id,
+                    direction: *direction,
+                })
+            }
+            (SortKey::Id(_, _), Some(_)) | (SortKey::Key { .. }, None) => {
+                Err(QueryExecutionError::InvalidCursor(
+                    "the cursor was not produced by a query with the same `orderBy`".to_string(),
+                ))
+            }
+            (SortKey::None, _)
+            | (SortKey::Key { .. }, _)
+            | (SortKey::ChildKey(_), _)
+            | (SortKey::Multi(_), _) => Err(QueryExecutionError::NotSupported(
+                "Cursors for queries that are ordered by fulltext search, child attributes \
+                 or several attributes"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+/// String representation that is useful for debugging when `walk_ast` fails
+impl<'a> fmt::Display for KeysetCursor<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.direction {
+            SortDirection::Asc => ">",
+            SortDirection::Desc => "<",
+        };
+        match &self.key {
+            Some((name, value)) => write!(f, "({}, id) {} ({}, {})", name, op, value, self.id),
+            None => write!(f, "id {} {}", op, self.id),
+        }
+    }
+}
+
+impl<'a> QueryFragment<Pg> for KeysetCursor<'a> {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+
+        let op = match self.direction {
+            SortDirection::Asc => " > ",
+            SortDirection::Desc => " < ",
+        };
+
+        match &self.key {
+            None => {
+                out.push_sql("c.");
+                out.push_identifier(PRIMARY_KEY_COLUMN)?;
+                out.push_sql(op);
+                self.id.walk_ast(out.reborrow())?;
+            }
+            Some((name, value)) if value.is_null() => {
+                // The cursor is in the block of rows where the sort key is
+                // null, which comes last for ascending and first for
+                // descending order
+                out.push_sql("(");
+                if let SortDirection::Desc = self.direction {
+                    out.push_sql("c.");
+                    out.push_identifier(name)?;
+                    out.push_sql(" is not null or ");
+                }
+                out.push_sql("(c.");
+                out.push_identifier(name)?;
+                out.push_sql(" is null and c.");
+                out.push_identifier(PRIMARY_KEY_COLUMN)?;
+                out.push_sql(op);
+                self.id.walk_ast(out.reborrow())?;
+                out.push_sql("))");
+            }
+            Some((name, value)) => {
+                out.push_sql("((c.");
+                out.push_identifier(name)?;
+                out.push_sql(", c.");
+                out.push_identifier(PRIMARY_KEY_COLUMN)?;
+                out.push_sql(")");
+                out.push_sql(op);
+                out.push_sql("(");
+                value.walk_ast(out.reborrow())?;
+                out.push_sql(", ");
+                self.id.walk_ast(out.reborrow())?;
+                out.push_sql(")");
+                if let SortDirection::Asc = self.direction {
+                    out.push_sql(" or c.");
+                    out.push_identifier(name)?;
+                    out.push_sql(" is null");
+                }
+                out.push_sql(")");
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Generate `[limit {first}] [offset {skip}]
 #[derive(Debug, Clone)]
 pub struct FilterRange(EntityRange);
@@ -4224,14 +4793,23 @@ impl<'a> FilterQuery<'a> {
         layout: &'a Layout,
         filter: Option<&'a EntityFilter>,
         order: EntityOrder,
-        range: EntityRange,
+        range: &'a EntityRange,
         block: BlockNumber,
         query_id: Option<String>,
         site: &'a Site,
     ) -> Result<Self, QueryExecutionError> {
         let sort_key = SortKey::new(order, collection, filter, layout, block)?;
-        let range = FilterRange(range);
-        let limit = ParentLimit { sort_key, range };
+        let cursor = range
+            .after
+            .as_ref()
+            .map(|cursor| KeysetCursor::new(&sort_key, collection, cursor))
+            .transpose()?;
+        let range = FilterRange(range.clone());
+        let limit = ParentLimit {
+            sort_key,
+            range,
+            cursor,
+        };
 
         Ok(FilterQuery {
             collection,
@@ -4265,6 +4843,7 @@ impl<'a> FilterQuery<'a> {
             out.push_sql(" and ");
             filter.walk_ast(out.reborrow())?;
         }
+        self.limit.after_cursor(out)?;
         out.push_sql("\n");
         Ok(())
     }
@@ -4549,6 +5128,224 @@ impl<'a> Query for FilterQuery<'a> {
 
 impl<'a, Conn> RunQueryDsl<Conn> for FilterQuery<'a> {}
 
+/// The result of an `AggregateQuery`: one entry per requested aggregate,
+/// rendered as text so that we can parse it according to the type of the
+/// underlying column
+#[derive(QueryableByName)]
+pub struct AggregateData {
+    #[diesel(sql_type = Array<Nullable<Text>>)]
+    aggregates: Vec<Option<String>>,
+}
+
+/// A query that computes aggregates like `count(*)` or `sum(column)` over
+/// all entities of one type that match a filter at a given block. It
+/// generates
+///
+///   select array[count(*)::text, sum(c.column)::text, ..]::text[] as aggregates
+///     from {table} c
+///    where {at_block} and {filter}
+#[derive(Debug, Clone)]
+pub struct AggregateQuery<'a> {
+    wh: &'a WholeTable<'a>,
+    aggregates: Vec<(&'a EntityAggregate, Option<dsl::Column<'a>>)>,
+}
+
+impl<'a> AggregateQuery<'a> {
+    pub fn new(
+        collection: &'a FilterCollection<'a>,
+        aggregates: &'a [EntityAggregate],
+    ) -> Result<Self, QueryExecutionError> {
+        let wh = match collection {
+            FilterCollection::All(entities) if entities.len() == 1 => &entities[0],
+            _ => {
+                return Err(QueryExecutionError::NotSupported(
+                    "aggregates can only be computed over a single entity type".to_string(),
+                ))
+            }
+        };
+
+        let aggregates = aggregates
+            .iter()
+            .map(|aggregate| {
+                let attr = match aggregate {
+                    EntityAggregate::Count => return Ok((aggregate, None)),
+                    EntityAggregate::Sum(attr)
+                    | EntityAggregate::Min(attr)
+                    | EntityAggregate::Max(attr) => attr,
+                };
+                let column = wh.table.column_for_field(attr)?;
+                let numeric = matches!(
+                    column.column_type(),
+                    ColumnType::Int
+                        | ColumnType::Int8
+                        | ColumnType::BigInt
+                        | ColumnType::BigDecimal
+                );
+                if column.is_list() || !numeric {
+                    return Err(QueryExecutionError::NotSupported(format!(
+                        "can not aggregate attribute `{}` of `{}` since it is not numeric",
+                        attr,
+                        wh.table.meta.object.as_str()
+                    )));
+                }
+                Ok((aggregate, Some(column)))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(AggregateQuery { wh, aggregates })
+    }
+
+    /// Turn the text representation of the aggregates that the database
+    /// returned into values of the appropriate type
+    pub fn values(&self, data: AggregateData) -> Result<Vec<Value>, StoreError> {
+        if data.aggregates.len() != self.aggregates.len() {
+            return Err(graph::constraint_violation!(
+                "expected {} aggregates but got {}",
+                self.aggregates.len(),
+                data.aggregates.len()
+            ));
+        }
+
+        fn big_int(text: &str) -> Result<Value, StoreError> {
+            scalar::BigInt::from_str(text)
+                .map(Value::BigInt)
+                .map_err(|e| graph::constraint_violation!("invalid BigInt `{}`: {}", text, e))
+        }
+
+        fn big_decimal(text: &str) -> Result<Value, StoreError> {
+            scalar::BigDecimal::from_str(text)
+                .map(Value::BigDecimal)
+                .map_err(|e| graph::constraint_violation!("invalid BigDecimal `{}`: {}", text, e))
+        }
+
+        fn int8(text: &str) -> Result<Value, StoreError> {
+            i64::from_str(text)
+                .map(Value::Int8)
+                .map_err(|e| graph::constraint_violation!("invalid Int8 `{}`: {}", text, e))
+        }
+
+        fn int(text: &str) -> Result<Value, StoreError> {
+            i32::from_str(text)
+                .map(Value::Int)
+                .map_err(|e| graph::constraint_violation!("invalid Int `{}`: {}", text, e))
+        }
+
+        self.aggregates
+            .iter()
+            .zip(data.aggregates)
+            .map(|((aggregate, column), text)| {
+                let text = match text {
+                    Some(text) => text,
+                    None => return Ok(Value::Null),
+                };
+                let column_type = match column {
+                    Some(column) => column.column_type(),
+                    None => return int8(&text),
+                };
+                match (aggregate, column_type) {
+                    (EntityAggregate::Sum(_), ColumnType::Int) => int8(&text),
+                    (EntityAggregate::Sum(_), ColumnType::Int8 | ColumnType::BigInt) => {
+                        big_int(&text)
+                    }
+                    (_, ColumnType::Int) => int(&text),
+                    (_, ColumnType::Int8) => int8(&text),
+                    (_, ColumnType::BigInt) => big_int(&text),
+                    (_, ColumnType::BigDecimal) => big_decimal(&text),
+                    (_, column_type) => Err(graph::constraint_violation!(
+                        "can not aggregate column of type {:?}",
+                        column_type
+                    )),
+                }
+            })
+            .collect()
+    }
+}
+
+/// String representation that is useful for debugging when `walk_ast` fails
+impl<'a> fmt::Display for AggregateQuery<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "aggregate {} over {}",
+            self.aggregates
+                .iter()
+                .map(|(agg, _)| format!("{:?}", agg))
+                .join(","),
+            self.wh
+                .table
+                .meta
+                .qualified_name
+                .as_str()
+                .replace("\\\"", "")
+        )?;
+        if let Some(filter) = &self.wh.filter {
+            write!(f, "{{{}}}", filter)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> QueryFragment<Pg> for AggregateQuery<'a> {
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, Pg>) -> QueryResult<()> {
+        out.unsafe_to_cache_prepared();
+
+        out.push_sql("select array[");
+        for (i, (aggregate, column)) in self.aggregates.iter().enumerate() {
+            if i > 0 {
+                out.push_sql(", ");
+            }
+            match (aggregate, column) {
+                (EntityAggregate::Count, _) => out.push_sql("count(*)"),
+                (EntityAggregate::Sum(_), Some(column)) => {
+                    out.push_sql("sum(");
+                    column.walk_ast(out.reborrow())?;
+                    out.push_sql(")");
+                }
+                (EntityAggregate::Min(_), Some(column)) => {
+                    out.push_sql("min(");
+                    column.walk_ast(out.reborrow())?;
+                    out.push_sql(")");
+                }
+                (EntityAggregate::Max(_), Some(column)) => {
+                    out.push_sql("max(");
+                    column.walk_ast(out.reborrow())?;
+                    out.push_sql(")");
+                }
+                (aggregate, None) => {
+                    return Err(constraint_violation!(
+                        "aggregate {:?} requires a column",
+                        aggregate
+                    ))
+                }
+            }
+            out.push_sql("::text");
+        }
+        out.push_sql("]::text[] as aggregates");
+
+        out.push_sql("\n  from ");
+        self.wh.from_table.walk_ast(out.reborrow())?;
+        out.push_sql("\n where ");
+        self.wh.at_block.walk_ast(out.reborrow())?;
+        if let Some(filter) = &self.wh.filter {
+            out.push_sql(" and ");
+            filter.walk_ast(out.reborrow())?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> QueryId for AggregateQuery<'a> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+impl<'a> Query for AggregateQuery<'a> {
+    type SqlType = Untyped;
+}
+
+impl<'a, Conn> RunQueryDsl<Conn> for AggregateQuery<'a> {}
+
 /// Reduce the upper bound of the current entry's block range to `block` as
 /// long as that does not result in an empty block range
 #[derive(Debug)]
@@ -18,7 +18,7 @@ use graph::{
         server::index_node::VersionInfo,
         store::{
             self, BlockPtrForNumber, BlockStore, DeploymentLocator, EnsLookup as EnsLookupTrait,
-            PruneReporter, PruneRequest, SubgraphFork,
+            PersistedQueryStore, PruneReporter, PruneRequest, SubgraphFork,
         },
     },
     constraint_violation,
@@ -44,7 +44,7 @@ use crate::{
         index::{IndexList, Method},
         Layout,
     },
-    writable::{SourceableStore, WritableStore},
+    writable::{ReplayStore, SourceableStore, WritableStore},
     NotificationSender,
 };
 use crate::{
@@ -1341,12 +1341,37 @@ impl EnsLookupTrait for EnsLookup {
     }
 }
 
+/// Reads the allow-list of persisted queries from the primary
+struct PersistedQueries {
+    primary: ConnectionPool,
+}
+
+#[async_trait::async_trait]
+impl PersistedQueryStore for PersistedQueries {
+    async fn persisted_queries(&self) -> Result<Vec<(String, String)>, StoreError> {
+        self.primary
+            .with_conn(|conn, _| {
+                primary::Connection::new(conn)
+                    .persisted_queries()
+                    .map(|queries| queries.into_iter().map(|pq| (pq.hash, pq.query)).collect())
+                    .map_err(CancelableError::from)
+            })
+            .await
+    }
+}
+
 #[async_trait::async_trait]
 impl SubgraphStoreTrait for SubgraphStore {
     fn ens_lookup(&self) -> Arc<dyn EnsLookupTrait> {
         Arc::new(EnsLookup::new(self.mirror.primary().clone()))
     }
 
+    fn persisted_queries(&self) -> Arc<dyn PersistedQueryStore> {
+        Arc::new(PersistedQueries {
+            primary: self.mirror.primary().clone(),
+        })
+    }
+
     // FIXME: This method should not get a node_id
     fn create_subgraph_deployment(
         &self,
@@ -1535,6 +1560,32 @@ impl SubgraphStoreTrait for SubgraphStore {
             .map(|store| store as Arc<dyn store::WritableStore>)
     }
 
+    async fn replay_writable(
+        self: Arc<Self>,
+        logger: Logger,
+        deployment: graph::components::store::DeploymentId,
+        block: BlockNumber,
+        manifest_idx_and_name: Arc<Vec<(u32, String)>>,
+    ) -> Result<Arc<dyn store::WritableStore>, StoreError> {
+        let deployment = deployment.into();
+        let this = self.clone();
+        let site = graph::spawn_blocking_allow_panic(move || -> Result<_, StoreError> {
+            this.find_site(deployment)
+        })
+        .await
+        .unwrap()?; // Propagate panics, there shouldn't be any.
+
+        let store = ReplayStore::new(
+            self.as_ref().clone(),
+            logger,
+            site,
+            block,
+            manifest_idx_and_name,
+        )
+        .await?;
+        Ok(Arc::new(store))
+    }
+
     async fn sourceable(
         self: Arc<Self>,
         deployment: graph::components::store::DeploymentId,
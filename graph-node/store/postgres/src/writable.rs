@@ -8,7 +8,9 @@ use std::{collections::BTreeMap, sync::Arc};
 use async_trait::async_trait;
 use graph::blockchain::block_stream::{EntitySourceOperation, FirehoseCursor};
 use graph::blockchain::BlockTime;
-use graph::components::store::{Batch, DeploymentCursorTracker, DerivedEntityQuery, ReadStore};
+use graph::components::store::{
+    Batch, DeploymentCursorTracker, DerivedEntityQuery, FindByQuery, ReadStore,
+};
 use graph::constraint_violation;
 use graph::data::store::IdList;
 use graph::data::subgraph::schema;
@@ -209,6 +211,12 @@ impl SyncStore {
             .map(FirehoseCursor::from)
     }
 
+    async fn check_history(&self, block: BlockNumber) -> Result<(), StoreError> {
+        self.writable
+            .check_history(self.site.cheap_clone(), block)
+            .await
+    }
+
     fn start_subgraph_deployment(&self, logger: &Logger) -> Result<(), StoreError> {
         retry::forever(&self.logger, "start_subgraph_deployment", || {
             let graft_base = match self.writable.graft_pending(&self.site.deployment)? {
@@ -287,6 +295,12 @@ impl SyncStore {
         })
     }
 
+    fn get_at(&self, key: &EntityKey, block: BlockNumber) -> Result<Option<Entity>, StoreError> {
+        retry::forever(&self.logger, "get_at", || {
+            self.writable.get_at(self.site.cheap_clone(), key, block)
+        })
+    }
+
     fn transact_block_operations(
         &self,
         batch: &Batch,
@@ -340,6 +354,16 @@ impl SyncStore {
         })
     }
 
+    fn find_by(
+        &self,
+        query: &FindByQuery,
+        block: BlockNumber,
+    ) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        retry::forever(&self.logger, "find_by", || {
+            self.writable.find_by(self.site.cheap_clone(), query, block)
+        })
+    }
+
     async fn is_deployment_synced(&self) -> Result<bool, StoreError> {
         retry::forever_async(&self.logger, "is_deployment_synced", || async {
             self.writable
@@ -1149,6 +1173,23 @@ impl Queue {
         }
     }
 
+    /// Get the entity for `key` as of `block` by looking at both the queue
+    /// and the store. Only changes in the queue up to and including
+    /// `block` are considered
+    fn get_at(&self, key: &EntityKey, block: BlockNumber) -> Result<Option<Entity>, StoreError> {
+        let (op, query_block) = BlockTracker::find_map(&self.queue, |batch, at| {
+            batch.last_op(key, at.min(block)).map(|op| match op {
+                EntityOp::Write { key: _, entity } => Some(entity.clone()),
+                EntityOp::Remove { .. } => None,
+            })
+        });
+
+        match op {
+            Some(entity) => Ok(entity),
+            None => self.store.get_at(key, query_block.min(block)),
+        }
+    }
+
     /// Get many entities at once by looking at both the queue and the store
     fn get_many(
         &self,
@@ -1257,6 +1298,53 @@ impl Queue {
         Ok(items_from_database)
     }
 
+    fn find_by(&self, query: &FindByQuery) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        // Get the newest version of the entities of this type from the
+        // queue; entities that don't match the query anymore become `None`
+        let (entities_in_queue, query_block) = BlockTracker::fold(
+            &self.queue,
+            BTreeMap::new(),
+            |mut map: BTreeMap<EntityKey, Option<Entity>>, batch, at| {
+                // Since we are going newest to oldest, do not clobber
+                // already existing entries in map as that would make us
+                // produce stale values
+                for op in batch.effective_ops(&query.entity_type, at) {
+                    let (key, entity) = match op {
+                        EntityOp::Write { key, entity } => {
+                            (key, query.matches(key, entity).then(|| entity.clone()))
+                        }
+                        EntityOp::Remove { key } => (key, None),
+                    };
+                    if !map.contains_key(key) {
+                        map.insert(key.clone(), entity);
+                    }
+                }
+                map
+            },
+        );
+
+        // Ask the store for enough entities that we still have
+        // `query.limit` of them after removing the ones the queue changed
+        let store_query = FindByQuery {
+            limit: query.limit + entities_in_queue.len(),
+            ..query.clone()
+        };
+        let mut entities = self.store.find_by(&store_query, query_block)?;
+
+        for (key, entity) in entities_in_queue {
+            match entity {
+                Some(entity) => {
+                    entities.insert(key, entity);
+                }
+                None => {
+                    entities.remove(&key);
+                }
+            }
+        }
+
+        Ok(entities.into_iter().take(query.limit).collect())
+    }
+
     /// Load dynamic data sources by looking at both the queue and the store
     async fn load_dynamic_data_sources(
         &self,
@@ -1268,11 +1356,15 @@ impl Queue {
         // as long as they were written at a block before whatever is still
         // in the queue. The overall list of dds is the list of dds from the
         // store plus the ones still in memory sorted by their block number.
-        let (mut queue_dds, query_block) =
-            BlockTracker::fold(&self.queue, Vec::new(), |mut dds, batch, at| {
+        let ((mut queue_dds, removed), query_block) = BlockTracker::fold(
+            &self.queue,
+            (Vec::new(), Vec::new()),
+            |(mut dds, mut removed), batch, at| {
                 dds.extend(batch.new_data_sources(at).cloned());
-                dds
-            });
+                removed.extend(batch.removed_data_sources(at).cloned());
+                (dds, removed)
+            },
+        );
         // Using a stable sort is important here so that dds created at the
         // same block stay in the order in which they were added (and
         // therefore will be loaded from the store in that order once the
@@ -1285,6 +1377,19 @@ impl Queue {
             .await?;
         dds.append(&mut queue_dds);
 
+        // Onchain data sources whose removal is still in the queue
+        for ds in dds.iter_mut() {
+            let removal = removed.iter().find(|removed: &&StoredDynamicDataSource| {
+                removed.causality_region == ds.causality_region
+                    && removed.manifest_idx == ds.manifest_idx
+                    && removed.param == ds.param
+                    && removed.creation_block == ds.creation_block
+            });
+            if let Some(removal) = removal {
+                ds.done_at = removal.done_at;
+            }
+        }
+
         Ok(dds)
     }
 
@@ -1397,6 +1502,13 @@ impl Writer {
         }
     }
 
+    fn get_at(&self, key: &EntityKey, block: BlockNumber) -> Result<Option<Entity>, StoreError> {
+        match self {
+            Writer::Sync(store) => store.get_at(key, block),
+            Writer::Async { queue, .. } => queue.get_at(key, block),
+        }
+    }
+
     fn get_many(
         &self,
         keys: BTreeSet<EntityKey>,
@@ -1417,6 +1529,13 @@ impl Writer {
         }
     }
 
+    fn find_by(&self, query: &FindByQuery) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        match self {
+            Writer::Sync(store) => store.find_by(query, BLOCK_NUMBER_MAX),
+            Writer::Async { queue, .. } => queue.find_by(query),
+        }
+    }
+
     async fn load_dynamic_data_sources(
         &self,
         manifest_idx_and_name: Vec<(u32, String)>,
@@ -1528,6 +1647,10 @@ impl ReadStore for WritableStore {
         self.writer.get(key)
     }
 
+    fn get_at(&self, key: &EntityKey, block: BlockNumber) -> Result<Option<Entity>, StoreError> {
+        self.writer.get_at(key, block)
+    }
+
     fn get_many(
         &self,
         keys: BTreeSet<EntityKey>,
@@ -1542,6 +1665,10 @@ impl ReadStore for WritableStore {
         self.writer.get_derived(key)
     }
 
+    fn find_by(&self, query: &FindByQuery) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        self.writer.find_by(query)
+    }
+
     fn input_schema(&self) -> InputSchema {
         self.store.input_schema()
     }
@@ -1669,7 +1796,7 @@ impl WritableStoreTrait for WritableStore {
         stopwatch: &StopwatchMetrics,
         data_sources: Vec<StoredDynamicDataSource>,
         deterministic_errors: Vec<SubgraphError>,
-        processed_data_sources: Vec<StoredDynamicDataSource>,
+        data_sources_to_remove: Vec<StoredDynamicDataSource>,
         is_non_fatal_errors_active: bool,
         is_caught_up_with_chain_head: bool,
     ) -> Result<(), StoreError> {
@@ -1696,7 +1823,7 @@ impl WritableStoreTrait for WritableStore {
             mods,
             data_sources,
             deterministic_errors,
-            processed_data_sources,
+            data_sources_to_remove,
             is_non_fatal_errors_active,
         )?;
         self.writer.write(batch, stopwatch).await?;
@@ -1785,3 +1912,207 @@ impl WritableStoreTrait for WritableStore {
         }
     }
 }
+
+/// A `WritableStore` that sees the state of a deployment before the block
+/// that is being replayed and refuses to change anything. Since it reads
+/// directly from the database, changes that are still in the write queue
+/// of a running deployment are not visible to it.
+pub struct ReplayStore {
+    store: Arc<SyncStore>,
+    /// The block at which all reads happen, i.e., the parent of the
+    /// block that is being replayed
+    block: BlockNumber,
+    block_ptr: Option<BlockPtr>,
+    block_cursor: FirehoseCursor,
+}
+
+impl ReplayStore {
+    pub(crate) async fn new(
+        subgraph_store: SubgraphStore,
+        logger: Logger,
+        site: Arc<Site>,
+        block: BlockNumber,
+        manifest_idx_and_name: Arc<Vec<(u32, String)>>,
+    ) -> Result<Self, StoreError> {
+        let block = block - 1;
+        let store = Arc::new(
+            SyncStore::new(
+                subgraph_store,
+                logger,
+                site,
+                manifest_idx_and_name,
+                Some(block),
+            )
+            .await?,
+        );
+        let block_ptr = store.block_ptr().await?;
+        let block_cursor = store.block_cursor().await?;
+
+        if block_ptr.as_ref().map_or(true, |ptr| ptr.number < block) {
+            return Err(constraint_violation!(
+                "can not replay block {} of {} since the deployment has not processed block {} yet",
+                block + 1,
+                store.site.deployment,
+                block
+            ));
+        }
+        // Reads at `block` would silently miss entity versions that
+        // pruning removed
+        store.check_history(block).await?;
+
+        Ok(Self {
+            store,
+            block,
+            block_ptr,
+            block_cursor,
+        })
+    }
+
+    fn read_only(&self, op: &str) -> StoreError {
+        constraint_violation!(
+            "{} is not possible while replaying block {} of {}",
+            op,
+            self.block + 1,
+            self.store.site.deployment
+        )
+    }
+}
+
+impl ReadStore for ReplayStore {
+    fn get(&self, key: &EntityKey) -> Result<Option<Entity>, StoreError> {
+        self.store.get(key, self.block)
+    }
+
+    fn get_at(&self, key: &EntityKey, block: BlockNumber) -> Result<Option<Entity>, StoreError> {
+        self.store.get_at(key, block.min(self.block))
+    }
+
+    fn get_many(
+        &self,
+        keys: BTreeSet<EntityKey>,
+    ) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        self.store.get_many(keys, self.block)
+    }
+
+    fn get_derived(
+        &self,
+        key: &DerivedEntityQuery,
+    ) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        self.store.get_derived(key, self.block, vec![])
+    }
+
+    fn find_by(&self, query: &FindByQuery) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        self.store.find_by(query, self.block)
+    }
+
+    fn input_schema(&self) -> InputSchema {
+        self.store.input_schema()
+    }
+}
+
+impl DeploymentCursorTracker for ReplayStore {
+    fn block_ptr(&self) -> Option<BlockPtr> {
+        self.block_ptr.clone()
+    }
+
+    fn firehose_cursor(&self) -> FirehoseCursor {
+        self.block_cursor.clone()
+    }
+
+    fn input_schema(&self) -> InputSchema {
+        self.store.input_schema()
+    }
+}
+
+#[async_trait::async_trait]
+impl WritableStoreTrait for ReplayStore {
+    async fn start_subgraph_deployment(&self, _logger: &Logger) -> Result<(), StoreError> {
+        // Starting a deployment might graft or copy it, and there is
+        // nothing to do for a deployment that has already been started
+        Ok(())
+    }
+
+    async fn revert_block_operations(
+        &self,
+        _block_ptr_to: BlockPtr,
+        _firehose_cursor: FirehoseCursor,
+    ) -> Result<(), StoreError> {
+        Err(self.read_only("reverting"))
+    }
+
+    async fn unfail_deterministic_error(
+        &self,
+        _current_ptr: &BlockPtr,
+        _parent_ptr: &BlockPtr,
+    ) -> Result<UnfailOutcome, StoreError> {
+        Ok(UnfailOutcome::Noop)
+    }
+
+    fn unfail_non_deterministic_error(
+        &self,
+        _current_ptr: &BlockPtr,
+    ) -> Result<UnfailOutcome, StoreError> {
+        Ok(UnfailOutcome::Noop)
+    }
+
+    async fn fail_subgraph(&self, _error: SubgraphError) -> Result<(), StoreError> {
+        Err(self.read_only("failing the subgraph"))
+    }
+
+    async fn transact_block_operations(
+        &self,
+        _block_ptr_to: BlockPtr,
+        _block_time: BlockTime,
+        _firehose_cursor: FirehoseCursor,
+        _mods: Vec<EntityModification>,
+        _stopwatch: &StopwatchMetrics,
+        _data_sources: Vec<StoredDynamicDataSource>,
+        _deterministic_errors: Vec<SubgraphError>,
+        _data_sources_to_remove: Vec<StoredDynamicDataSource>,
+        _is_non_fatal_errors_active: bool,
+        _is_caught_up_with_chain_head: bool,
+    ) -> Result<(), StoreError> {
+        Err(self.read_only("writing changes"))
+    }
+
+    fn deployment_synced(&self, _block_ptr: BlockPtr) -> Result<(), StoreError> {
+        Err(self.read_only("marking the deployment as synced"))
+    }
+
+    fn is_deployment_synced(&self) -> bool {
+        false
+    }
+
+    fn unassign_subgraph(&self) -> Result<(), StoreError> {
+        Err(self.read_only("unassigning the deployment"))
+    }
+
+    async fn load_dynamic_data_sources(
+        &self,
+        manifest_idx_and_name: Vec<(u32, String)>,
+    ) -> Result<Vec<StoredDynamicDataSource>, StoreError> {
+        self.store
+            .load_dynamic_data_sources(self.block, manifest_idx_and_name)
+            .await
+    }
+
+    async fn causality_region_curr_val(&self) -> Result<Option<CausalityRegion>, StoreError> {
+        self.store.causality_region_curr_val().await
+    }
+
+    fn shard(&self) -> &str {
+        self.store.shard()
+    }
+
+    async fn health(&self) -> Result<schema::SubgraphHealth, StoreError> {
+        self.store.health().await
+    }
+
+    async fn flush(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    async fn restart(self: Arc<Self>) -> Result<Option<Arc<dyn WritableStoreTrait>>, StoreError> {
+        Ok(None)
+    }
+}
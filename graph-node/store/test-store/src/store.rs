@@ -388,6 +388,48 @@ pub async fn transact_entities_and_dynamic_data_sources(
         .await
 }
 
+/// Transact a block that only removes the given dynamic data sources
+pub async fn transact_removed_data_sources(
+    store: &Arc<DieselSubgraphStore>,
+    deployment: DeploymentLocator,
+    block_ptr_to: BlockPtr,
+    removed: Vec<StoredDynamicDataSource>,
+    manifest_idx_and_name: Vec<(u32, String)>,
+) -> Result<(), StoreError> {
+    let store = store
+        .cheap_clone()
+        .writable(
+            LOGGER.clone(),
+            deployment.id,
+            Arc::new(manifest_idx_and_name),
+        )
+        .await?;
+
+    let metrics_registry = Arc::new(MetricsRegistry::mock());
+    let stopwatch_metrics = StopwatchMetrics::new(
+        Logger::root(slog::Discard, o!()),
+        deployment.hash.clone(),
+        "transact",
+        metrics_registry.clone(),
+        store.shard().to_string(),
+    );
+    let block_time = BlockTime::for_test(&block_ptr_to);
+    store
+        .transact_block_operations(
+            block_ptr_to,
+            block_time,
+            FirehoseCursor::None,
+            Vec::new(),
+            &stopwatch_metrics,
+            Vec::new(),
+            Vec::new(),
+            removed,
+            false,
+            false,
+        )
+        .await
+}
+
 /// Revert to block `ptr` and wait for the store to process the changes
 pub async fn revert_block(store: &Arc<Store>, deployment: &DeploymentLocator, ptr: &BlockPtr) {
     store
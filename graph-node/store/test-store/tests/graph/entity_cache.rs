@@ -1,8 +1,8 @@
 use graph::blockchain::block_stream::FirehoseCursor;
 use graph::blockchain::BlockTime;
 use graph::components::store::{
-    DeploymentCursorTracker, DerivedEntityQuery, GetScope, LoadRelatedRequest, ReadStore,
-    StoredDynamicDataSource, WritableStore,
+    DeploymentCursorTracker, DerivedEntityQuery, FindByQuery, GetScope, LoadRelatedRequest,
+    ReadStore, StoredDynamicDataSource, WritableStore,
 };
 use graph::data::store::Id;
 use graph::data::subgraph::schema::{DeploymentCreate, SubgraphError, SubgraphHealth};
@@ -59,6 +59,10 @@ impl ReadStore for MockStore {
         Ok(self.get_many_res.get(key).cloned())
     }
 
+    fn get_at(&self, key: &EntityKey, _block: BlockNumber) -> Result<Option<Entity>, StoreError> {
+        Ok(self.get_many_res.get(key).cloned())
+    }
+
     fn get_many(
         &self,
         _keys: BTreeSet<EntityKey>,
@@ -73,6 +77,16 @@ impl ReadStore for MockStore {
         Ok(self.get_many_res.clone())
     }
 
+    fn find_by(&self, query: &FindByQuery) -> Result<BTreeMap<EntityKey, Entity>, StoreError> {
+        Ok(self
+            .get_many_res
+            .iter()
+            .filter(|(key, entity)| query.matches(key, entity))
+            .take(query.limit)
+            .map(|(key, entity)| (key.clone(), entity.clone()))
+            .collect())
+    }
+
     fn input_schema(&self) -> InputSchema {
         SCHEMA.clone()
     }
@@ -752,6 +766,99 @@ fn check_for_delete_async_related() {
         assert_eq!(result, expeted_vec);
     });
 }
+fn find_wallets_by_account(cache: &mut EntityCache, account: &str, limit: usize) -> Vec<String> {
+    let query = FindByQuery {
+        entity_type: WALLET_TYPE.clone(),
+        attribute: "account".into(),
+        value: Value::from(account),
+        causality_region: CausalityRegion::ONCHAIN,
+        limit,
+    };
+    cache
+        .find_by(&query)
+        .unwrap()
+        .iter()
+        .map(|entity| entity.id().to_string())
+        .collect()
+}
+
+#[test]
+fn find_by_attribute() {
+    run_store_test(|mut cache, _store, _deployment, _writable| async move {
+        assert_eq!(
+            vec!["1", "2", "3"],
+            find_wallets_by_account(&mut cache, "1", 10)
+        );
+        assert_eq!(vec!["1", "2"], find_wallets_by_account(&mut cache, "1", 2));
+        assert_eq!(vec!["4"], find_wallets_by_account(&mut cache, "2", 10));
+        assert!(find_wallets_by_account(&mut cache, "3", 10).is_empty());
+
+        let query = FindByQuery {
+            entity_type: ACCOUNT_TYPE.clone(),
+            attribute: "email".into(),
+            value: Value::from("dinici@email.com"),
+            causality_region: CausalityRegion::ONCHAIN,
+            limit: 10,
+        };
+        let result = cache.find_by(&query).unwrap();
+        assert_eq!(1, result.len());
+        assert_eq!("Cindini", result[0].get("name").unwrap().as_str().unwrap());
+    });
+}
+
+#[test]
+fn find_by_sees_changes_in_block() {
+    run_store_test(|mut cache, _store, _deployment, _writable| async move {
+        let account1 = ACCOUNT_TYPE.parse_id("1").unwrap();
+        let account2 = ACCOUNT_TYPE.parse_id("2").unwrap();
+
+        // A new wallet for account 1, wallet 2 moves to account 2, and
+        // wallet 3 is removed
+        let wallet0 = create_wallet_entity_no_vid("0", &account1, 10);
+        cache
+            .set(WALLET_TYPE.parse_key("0").unwrap(), wallet0, 0, None)
+            .unwrap();
+        let wallet2 = create_wallet_entity_no_vid("2", &account2, 92);
+        cache
+            .set(WALLET_TYPE.parse_key("2").unwrap(), wallet2, 0, None)
+            .unwrap();
+        cache.remove(WALLET_TYPE.parse_key("3").unwrap());
+
+        assert_eq!(vec!["0", "1"], find_wallets_by_account(&mut cache, "1", 10));
+        assert_eq!(vec!["0"], find_wallets_by_account(&mut cache, "1", 1));
+        assert_eq!(vec!["2", "4"], find_wallets_by_account(&mut cache, "2", 10));
+    });
+}
+
+#[test]
+fn find_by_checks_query() {
+    run_store_test(|mut cache, _store, _deployment, _writable| async move {
+        let query = |attribute: &str, value: Value, limit: usize| FindByQuery {
+            entity_type: ACCOUNT_TYPE.clone(),
+            attribute: attribute.into(),
+            value,
+            causality_region: CausalityRegion::ONCHAIN,
+            limit,
+        };
+
+        // Unknown and derived attributes
+        assert!(cache
+            .find_by(&query("friends", Value::from("1"), 10))
+            .is_err());
+        assert!(cache
+            .find_by(&query("wallets", Value::from("1"), 10))
+            .is_err());
+        // The value needs to have the type of the attribute
+        assert!(cache.find_by(&query("age", Value::from("67"), 10)).is_err());
+        assert!(cache.find_by(&query("age", Value::Null, 10)).is_err());
+        // The limit is bounded
+        assert!(cache.find_by(&query("age", Value::Int(67), 1001)).is_err());
+
+        let result = cache.find_by(&query("age", Value::Int(67), 10)).unwrap();
+        assert_eq!(1, result.len());
+    });
+}
+
 #[test]
 fn scoped_get() {
     run_store_test(|mut cache, _store, _deployment, _writable| async move {
@@ -588,13 +588,24 @@ async fn insert_test_entities(
     insert_at(entities0, &deployment, BLOCKS[0].clone()).await;
     insert_at(entities1, &deployment, BLOCKS[1].clone()).await;
 
-    // We ingest block 2 with no changes to the subgraph solely to trigger
-    // an hourly rollup. Make sure that the block times between genesis and
-    // block 2 actually span an hour.
+    // Ingesting block 2 triggers an hourly rollup. Make sure that the block
+    // times between genesis and block 2 actually span an hour. The plays
+    // in block 2 fall into the next hour, and therefore stay in the
+    // current bucket which has not been rolled up
     let t0 = BlockTime::for_test(&BLOCKS[0]).as_secs_since_epoch();
     let t2 = BlockTime::for_test(&BLOCKS[2]).as_secs_since_epoch();
     assert!(t2 - t0 > 3600);
-    insert_at(vec![], &deployment, BLOCKS[2].clone()).await;
+    let ts2 = BlockTime::for_test(&BLOCKS[2]);
+    let entities2 = vec![(
+        "Plays",
+        vec![
+            entity! { is => id: 6i64, timestamp: ts2, song: s[2], user: "u2", vid: 5i64 },
+            entity! { is => id: 7i64, timestamp: ts2, song: s[1], user: "u1", vid: 6i64 },
+            entity! { is => id: 8i64, timestamp: ts2, song: s[1], user: "u2", vid: 7i64 },
+        ],
+    )];
+    let entities2 = insert_ops(&manifest.schema, entities2);
+    insert_at(entities2, &deployment, BLOCKS[2].clone()).await;
     deployment
 }
 
@@ -1011,6 +1022,106 @@ fn can_query_with_sorting_by_child_entity() {
     })
 }
 
+#[test]
+fn can_query_with_sorting_by_several_attributes() {
+    const QUERY: &str = "
+    query {
+        desc: musicians(first: 100, orderBy: [{ field: mainBand__name, direction: desc }, { field: name }]) {
+            name
+            mainBand {
+                name
+            }
+        }
+        asc: musicians(first: 100, orderBy: [{ field: mainBand__name }, { field: name, direction: desc }]) {
+            name
+            mainBand {
+                name
+            }
+        }
+    }";
+
+    run_query(QUERY, |result, _| {
+        let exp = object! {
+            desc: vec![
+                object! { name: "Valerie", mainBand: r::Value::Null },
+                object! { name: "John", mainBand: object! { name: "The Musicians" } },
+                object! { name: "Lisa", mainBand: object! { name: "The Musicians" } },
+                object! { name: "Tom",  mainBand: object! { name: "The Amateurs"} },
+                ],
+            asc: vec![
+                object! { name: "Tom",  mainBand: object! { name: "The Amateurs"} },
+                object! { name: "Lisa", mainBand: object! { name: "The Musicians" } },
+                object! { name: "John", mainBand: object! { name: "The Musicians" } },
+                object! { name: "Valerie", mainBand: r::Value::Null },
+                ]
+        };
+
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    })
+}
+
+#[test]
+fn can_query_aggregates() {
+    const QUERY: &str = "
+    query {
+        all: musician_aggregate {
+            count
+            sum { favoriteCount }
+            min { favoriteCount }
+            max { favoriteCount }
+        }
+        filtered: musician_aggregate(where: { mainBand: \"b1\" }) {
+            count
+            sum { favoriteCount }
+        }
+        early: musician_aggregate(block: { number: 0 }) {
+            count
+            max { favoriteCount }
+        }
+        none: musician_aggregate(where: { name: \"Nobody\" }) {
+            count
+            min { favoriteCount }
+        }
+        songStat_aggregate {
+            count
+            sum { played }
+            min { played }
+        }
+    }";
+
+    run_query(QUERY, |result, _| {
+        let exp = object! {
+            all: object! {
+                count: 4,
+                sum: object! { favoriteCount: "135" },
+                min: object! { favoriteCount: "5" },
+                max: object! { favoriteCount: "100" },
+            },
+            filtered: object! {
+                count: 2,
+                sum: object! { favoriteCount: "110" },
+            },
+            early: object! {
+                count: 2,
+                max: object! { favoriteCount: "100" },
+            },
+            none: object! {
+                count: 0,
+                min: object! { favoriteCount: r::Value::Null },
+            },
+            songStat_aggregate: object! {
+                count: 2,
+                sum: object! { played: "25" },
+                min: object! { played: 10 },
+            },
+        };
+
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    })
+}
+
 #[test]
 fn can_query_with_sorting_by_derived_child_entity() {
     const QUERY: &str = "
@@ -1316,6 +1427,48 @@ fn can_query_with_child_filter_on_list_type_field() {
     })
 }
 
+#[test]
+fn can_query_with_regex_and_list_filters() {
+    const QUERY: &str = "
+    query {
+        matches: musicians(first: 100, orderBy: id, where: { name_matches: \"^[JT]o\" }) {
+            name
+        }
+        matchesNoCase: musicians(first: 100, orderBy: id, where: { name_matches_nocase: \"^v\" }) {
+            name
+        }
+        overlaps: musicians(first: 100, orderBy: id, where: { bands_overlaps: [\"b2\", \"b3\"] }) {
+            name
+        }
+        containedBy: musicians(first: 100, orderBy: id, where: { bands_contained_by: [\"b1\"] }) {
+            name
+        }
+    }";
+
+    run_query(QUERY, |result, _| {
+        let exp = object! {
+            matches: vec![
+                object! { name: "John" },
+                object! { name: "Tom" },
+            ],
+            matchesNoCase: vec![
+                object! { name: "Valerie" },
+            ],
+            overlaps: vec![
+                object! { name: "John" },
+                object! { name: "Tom" },
+            ],
+            containedBy: vec![
+                object! { name: "Lisa" },
+                object! { name: "Valerie" },
+            ],
+        };
+
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    })
+}
+
 #[test]
 fn can_query_with_child_filter_on_derived_list_type_field() {
     const QUERY: &str = "
@@ -1646,6 +1799,50 @@ fn query_variables_are_used() {
     );
 }
 
+#[test]
+fn order_by_variables_are_used() {
+    // `orderBy` used to take a single `Musician_orderBy`; variables of that
+    // type still work alongside lists of `Musician_order`
+    const QUERY: &str = "
+    query musicians($o: Musician_orderBy, $d: OrderDirection, $order: [Musician_order!]) {
+      single: musicians(first: 100, orderBy: $o, orderDirection: $d) {
+        name
+      }
+      list: musicians(first: 100, orderBy: $order) {
+        name
+      }
+    }";
+
+    run_query(
+        (
+            QUERY,
+            object![
+                o: "name",
+                d: "desc",
+                order: vec![object! { field: "name", direction: "asc" }]
+            ],
+        ),
+        |result, _| {
+            let exp = object! {
+                single: vec![
+                    object! { name: "Valerie" },
+                    object! { name: "Tom" },
+                    object! { name: "Lisa" },
+                    object! { name: "John" },
+                ],
+                list: vec![
+                    object! { name: "John" },
+                    object! { name: "Lisa" },
+                    object! { name: "Tom" },
+                    object! { name: "Valerie" },
+                ]
+            };
+            let data = extract_data!(result).unwrap();
+            assert_eq!(data, exp);
+        },
+    );
+}
+
 #[test]
 fn mixed_parent_child_id() {
     // Check that any combination of parent and child id type (String or
@@ -3020,6 +3217,44 @@ fn simple_aggregation() {
         }
     }"#;
 
+    // The plays from block 2 are in the current bucket, which starts one
+    // hour after the rolled up one
+    const SONG_QUERY_CURRENT: &str = "
+    query {
+        songPlays_collection(interval: hour, current: include) {
+            id
+            timestamp
+            song { id }
+            played
+        }
+    }";
+
+    const USER_QUERY_CURRENT: &str = r#"
+    query {
+        userPlays_collection(interval: hour, current: include, where: { timestamp_gt: "1000000" }) {
+            id
+            timestamp
+            user { id }
+            played
+        }
+    }"#;
+
+    // At block 1, nothing has been rolled up yet, and the current bucket
+    // is computed from all plays
+    const SONG_QUERY_CURRENT_AT_1: &str = "
+    query {
+        songPlays_collection(interval: hour, current: include, block: { number: 1 }) {
+            id
+            timestamp
+            song { id }
+            played
+        }
+    }";
+
+    fn ts1() -> r::Value {
+        r::Value::Timestamp(Timestamp::since_epoch(3600, 0).unwrap())
+    }
+
     run_query(SONG_QUERY, |result, id_type| {
         let s = id_type.songs();
         let exp = object! {
@@ -3031,6 +3266,40 @@ fn simple_aggregation() {
         let data = extract_data!(result).unwrap();
         assert_eq!(data, exp);
     });
+    run_query(SONG_QUERY_CURRENT, |result, id_type| {
+        let s = id_type.songs();
+        let exp = object! {
+            songPlays_collection: vec![
+                object! { id: "8", timestamp: ts1(), song: object! { id: s[1] }, played: 2 },
+                object! { id: "6", timestamp: ts1(), song: object! { id: s[2] }, played: 1 },
+                object! { id: "5", timestamp: ts0(), song: object! { id: s[1] }, played: 4 },
+                object! { id: "3", timestamp: ts0(), song: object! { id: s[2] }, played: 1 },
+            ]
+        };
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    });
+    run_query(USER_QUERY_CURRENT, |result, _| {
+        let exp = object! {
+            userPlays_collection: vec![
+                object! { id: "8", timestamp: ts1(), user: object! { id: "u2" }, played: 2 },
+                object! { id: "7", timestamp: ts1(), user: object! { id: "u1" }, played: 1 },
+            ]
+        };
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    });
+    run_query(SONG_QUERY_CURRENT_AT_1, |result, id_type| {
+        let s = id_type.songs();
+        let exp = object! {
+            songPlays_collection: vec![
+                object! { id: "5", timestamp: ts0(), song: object! { id: s[1] }, played: 4 },
+                object! { id: "3", timestamp: ts0(), song: object! { id: s[2] }, played: 1 },
+            ]
+        };
+        let data = extract_data!(result).unwrap();
+        assert_eq!(data, exp);
+    });
     run_query(USER_QUERY1, |result, _| {
         let exp = object! {
             userPlays_collection: vec![
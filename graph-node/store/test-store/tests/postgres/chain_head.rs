@@ -9,7 +9,7 @@ use graph::futures03::executor;
 use std::future::Future;
 use std::sync::Arc;
 
-use graph::prelude::web3::types::H256;
+use graph::prelude::web3::types::{Log, TransactionReceipt, H256};
 use graph::prelude::{anyhow::anyhow, anyhow::Error};
 use graph::prelude::{serde_json as json, EthereumBlock};
 use graph::prelude::{BlockNumber, QueryStoreManager, QueryTarget};
@@ -487,6 +487,90 @@ fn eth_call_cache() {
     })
 }
 
+#[test]
+fn log_cache() {
+    let chain = vec![&*GENESIS_BLOCK, &*BLOCK_ONE, &*BLOCK_TWO];
+
+    run_test_async(chain, |store, _, _| async move {
+        fn log(number: u64, index: u64) -> Log {
+            Log {
+                address: H160::from_low_u64_be(1),
+                topics: vec![H256::from_low_u64_be(index)],
+                data: Default::default(),
+                block_hash: Some(H256::from_low_u64_be(number)),
+                block_number: Some(number.into()),
+                transaction_hash: Some(H256::from_low_u64_be(100 + index)),
+                transaction_index: None,
+                log_index: Some(index.into()),
+                transaction_log_index: None,
+                log_type: None,
+                removed: Some(false),
+            }
+        }
+
+        let filter_id = [1u8; 32];
+        let logs = vec![log(2, 0), log(5, 1), log(9, 2)];
+        store
+            .set_cached_logs(&filter_id, 1, 10, &logs)
+            .await
+            .unwrap();
+
+        let cached = store.cached_logs(&filter_id, 1, 10).await.unwrap();
+        if store.chain == FAKE_NETWORK_SHARED {
+            // Shared storage has no log cache
+            assert_eq!(None, cached);
+            return;
+        }
+        assert_eq!(Some(logs.clone()), cached);
+
+        // A range inside a cached range only returns logs in that range
+        let cached = store.cached_logs(&filter_id, 3, 9).await.unwrap();
+        assert_eq!(Some(vec![log(5, 1), log(9, 2)]), cached);
+
+        // Ranges that are not covered and other filters are misses
+        let cached = store.cached_logs(&filter_id, 5, 11).await.unwrap();
+        assert_eq!(None, cached);
+        let cached = store.cached_logs(&[2u8; 32], 1, 10).await.unwrap();
+        assert_eq!(None, cached);
+
+        let receipt = TransactionReceipt {
+            transaction_hash: H256::from_low_u64_be(100),
+            block_hash: Some(H256::from_low_u64_be(2)),
+            block_number: Some(2.into()),
+            logs: vec![log(2, 0)],
+            ..Default::default()
+        };
+        store
+            .set_cached_receipts(&[Arc::new(receipt.clone())])
+            .await
+            .unwrap();
+        let cached = store
+            .cached_receipts(&[receipt.transaction_hash, H256::from_low_u64_be(101)])
+            .await
+            .unwrap();
+        assert_eq!(vec![receipt.clone()], cached);
+
+        // Removing a range removes all log ranges that overlap it, even
+        // though the chain has no head
+        assert_eq!(None, store.cheap_clone().chain_head_ptr().await.unwrap());
+        store.clear_log_cache(8, 8).await.unwrap();
+        let cached = store.cached_logs(&filter_id, 1, 10).await.unwrap();
+        assert_eq!(None, cached);
+        let cached = store
+            .cached_receipts(&[receipt.transaction_hash])
+            .await
+            .unwrap();
+        assert_eq!(vec![receipt], cached);
+
+        store.clear_log_cache(0, BlockNumber::MAX).await.unwrap();
+        let cached = store
+            .cached_receipts(&[H256::from_low_u64_be(100)])
+            .await
+            .unwrap();
+        assert!(cached.is_empty());
+    })
+}
+
 #[test]
 /// Tests only query correctness. No data is involved.
 fn test_transaction_receipts_in_block_function() {
@@ -1338,6 +1338,58 @@ fn check_find() {
                 )),
             );
 
+        // list overlaps and contained by
+        let checker = checker
+            .check(
+                vec!["2", "3"],
+                user_query().filter(EntityFilter::Overlaps(
+                    "drinks".into(),
+                    vec!["wine", "tea"].into(),
+                )),
+            )
+            .check(
+                vec![],
+                user_query().filter(EntityFilter::Overlaps(
+                    "drinks".into(),
+                    vec!["water"].into(),
+                )),
+            )
+            .check(
+                vec!["3"],
+                user_query().filter(EntityFilter::ContainedBy(
+                    "drinks".into(),
+                    vec!["water", "tea", "coffee"].into(),
+                )),
+            )
+            .check(
+                vec![],
+                user_query().filter(EntityFilter::ContainedBy(
+                    "drinks".into(),
+                    vec!["beer"].into(),
+                )),
+            );
+
+        // regular expressions
+        let checker = checker
+            .check(
+                vec!["2", "3"],
+                user_query().filter(EntityFilter::Matches(
+                    "name".into(),
+                    "^[CS].*(ni|na)$".into(),
+                )),
+            )
+            .check(
+                vec![],
+                user_query().filter(EntityFilter::Matches("name".into(), "^cindini$".into())),
+            )
+            .check(
+                vec!["2"],
+                user_query().filter(EntityFilter::MatchesNoCase(
+                    "name".into(),
+                    "^cindini$".into(),
+                )),
+            );
+
         // string attributes
         let checker = checker
             .check(
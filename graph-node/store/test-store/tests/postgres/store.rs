@@ -10,7 +10,9 @@ use lazy_static::lazy_static;
 use std::{marker::PhantomData, str::FromStr};
 use test_store::*;
 
-use graph::components::store::{DeploymentLocator, ReadStore, WritableStore};
+use graph::components::store::{
+    DeploymentLocator, ReadStore, StoredDynamicDataSource, WritableStore,
+};
 use graph::data::subgraph::*;
 use graph::{
     blockchain::DataSource,
@@ -1148,6 +1150,61 @@ fn revert_block_with_dynamic_data_source_operations() {
     })
 }
 
+#[test]
+fn revert_removal_of_dynamic_data_source() {
+    run_test(|store, writable, deployment| async move {
+        let subgraph_store = store.subgraph_store();
+        let manifest_idx_and_name = vec![(0, "example data source".to_string())];
+
+        // Create a dynamic data source at block 3 and remove it at block 4
+        let mut data_source = mock_data_source();
+        data_source.creation_block = Some(TEST_BLOCK_3_PTR.number);
+        let stored = data_source.as_stored_dynamic_data_source();
+        transact_entities_and_dynamic_data_sources(
+            &subgraph_store,
+            deployment.clone(),
+            TEST_BLOCK_3_PTR.clone(),
+            vec![stored.clone()],
+            vec![],
+            manifest_idx_and_name.clone(),
+        )
+        .await
+        .unwrap();
+
+        let removed = StoredDynamicDataSource {
+            done_at: Some(TEST_BLOCK_4_PTR.number),
+            ..stored.clone()
+        };
+        transact_removed_data_sources(
+            &subgraph_store,
+            deployment.clone(),
+            TEST_BLOCK_4_PTR.clone(),
+            vec![removed.clone()],
+            manifest_idx_and_name.clone(),
+        )
+        .await
+        .unwrap();
+        flush(&deployment).await.unwrap();
+
+        // The removed data source is still loaded, but marked as done
+        let loaded_dds = writable
+            .load_dynamic_data_sources(manifest_idx_and_name.clone())
+            .await
+            .unwrap();
+        assert_eq!(vec![removed], loaded_dds);
+
+        // Reverting the block that removed the data source makes it
+        // active again
+        revert_block(&store, &deployment, &TEST_BLOCK_3_PTR).await;
+
+        let loaded_dds = writable
+            .load_dynamic_data_sources(manifest_idx_and_name)
+            .await
+            .unwrap();
+        assert_eq!(vec![stored], loaded_dds);
+    })
+}
+
 #[test]
 fn subgraph_schema_types_have_subgraph_id_directive() {
     run_test(|store, _, deployment| async move {
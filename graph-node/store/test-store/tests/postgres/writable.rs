@@ -10,7 +10,8 @@ use std::ops::Range;
 use test_store::*;
 
 use graph::components::store::{
-    DeploymentLocator, DerivedEntityQuery, SourceableStore, WritableStore,
+    DeploymentLocator, DerivedEntityQuery, FindByQuery, PruneReporter, PruneRequest,
+    SourceableStore, WritableStore,
 };
 use graph::data::subgraph::*;
 use graph::semver::Version;
@@ -284,6 +285,20 @@ fn count_get_derived(writable: &dyn WritableStore) -> i32 {
     counter.get("count").unwrap().as_int().unwrap()
 }
 
+fn count_find_by(writable: &dyn WritableStore) -> i32 {
+    let key = count_key("1");
+    let query = FindByQuery {
+        entity_type: key.entity_type.clone(),
+        attribute: Word::from("id"),
+        value: Value::from("1"),
+        causality_region: CausalityRegion::ONCHAIN,
+        limit: 1,
+    };
+    let map = writable.find_by(&query).unwrap();
+    let counter = map.get(&key).unwrap();
+    counter.get("count").unwrap().as_int().unwrap()
+}
+
 #[test]
 fn get_batch() {
     get_with_pending(true, count_get);
@@ -314,6 +329,131 @@ fn get_derived_nobatch() {
     get_with_pending(false, count_get_derived);
 }
 
+#[test]
+fn find_by_batch() {
+    get_with_pending(true, count_find_by);
+}
+
+#[test]
+fn find_by_nobatch() {
+    get_with_pending(false, count_find_by);
+}
+
+#[test]
+fn get_at() {
+    run_test(|store, writable, _, deployment| async move {
+        let subgraph_store = store.subgraph_store();
+
+        let count_at = |block: BlockNumber| {
+            writable
+                .get_at(&count_key("1"), block)
+                .unwrap()
+                .map(|counter| counter.get("count").unwrap().as_int().unwrap())
+        };
+        let check = || {
+            assert_eq!(None, count_at(0));
+            assert_eq!(Some(1), count_at(1));
+            assert_eq!(Some(2), count_at(2));
+            assert_eq!(None, count_at(3));
+            assert_eq!(Some(4), count_at(4));
+            assert_eq!(None, count_at(5));
+            assert_eq!(Some(6), count_at(6));
+        };
+
+        for count in 1..4 {
+            insert_count(&subgraph_store, &deployment, count, count, false).await;
+        }
+
+        // Read old versions while some of the changes are still queued
+        pause_writer(&deployment).await;
+        for count in 4..7 {
+            insert_count(&subgraph_store, &deployment, count, count, false).await;
+        }
+        check();
+
+        writable.flush().await.unwrap();
+        check();
+
+        // There are no entities before the start block
+        assert_eq!(None, count_at(-1));
+
+        // Versions that pruning might have removed can not be read
+        prune(&subgraph_store, &deployment).await;
+        match writable.get_at(&count_key("1"), 2) {
+            Err(StoreError::HistoryPruned(2, 3)) => (),
+            res => panic!("expected HistoryPruned(2, 3) but got {:?}", res),
+        }
+        assert_eq!(None, count_at(3));
+        assert_eq!(Some(4), count_at(4));
+        assert_eq!(Some(6), count_at(6));
+    })
+}
+
+/// Prune `deployment`, which must be at block 6, so that it only keeps
+/// history from block 3 on
+async fn prune(store: &Arc<DieselSubgraphStore>, deployment: &DeploymentLocator) {
+    struct Progress;
+    impl PruneReporter for Progress {}
+
+    let req = PruneRequest::new(deployment, 3, 1, 0, 6).unwrap();
+    store
+        .prune(Box::new(Progress), deployment, req)
+        .await
+        .expect("pruning works");
+}
+
+#[test]
+fn replay_store() {
+    run_test(|store, writable, _, deployment| async move {
+        let subgraph_store = store.subgraph_store();
+
+        for count in 1..7 {
+            insert_count(&subgraph_store, &deployment, count, count, false).await;
+        }
+        writable.flush().await.unwrap();
+
+        let replay = |block: BlockNumber| {
+            subgraph_store.cheap_clone().replay_writable(
+                LOGGER.clone(),
+                deployment.id,
+                block,
+                Arc::new(Vec::new()),
+            )
+        };
+
+        // Replaying block 3 sees the state at block 2, not the latest one
+        let replay3 = replay(3).await.unwrap();
+        let counter = replay3.get(&count_key("1")).unwrap().unwrap();
+        assert_eq!(Some(2), counter.get("count").unwrap().as_int());
+        assert_eq!(2, count_find_by(replay3.as_ref()));
+        replay3
+            .revert_block_operations(block_pointer(1), FirehoseCursor::None)
+            .await
+            .expect_err("replaying can not change the deployment");
+        replay3
+            .deployment_synced(block_pointer(3))
+            .expect_err("replaying can not change the deployment");
+
+        // The start block has no state before it
+        let replay0 = replay(0).await.unwrap();
+        assert_eq!(None, replay0.get(&count_key("1")).unwrap());
+
+        // The deployment has only processed block 6
+        replay(8)
+            .await
+            .err()
+            .expect("blocks after the next one can not be replayed");
+
+        // Pruning removes the state that replaying block 3 needs
+        prune(&subgraph_store, &deployment).await;
+        match replay(3).await.err() {
+            Some(StoreError::HistoryPruned(2, 3)) => (),
+            res => panic!("expected HistoryPruned(2, 3) but got {:?}", res),
+        }
+        replay(4).await.unwrap();
+    })
+}
+
 #[test]
 fn restart() {
     run_test(|store, writable, _, deployment| async move {
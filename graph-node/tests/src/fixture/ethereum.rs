@@ -33,6 +33,7 @@ pub async fn chain(
     let triggers_adapter = triggers_adapter.unwrap_or(Arc::new(NoopAdapterSelector {
         triggers_in_block_sleep: Duration::ZERO,
         x: PhantomData,
+        blocks: blocks.clone(),
     }));
 
     let CommonChainConfig {
@@ -534,6 +534,7 @@ pub async fn setup<C: Blockchain>(
         blockchain_map.cheap_clone(),
         stores.network_store.cheap_clone(),
         link_resolver.cheap_clone(),
+        mock_registry.mapping_profiles(),
     ));
 
     // Create IPFS-based subgraph provider
@@ -927,9 +928,11 @@ impl RuntimeAdapterBuilder for NoopRuntimeAdapterBuilder {
     }
 }
 
-pub struct NoopAdapterSelector<C> {
+pub struct NoopAdapterSelector<C: Blockchain> {
     pub x: PhantomData<C>,
     pub triggers_in_block_sleep: Duration,
+    /// The blocks that `scan_triggers` returns
+    pub blocks: Vec<BlockWithTriggers<C>>,
 }
 
 impl<C: Blockchain> TriggersAdapterSelector<C> for NoopAdapterSelector<C> {
@@ -948,6 +951,7 @@ impl<C: Blockchain> TriggersAdapterSelector<C> for NoopAdapterSelector<C> {
             x: PhantomData,
             triggers_in_block,
             triggers_in_block_sleep: self.triggers_in_block_sleep,
+            blocks: self.blocks.clone(),
         }))
     }
 }
@@ -970,6 +974,7 @@ impl<C: Blockchain> TriggersAdapterSelector<C> for MockAdapterSelector<C> {
             x: PhantomData,
             triggers_in_block: self.triggers_in_block.clone(),
             triggers_in_block_sleep: self.triggers_in_block_sleep,
+            blocks: Vec::new(),
         }))
     }
 }
@@ -979,6 +984,7 @@ struct MockTriggersAdapter<C: Blockchain> {
     triggers_in_block_sleep: Duration,
     triggers_in_block:
         Arc<dyn Fn(<C as Blockchain>::Block) -> Result<BlockWithTriggers<C>, Error> + Sync + Send>,
+    blocks: Vec<BlockWithTriggers<C>>,
 }
 
 #[async_trait]
@@ -1006,11 +1012,17 @@ impl<C: Blockchain> TriggersAdapter<C> for MockTriggersAdapter<C> {
 
     async fn scan_triggers(
         &self,
-        _from: BlockNumber,
-        _to: BlockNumber,
+        from: BlockNumber,
+        to: BlockNumber,
         _filter: &C::TriggerFilter,
     ) -> Result<(Vec<BlockWithTriggers<C>>, BlockNumber), Error> {
-        todo!()
+        let blocks = self
+            .blocks
+            .iter()
+            .filter(|block| (from..=to).contains(&block.ptr().number))
+            .cloned()
+            .collect();
+        Ok((blocks, to))
     }
 
     async fn triggers_in_block(
@@ -330,6 +330,56 @@ async fn api_version_0_0_8() {
     assert_eq_ignore_backtrace(&err, &expected_err);
 }
 
+#[tokio::test]
+async fn replay_block() {
+    let RunnerTestRecipe { stores, test_info } =
+        RunnerTestRecipe::new_with_custom_cmd("replay_block", "api-version", "deploy:test-0-0-7")
+            .await;
+
+    let blocks = {
+        let block_0 = genesis();
+        let mut block_1 = empty_block(block_0.ptr(), test_ptr(1));
+        push_test_log(&mut block_1, "1");
+        let mut block_2 = empty_block(block_1.ptr(), test_ptr(2));
+        push_test_log(&mut block_2, "2");
+        vec![block_0, block_1, block_2]
+    };
+
+    let stop_block = blocks.last().unwrap().block.ptr();
+
+    let chain = chain(&test_info.test_name, blocks, &stores, None).await;
+    let ctx = fixture::setup(&test_info, &stores, &chain, None, None).await;
+
+    ctx.start_and_sync_to(stop_block.clone()).await;
+    ctx.provider.stop(ctx.deployment.clone()).await.unwrap();
+
+    let (logger, deployment, raw) = ctx.get_runner_context().await;
+    let replay = ctx
+        .instance_manager
+        .replay_block(logger, deployment, raw, 2)
+        .await
+        .unwrap();
+
+    assert_eq!(stop_block, replay.block_ptr);
+    let results: Vec<_> = replay
+        .modifications
+        .iter()
+        .filter(|m| m.key().entity_type.typename() == "TestResult")
+        .map(|m| m.key().entity_id.to_string())
+        .collect();
+    assert_eq!(vec!["2".to_string()], results);
+
+    // Replaying does not write anything
+    let query_res = ctx
+        .query(r#"{ testResults(orderBy: id) { id } }"#)
+        .await
+        .unwrap();
+    assert_json_eq!(
+        query_res,
+        Some(object! { testResults: vec![object! { id: "1" }, object! { id: "2" }] })
+    );
+}
+
 #[tokio::test]
 async fn derived_loaders() {
     let RunnerTestRecipe { stores, test_info } =
@@ -702,6 +752,7 @@ async fn file_data_sources() {
     let adapter_selector = NoopAdapterSelector {
         x: PhantomData,
         triggers_in_block_sleep: Duration::from_millis(150),
+        blocks: vec![],
     };
     let chain = chain(
         &test_info.test_name,
@@ -1236,6 +1287,7 @@ async fn arweave_file_data_sources() {
     let adapter_selector = NoopAdapterSelector {
         x: PhantomData,
         triggers_in_block_sleep: Duration::from_millis(1500),
+        blocks: vec![],
     };
     let chain = chain(
         &test_info.test_name,